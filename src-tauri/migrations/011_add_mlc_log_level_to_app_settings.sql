@@ -0,0 +1,5 @@
+-- Add MLC server log level to app_settings
+-- NULL means the sidecar's default verbosity
+
+ALTER TABLE app_settings
+ADD COLUMN mlc_log_level TEXT;
@@ -0,0 +1,5 @@
+-- Add MLC server max tokens to app_settings
+-- NULL means the sidecar's default generation limit
+
+ALTER TABLE app_settings
+ADD COLUMN mlc_max_tokens INTEGER;
@@ -0,0 +1,6 @@
+-- Add MLC server sampling defaults to app_settings
+-- NULL means the sidecar's default for that parameter
+
+ALTER TABLE app_settings ADD COLUMN mlc_temperature REAL;
+ALTER TABLE app_settings ADD COLUMN mlc_top_p REAL;
+ALTER TABLE app_settings ADD COLUMN mlc_repetition_penalty REAL;
@@ -0,0 +1,5 @@
+-- Add per-conversation system prompt
+-- NULL falls back to app_settings.system_prompt; an empty string means no system message
+
+ALTER TABLE conversations
+ADD COLUMN system_prompt TEXT;
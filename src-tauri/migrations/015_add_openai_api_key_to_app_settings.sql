@@ -0,0 +1,5 @@
+-- Add OpenAI API key to app_settings
+-- Write-only from the frontend's perspective; the backend only reports whether it is set
+
+ALTER TABLE app_settings
+ADD COLUMN openai_api_key TEXT;
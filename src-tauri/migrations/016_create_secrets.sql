@@ -0,0 +1,6 @@
+-- Fallback storage for secrets when the OS keychain is unavailable
+CREATE TABLE IF NOT EXISTS secrets (
+  name TEXT PRIMARY KEY,
+  value TEXT NOT NULL,
+  updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
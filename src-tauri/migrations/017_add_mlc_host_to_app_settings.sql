@@ -0,0 +1,5 @@
+-- Add MLC server bind host to app_settings
+-- NULL keeps the loopback default (127.0.0.1)
+
+ALTER TABLE app_settings
+ADD COLUMN mlc_host TEXT;
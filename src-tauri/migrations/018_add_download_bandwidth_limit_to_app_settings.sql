@@ -0,0 +1,5 @@
+-- Add model download bandwidth cap (bytes per second) to app_settings
+-- NULL means unlimited
+
+ALTER TABLE app_settings
+ADD COLUMN download_max_bytes_per_sec INTEGER;
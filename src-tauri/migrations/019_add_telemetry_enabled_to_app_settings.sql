@@ -0,0 +1,4 @@
+-- Add opt-in local telemetry log flag to app_settings (off by default)
+
+ALTER TABLE app_settings
+ADD COLUMN telemetry_enabled INTEGER NOT NULL DEFAULT 0;
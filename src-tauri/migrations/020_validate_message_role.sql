@@ -0,0 +1,16 @@
+-- Reject unknown message roles and reasoning on non-assistant messages.
+-- Existing rows are left as they are; only new writes are checked.
+
+CREATE TRIGGER IF NOT EXISTS messages_validate_insert BEFORE INSERT ON messages BEGIN
+  SELECT RAISE(ABORT, 'invalid message role')
+  WHERE NEW.role NOT IN ('user', 'assistant', 'system', 'tool');
+  SELECT RAISE(ABORT, 'reasoning is only allowed on assistant messages')
+  WHERE NEW.reasoning IS NOT NULL AND NEW.role <> 'assistant';
+END;
+
+CREATE TRIGGER IF NOT EXISTS messages_validate_update BEFORE UPDATE OF role, reasoning ON messages BEGIN
+  SELECT RAISE(ABORT, 'invalid message role')
+  WHERE NEW.role NOT IN ('user', 'assistant', 'system', 'tool');
+  SELECT RAISE(ABORT, 'reasoning is only allowed on assistant messages')
+  WHERE NEW.reasoning IS NOT NULL AND NEW.role <> 'assistant';
+END;
@@ -0,0 +1,9 @@
+-- Tool-call results are stored as role = 'tool' messages. tool_call_id matches the
+-- id the model gave the call; parent_message_id is the assistant message that made it.
+-- tool_arguments holds the call's JSON arguments, replayed with its tool_call_id and
+-- tool_name as the requesting assistant message's tool_calls.
+
+ALTER TABLE messages ADD COLUMN tool_name TEXT;
+ALTER TABLE messages ADD COLUMN tool_call_id TEXT;
+ALTER TABLE messages ADD COLUMN parent_message_id INTEGER REFERENCES messages (id);
+ALTER TABLE messages ADD COLUMN tool_arguments TEXT;
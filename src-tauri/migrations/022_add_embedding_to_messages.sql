@@ -0,0 +1,14 @@
+-- Add per-message embedding vector (little-endian f32 BLOB) for semantic search, with
+-- the model that produced it; vectors from different models are not comparable
+
+ALTER TABLE messages
+ADD COLUMN embedding BLOB;
+
+ALTER TABLE messages
+ADD COLUMN embedding_model TEXT;
+
+-- An embedding describes the text it was computed from, so an edit drops it
+CREATE TRIGGER IF NOT EXISTS messages_clear_embedding AFTER UPDATE OF content ON messages
+WHEN new.content IS NOT old.content AND new.embedding IS NOT NULL BEGIN
+  UPDATE messages SET embedding = NULL, embedding_model = NULL WHERE id = new.id;
+END;
@@ -0,0 +1,4 @@
+-- Add flag to app_settings controlling the hung-server watchdog (opt-in, off by default)
+
+ALTER TABLE app_settings
+ADD COLUMN mlc_watchdog_enabled INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,4 @@
+-- Add relocated Hugging Face hub directory to app_settings (NULL = resolve from the environment)
+
+ALTER TABLE app_settings
+ADD COLUMN model_cache_dir TEXT;
@@ -0,0 +1,4 @@
+-- Add free-form JSON metadata to messages (attachment references, provider, model used)
+
+ALTER TABLE messages
+ADD COLUMN metadata TEXT;
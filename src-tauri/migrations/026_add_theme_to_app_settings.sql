@@ -0,0 +1,4 @@
+-- Add app theme to app_settings ('light', 'dark' or 'system'; NULL = follow the system)
+
+ALTER TABLE app_settings
+ADD COLUMN theme TEXT;
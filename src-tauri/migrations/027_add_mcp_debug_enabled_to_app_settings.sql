@@ -0,0 +1,4 @@
+-- Add flag to app_settings that keeps the last raw MCP exchange per server for debugging (off by default)
+
+ALTER TABLE app_settings
+ADD COLUMN mcp_debug_enabled INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,4 @@
+-- Add flag to app_settings that logs chat provider request metadata and timing (off by default)
+
+ALTER TABLE app_settings
+ADD COLUMN provider_request_logging INTEGER NOT NULL DEFAULT 0;
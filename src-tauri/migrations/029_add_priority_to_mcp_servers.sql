@@ -0,0 +1,4 @@
+-- Add priority to mcp_servers; higher-priority servers win when tool names overlap
+
+ALTER TABLE mcp_servers
+ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,4 @@
+-- Add flag to app_settings that kills leftover openchat-mlx-server processes before the server starts (off by default)
+
+ALTER TABLE app_settings
+ADD COLUMN mlc_kill_orphans INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,8 @@
+-- Add message history limits: a global default and a per-conversation override
+-- NULL in conversations falls back to app_settings.max_history_messages; 0 means no limit
+
+ALTER TABLE app_settings
+ADD COLUMN max_history_messages INTEGER;
+
+ALTER TABLE conversations
+ADD COLUMN max_history_messages INTEGER;
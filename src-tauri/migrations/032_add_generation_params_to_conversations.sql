@@ -0,0 +1,5 @@
+-- Add per-conversation generation parameters (JSON: temperature, top_p, max_tokens, stop)
+-- NULL means the conversation uses the global defaults from app_settings
+
+ALTER TABLE conversations
+ADD COLUMN generation_params TEXT;
@@ -0,0 +1,5 @@
+-- Add an optional .env-style file to stdio MCP servers
+-- Its variables are loaded at spawn time, under the explicit env (explicit wins)
+
+ALTER TABLE mcp_servers
+ADD COLUMN env_file TEXT;
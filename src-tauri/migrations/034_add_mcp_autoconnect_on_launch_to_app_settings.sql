@@ -0,0 +1,4 @@
+-- Add flag to app_settings that connects all enabled MCP servers in the background at launch (off by default)
+
+ALTER TABLE app_settings
+ADD COLUMN mcp_autoconnect_on_launch INTEGER NOT NULL DEFAULT 0;
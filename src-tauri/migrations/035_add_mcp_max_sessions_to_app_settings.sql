@@ -0,0 +1,4 @@
+-- Add cap on live MCP sessions to app_settings (NULL uses the built-in default)
+
+ALTER TABLE app_settings
+ADD COLUMN mcp_max_sessions INTEGER;
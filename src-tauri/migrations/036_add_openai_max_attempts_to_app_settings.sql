@@ -0,0 +1,4 @@
+-- Add attempts of an OpenAI completion to app_settings (NULL uses the built-in default)
+
+ALTER TABLE app_settings
+ADD COLUMN openai_max_attempts INTEGER;
@@ -0,0 +1,650 @@
+//! One-shot chat completions against a chosen backend, used to re-run a turn through
+//! a provider other than the one the conversation normally uses.
+
+use crate::mlc_server::{check_range, validate_max_tokens, MLCServerManager, SamplingDefaults};
+use crate::settings::{self, Setting};
+use crate::telemetry::{self, TelemetryEvent};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+/// Base URL of the OpenAI API.
+pub const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+/// Timeout of a regenerated completion, which includes prompt processing.
+const COMPLETION_TIMEOUT: Duration = Duration::from_secs(180);
+/// Timeout of the models-list call that verifies an API key.
+const KEY_TEST_TIMEOUT: Duration = Duration::from_secs(15);
+/// Attempts of an OpenAI completion when `openai_max_attempts` is unset.
+pub const OPENAI_DEFAULT_MAX_ATTEMPTS: u32 = 3;
+/// Most attempts `openai_max_attempts` may be set to.
+pub const OPENAI_MAX_ATTEMPTS_LIMIT: u32 = 10;
+/// Delay before the first retry; doubled for each later one.
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+/// Longest wait between attempts, including one asked for by `Retry-After`.
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// Backend a turn can be sent to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    /// The bundled MLC server.
+    Local,
+    OpenAi,
+}
+
+impl Provider {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Provider::Local => "local",
+            Provider::OpenAi => "openai",
+        }
+    }
+}
+
+impl FromStr for Provider {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "local" | "mlc" => Ok(Provider::Local),
+            "openai" => Ok(Provider::OpenAi),
+            other => Err(format!(
+                "unknown provider '{other}' (expected 'local' or 'openai')"
+            )),
+        }
+    }
+}
+
+/// Most stop sequences a conversation may set (the OpenAI API limit).
+pub const MAX_STOP_SEQUENCES: usize = 4;
+/// Longest stop sequence accepted, in characters.
+pub const MAX_STOP_SEQUENCE_CHARS: usize = 64;
+
+/// Sampling and stop settings sent with a completion request. Unset fields are left
+/// out of the request so the backend's own defaults apply.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GenerationParams {
+    #[serde(default)]
+    pub temperature: Option<f64>,
+    #[serde(default)]
+    pub top_p: Option<f64>,
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    #[serde(default)]
+    pub stop: Vec<String>,
+}
+
+impl GenerationParams {
+    /// Checks ranges and the stop sequence count and lengths.
+    pub fn validate(&self) -> Result<(), String> {
+        check_range("temperature", self.temperature, 0.0, 2.0, true)?;
+        check_range("top_p", self.top_p, 0.0, 1.0, false)?;
+        if let Some(max_tokens) = self.max_tokens {
+            validate_max_tokens(i64::from(max_tokens))?;
+        }
+        if self.stop.len() > MAX_STOP_SEQUENCES {
+            return Err(format!(
+                "at most {MAX_STOP_SEQUENCES} stop sequences are allowed, got {}",
+                self.stop.len()
+            ));
+        }
+        for stop in &self.stop {
+            let chars = stop.chars().count();
+            if chars == 0 || chars > MAX_STOP_SEQUENCE_CHARS {
+                return Err(format!(
+                    "stop sequences must be 1 to {MAX_STOP_SEQUENCE_CHARS} characters, got {chars}"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The global defaults from `app_settings` (the model server's sampling and max
+    /// tokens settings). Invalid stored values are ignored.
+    pub async fn global_defaults(pool: &SqlitePool) -> Result<Self, String> {
+        let sampling = SamplingDefaults::load(pool).await?;
+        let sampling = if sampling.validate().is_ok() {
+            sampling
+        } else {
+            SamplingDefaults::default()
+        };
+        let max_tokens = settings::get_setting::<i64>(pool, Setting::MlcMaxTokens)
+            .await?
+            .and_then(|v| validate_max_tokens(v).ok());
+        Ok(Self {
+            temperature: sampling.temperature,
+            top_p: sampling.top_p,
+            max_tokens,
+            stop: Vec::new(),
+        })
+    }
+
+    /// These params with any unset field taken from `defaults`.
+    pub fn merged_over(self, defaults: &Self) -> Self {
+        Self {
+            temperature: self.temperature.or(defaults.temperature),
+            top_p: self.top_p.or(defaults.top_p),
+            max_tokens: self.max_tokens.or(defaults.max_tokens),
+            stop: if self.stop.is_empty() {
+                defaults.stop.clone()
+            } else {
+                self.stop
+            },
+        }
+    }
+
+    /// Adds the set fields to a chat completions request body.
+    fn apply_to(&self, body: &mut serde_json::Value) {
+        if let Some(temperature) = self.temperature {
+            body["temperature"] = temperature.into();
+        }
+        if let Some(top_p) = self.top_p {
+            body["top_p"] = top_p.into();
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            body["max_tokens"] = max_tokens.into();
+        }
+        if !self.stop.is_empty() {
+            body["stop"] = self.stop.clone().into();
+        }
+    }
+}
+
+/// Text of a completed reply, with the model's reasoning when it reports any.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatReply {
+    pub content: String,
+    pub reasoning: Option<String>,
+}
+
+/// Why one completion attempt failed.
+#[derive(Debug)]
+struct AttemptError {
+    message: String,
+    /// Rate limits (429) and server errors (5xx) are worth retrying.
+    retryable: bool,
+    /// Wait the provider asked for in `Retry-After`.
+    retry_after: Option<Duration>,
+}
+
+impl From<String> for AttemptError {
+    fn from(message: String) -> Self {
+        Self {
+            message,
+            retryable: false,
+            retry_after: None,
+        }
+    }
+}
+
+/// Sends `messages` to `{base_url}/chat/completions` with `params` and returns the
+/// reply. Rate-limited (429) and server-error (5xx) responses are retried with
+/// exponential backoff, honoring `Retry-After`, for up to `max_attempts` attempts;
+/// any other failure, such as a rejected key, is returned at once.
+pub async fn complete(
+    base_url: &str,
+    api_key: Option<&str>,
+    model: &str,
+    messages: &[serde_json::Value],
+    params: &GenerationParams,
+    max_attempts: u32,
+) -> Result<ChatReply, String> {
+    let mut attempt = 0;
+    loop {
+        match complete_once(base_url, api_key, model, messages, params).await {
+            Ok(reply) => return Ok(reply),
+            Err(e) if e.retryable && attempt + 1 < max_attempts => {
+                let delay = retry_delay(attempt, e.retry_after);
+                log::warn!(
+                    "{}; retrying in {}s (attempt {} of {max_attempts})",
+                    e.message,
+                    delay.as_secs(),
+                    attempt + 2
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e.message),
+        }
+    }
+}
+
+/// Wait before retry number `attempt + 1`: `Retry-After` when the provider sent it,
+/// otherwise `RETRY_BASE_DELAY` doubled per attempt, capped at `RETRY_MAX_DELAY`.
+fn retry_delay(attempt: u32, retry_after: Option<Duration>) -> Duration {
+    retry_after
+        .unwrap_or_else(|| RETRY_BASE_DELAY.saturating_mul(2u32.saturating_pow(attempt)))
+        .min(RETRY_MAX_DELAY)
+}
+
+/// A `Retry-After` header in delay-seconds form. The HTTP-date form is ignored and
+/// falls back to the exponential delay.
+fn parse_retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?;
+    value.trim().parse::<u64>().ok().map(Duration::from_secs)
+}
+
+/// One completion request, without retries.
+async fn complete_once(
+    base_url: &str,
+    api_key: Option<&str>,
+    model: &str,
+    messages: &[serde_json::Value],
+    params: &GenerationParams,
+) -> Result<ChatReply, AttemptError> {
+    let mut body = serde_json::json!({
+        "model": model,
+        "messages": messages,
+        "stream": false,
+    });
+    params.apply_to(&mut body);
+    let mut request = crate::http_client::shared_client()
+        .post(format!("{base_url}/chat/completions"))
+        .timeout(COMPLETION_TIMEOUT)
+        .json(&body);
+    if let Some(key) = api_key {
+        request = request.bearer_auth(key);
+    }
+    let resp = request
+        .send()
+        .await
+        .map_err(|e| format!("chat request failed: {e}"))?;
+    let status = resp.status();
+    if !status.is_success() {
+        let retry_after = parse_retry_after(resp.headers());
+        let body: serde_json::Value = resp.json().await.unwrap_or_default();
+        return Err(AttemptError {
+            message: format!("chat request failed: {}", provider_error(status, &body)),
+            retryable: status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error(),
+            retry_after,
+        });
+    }
+    let body: serde_json::Value = resp
+        .json()
+        .await
+        .map_err(|e| format!("invalid chat response: {e}"))?;
+    Ok(parse_reply(&body).ok_or_else(|| "the model returned an empty reply".to_string())?)
+}
+
+/// The configured attempts of an OpenAI completion, defaulting to
+/// `OPENAI_DEFAULT_MAX_ATTEMPTS`.
+pub async fn openai_max_attempts(pool: &SqlitePool) -> Result<u32, String> {
+    Ok(
+        settings::get_setting::<i64>(pool, Setting::OpenaiMaxAttempts)
+            .await?
+            .map_or(OPENAI_DEFAULT_MAX_ATTEMPTS, |max| {
+                max.clamp(1, i64::from(OPENAI_MAX_ATTEMPTS_LIMIT)) as u32
+            }),
+    )
+}
+
+/// Outcome of `test_api_key`.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ProviderKeyTest {
+    pub ok: bool,
+    /// Models the key can use, on success.
+    pub model_count: Option<usize>,
+    /// The provider's error message, on failure.
+    pub error: Option<String>,
+}
+
+/// Verifies `api_key` with a `GET {base_url}/models`, which costs nothing. A rejected
+/// key is a failed test carrying the provider's message; only an unreachable provider
+/// is an error. The key is never logged.
+pub async fn test_api_key(base_url: &str, api_key: &str) -> Result<ProviderKeyTest, String> {
+    let resp = crate::http_client::shared_client()
+        .get(format!("{base_url}/models"))
+        .timeout(KEY_TEST_TIMEOUT)
+        .bearer_auth(api_key)
+        .send()
+        .await
+        .map_err(|e| format!("could not reach the provider: {}", e.without_url()))?;
+    let status = resp.status();
+    let body: serde_json::Value = resp.json().await.unwrap_or_default();
+    if !status.is_success() {
+        return Ok(ProviderKeyTest {
+            ok: false,
+            model_count: None,
+            error: Some(provider_error(status, &body)),
+        });
+    }
+    Ok(ProviderKeyTest {
+        ok: true,
+        model_count: body["data"].as_array().map(Vec::len),
+        error: None,
+    })
+}
+
+/// The message of an OpenAI-style `{"error": {"message": ...}}` body, or the status.
+fn provider_error(status: reqwest::StatusCode, body: &serde_json::Value) -> String {
+    match body.pointer("/error/message").and_then(|m| m.as_str()) {
+        Some(message) if !message.trim().is_empty() => format!("HTTP {status}: {message}"),
+        _ => format!("HTTP {status}"),
+    }
+}
+
+/// `complete`, logging the request's provider, model, size, latency and outcome when
+/// provider request logging is enabled. Message content is never logged. Requests to
+/// the local server are counted as in flight so its watchdog leaves it alone, and are
+/// not retried; OpenAI requests get `openai_max_attempts` attempts.
+pub async fn complete_logged(
+    app: &AppHandle,
+    provider: Provider,
+    base_url: &str,
+    api_key: Option<&str>,
+    model: &str,
+    messages: &[serde_json::Value],
+    params: &GenerationParams,
+) -> Result<ChatReply, String> {
+    let _in_flight = match provider {
+        Provider::Local => app
+            .try_state::<std::sync::Arc<MLCServerManager>>()
+            .map(|server| server.track_request()),
+        Provider::OpenAi => None,
+    };
+    let max_attempts = match (provider, app.try_state::<SqlitePool>()) {
+        (Provider::OpenAi, Some(pool)) => openai_max_attempts(&pool)
+            .await
+            .unwrap_or(OPENAI_DEFAULT_MAX_ATTEMPTS),
+        (Provider::OpenAi, None) => OPENAI_DEFAULT_MAX_ATTEMPTS,
+        (Provider::Local, _) => 1,
+    };
+    let started = Instant::now();
+    let result = complete(base_url, api_key, model, messages, params, max_attempts).await;
+    log_request(
+        app,
+        provider,
+        model,
+        messages,
+        started.elapsed(),
+        result.is_ok(),
+    )
+    .await;
+    result
+}
+
+/// Logs a chat request's provider, model, size, latency and outcome when provider
+/// request logging is enabled. Used for backend completions and, through
+/// `log_chat_request`, for the requests the chat view streams itself.
+pub async fn log_request(
+    app: &AppHandle,
+    provider: Provider,
+    model: &str,
+    messages: &[serde_json::Value],
+    latency: Duration,
+    ok: bool,
+) {
+    let logging = match app.try_state::<sqlx::SqlitePool>() {
+        Some(pool) => settings::get_setting::<bool>(&pool, Setting::ProviderRequestLogging)
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or(false),
+        None => false,
+    };
+    if !logging {
+        return;
+    }
+    let event = TelemetryEvent::ProviderRequest {
+        provider: provider.as_str().to_string(),
+        model: model.to_string(),
+        message_count: messages.len(),
+        prompt_tokens_estimate: estimate_prompt_tokens(messages),
+        latency_ms: latency.as_millis() as u64,
+        ok,
+    };
+    log::info!("provider request: {event:?}");
+    telemetry::record(app, event);
+}
+
+/// Estimated prompt size in tokens, for logs: the sum of `estimate_message_tokens`.
+fn estimate_prompt_tokens(messages: &[serde_json::Value]) -> u64 {
+    messages.iter().map(estimate_message_tokens).sum()
+}
+
+/// Average characters per token of English text for BPE tokenizers such as OpenAI's
+/// and the Llama/Qwen families served locally.
+const CHARS_PER_TOKEN: usize = 4;
+/// Tokens each chat message costs beyond its content (role and separators).
+const MESSAGE_TOKEN_OVERHEAD: u64 = 4;
+
+/// Estimated tokens of one chat message: its content plus the per-message overhead.
+pub fn estimate_message_tokens(message: &serde_json::Value) -> u64 {
+    let chars = message
+        .get("content")
+        .and_then(|c| c.as_str())
+        .map_or(0, |c| c.chars().count());
+    chars.div_ceil(CHARS_PER_TOKEN) as u64 + MESSAGE_TOKEN_OVERHEAD
+}
+
+/// Estimated token count of one stored message.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct MessageTokens {
+    pub message_id: i64,
+    pub tokens: u64,
+}
+
+/// Estimated token counts for a conversation as it would be sent in full.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ConversationTokens {
+    /// The effective system prompt; `0` when there is none.
+    pub system_prompt: u64,
+    pub messages: Vec<MessageTokens>,
+    pub total: u64,
+}
+
+/// Estimates tokens for the system prompt and each `(message id, chat message)`.
+pub fn count_conversation_tokens<'a>(
+    system_prompt: Option<&str>,
+    messages: impl IntoIterator<Item = (i64, &'a serde_json::Value)>,
+) -> ConversationTokens {
+    let system_prompt = system_prompt.map_or(0, |prompt| {
+        estimate_message_tokens(&serde_json::json!({ "role": "system", "content": prompt }))
+    });
+    let messages: Vec<MessageTokens> = messages
+        .into_iter()
+        .map(|(message_id, message)| MessageTokens {
+            message_id,
+            tokens: estimate_message_tokens(message),
+        })
+        .collect();
+    let total = system_prompt + messages.iter().map(|m| m.tokens).sum::<u64>();
+    ConversationTokens {
+        system_prompt,
+        messages,
+        total,
+    }
+}
+
+/// Reply of the first choice, if its assistant text is non-empty. Reasoning is read
+/// from `reasoning_content` (DeepSeek, vLLM) or `reasoning` (OpenRouter, MLX servers).
+pub fn parse_reply(body: &serde_json::Value) -> Option<ChatReply> {
+    let message = body.pointer("/choices/0/message")?;
+    let text = |field: &str| {
+        let text = message.get(field)?.as_str()?.trim();
+        (!text.is_empty()).then(|| text.to_string())
+    };
+    Some(ChatReply {
+        content: text("content")?,
+        reasoning: text("reasoning_content").or_else(|| text("reasoning")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        count_conversation_tokens, estimate_prompt_tokens, parse_reply, parse_retry_after,
+        provider_error, retry_delay, ChatReply, GenerationParams, MessageTokens, Provider,
+        MAX_STOP_SEQUENCES, MAX_STOP_SEQUENCE_CHARS, RETRY_MAX_DELAY,
+    };
+
+    #[test]
+    fn parse_reply_requires_non_empty_content() {
+        let body = |content: serde_json::Value| serde_json::json!({ "choices": [{ "message": { "role": "assistant", "content": content } }] });
+        let text = |body: serde_json::Value| parse_reply(&body).map(|reply| reply.content);
+        assert_eq!(text(body(" OK\n".into())), Some("OK".to_string()));
+        assert_eq!(text(body("  ".into())), None);
+        assert_eq!(text(body(serde_json::Value::Null)), None);
+        assert_eq!(text(serde_json::json!({ "choices": [] })), None);
+    }
+
+    #[test]
+    fn retry_delay_backs_off_and_honors_retry_after() {
+        use std::time::Duration;
+        assert_eq!(retry_delay(0, None), Duration::from_secs(1));
+        assert_eq!(retry_delay(3, None), Duration::from_secs(8));
+        assert_eq!(retry_delay(30, None), RETRY_MAX_DELAY);
+        assert_eq!(
+            retry_delay(0, Some(Duration::from_secs(7))),
+            Duration::from_secs(7)
+        );
+        assert_eq!(
+            retry_delay(0, Some(Duration::from_secs(3600))),
+            RETRY_MAX_DELAY
+        );
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        assert_eq!(parse_retry_after(&headers), None);
+        headers.insert(reqwest::header::RETRY_AFTER, "12".parse().unwrap());
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(12)));
+        headers.insert(
+            reqwest::header::RETRY_AFTER,
+            "Wed, 21 Oct 2026 07:28:00 GMT".parse().unwrap(),
+        );
+        assert_eq!(parse_retry_after(&headers), None);
+    }
+
+    #[test]
+    fn provider_errors_carry_the_provider_message() {
+        let body = serde_json::json!({ "error": { "message": "Incorrect API key provided" } });
+        assert_eq!(
+            provider_error(reqwest::StatusCode::UNAUTHORIZED, &body),
+            "HTTP 401 Unauthorized: Incorrect API key provided"
+        );
+        assert_eq!(
+            provider_error(reqwest::StatusCode::BAD_GATEWAY, &serde_json::Value::Null),
+            "HTTP 502 Bad Gateway"
+        );
+    }
+
+    #[test]
+    fn parse_reply_keeps_reasoning() {
+        let body = serde_json::json!({ "choices": [{ "message": {
+            "role": "assistant", "content": "4", "reasoning_content": " 2 + 2 = 4 "
+        } }] });
+        assert_eq!(
+            parse_reply(&body),
+            Some(ChatReply {
+                content: "4".to_string(),
+                reasoning: Some("2 + 2 = 4".to_string()),
+            })
+        );
+        let body = serde_json::json!({ "choices": [{ "message": {
+            "role": "assistant", "content": "4", "reasoning": "added"
+        } }] });
+        assert_eq!(
+            parse_reply(&body)
+                .and_then(|reply| reply.reasoning)
+                .as_deref(),
+            Some("added")
+        );
+    }
+
+    #[test]
+    fn estimates_prompt_tokens_per_message() {
+        let messages = [
+            serde_json::json!({ "role": "system", "content": "12345678" }),
+            serde_json::json!({ "role": "user", "content": "abc" }),
+            serde_json::json!({ "role": "tool", "tool_call_id": "1" }),
+        ];
+        // Content of 2, 1 and 0 tokens plus the per-message overhead
+        assert_eq!(estimate_prompt_tokens(&messages), 15);
+        assert_eq!(estimate_prompt_tokens(&[]), 0);
+    }
+
+    #[test]
+    fn counts_conversation_tokens_per_message() {
+        let question = serde_json::json!({ "role": "user", "content": "How are you today?" });
+        let answer = serde_json::json!({ "role": "assistant", "content": "" });
+        let counted = count_conversation_tokens(Some("Be brief."), [(1, &question), (2, &answer)]);
+        assert_eq!(counted.system_prompt, 3 + 4);
+        assert_eq!(
+            counted.messages,
+            vec![
+                MessageTokens {
+                    message_id: 1,
+                    tokens: 5 + 4
+                },
+                MessageTokens {
+                    message_id: 2,
+                    tokens: 4
+                },
+            ]
+        );
+        assert_eq!(counted.total, 7 + 9 + 4);
+        assert_eq!(count_conversation_tokens(None, []).total, 0);
+    }
+
+    #[test]
+    fn validates_and_merges_generation_params() {
+        let params = GenerationParams {
+            temperature: Some(0.2),
+            stop: vec!["###".into()],
+            ..Default::default()
+        };
+        assert!(params.validate().is_ok());
+        let defaults = GenerationParams {
+            temperature: Some(0.7),
+            top_p: Some(0.9),
+            max_tokens: Some(512),
+            stop: Vec::new(),
+        };
+        let merged = params.merged_over(&defaults);
+        assert_eq!(merged.temperature, Some(0.2));
+        assert_eq!(merged.top_p, Some(0.9));
+        assert_eq!(merged.max_tokens, Some(512));
+        assert_eq!(merged.stop, vec!["###".to_string()]);
+
+        let mut body = serde_json::json!({});
+        merged.apply_to(&mut body);
+        assert_eq!(body["stop"], serde_json::json!(["###"]));
+
+        let invalid = [
+            GenerationParams {
+                temperature: Some(2.5),
+                ..Default::default()
+            },
+            GenerationParams {
+                top_p: Some(0.0),
+                ..Default::default()
+            },
+            GenerationParams {
+                max_tokens: Some(0),
+                ..Default::default()
+            },
+            GenerationParams {
+                stop: vec!["a".into(); MAX_STOP_SEQUENCES + 1],
+                ..Default::default()
+            },
+            GenerationParams {
+                stop: vec!["x".repeat(MAX_STOP_SEQUENCE_CHARS + 1)],
+                ..Default::default()
+            },
+            GenerationParams {
+                stop: vec![String::new()],
+                ..Default::default()
+            },
+        ];
+        for params in invalid {
+            assert!(params.validate().is_err(), "{params:?}");
+        }
+    }
+
+    #[test]
+    fn parses_provider_names() {
+        assert_eq!("OpenAI".parse::<Provider>(), Ok(Provider::OpenAi));
+        assert_eq!("mlc".parse::<Provider>(), Ok(Provider::Local));
+        assert!("anthropic".parse::<Provider>().is_err());
+    }
+}
@@ -1,18 +1,42 @@
+use crate::chat_provider::{self, Provider};
+use crate::conversation_export;
+use crate::db;
+use crate::embeddings;
+use crate::log_export;
 use crate::mcp;
 use crate::mcp::constants::{
-    MCP_DEFAULT_CONNECT_TIMEOUT_MS, MCP_DEFAULT_LIST_TOOLS_TIMEOUT_MS,
-    MCP_DEFAULT_TOOL_CALL_TIMEOUT_MS,
+    MCP_DEFAULT_COMPLETION_TIMEOUT_MS, MCP_DEFAULT_CONNECT_TIMEOUT_MS, MCP_DEFAULT_LATENCY_SAMPLES,
+    MCP_DEFAULT_LIST_TOOLS_TIMEOUT_MS, MCP_DEFAULT_MAX_SESSIONS, MCP_DEFAULT_PING_TIMEOUT_MS,
+    MCP_DEFAULT_SET_LOG_LEVEL_TIMEOUT_MS, MCP_DEFAULT_TOOL_CALL_TIMEOUT_MS, MCP_LOG_LEVELS,
+    MCP_MAX_LATENCY_SAMPLES,
 };
 use crate::mcp::serde_utils::merge_auth_header;
 use crate::mcp::session::ensure_mcp_session;
 use crate::mcp::McpManager;
-use crate::mlc_server::{MLCServerManager, MLCServerStatus};
-use crate::model_download::ensure_hf_model_cached;
-use serde::Deserialize;
+use crate::mlc_server::{
+    api_base_url, normalize_log_level, validate_host, validate_max_tokens, MLCServerManager,
+    MLCServerStatus, SamplingDefaults, ServerCommandError,
+};
+use crate::model_card;
+use crate::model_catalog::{self, RecommendedModel};
+use crate::model_download::{self, ensure_hf_model_cached, DownloadRegistry};
+use crate::model_store::{self, app_models_dir, read_model_info, ModelInfo, ModelLocation};
+use crate::models::{self, Role};
+use crate::process_info::{port_occupant, PortOccupant, RunningProcess};
+use crate::safe_mode::SafeMode;
+use crate::secrets;
+use crate::self_test;
+use crate::settings::{self, Setting};
+use crate::telemetry::TelemetryLog;
+use crate::theme::{self, Theme};
+use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
-use tauri::{AppHandle, State};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tauri::{AppHandle, Emitter, Manager, State};
 
 type CmdResult<T> = Result<T, String>;
+/// Result of commands that need a ready model server; see `ServerCommandError`.
+type ServerCmdResult<T> = Result<T, ServerCommandError>;
 
 // MLC Server Management Commands
 
@@ -37,6 +61,955 @@ pub async fn mlc_restart(
     manager.restart().await
 }
 
+/// Whether the MLC server has been HTTP ready at least once this session. Pairs with
+/// the one-time `mlc-first-ready` event for callers that subscribe late.
+#[tauri::command]
+pub async fn mlc_has_been_ready(
+    manager: State<'_, std::sync::Arc<MLCServerManager>>,
+) -> CmdResult<bool> {
+    Ok(manager.has_been_ready())
+}
+
+/// Latest model-load progress of a starting server, or `None` once it is ready or
+/// stopped. Pairs with `mlc-load-progress` events for views that mount mid-load.
+#[tauri::command]
+pub async fn mlc_get_load_progress(
+    manager: State<'_, std::sync::Arc<MLCServerManager>>,
+) -> CmdResult<Option<crate::mlc_server::MlcLoadProgress>> {
+    Ok(manager.load_progress())
+}
+
+/// Returns the persisted MLC server log level, if set.
+#[tauri::command]
+pub async fn mlc_get_log_level(pool: State<'_, SqlitePool>) -> CmdResult<Option<String>> {
+    settings::get_setting::<String>(&pool, Setting::MlcLogLevel).await
+}
+
+/// Validates and persists the MLC server log level (`None` restores the default).
+/// Takes effect the next time the server is started or restarted.
+#[tauri::command]
+pub async fn mlc_set_log_level(
+    level: Option<String>,
+    pool: State<'_, SqlitePool>,
+) -> CmdResult<Option<String>> {
+    let level = level.as_deref().map(normalize_log_level).transpose()?;
+    settings::set_setting(&pool, Setting::MlcLogLevel, level.clone()).await?;
+    Ok(level)
+}
+
+/// Returns the persisted MLC server max tokens, if set.
+#[tauri::command]
+pub async fn mlc_get_max_tokens(pool: State<'_, SqlitePool>) -> CmdResult<Option<u32>> {
+    let value = settings::get_setting::<i64>(&pool, Setting::MlcMaxTokens).await?;
+    Ok(value.and_then(|v| validate_max_tokens(v).ok()))
+}
+
+/// Validates and persists the MLC server max tokens (`None` restores the default).
+/// Takes effect the next time the server is started or restarted.
+#[tauri::command]
+pub async fn mlc_set_max_tokens(
+    max_tokens: Option<i64>,
+    pool: State<'_, SqlitePool>,
+) -> CmdResult<Option<u32>> {
+    let max_tokens = max_tokens.map(validate_max_tokens).transpose()?;
+    settings::set_setting(&pool, Setting::MlcMaxTokens, max_tokens.map(i64::from)).await?;
+    Ok(max_tokens)
+}
+
+/// Returns the persisted MLC server sampling defaults.
+#[tauri::command]
+pub async fn mlc_get_sampling_defaults(pool: State<'_, SqlitePool>) -> CmdResult<SamplingDefaults> {
+    SamplingDefaults::load(&pool).await
+}
+
+/// Validates and persists MLC server sampling defaults; unset fields fall back to the
+/// sidecar default. Takes effect the next time the server is started or restarted.
+#[tauri::command]
+pub async fn mlc_set_sampling_defaults(
+    defaults: SamplingDefaults,
+    pool: State<'_, SqlitePool>,
+) -> CmdResult<SamplingDefaults> {
+    defaults.save(&pool).await?;
+    Ok(defaults)
+}
+
+/// Returns the persisted MLC server bind host, if set (`None` means loopback only).
+#[tauri::command]
+pub async fn mlc_get_host(pool: State<'_, SqlitePool>) -> CmdResult<Option<String>> {
+    settings::get_setting::<String>(&pool, Setting::MlcHost).await
+}
+
+/// Validates and persists the MLC server bind host (`None` restores 127.0.0.1), then
+/// restarts the server. A non-loopback host is reported in the status `warning`.
+#[tauri::command]
+pub async fn mlc_set_host(
+    host: Option<String>,
+    pool: State<'_, SqlitePool>,
+    manager: State<'_, std::sync::Arc<MLCServerManager>>,
+) -> CmdResult<MLCServerStatus> {
+    let host = host.as_deref().map(validate_host).transpose()?;
+    settings::set_setting(&pool, Setting::MlcHost, host).await?;
+    manager.restart().await
+}
+
+/// Returns whether the hung-server watchdog is enabled (off by default).
+#[tauri::command]
+pub async fn mlc_get_watchdog_enabled(pool: State<'_, SqlitePool>) -> CmdResult<bool> {
+    Ok(
+        settings::get_setting::<bool>(&pool, Setting::MlcWatchdogEnabled)
+            .await?
+            .unwrap_or(false),
+    )
+}
+
+/// Persists the watchdog flag and applies it to the running server immediately.
+#[tauri::command]
+pub async fn mlc_set_watchdog_enabled(
+    enabled: bool,
+    pool: State<'_, SqlitePool>,
+    manager: State<'_, std::sync::Arc<MLCServerManager>>,
+) -> CmdResult<()> {
+    settings::set_setting(&pool, Setting::MlcWatchdogEnabled, Some(enabled)).await?;
+    manager.set_watchdog_enabled(enabled).await;
+    Ok(())
+}
+
+/// Marks a chat request to the local server as started, so the watchdog does not probe
+/// (and restart) a server that is busy generating. Returns a lease to pass to
+/// `mlc_request_finished`; a lease that is never finished lapses on its own.
+#[tauri::command]
+pub async fn mlc_request_started(
+    manager: State<'_, std::sync::Arc<MLCServerManager>>,
+) -> CmdResult<u64> {
+    Ok(manager.request_started())
+}
+
+/// Marks the chat request holding `lease` from `mlc_request_started` as finished.
+#[tauri::command]
+pub async fn mlc_request_finished(
+    lease: u64,
+    manager: State<'_, std::sync::Arc<MLCServerManager>>,
+) -> CmdResult<()> {
+    manager.request_finished(lease);
+    Ok(())
+}
+
+/// Returns whether leftover `openchat-mlx-server` processes are killed before the
+/// server starts (off by default).
+#[tauri::command]
+pub async fn mlc_get_kill_orphans(pool: State<'_, SqlitePool>) -> CmdResult<bool> {
+    Ok(
+        settings::get_setting::<bool>(&pool, Setting::MlcKillOrphans)
+            .await?
+            .unwrap_or(false),
+    )
+}
+
+/// Persists the orphan cleanup flag; applies to the next server start.
+#[tauri::command]
+pub async fn mlc_set_kill_orphans(enabled: bool, pool: State<'_, SqlitePool>) -> CmdResult<()> {
+    settings::set_setting(&pool, Setting::MlcKillOrphans, Some(enabled)).await
+}
+
+/// Lists `openchat-mlx-server` processes from earlier runs, excluding the server this
+/// app is currently managing.
+#[tauri::command]
+pub async fn find_orphaned_servers(
+    manager: State<'_, std::sync::Arc<MLCServerManager>>,
+) -> CmdResult<Vec<RunningProcess>> {
+    manager.find_orphaned_servers().await
+}
+
+/// Kills the processes `find_orphaned_servers` reports and returns them.
+#[tauri::command]
+pub async fn kill_orphaned_servers(
+    manager: State<'_, std::sync::Arc<MLCServerManager>>,
+) -> CmdResult<Vec<RunningProcess>> {
+    manager.kill_orphaned_servers().await
+}
+
+/// Proxies an admin request (`GET /v1/stats`, `POST /v1/admin/...`) to the managed
+/// server and returns its JSON. Only allowlisted paths and methods are accepted.
+#[tauri::command]
+pub async fn mlc_admin_request(
+    method: String,
+    path: String,
+    body: Option<serde_json::Value>,
+    manager: State<'_, std::sync::Arc<MLCServerManager>>,
+) -> ServerCmdResult<serde_json::Value> {
+    manager.admin_request(&method, &path, body).await
+}
+
+/// Returns the process listening on `port`, if one can be identified.
+#[tauri::command]
+pub async fn diagnose_port(port: u16) -> CmdResult<Option<PortOccupant>> {
+    tauri::async_runtime::spawn_blocking(move || port_occupant(port))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// ------------------ System Health ------------------
+
+/// Aggregated status for the status bar: MLC server, MCP sessions, and downloads.
+#[derive(Serialize)]
+pub struct SystemHealth {
+    pub mlc: MLCServerStatus,
+    pub mcp_sessions: Vec<mcp::McpSessionHealth>,
+    pub downloads_in_progress: bool,
+    pub active_downloads: Vec<String>,
+}
+
+/// Returns MLC, MCP, and download status in one call. MCP liveness comes from cached
+/// session state unless `probe` is set, in which case each live session is pinged.
+/// Enabled servers without a session are reported as disconnected.
+#[tauri::command]
+pub async fn get_system_health(
+    probe: Option<bool>,
+    mlc_manager: State<'_, std::sync::Arc<MLCServerManager>>,
+    mcp_manager: State<'_, std::sync::Arc<McpManager>>,
+    downloads: State<'_, std::sync::Arc<DownloadRegistry>>,
+    pool: State<'_, SqlitePool>,
+) -> CmdResult<SystemHealth> {
+    let mut mcp_sessions = mcp_manager
+        .session_health(probe.unwrap_or(false), MCP_DEFAULT_PING_TIMEOUT_MS)
+        .await;
+    for id in mcp::store::list_enabled_mcp_server_ids(&pool).await? {
+        if !mcp_sessions.iter().any(|s| s.id == id) {
+            mcp_sessions.push(mcp::McpSessionHealth {
+                id,
+                connected: false,
+                alive: false,
+            });
+        }
+    }
+    mcp_sessions.sort_by_key(|s| s.id);
+
+    let active_downloads = downloads.active();
+    Ok(SystemHealth {
+        mlc: mlc_manager.get_status().await,
+        mcp_sessions,
+        downloads_in_progress: !active_downloads.is_empty(),
+        active_downloads,
+    })
+}
+
+/// Smoke-tests the whole setup: makes sure the MLC server is ready (starting it and
+/// waiting for the model to load if needed), sends a trivial prompt and checks for a
+/// non-empty reply, and with `include_mcp` pings every enabled MCP server.
+#[tauri::command]
+pub async fn self_test(
+    include_mcp: Option<bool>,
+    mlc_manager: State<'_, std::sync::Arc<MLCServerManager>>,
+    mcp_manager: State<'_, std::sync::Arc<McpManager>>,
+    pool: State<'_, SqlitePool>,
+) -> CmdResult<self_test::SelfTestReport> {
+    let mut report = self_test::SelfTestReport::default();
+
+    let server = report
+        .run(
+            "server_ready",
+            wait_for_server_ready(&mlc_manager),
+            |addr: &std::net::SocketAddr| Some(api_base_url(*addr)),
+        )
+        .await;
+    if let Some(addr) = server {
+        let base_url = api_base_url(addr);
+        let model = report
+            .run(
+                "model",
+                async {
+                    match settings::get_setting::<String>(&pool, Setting::Model).await {
+                        Ok(Some(model)) if !model.trim().is_empty() => Ok(model),
+                        _ => self_test::first_served_model(addr).await,
+                    }
+                },
+                |model: &String| Some(model.clone()),
+            )
+            .await;
+        if let Some(model) = model {
+            report
+                .run(
+                    "chat_round_trip",
+                    self_test::chat_round_trip(&base_url, &model),
+                    |reply: &String| Some(reply.clone()),
+                )
+                .await;
+        }
+    }
+
+    if include_mcp.unwrap_or(false) {
+        for id in mcp::store::list_enabled_mcp_server_ids(&pool).await? {
+            report
+                .run(
+                    format!("mcp_ping:{id}"),
+                    async {
+                        ensure_session_for_id(id, &mcp_manager, &pool).await?;
+                        mcp_manager.ping(id, MCP_DEFAULT_PING_TIMEOUT_MS).await
+                    },
+                    |_| None,
+                )
+                .await;
+        }
+    }
+    Ok(report.finish())
+}
+
+/// Returns the server's API base URL, starting it first if it isn't running and then
+/// waiting up to `SELF_TEST_READY_TIMEOUT` for it to become ready.
+async fn wait_for_server_ready(
+    manager: &std::sync::Arc<MLCServerManager>,
+) -> Result<std::net::SocketAddr, String> {
+    if let Ok(addr) = manager.ready_addr().await {
+        return Ok(addr);
+    }
+    if !manager.get_status().await.is_running {
+        manager.start().await?;
+    }
+    let deadline = std::time::Instant::now() + self_test::SELF_TEST_READY_TIMEOUT;
+    loop {
+        if let Ok(addr) = manager.ready_addr().await {
+            return Ok(addr);
+        }
+        let status = manager.get_status().await;
+        if !status.is_running {
+            return Err(status
+                .error
+                .unwrap_or_else(|| "the model server stopped while starting".to_string()));
+        }
+        if std::time::Instant::now() >= deadline {
+            return Err(format!(
+                "the model server did not become ready within {}s",
+                self_test::SELF_TEST_READY_TIMEOUT.as_secs()
+            ));
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    }
+}
+
+// ------------------ Conversation Commands ------------------
+
+/// Forks a conversation at `from_message_id` into a new conversation and returns it.
+/// When `new_title` is omitted the original title is reused.
+#[tauri::command]
+pub async fn branch_conversation(
+    conversation_id: i64,
+    from_message_id: i64,
+    new_title: Option<String>,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<db::Conversation> {
+    db::branch_conversation(
+        &pool,
+        conversation_id,
+        from_message_id,
+        new_title.as_deref(),
+    )
+    .await
+}
+
+/// Writes a conversation to `path` as `markdown` or `json`, streaming messages from
+/// the database in pages instead of building the export in memory. Returns the number
+/// of bytes written.
+#[tauri::command]
+pub async fn export_conversation_to_file(
+    id: i64,
+    path: String,
+    format: String,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<u64> {
+    let format: conversation_export::ExportFormat = format.parse()?;
+    conversation_export::export_conversation_to_file(&pool, id, std::path::Path::new(&path), format)
+        .await
+}
+
+/// Sets a message's lifecycle status and emits `message-status-changed`.
+#[tauri::command]
+pub async fn set_message_status(
+    id: i64,
+    status: String,
+    app: AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<()> {
+    let status: db::MessageStatus = status.parse()?;
+    let change = db::set_message_status(&pool, id, status).await?;
+    let _ = app.emit(db::MESSAGE_STATUS_CHANGED_EVENT, change);
+    Ok(())
+}
+
+/// Loads a message with `before`/`after` neighbours from its conversation (each capped
+/// at 100) so a search hit can be shown in context. The target has `is_target` set.
+#[tauri::command]
+pub async fn get_message_context(
+    message_id: i64,
+    before: u32,
+    after: u32,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<Vec<db::ContextMessage>> {
+    db::message_context(&pool, message_id, before, after).await
+}
+
+/// Computes and stores the embedding of one message. Fails with `service_unavailable`
+/// until the model server is ready, or if its model doesn't support embeddings.
+#[tauri::command]
+pub async fn embed_message(
+    message_id: i64,
+    pool: tauri::State<'_, SqlitePool>,
+    mlc: State<'_, std::sync::Arc<MLCServerManager>>,
+) -> ServerCmdResult<()> {
+    let embedded = embed_messages_by_id(&pool, &mlc, &[message_id]).await?;
+    if embedded == 0 {
+        return Err("message not found".into());
+    }
+    Ok(())
+}
+
+/// Batch variant of `embed_message`; returns how many messages were embedded.
+/// Messages edited while their batch was being embedded are skipped.
+#[tauri::command]
+pub async fn embed_messages(
+    message_ids: Vec<i64>,
+    pool: tauri::State<'_, SqlitePool>,
+    mlc: State<'_, std::sync::Arc<MLCServerManager>>,
+) -> ServerCmdResult<usize> {
+    embed_messages_by_id(&pool, &mlc, &message_ids).await
+}
+
+async fn embed_messages_by_id(
+    pool: &SqlitePool,
+    mlc: &MLCServerManager,
+    ids: &[i64],
+) -> ServerCmdResult<usize> {
+    let base_url = mlc.require_ready().await?;
+    let model = embedding_model(pool, mlc).await?;
+    let messages = db::message_contents(pool, ids).await?;
+    let mut embedded = 0;
+    for batch in messages.chunks(embeddings::EMBEDDING_BATCH_SIZE) {
+        let inputs: Vec<String> = batch.iter().map(|(_, content)| content.clone()).collect();
+        let vectors = embeddings::embed_texts(&base_url, &model, &inputs).await?;
+        for ((id, content), vector) in batch.iter().zip(vectors) {
+            let encoded = embeddings::encode_vector(&vector);
+            if db::set_message_embedding(pool, *id, content, &model, &encoded).await? {
+                embedded += 1;
+            }
+        }
+    }
+    Ok(embedded)
+}
+
+/// Model named in embeddings requests: the configured model, or the one the server
+/// reports serving when none is configured.
+async fn embedding_model(pool: &SqlitePool, mlc: &MLCServerManager) -> ServerCmdResult<String> {
+    let configured = settings::get_setting::<String>(pool, Setting::Model)
+        .await?
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty());
+    match configured {
+        Some(model) => Ok(model),
+        None => mlc
+            .get_status()
+            .await
+            .loaded_model
+            .ok_or_else(|| "No model is configured for embeddings".into()),
+    }
+}
+
+/// Embeds `query` and returns the `k` embedded messages most similar to it by cosine
+/// similarity. Only messages embedded via `embed_message(s)` with the current model,
+/// and not edited since, are searched.
+#[tauri::command]
+pub async fn semantic_search(
+    query: String,
+    k: usize,
+    pool: tauri::State<'_, SqlitePool>,
+    mlc: State<'_, std::sync::Arc<MLCServerManager>>,
+) -> ServerCmdResult<Vec<embeddings::SemanticHit>> {
+    if query.trim().is_empty() {
+        return Err("Search query is empty".into());
+    }
+    let base_url = mlc.require_ready().await?;
+    let model = embedding_model(&pool, &mlc).await?;
+    let query_vector = embeddings::embed_texts(&base_url, &model, &[query])
+        .await?
+        .pop()
+        .ok_or("embeddings response was empty")?;
+    let rows = db::message_embeddings(&pool, &model).await?;
+    Ok(embeddings::top_k(&query_vector, rows, k))
+}
+
+static NEXT_SEARCH_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Starts a full-text message search and returns its id immediately. Matches arrive,
+/// newest first, as `search-result` events one page at a time, followed by a single
+/// `search-done`. At most `max_results` matches are sent (default 500, max 5000).
+#[tauri::command]
+pub async fn search_messages_stream(
+    query: String,
+    max_results: Option<u32>,
+    app: AppHandle,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<u64> {
+    let max_results = max_results.unwrap_or(db::SEARCH_DEFAULT_MAX_RESULTS);
+    if max_results == 0 || max_results > db::SEARCH_MAX_RESULTS_LIMIT {
+        return Err(format!(
+            "max_results must be between 1 and {}",
+            db::SEARCH_MAX_RESULTS_LIMIT
+        ));
+    }
+    let fts_query = db::fts_prefix_query(&query).ok_or("Search query is empty")?;
+    let search_id = NEXT_SEARCH_ID.fetch_add(1, Ordering::Relaxed);
+    let pool = pool.inner().clone();
+    tauri::async_runtime::spawn(async move {
+        let done = stream_search_results(&app, &pool, search_id, &fts_query, max_results).await;
+        let _ = app.emit(db::SEARCH_DONE_EVENT, done);
+    });
+    Ok(search_id)
+}
+
+/// Emits pages of matches until the results or the cap run out.
+async fn stream_search_results(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    search_id: u64,
+    fts_query: &str,
+    max_results: u32,
+) -> db::SearchDone {
+    let mut total = 0;
+    let mut before_id = None;
+    let mut error = None;
+    while total < max_results {
+        let limit = db::SEARCH_PAGE_SIZE.min(max_results - total);
+        let hits = match db::search_messages_page(pool, fts_query, before_id, limit).await {
+            Ok(hits) => hits,
+            Err(e) => {
+                error = Some(e);
+                break;
+            }
+        };
+        let page_len = hits.len() as u32;
+        total += page_len;
+        before_id = hits.last().map(|h| h.id);
+        if page_len > 0 {
+            let _ = app.emit(
+                db::SEARCH_RESULT_EVENT,
+                db::SearchResultPage { search_id, hits },
+            );
+        }
+        if page_len < limit {
+            break;
+        }
+    }
+    db::SearchDone {
+        search_id,
+        total,
+        truncated: error.is_none() && total >= max_results,
+        error,
+    }
+}
+
+/// Returns id, title, updated_at, message count, and a last-message preview for every
+/// conversation so the sidebar can render without per-conversation queries.
+#[tauri::command]
+pub async fn get_conversation_summaries(
+    preview_chars: Option<u32>,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<Vec<db::ConversationSummary>> {
+    db::list_conversation_summaries(
+        &pool,
+        preview_chars.unwrap_or(db::CONVERSATION_PREVIEW_CHARS),
+    )
+    .await
+}
+
+/// Appends a message to a conversation and returns its id. `role` must be one of
+/// `user`, `assistant`, `system` or `tool`; `reasoning` is only accepted on
+/// assistant messages. `metadata`, if given, must be a JSON object (attachments,
+/// provider, model used, ...).
+#[tauri::command]
+pub async fn add_message(
+    conversation_id: i64,
+    role: String,
+    content: String,
+    reasoning: Option<String>,
+    metadata: Option<serde_json::Value>,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<i64> {
+    let role: Role = role.parse()?;
+    db::add_message(
+        &pool,
+        conversation_id,
+        role,
+        &content,
+        reasoning.as_deref(),
+        metadata.as_ref(),
+    )
+    .await
+}
+
+/// Re-runs the conversation's last user message through `provider` (`local` or
+/// `openai`) with `model`, replaces the reply that followed it, and records which
+/// provider and model produced the new one in its metadata. The conversation's
+/// default provider is left alone. Returns the new message id.
+///
+/// The new reply is stored as `pending` first, with `message-status-changed` events
+/// as it moves on. It is marked `error` if generation fails, and the old reply is
+/// kept. The old reply is deleted only once the new one completes.
+#[tauri::command]
+pub async fn regenerate_with(
+    conversation_id: i64,
+    provider: String,
+    model: String,
+    app: AppHandle,
+    mlc_manager: State<'_, std::sync::Arc<MLCServerManager>>,
+    pool: State<'_, SqlitePool>,
+) -> ServerCmdResult<i64> {
+    let provider: Provider = provider.parse()?;
+    if model.trim().is_empty() {
+        return Err("model must not be empty".to_string().into());
+    }
+    let turn = db::last_turn(&pool, conversation_id).await?;
+    let params = db::effective_generation_params(&pool, conversation_id).await?;
+    let omitted = turn.history.omitted;
+    let mut messages = turn.history.messages;
+    if let Some(prompt) = db::effective_system_prompt(&pool, conversation_id).await? {
+        messages.insert(
+            0,
+            serde_json::json!({ "role": "system", "content": prompt }),
+        );
+    }
+
+    let mut metadata = serde_json::json!({ "provider": provider.as_str(), "model": model });
+    if omitted > 0 {
+        metadata["omitted_history_messages"] = omitted.into();
+    }
+    let pending = db::insert_pending_reply(&pool, conversation_id, &metadata).await?;
+    let reply_id = pending.id;
+    let _ = app.emit(db::MESSAGE_STATUS_CHANGED_EVENT, pending);
+
+    let reply = match provider {
+        Provider::Local => match mlc_manager.require_ready().await {
+            Ok(base_url) => chat_provider::complete_logged(
+                &app, provider, &base_url, None, &model, &messages, &params,
+            )
+            .await
+            .map_err(Into::into),
+            Err(e) => Err(e),
+        },
+        Provider::OpenAi => match secrets::get_secret(&pool, secrets::OPENAI_API_KEY).await {
+            Ok(Some(key)) => chat_provider::complete_logged(
+                &app,
+                provider,
+                chat_provider::OPENAI_BASE_URL,
+                Some(&key),
+                &model,
+                &messages,
+                &params,
+            )
+            .await
+            .map_err(Into::into),
+            Ok(None) => Err("no OpenAI API key is configured".into()),
+            Err(e) => Err(e.into()),
+        },
+    };
+    let change = match reply {
+        Ok(reply) => {
+            db::replace_reply(
+                &pool,
+                reply_id,
+                &reply.content,
+                reply.reasoning.as_deref(),
+                &turn.reply_message_ids,
+            )
+            .await
+        }
+        Err(e) => {
+            if let Ok(change) =
+                db::set_message_status(&pool, reply_id, db::MessageStatus::Error).await
+            {
+                let _ = app.emit(db::MESSAGE_STATUS_CHANGED_EVENT, change);
+            }
+            return Err(e);
+        }
+    }?;
+    let _ = app.emit(db::MESSAGE_STATUS_CHANGED_EVENT, change);
+    Ok(reply_id)
+}
+
+/// Stores a tool-call result as a `tool` message linked to the assistant message that
+/// requested it, with the call's `arguments` so the call can be replayed. Returns the
+/// new message id.
+#[tauri::command]
+pub async fn append_tool_result(
+    assistant_message_id: i64,
+    tool_call_id: String,
+    tool_name: String,
+    arguments: Option<serde_json::Value>,
+    content: String,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<i64> {
+    db::append_tool_result(
+        &pool,
+        assistant_message_id,
+        &tool_call_id,
+        &tool_name,
+        arguments.as_ref(),
+        &content,
+    )
+    .await
+}
+
+/// Returns a conversation's messages in the chat API shape, including tool results,
+/// ready to send as the next request's `messages`. Only the newest messages allowed by
+/// the history limit are included.
+#[tauri::command]
+pub async fn get_provider_messages(
+    conversation_id: i64,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<Vec<serde_json::Value>> {
+    Ok(db::provider_history(&pool, conversation_id).await?.messages)
+}
+
+/// Like `get_provider_messages`, but also reports the ids of the included messages and
+/// how many older ones the history limit left out, so the chat view can send the same
+/// messages and show that older ones were not sent.
+#[tauri::command]
+pub async fn get_provider_history(
+    conversation_id: i64,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<db::ProviderHistory> {
+    db::provider_history(&pool, conversation_id).await
+}
+
+/// Estimates the tokens a conversation consumes: its effective system prompt and each
+/// stored message, plus the total. Uses a characters-per-token estimate that suits
+/// both the local models and the cloud provider, since no tokenizer is bundled.
+#[tauri::command]
+pub async fn count_conversation_tokens(
+    conversation_id: i64,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<chat_provider::ConversationTokens> {
+    let history = db::full_provider_history(&pool, conversation_id).await?;
+    let system_prompt = db::effective_system_prompt(&pool, conversation_id).await?;
+    Ok(chat_provider::count_conversation_tokens(
+        system_prompt.as_deref(),
+        history
+            .included_message_ids
+            .iter()
+            .copied()
+            .zip(&history.messages),
+    ))
+}
+
+/// Returns the conversation's own history limit (`None` = uses the global limit).
+#[tauri::command]
+pub async fn get_conversation_max_history(
+    conversation_id: i64,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<Option<i64>> {
+    db::get_conversation_max_history(&pool, conversation_id).await
+}
+
+/// Sets how many stored messages are sent per request for the conversation. `None`
+/// reverts to the global limit; `0` sends the whole history.
+#[tauri::command]
+pub async fn set_conversation_max_history(
+    conversation_id: i64,
+    max: Option<i64>,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<()> {
+    db::set_conversation_max_history(&pool, conversation_id, max).await
+}
+
+/// Returns the conversation's own temperature, top_p, max tokens and stop sequences
+/// (`None` = uses the global defaults).
+#[tauri::command]
+pub async fn get_conversation_generation_params(
+    conversation_id: i64,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<Option<chat_provider::GenerationParams>> {
+    db::get_conversation_generation_params(&pool, conversation_id).await
+}
+
+/// Returns the generation params a request for the conversation uses: its own, with
+/// unset fields taken from the global defaults.
+#[tauri::command]
+pub async fn get_effective_generation_params(
+    conversation_id: i64,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<chat_provider::GenerationParams> {
+    db::effective_generation_params(&pool, conversation_id).await
+}
+
+/// Validates and persists the conversation's generation params; unset fields fall
+/// back to the global defaults at request time. `None` clears them.
+#[tauri::command]
+pub async fn set_conversation_generation_params(
+    conversation_id: i64,
+    params: Option<chat_provider::GenerationParams>,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<()> {
+    db::set_conversation_generation_params(&pool, conversation_id, params.as_ref()).await
+}
+
+/// Returns the global history limit; `None` or `0` means no limit.
+#[tauri::command]
+pub async fn get_max_history_messages(pool: State<'_, SqlitePool>) -> CmdResult<Option<i64>> {
+    settings::get_setting::<i64>(&pool, Setting::MaxHistoryMessages).await
+}
+
+/// Sets the global history limit used by conversations without their own.
+#[tauri::command]
+pub async fn set_max_history_messages(
+    max: Option<i64>,
+    pool: State<'_, SqlitePool>,
+) -> CmdResult<()> {
+    if let Some(max) = max {
+        models::validate_max_history(max)?;
+    }
+    settings::set_setting(&pool, Setting::MaxHistoryMessages, max).await
+}
+
+/// Returns the conversation's own system prompt (`None` = uses the global default).
+#[tauri::command]
+pub async fn get_conversation_system_prompt(
+    conversation_id: i64,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<Option<String>> {
+    db::get_conversation_system_prompt(&pool, conversation_id).await
+}
+
+/// Sets the conversation's system prompt. `None` reverts to the global default; an
+/// empty string sends no system message for this conversation.
+#[tauri::command]
+pub async fn set_conversation_system_prompt(
+    conversation_id: i64,
+    prompt: Option<String>,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<()> {
+    db::set_conversation_system_prompt(&pool, conversation_id, prompt.as_deref()).await
+}
+
+/// Resolves the system prompt to prepend for a conversation, or `None` for no
+/// system message.
+#[tauri::command]
+pub async fn get_effective_system_prompt(
+    conversation_id: i64,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<Option<String>> {
+    db::effective_system_prompt(&pool, conversation_id).await
+}
+
+// ------------------ Database Maintenance Commands ------------------
+
+/// Optimizes FTS indexes and vacuums the database; returns its size before and after.
+#[tauri::command]
+pub async fn optimize_database(
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<db::OptimizeReport> {
+    db::optimize_database(&pool).await
+}
+
+/// Reports applied and pending migrations and whether a pending one would fail
+/// against the current schema (e.g. tables created by an older build).
+#[tauri::command]
+pub async fn check_database_schema(
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<db::SchemaCheck> {
+    db::check_schema(&pool).await.map_err(|e| e.to_string())
+}
+
+// ------------------ App Reset Commands ------------------
+
+/// What `reset_app_state` should clear. Everything defaults to off.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct ResetOptions {
+    pub stop_server: bool,
+    pub disconnect_mcp: bool,
+    pub clear_settings: bool,
+    pub delete_conversations: bool,
+    /// Must also be set for `delete_conversations` to take effect.
+    pub confirm_delete_conversations: bool,
+    /// Models to remove from the cache (including partial downloads). The model
+    /// cache is otherwise left alone.
+    pub remove_models: Vec<String>,
+}
+
+/// What `reset_app_state` actually cleared.
+#[derive(Serialize, Default)]
+pub struct ResetSummary {
+    pub server_stopped: bool,
+    pub mcp_sessions_closed: Vec<i64>,
+    pub settings_cleared: bool,
+    pub conversations_deleted: u64,
+    pub models_removed: Vec<String>,
+}
+
+/// Scoped troubleshooting reset. Steps run in a fixed order and stop at the first
+/// failure; deleting conversations requires `confirm_delete_conversations`. Model ids
+/// are validated before anything is touched. Clearing settings re-applies the
+/// defaults to the running server config, the MCP manager and the theme.
+#[tauri::command]
+pub async fn reset_app_state(
+    options: ResetOptions,
+    app: AppHandle,
+    pool: State<'_, SqlitePool>,
+    mlc: State<'_, std::sync::Arc<MLCServerManager>>,
+    mcp: State<'_, std::sync::Arc<McpManager>>,
+) -> CmdResult<ResetSummary> {
+    if options.delete_conversations && !options.confirm_delete_conversations {
+        return Err("Deleting conversations requires confirm_delete_conversations".to_string());
+    }
+    for repo_id in &options.remove_models {
+        model_store::validate_repo_id(repo_id)?;
+    }
+    let mut summary = ResetSummary::default();
+
+    if options.stop_server {
+        summary.server_stopped = mlc.get_status().await.is_running;
+        mlc.stop().await?;
+    }
+    if options.disconnect_mcp {
+        summary.mcp_sessions_closed = mcp.drop_all_sessions().await;
+    }
+    if options.clear_settings {
+        db::reset_app_settings(&pool).await?;
+        model_download::set_bandwidth_limit(None);
+        // Keep a moved model cache; the models still live there
+        let hub = model_store::hub_dir();
+        if hub.source == model_store::HubDirSource::Override {
+            settings::set_setting(
+                &pool,
+                Setting::ModelCacheDir,
+                Some(hub.path.to_string_lossy().into_owned()),
+            )
+            .await?;
+        }
+        crate::telemetry::load_enabled(&app).await;
+        mlc.load_config_from_settings().await;
+        mcp.set_debug(false);
+        mcp.set_max_sessions(MCP_DEFAULT_MAX_SESSIONS);
+        theme::load_theme(&app).await;
+        summary.settings_cleared = true;
+    }
+    if options.delete_conversations {
+        summary.conversations_deleted = db::delete_all_conversations(&pool).await?;
+    }
+    for repo_id in options.remove_models {
+        let aborted = model_download::abort_and_remove_download(&app, &repo_id).await?;
+        let final_dir = model_store::model_cache_dir(&repo_id);
+        let had_cache = final_dir.exists();
+        if had_cache {
+            tauri::async_runtime::spawn_blocking(move || std::fs::remove_dir_all(&final_dir))
+                .await
+                .map_err(|e| format!("join error: {e}"))?
+                .map_err(|e| format!("Failed to remove {repo_id} from the model cache: {e}"))?;
+        }
+        if aborted || had_cache {
+            summary.models_removed.push(repo_id);
+        }
+    }
+    Ok(summary)
+}
+
 // ------------------ MCP check command ------------------
 
 #[allow(dead_code)]
@@ -53,6 +1026,8 @@ pub enum McpServerConfig {
         command: String,
         args: Option<Vec<String>>,
         env: Option<serde_json::Value>,
+        #[serde(default)]
+        env_file: Option<String>,
         cwd: Option<String>,
     },
     #[serde(rename = "http")]
@@ -78,6 +1053,7 @@ pub async fn mcp_check_server(config: McpServerConfig) -> CmdResult<McpCheckResu
             command,
             args,
             env,
+            env_file,
             cwd,
             connect_timeout_ms,
             list_tools_timeout_ms,
@@ -88,6 +1064,7 @@ pub async fn mcp_check_server(config: McpServerConfig) -> CmdResult<McpCheckResu
                 command: &command,
                 args: &args_vec,
                 env: env.as_ref(),
+                env_file: env_file.as_deref(),
                 cwd: cwd.as_deref(),
                 connect_timeout_ms: connect_timeout_ms.unwrap_or(MCP_DEFAULT_CONNECT_TIMEOUT_MS),
                 list_tools_timeout_ms: list_tools_timeout_ms
@@ -119,6 +1096,12 @@ pub async fn mcp_check_server(config: McpServerConfig) -> CmdResult<McpCheckResu
     Ok(result)
 }
 
+/// Lists the supported MCP transports with the fields each one accepts.
+#[tauri::command]
+pub async fn mcp_transport_schemas() -> CmdResult<Vec<mcp::transports::McpTransportSchema>> {
+    Ok(mcp::transports::transport_schemas())
+}
+
 // ------------------ MCP list/call commands ------------------
 
 #[tauri::command]
@@ -134,37 +1117,774 @@ pub async fn mcp_list_tools(
         .await
 }
 
+/// Lists the tools of every enabled server as one flat list, each annotated with its
+/// server. Servers that cannot be reached are reported in `failed`.
+#[tauri::command]
+pub async fn mcp_list_all_tools(
+    manager: tauri::State<'_, std::sync::Arc<McpManager>>,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<mcp::McpToolCatalog> {
+    mcp::session::list_all_mcp_tools(&manager, &pool).await
+}
+
+/// Calls `tool` on server `id`. With `validate`, `args` are first checked against the
+/// tool's cached `inputSchema` and rejected client-side with a precise error.
 #[tauri::command]
 pub async fn mcp_call_tool(
     id: i64,
     tool: String,
     args: serde_json::Value,
+    validate: Option<bool>,
     manager: tauri::State<'_, std::sync::Arc<McpManager>>,
     pool: tauri::State<'_, SqlitePool>,
 ) -> CmdResult<String> {
     ensure_session_for_id(id, &manager, &pool).await?;
+    if validate.unwrap_or(false) {
+        manager
+            .validate_tool_args(id, &tool, &args, MCP_DEFAULT_LIST_TOOLS_TIMEOUT_MS)
+            .await?;
+    }
     // Default timeout for calling a tool
     manager
         .call_tool(id, &tool, args, MCP_DEFAULT_TOOL_CALL_TIMEOUT_MS)
         .await
 }
 
+/// Calls a tool by its `"serverName.toolName"` name, so callers need not track server
+/// ids. Fails if the name is ambiguous or the server is disabled.
+#[tauri::command]
+pub async fn mcp_call_by_qualified_name(
+    name: String,
+    args: serde_json::Value,
+    manager: tauri::State<'_, std::sync::Arc<McpManager>>,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<String> {
+    let (id, tool) = mcp::session::resolve_qualified_tool_name(&pool, &name).await?;
+    ensure_session_for_id(id, &manager, &pool).await?;
+    manager
+        .call_tool(id, &tool, args, MCP_DEFAULT_TOOL_CALL_TIMEOUT_MS)
+        .await
+}
+
+/// Suggests values for `argument` of a prompt or resource on server `id` via
+/// `completion/complete`. Empty when the server does not support completions.
+#[tauri::command]
+pub async fn mcp_complete(
+    id: i64,
+    reference: mcp::McpCompletionRef,
+    argument: mcp::McpCompletionArgument,
+    manager: tauri::State<'_, std::sync::Arc<McpManager>>,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<mcp::McpCompletion> {
+    ensure_session_for_id(id, &manager, &pool).await?;
+    manager
+        .complete_argument(id, &reference, &argument, MCP_DEFAULT_COMPLETION_TIMEOUT_MS)
+        .await
+}
+
+/// Drops the cached session for `id` and reconnects using the latest env/headers from
+/// the DB, respawning stdio servers. Returns the refreshed tool count.
+#[tauri::command]
+pub async fn mcp_refresh_credentials(
+    id: i64,
+    manager: tauri::State<'_, std::sync::Arc<McpManager>>,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<usize> {
+    manager.drop_session(id).await;
+    ensure_session_for_id(id, &manager, &pool).await?;
+    let tools = manager
+        .list_tools(id, MCP_DEFAULT_LIST_TOOLS_TIMEOUT_MS)
+        .await?;
+    Ok(tools.len())
+}
+
+/// Disconnects every MCP session (killing stdio children) and reconnects all enabled
+/// servers concurrently. Returns a per-server success/failure report.
+#[tauri::command]
+pub async fn mcp_reconnect_all(
+    manager: tauri::State<'_, std::sync::Arc<McpManager>>,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<Vec<mcp::McpReconnectResult>> {
+    mcp::session::reconnect_all_mcp_sessions(&manager, &pool).await
+}
+
+/// Pings cached sessions and reconnects enabled servers whose session died, e.g. while
+/// the machine slept. Call when the app becomes visible again; returns a result per
+/// reconnected server (empty when every session is healthy).
+#[tauri::command]
+pub async fn mcp_reconnect_stale(
+    manager: tauri::State<'_, std::sync::Arc<McpManager>>,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<Vec<mcp::McpReconnectResult>> {
+    mcp::session::reconnect_stale_mcp_sessions(&manager, &pool).await
+}
+
+/// Disconnects cached sessions whose server row is disabled or deleted. Call after
+/// changing MCP server settings; returns the ids that were disconnected.
+#[tauri::command]
+pub async fn mcp_disconnect_disabled(
+    manager: tauri::State<'_, std::sync::Arc<McpManager>>,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<Vec<i64>> {
+    mcp::session::disconnect_disabled_mcp_sessions(&manager, &pool).await
+}
+
+/// Returns which features server `id` negotiated at `initialize` (tools, resources,
+/// prompts, logging, completions) and its protocol version, connecting first if needed.
+/// Lets the UI hide actions a server does not support.
+#[tauri::command]
+pub async fn mcp_capabilities(
+    id: i64,
+    manager: tauri::State<'_, std::sync::Arc<McpManager>>,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<mcp::McpCapabilities> {
+    ensure_session_for_id(id, &manager, &pool).await?;
+    manager.capabilities(id).await
+}
+
+/// Asks server `id` to send `mcp-log` notifications at `level` (one of the
+/// `logging/setLevel` levels, e.g. `debug` or `warning`) and above.
+#[tauri::command]
+pub async fn mcp_set_log_level(
+    id: i64,
+    level: String,
+    manager: tauri::State<'_, std::sync::Arc<McpManager>>,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<()> {
+    if !MCP_LOG_LEVELS.contains(&level.as_str()) {
+        return Err(format!(
+            "invalid log level '{level}': expected one of {}",
+            MCP_LOG_LEVELS.join(", ")
+        ));
+    }
+    ensure_session_for_id(id, &manager, &pool).await?;
+    manager
+        .set_log_level(id, &level, MCP_DEFAULT_SET_LOG_LEVEL_TIMEOUT_MS)
+        .await
+}
+
+/// Returns the last error recorded for server `id` (connect, list or call), or `None`
+/// if its most recent operation succeeded.
+#[tauri::command]
+pub async fn mcp_last_error(
+    id: i64,
+    manager: tauri::State<'_, std::sync::Arc<McpManager>>,
+) -> CmdResult<Option<mcp::McpServerError>> {
+    Ok(manager.last_error(id))
+}
+
+/// Times `samples` (default 5, at most 50) lightweight round trips to server `id`
+/// over its cached session and reports min/median/max latency.
+#[tauri::command]
+pub async fn mcp_measure_latency(
+    id: i64,
+    samples: Option<u32>,
+    manager: tauri::State<'_, std::sync::Arc<McpManager>>,
+    pool: tauri::State<'_, SqlitePool>,
+) -> CmdResult<mcp::McpLatency> {
+    let samples = samples.unwrap_or(MCP_DEFAULT_LATENCY_SAMPLES);
+    if samples == 0 || samples > MCP_MAX_LATENCY_SAMPLES {
+        return Err(format!(
+            "samples must be between 1 and {MCP_MAX_LATENCY_SAMPLES}"
+        ));
+    }
+    ensure_session_for_id(id, &manager, &pool).await?;
+    manager
+        .measure_latency(id, samples, MCP_DEFAULT_PING_TIMEOUT_MS)
+        .await
+}
+
+/// Returns the last raw request/response sent to server `id`, redacted and size
+/// capped. Only recorded while MCP debug mode is on.
+#[tauri::command]
+pub async fn mcp_last_exchange(
+    id: i64,
+    manager: tauri::State<'_, std::sync::Arc<McpManager>>,
+) -> CmdResult<Option<mcp::McpExchange>> {
+    Ok(manager.last_exchange(id))
+}
+
+/// Returns whether MCP debug mode (keeping the last exchange per server) is on.
+#[tauri::command]
+pub async fn mcp_get_debug_enabled(pool: State<'_, SqlitePool>) -> CmdResult<bool> {
+    Ok(
+        settings::get_setting::<bool>(&pool, Setting::McpDebugEnabled)
+            .await?
+            .unwrap_or(false),
+    )
+}
+
+/// Persists MCP debug mode and applies it immediately; turning it off discards
+/// anything already captured.
+#[tauri::command]
+pub async fn mcp_set_debug_enabled(
+    enabled: bool,
+    pool: State<'_, SqlitePool>,
+    manager: State<'_, std::sync::Arc<McpManager>>,
+) -> CmdResult<()> {
+    settings::set_setting(&pool, Setting::McpDebugEnabled, Some(enabled)).await?;
+    manager.set_debug(enabled);
+    Ok(())
+}
+
+/// Whether enabled MCP servers are connected in the background at launch.
+#[tauri::command]
+pub async fn mcp_get_autoconnect_on_launch(pool: State<'_, SqlitePool>) -> CmdResult<bool> {
+    Ok(
+        settings::get_setting::<bool>(&pool, Setting::McpAutoconnectOnLaunch)
+            .await?
+            .unwrap_or(false),
+    )
+}
+
+/// Persists whether enabled MCP servers are connected at launch; takes effect on the
+/// next launch and is ignored in safe mode.
+#[tauri::command]
+pub async fn mcp_set_autoconnect_on_launch(
+    enabled: bool,
+    pool: State<'_, SqlitePool>,
+) -> CmdResult<()> {
+    settings::set_setting(&pool, Setting::McpAutoconnectOnLaunch, Some(enabled)).await
+}
+
+/// Returns the cap on live MCP sessions; the least recently used session is
+/// disconnected beyond it.
+#[tauri::command]
+pub async fn mcp_get_max_sessions(pool: State<'_, SqlitePool>) -> CmdResult<usize> {
+    Ok(settings::get_setting::<i64>(&pool, Setting::McpMaxSessions)
+        .await?
+        .map_or(mcp::constants::MCP_DEFAULT_MAX_SESSIONS, |max| max as usize))
+}
+
+/// Persists and applies the cap on live MCP sessions (1 to `MCP_MAX_SESSIONS_LIMIT`).
+/// Takes effect when the next session is created.
+#[tauri::command]
+pub async fn mcp_set_max_sessions(
+    max: usize,
+    pool: State<'_, SqlitePool>,
+    manager: State<'_, std::sync::Arc<McpManager>>,
+) -> CmdResult<()> {
+    if !(1..=mcp::constants::MCP_MAX_SESSIONS_LIMIT).contains(&max) {
+        return Err(format!(
+            "max sessions must be between 1 and {}",
+            mcp::constants::MCP_MAX_SESSIONS_LIMIT
+        ));
+    }
+    settings::set_setting(&pool, Setting::McpMaxSessions, Some(max as i64)).await?;
+    manager.set_max_sessions(max);
+    Ok(())
+}
+
+/// Sets a server's priority. Higher-priority servers are listed first and win when a
+/// qualified tool name matches more than one server.
+#[tauri::command]
+pub async fn set_server_priority(
+    id: i64,
+    priority: i64,
+    pool: State<'_, SqlitePool>,
+) -> CmdResult<()> {
+    mcp::store::set_mcp_server_priority(&pool, id, priority).await
+}
+
+// ------------------ MCP import/export commands ------------------
+
+/// Imports servers from a Claude-desktop style `mcpServers` JSON block. Entries whose
+/// name already exists are skipped; invalid entries are reported individually.
+#[tauri::command]
+pub async fn import_mcp_servers(
+    json: String,
+    pool: State<'_, SqlitePool>,
+    manager: State<'_, std::sync::Arc<McpManager>>,
+) -> CmdResult<mcp::import::McpImportResult> {
+    let result = mcp::import::import_mcp_servers(&pool, &json).await?;
+    // Imported rows may have disabled servers that are currently connected.
+    mcp::session::disconnect_disabled_mcp_sessions(&manager, &pool).await?;
+    Ok(result)
+}
+
+/// Returns all saved servers in the `mcpServers` JSON shape, including disabled ones.
+/// With `redact_secrets`, env and header values are replaced by a placeholder.
+#[tauri::command]
+pub async fn export_mcp_servers(
+    redact_secrets: Option<bool>,
+    pool: State<'_, SqlitePool>,
+) -> CmdResult<serde_json::Value> {
+    mcp::export::export_mcp_servers(&pool, redact_secrets.unwrap_or(false)).await
+}
+
+// ------------------ Secret Commands ------------------
+
+/// Stores a secret in the OS keychain (or the database fallback). Never echoes it back.
+#[tauri::command]
+pub async fn set_secret(name: String, value: String, pool: State<'_, SqlitePool>) -> CmdResult<()> {
+    if value.trim().is_empty() {
+        return Err("secret value cannot be empty".into());
+    }
+    secrets::set_secret(&pool, &name, value.trim()).await
+}
+
+/// Whether a secret named `name` is stored.
+#[tauri::command]
+pub async fn has_secret(name: String, pool: State<'_, SqlitePool>) -> CmdResult<bool> {
+    secrets::has_secret(&pool, &name).await
+}
+
+/// Deletes the secret named `name`, if present.
+#[tauri::command]
+pub async fn delete_secret(name: String, pool: State<'_, SqlitePool>) -> CmdResult<()> {
+    secrets::delete_secret(&pool, &name).await
+}
+
+/// Stores the OpenAI API key in the keychain. The key is never returned to the frontend.
+#[tauri::command]
+pub async fn set_openai_api_key(api_key: String, pool: State<'_, SqlitePool>) -> CmdResult<()> {
+    let api_key = api_key.trim();
+    if api_key.is_empty() {
+        return Err("API key cannot be empty".into());
+    }
+    secrets::set_secret(&pool, secrets::OPENAI_API_KEY, api_key).await?;
+    clear_legacy_openai_api_key(&pool).await
+}
+
+/// Removes the stored OpenAI API key.
+#[tauri::command]
+pub async fn clear_openai_api_key(pool: State<'_, SqlitePool>) -> CmdResult<()> {
+    secrets::delete_secret(&pool, secrets::OPENAI_API_KEY).await?;
+    clear_legacy_openai_api_key(&pool).await
+}
+
+/// Whether an OpenAI API key is configured, either in the secret store or still in
+/// the legacy `app_settings` column awaiting migration. Read-only.
+#[tauri::command]
+pub async fn has_openai_api_key(pool: State<'_, SqlitePool>) -> CmdResult<bool> {
+    let legacy = settings::get_setting::<String>(&pool, Setting::OpenaiApiKey).await?;
+    if legacy.is_some_and(|k| !k.is_empty()) {
+        return Ok(true);
+    }
+    secrets::has_secret(&pool, secrets::OPENAI_API_KEY).await
+}
+
+/// Checks the stored OpenAI API key against the provider's models list, returning
+/// the provider's error message if it is rejected. The key is never logged.
+#[tauri::command]
+pub async fn test_cloud_provider(
+    pool: State<'_, SqlitePool>,
+) -> CmdResult<chat_provider::ProviderKeyTest> {
+    let key = secrets::get_secret(&pool, secrets::OPENAI_API_KEY)
+        .await?
+        .ok_or("no OpenAI API key is configured")?;
+    chat_provider::test_api_key(chat_provider::OPENAI_BASE_URL, &key).await
+}
+
+/// Returns how many attempts an OpenAI completion gets before a rate limit or
+/// server error is reported.
+#[tauri::command]
+pub async fn get_openai_max_attempts(pool: State<'_, SqlitePool>) -> CmdResult<u32> {
+    chat_provider::openai_max_attempts(&pool).await
+}
+
+/// Persists how many attempts an OpenAI completion gets (1 to
+/// `OPENAI_MAX_ATTEMPTS_LIMIT`); 1 disables retries.
+#[tauri::command]
+pub async fn set_openai_max_attempts(max: u32, pool: State<'_, SqlitePool>) -> CmdResult<()> {
+    if !(1..=chat_provider::OPENAI_MAX_ATTEMPTS_LIMIT).contains(&max) {
+        return Err(format!(
+            "max attempts must be between 1 and {}",
+            chat_provider::OPENAI_MAX_ATTEMPTS_LIMIT
+        ));
+    }
+    settings::set_setting(&pool, Setting::OpenaiMaxAttempts, Some(i64::from(max))).await
+}
+
+async fn clear_legacy_openai_api_key(pool: &SqlitePool) -> CmdResult<()> {
+    settings::set_setting::<String>(pool, Setting::OpenaiApiKey, None).await
+}
+
+// ------------------ Build Feature Commands ------------------
+
+/// Whether this build includes an in-process inference engine. Always `false`: local
+/// models run in the openchat-mlx-server sidecar, managed by the `mlc_*` commands.
+#[tauri::command]
+pub async fn local_inference_available() -> CmdResult<bool> {
+    Ok(false)
+}
+
+/// Whether the app was launched in safe mode (`OPENCHAT_SAFE_MODE`). The frontend
+/// skips starting the server and loading the model when it is.
+#[tauri::command]
+pub async fn is_safe_mode(safe_mode: State<'_, SafeMode>) -> CmdResult<bool> {
+    Ok(safe_mode.0)
+}
+
 // ------------------ Environment Variable Commands ------------------
 
+/// Environment variables the frontend may read. Anything that can hold a secret,
+/// such as `OPENAI_API_KEY` or `HF_TOKEN`, is deliberately left out.
+const EXPOSED_ENV_VARS: &[&str] = &["HF_ENDPOINT", crate::safe_mode::SAFE_MODE_ENV];
+
+/// Returns an environment variable from `EXPOSED_ENV_VARS`; any other name is
+/// rejected.
 #[tauri::command]
 pub async fn get_env_var(name: String) -> CmdResult<Option<String>> {
+    check_exposed_env_var(&name)?;
     Ok(std::env::var(&name).ok())
 }
 
+fn check_exposed_env_var(name: &str) -> CmdResult<()> {
+    if EXPOSED_ENV_VARS.contains(&name) {
+        Ok(())
+    } else {
+        Err(format!(
+            "environment variable '{name}' is not readable from the app"
+        ))
+    }
+}
+
+// ------------------ Model Info Commands ------------------
+
+/// Returns architecture, context length, quantization and size for a cached model.
+#[tauri::command]
+pub async fn get_model_info(repo_id: String) -> CmdResult<ModelInfo> {
+    tauri::async_runtime::spawn_blocking(move || read_model_info(&repo_id))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Returns the files a cached model is missing (config, tokenizer, weight shards);
+/// empty when the server should be able to load it.
+#[tauri::command]
+pub async fn verify_model(repo_id: String) -> CmdResult<Vec<String>> {
+    if !model_store::is_model_cached(&repo_id) {
+        return Err(format!("model not cached: {repo_id}"));
+    }
+    let dir = model_store::model_cache_dir(&repo_id);
+    tauri::async_runtime::spawn_blocking(move || model_store::missing_model_files(&dir))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Re-downloads the files a cached model is missing (see `verify_model`) and returns
+/// any that are still missing afterwards.
+#[tauri::command]
+pub async fn repair_model(app: tauri::AppHandle, repo_id: String) -> CmdResult<Vec<String>> {
+    model_store::validate_repo_id(&repo_id)?;
+    model_download::repair_cached_model(&app, &repo_id).await?;
+    let dir = model_store::model_cache_dir(&repo_id);
+    tauri::async_runtime::spawn_blocking(move || model_store::missing_model_files(&dir))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Returns the effective Hugging Face hub directory models are cached in, and whether
+/// it came from a moved cache, `HF_HUB_CACHE`, `HF_HOME`, `XDG_CACHE_HOME` or the
+/// platform default.
+#[tauri::command]
+pub async fn get_hub_dir() -> CmdResult<model_store::HubDir> {
+    Ok(model_store::hub_dir())
+}
+
+/// Moves the models OpenChat downloaded, and the configured model, to `new_base_dir`
+/// and stores it as the hub directory. Other repos in a shared Hugging Face cache stay
+/// where they are. Refuses while downloads are running; the server picks the new
+/// location up on its next start.
+#[tauri::command]
+pub async fn move_model_cache(
+    pool: State<'_, SqlitePool>,
+    downloads: State<'_, std::sync::Arc<DownloadRegistry>>,
+    new_base_dir: String,
+) -> CmdResult<model_store::ModelCacheMove> {
+    let to = std::path::PathBuf::from(new_base_dir.trim());
+    if !to.is_absolute() {
+        return Err("new model cache location must be an absolute path".into());
+    }
+    let downloading = downloads.active();
+    if !downloading.is_empty() {
+        return Err(format!(
+            "cannot move the model cache while downloading: {}",
+            downloading.join(", ")
+        ));
+    }
+    let from = model_store::hub_dir().path;
+    let same_dir = match (from.canonicalize(), to.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => from == to,
+    };
+    if same_dir {
+        return Err(format!("models are already stored in {}", to.display()));
+    }
+
+    let tracked: Vec<String> = settings::get_setting::<String>(&pool, Setting::Model)
+        .await?
+        .into_iter()
+        .collect();
+    let moved = {
+        let (from, to) = (from.clone(), to.clone());
+        tauri::async_runtime::spawn_blocking(move || {
+            model_store::move_model_dirs(&from, &to, &tracked)
+        })
+        .await
+        .map_err(|e| e.to_string())??
+    };
+    // Point lookups at the new location even if persisting fails, since the
+    // models are already there.
+    model_store::set_hub_dir_override(Some(to.clone()));
+    settings::set_setting(
+        &pool,
+        Setting::ModelCacheDir,
+        Some(to.to_string_lossy().into_owned()),
+    )
+    .await
+    .map_err(|e| {
+        format!(
+            "models were moved to {} but the new location could not be saved: {e}",
+            to.display()
+        )
+    })?;
+    log::info!(
+        "moved {} model(s) from {} to {}",
+        moved.models.len(),
+        from.display(),
+        to.display()
+    );
+    Ok(moved)
+}
+
+/// Returns the curated list of recommended MLX models, marking those already cached.
+#[tauri::command]
+pub async fn list_recommended_models() -> CmdResult<Vec<RecommendedModel>> {
+    tauri::async_runtime::spawn_blocking(model_catalog::list_recommended_models)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Fetches just the README, config and file sizes of Hugging Face repo `repo_id` for a
+/// preview before downloading. Results are cached for a few minutes.
+#[tauri::command]
+pub async fn fetch_model_card(repo_id: String) -> CmdResult<model_card::ModelCard> {
+    model_card::fetch_model_card(repo_id.trim()).await
+}
+
+/// Checks from a repo's file list, tags and config (without downloading weights)
+/// whether the MLX server can load it, so incompatible repos are caught before a
+/// multi-gigabyte download.
+#[tauri::command]
+pub async fn check_model_compatibility(
+    repo_id: String,
+) -> CmdResult<model_card::ModelCompatibility> {
+    model_card::check_model_compatibility(repo_id.trim()).await
+}
+
+/// Resolves a model spec to an absolute local path or a Hugging Face repo. Relative
+/// paths resolve against the app's models directory, never the working directory.
+#[tauri::command]
+pub async fn resolve_model_path(model: String, app: AppHandle) -> CmdResult<ModelLocation> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {e}"))?;
+    Ok(model_store::resolve_model_path(
+        &model,
+        &app_models_dir(&app_data_dir),
+    ))
+}
+
 // ------------------ Model Download Commands ------------------
 
 /// Downloads a Hugging Face model to the local cache if not already present.
-/// Emits `mlc-download-progress` events during the download process.
+/// Emits `mlc-download-progress` events during the download process. Returns
+/// `already_downloading` instead of starting a second download of the same repo.
 #[tauri::command]
-pub async fn download_model(app: AppHandle, repo_id: String) -> CmdResult<()> {
+pub async fn download_model(
+    app: AppHandle,
+    repo_id: String,
+) -> CmdResult<model_download::DownloadOutcome> {
     ensure_hf_model_cached(&app, &repo_id).await
 }
 
+/// Returns the persisted download bandwidth cap in bytes/sec (`None` = unlimited).
+#[tauri::command]
+pub async fn get_download_bandwidth_limit(pool: State<'_, SqlitePool>) -> CmdResult<Option<u64>> {
+    let value = settings::get_setting::<i64>(&pool, Setting::DownloadMaxBytesPerSec).await?;
+    Ok(value.and_then(|v| model_download::validate_bandwidth_limit(v).ok()))
+}
+
+/// Validates and persists the download bandwidth cap (`None` removes it). Applies
+/// immediately, including to downloads in progress.
+#[tauri::command]
+pub async fn set_download_bandwidth_limit(
+    bytes_per_sec: Option<i64>,
+    pool: State<'_, SqlitePool>,
+) -> CmdResult<Option<u64>> {
+    let limit = bytes_per_sec
+        .map(model_download::validate_bandwidth_limit)
+        .transpose()?;
+    settings::set_setting(
+        &pool,
+        Setting::DownloadMaxBytesPerSec,
+        limit.map(|v| v as i64),
+    )
+    .await?;
+    model_download::set_bandwidth_limit(limit);
+    Ok(limit)
+}
+
+/// Cancels the download of `repo_id` and deletes its partial files. Safe to call when
+/// nothing is downloading; returns whether anything was aborted or removed.
+#[tauri::command]
+pub async fn abort_and_remove_download(app: AppHandle, repo_id: String) -> CmdResult<bool> {
+    model_download::abort_and_remove_download(&app, &repo_id).await
+}
+
+/// Cancels the download of `repo_id`, active or paused, keeping its partial files.
+/// Returns whether anything was cancelled.
+#[tauri::command]
+pub async fn cancel_download(app: AppHandle, repo_id: String) -> CmdResult<bool> {
+    model_download::cancel_download(&app, &repo_id).await
+}
+
+/// Returns active and paused downloads with their byte progress and phase, so the UI
+/// can recover download state after a reload.
+#[tauri::command]
+pub async fn get_download_status(
+    downloads: State<'_, std::sync::Arc<DownloadRegistry>>,
+) -> CmdResult<Vec<model_download::DownloadStatus>> {
+    Ok(downloads.status())
+}
+
+/// Pauses the download of `repo_id`, keeping its partial files. Returns whether a
+/// download was active.
+#[tauri::command]
+pub async fn pause_download(app: AppHandle, repo_id: String) -> CmdResult<bool> {
+    model_download::pause_download(&app, &repo_id).await
+}
+
+/// Resumes a paused download from its partial files; resolves when it completes.
+#[tauri::command]
+pub async fn resume_download(
+    app: AppHandle,
+    repo_id: String,
+) -> CmdResult<model_download::DownloadOutcome> {
+    model_download::resume_download(&app, &repo_id).await
+}
+
+// ------------------ Log Export Commands ------------------
+
+/// Writes a zip for bug reports to `dest_path` containing the app log files, recent
+/// model server output and version/platform info, with obvious secrets redacted.
+#[tauri::command]
+pub async fn export_logs(
+    dest_path: String,
+    app: AppHandle,
+    mlc: State<'_, std::sync::Arc<MLCServerManager>>,
+) -> CmdResult<log_export::LogExportSummary> {
+    let log_dir = app
+        .path()
+        .app_log_dir()
+        .map_err(|e| format!("Failed to resolve log dir: {e}"))?;
+    let server_output = mlc.recent_output();
+    let system_info = serde_json::json!({
+        "app_version": app.package_info().version.to_string(),
+        "tauri_version": tauri::VERSION,
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "os_family": std::env::consts::FAMILY,
+        "mlc_status": mlc.get_status().await,
+    });
+    tauri::async_runtime::spawn_blocking(move || {
+        log_export::write_log_archive(
+            std::path::Path::new(&dest_path),
+            &log_dir,
+            &server_output,
+            &system_info,
+        )
+    })
+    .await
+    .map_err(|e| format!("join error: {e}"))?
+}
+
+// ------------------ Telemetry Commands ------------------
+
+/// Returns whether the local lifecycle event log is enabled (off by default).
+#[tauri::command]
+pub async fn get_telemetry_enabled(pool: State<'_, SqlitePool>) -> CmdResult<bool> {
+    Ok(
+        settings::get_setting::<bool>(&pool, Setting::TelemetryEnabled)
+            .await?
+            .unwrap_or(false),
+    )
+}
+
+/// Persists the telemetry opt-in and applies it immediately.
+#[tauri::command]
+pub async fn set_telemetry_enabled(
+    enabled: bool,
+    pool: State<'_, SqlitePool>,
+    log: State<'_, TelemetryLog>,
+) -> CmdResult<()> {
+    settings::set_setting(&pool, Setting::TelemetryEnabled, Some(enabled)).await?;
+    log.set_enabled(enabled);
+    Ok(())
+}
+
+/// Returns the retained telemetry events as JSON lines, oldest first.
+#[tauri::command]
+pub async fn export_telemetry_log(log: State<'_, TelemetryLog>) -> CmdResult<String> {
+    log.export()
+        .map_err(|e| format!("Failed to read telemetry log: {e}"))
+}
+
+/// Returns whether chat provider requests are logged with their timing (off by
+/// default). Covers the chat view's streamed requests (reported by
+/// `log_chat_request`) as well as backend completions such as regeneration. Only metadata is
+/// logged, never message content.
+#[tauri::command]
+pub async fn get_provider_request_logging(pool: State<'_, SqlitePool>) -> CmdResult<bool> {
+    Ok(
+        settings::get_setting::<bool>(&pool, Setting::ProviderRequestLogging)
+            .await?
+            .unwrap_or(false),
+    )
+}
+
+/// Persists whether chat provider requests are logged; applies to the next request.
+#[tauri::command]
+pub async fn set_provider_request_logging(
+    enabled: bool,
+    pool: State<'_, SqlitePool>,
+) -> CmdResult<()> {
+    settings::set_setting(&pool, Setting::ProviderRequestLogging, Some(enabled)).await
+}
+
+/// Reports a chat request the chat view streamed to the local server, for provider
+/// request logging. `message_ids` are the stored messages sent with the request
+/// (`ProviderHistory::included_message_ids`); the size is estimated from the database,
+/// so no message content crosses IPC. A no-op when logging is off.
+#[tauri::command]
+pub async fn log_chat_request(
+    model: String,
+    message_ids: Vec<i64>,
+    latency_ms: u64,
+    ok: bool,
+    app: tauri::AppHandle,
+    pool: State<'_, SqlitePool>,
+) -> CmdResult<()> {
+    let messages: Vec<serde_json::Value> = db::message_contents(&pool, &message_ids)
+        .await?
+        .into_iter()
+        .map(|(_, content)| serde_json::json!({ "content": content }))
+        .collect();
+    chat_provider::log_request(
+        &app,
+        chat_provider::Provider::Local,
+        &model,
+        &messages,
+        std::time::Duration::from_millis(latency_ms),
+        ok,
+    )
+    .await;
+    Ok(())
+}
+
 async fn ensure_session_for_id(
     id: i64,
     manager: &std::sync::Arc<McpManager>,
@@ -172,3 +1892,37 @@ async fn ensure_session_for_id(
 ) -> CmdResult<()> {
     ensure_mcp_session(id, manager, pool).await
 }
+
+// ------------------ Appearance Commands ------------------
+
+/// Returns the app theme: `light`, `dark` or `system` (the default).
+#[tauri::command]
+pub async fn get_theme(pool: State<'_, SqlitePool>) -> CmdResult<Theme> {
+    theme::get_theme(&pool).await
+}
+
+/// Persists and applies the app theme, emitting `theme-changed`.
+#[tauri::command]
+pub async fn set_theme(
+    theme: String,
+    app: AppHandle,
+    pool: State<'_, SqlitePool>,
+) -> CmdResult<Theme> {
+    let theme: Theme = theme.parse()?;
+    theme::set_theme(&app, &pool, theme).await?;
+    Ok(theme)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_exposed_env_var;
+
+    #[test]
+    fn only_allowlisted_env_vars_are_readable() {
+        assert!(check_exposed_env_var("HF_ENDPOINT").is_ok());
+        assert!(check_exposed_env_var("OPENCHAT_SAFE_MODE").is_ok());
+        for name in ["OPENAI_API_KEY", "HF_TOKEN", "HOME", "hf_endpoint", ""] {
+            assert!(check_exposed_env_var(name).is_err(), "{name} was readable");
+        }
+    }
+}
@@ -0,0 +1,328 @@
+//! Writes a conversation to a Markdown or JSON file, loading messages from the
+//! database a page at a time so large histories never sit in memory at once. File
+//! I/O runs on blocking tasks so it never stalls the async runtime.
+
+use crate::db::{self, Conversation, ExportMessage};
+use sqlx::SqlitePool;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// File format written by `export_conversation_to_file`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Same layout as the "copy as Markdown" action; only completed messages.
+    Markdown,
+    /// `{"conversation": {...}, "messages": [...]}` with every message.
+    Json,
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "markdown" | "md" => Ok(ExportFormat::Markdown),
+            "json" => Ok(ExportFormat::Json),
+            other => Err(format!(
+                "invalid export format '{other}': expected markdown or json"
+            )),
+        }
+    }
+}
+
+/// Counts the bytes passed through to the inner writer.
+struct CountingWriter<W> {
+    inner: W,
+    written: u64,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+type FileWriter = CountingWriter<BufWriter<fs::File>>;
+
+/// Runs `op` on a blocking task.
+async fn blocking<T: Send + 'static>(
+    op: impl FnOnce() -> io::Result<T> + Send + 'static,
+) -> io::Result<T> {
+    tauri::async_runtime::spawn_blocking(op)
+        .await
+        .map_err(io::Error::other)?
+}
+
+/// The export file. Output is rendered into `pending` a page at a time and handed to
+/// a blocking task to write.
+struct ExportFile {
+    /// `None` only while a write is in progress.
+    file: Option<FileWriter>,
+    pending: Vec<u8>,
+}
+
+impl ExportFile {
+    async fn create(dest: &Path) -> io::Result<Self> {
+        let dest = dest.to_path_buf();
+        let file = blocking(move || fs::File::create(dest)).await?;
+        Ok(Self {
+            file: Some(CountingWriter {
+                inner: BufWriter::new(file),
+                written: 0,
+            }),
+            pending: Vec::new(),
+        })
+    }
+
+    /// Writes out the pending output, flushing the file too if `flush`.
+    async fn write_pending(&mut self, flush: bool) -> io::Result<()> {
+        let mut file = self
+            .file
+            .take()
+            .ok_or_else(|| io::Error::other("export file is closed"))?;
+        let chunk = std::mem::take(&mut self.pending);
+        let (file, result) = blocking(move || {
+            let result = file
+                .write_all(&chunk)
+                .and_then(|_| if flush { file.flush() } else { Ok(()) });
+            Ok((file, result))
+        })
+        .await?;
+        self.file = Some(file);
+        result
+    }
+
+    /// Bytes written to the file so far.
+    fn written(&self) -> u64 {
+        self.file.as_ref().map_or(0, |f| f.written)
+    }
+}
+
+/// Streams conversation `conversation_id` to `dest` in `format` and returns the number
+/// of bytes written. A partially written file is removed on failure.
+pub async fn export_conversation_to_file(
+    pool: &SqlitePool,
+    conversation_id: i64,
+    dest: &Path,
+    format: ExportFormat,
+) -> Result<u64, String> {
+    let conversation = db::get_conversation(pool, conversation_id).await?;
+    let mut out = ExportFile::create(dest)
+        .await
+        .map_err(|e| format!("Failed to create {dest:?}: {e}"))?;
+    let result = write_export(pool, &conversation, &mut out, format).await;
+    let result = match result {
+        Ok(()) => out
+            .write_pending(true)
+            .await
+            .map_err(|e| format!("Failed to write {dest:?}: {e}")),
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(()) => Ok(out.written()),
+        Err(e) => {
+            drop(out);
+            let dest = dest.to_path_buf();
+            let _ = blocking(move || fs::remove_file(dest)).await;
+            Err(e)
+        }
+    }
+}
+
+async fn write_export(
+    pool: &SqlitePool,
+    conversation: &Conversation,
+    out: &mut ExportFile,
+    format: ExportFormat,
+) -> Result<(), String> {
+    let io_err = |e: io::Error| format!("Failed to write export: {e}");
+    match format {
+        ExportFormat::Markdown => {
+            let message_count = db::conversation_message_count(pool, conversation.id).await?;
+            write_markdown_header(&mut out.pending, conversation, message_count).map_err(io_err)?;
+        }
+        ExportFormat::Json => {
+            let conversation = serde_json::to_string(conversation).map_err(|e| e.to_string())?;
+            write!(
+                out.pending,
+                "{{\"conversation\":{conversation},\"messages\":["
+            )
+            .map_err(io_err)?;
+        }
+    }
+
+    let mut after_id = None;
+    let mut written_messages = 0usize;
+    loop {
+        let page =
+            db::export_messages_page(pool, conversation.id, after_id, db::EXPORT_PAGE_SIZE).await?;
+        for message in &page {
+            match format {
+                ExportFormat::Markdown => {
+                    if message.status != db::MessageStatus::Complete.as_str() {
+                        continue;
+                    }
+                    if written_messages > 0 {
+                        out.pending.extend_from_slice(b"---\n\n");
+                    }
+                    write_markdown_message(&mut out.pending, message).map_err(io_err)?;
+                }
+                ExportFormat::Json => {
+                    if written_messages > 0 {
+                        out.pending.push(b',');
+                    }
+                    let message = serde_json::to_string(message).map_err(|e| e.to_string())?;
+                    write!(out.pending, "\n{message}").map_err(io_err)?;
+                }
+            }
+            written_messages += 1;
+        }
+        out.write_pending(false).await.map_err(io_err)?;
+        after_id = page.last().map(|m| m.id);
+        if page.len() < db::EXPORT_PAGE_SIZE as usize {
+            break;
+        }
+    }
+
+    match format {
+        ExportFormat::Markdown if written_messages == 0 => out
+            .pending
+            .extend_from_slice(b"*No completed messages in this conversation.*\n"),
+        ExportFormat::Markdown => {}
+        ExportFormat::Json => out.pending.extend_from_slice(b"\n]}\n"),
+    }
+    Ok(())
+}
+
+fn write_markdown_header<W: Write>(
+    out: &mut W,
+    conversation: &Conversation,
+    message_count: i64,
+) -> io::Result<()> {
+    let title = conversation
+        .title
+        .as_deref()
+        .filter(|t| !t.is_empty())
+        .unwrap_or("Untitled Conversation");
+    write!(
+        out,
+        "# {title}\n\n*Exported on {}*\n\n**Created:** {}\n**Last Updated:** {}\n**Messages:** {message_count}\n\n---\n\n",
+        chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC"),
+        conversation.created_at,
+        conversation.updated_at,
+    )
+}
+
+fn write_markdown_message<W: Write>(out: &mut W, message: &ExportMessage) -> io::Result<()> {
+    let role = match (message.role.as_str(), message.tool_name.as_deref()) {
+        ("user", _) => "User".to_string(),
+        ("assistant", _) => "Assistant".to_string(),
+        ("system", _) => "System".to_string(),
+        ("tool", Some(name)) => format!("Tool `{name}`"),
+        _ => "Tool".to_string(),
+    };
+    write!(out, "## {role} *({})*\n\n", message.created_at)?;
+    if let Some(reasoning) = message
+        .reasoning
+        .as_deref()
+        .filter(|r| !r.is_empty() && message.role == "assistant")
+    {
+        write!(out, "**Reasoning:**\n{reasoning}\n\n")?;
+    }
+    if !message.content.is_empty() {
+        write!(out, "{}\n\n", message.content)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &str, content: &str, reasoning: Option<&str>) -> ExportMessage {
+        ExportMessage {
+            id: 1,
+            role: role.into(),
+            content: content.into(),
+            reasoning: reasoning.map(str::to_string),
+            tool_name: None,
+            tool_call_id: None,
+            tool_arguments: None,
+            metadata: None,
+            status: "complete".into(),
+            created_at: "2024-01-02 03:04:05".into(),
+        }
+    }
+
+    #[test]
+    fn markdown_message_matches_copy_layout() {
+        let mut out = Vec::new();
+        write_markdown_message(&mut out, &message("assistant", "Hi", Some("think"))).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "## Assistant *(2024-01-02 03:04:05)*\n\n**Reasoning:**\nthink\n\nHi\n\n"
+        );
+
+        let mut out = Vec::new();
+        write_markdown_message(&mut out, &message("user", "Hello", Some("ignored"))).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "## User *(2024-01-02 03:04:05)*\n\nHello\n\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn json_export_keeps_tool_columns_and_metadata() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        for migration in crate::migrations::migrations() {
+            sqlx::raw_sql(migration.sql).execute(&pool).await.unwrap();
+        }
+        let conversation: i64 =
+            sqlx::query_scalar("INSERT INTO conversations (title) VALUES ('Export') RETURNING id")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        sqlx::query(
+            "INSERT INTO messages (conversation_id, role, content, tool_name, tool_call_id, tool_arguments, metadata)
+             VALUES (?, 'assistant', '', 'search', 'call_1', '{\"q\":\"rust\"}', '{\"model\":\"m\"}')",
+        )
+        .bind(conversation)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let dest = std::env::temp_dir().join(format!(
+            "openchat-conversation-export-{}.json",
+            std::process::id()
+        ));
+        let written = export_conversation_to_file(&pool, conversation, &dest, ExportFormat::Json)
+            .await
+            .unwrap();
+        let text = fs::read_to_string(&dest).unwrap();
+        let _ = fs::remove_file(&dest);
+        assert_eq!(written, text.len() as u64);
+        let export: serde_json::Value = serde_json::from_str(&text).unwrap();
+        let message = &export["messages"][0];
+        assert_eq!(message["tool_call_id"], "call_1");
+        assert_eq!(message["tool_arguments"], "{\"q\":\"rust\"}");
+        assert_eq!(message["metadata"], "{\"model\":\"m\"}");
+    }
+
+    #[test]
+    fn parses_export_formats() {
+        assert_eq!("markdown".parse(), Ok(ExportFormat::Markdown));
+        assert_eq!("json".parse(), Ok(ExportFormat::Json));
+        assert!("csv".parse::<ExportFormat>().is_err());
+    }
+}
@@ -1,55 +1,1931 @@
+use crate::chat_provider::GenerationParams;
+use crate::models::{self, Role};
+use crate::settings::{self, Setting};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqliteSynchronous};
 use sqlx::SqlitePool;
-use std::path::Path;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-pub async fn init_pool(db_file: &Path) -> Result<SqlitePool, sqlx::Error> {
-    let conn_str = format!("sqlite://{}?mode=rwc", db_file.display());
-    SqlitePool::connect(&conn_str).await
+// Primary SQLite result codes (extended codes carry these in the low byte).
+const SQLITE_BUSY: i64 = 5;
+const SQLITE_LOCKED: i64 = 6;
+const SQLITE_CORRUPT: i64 = 11;
+const SQLITE_NOTADB: i64 = 26;
+
+/// Why the app database could not be opened.
+#[derive(Debug)]
+pub enum DbInitError {
+    /// Another process holds a lock on the database file.
+    Locked,
+    /// The file is damaged or is not a SQLite database.
+    Corrupt(String),
+    /// A schema migration is recorded as failed in `_sqlx_migrations`.
+    MigrationFailed { version: i64, description: String },
+    /// A pending migration cannot apply to the existing schema, typically because the
+    /// tables were created outside the migration framework by an older build.
+    SchemaConflict(SchemaConflict),
+    /// Any other connection or query error.
+    Other(sqlx::Error),
+}
+
+impl fmt::Display for DbInitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DbInitError::Locked => write!(f, "database is locked by another process"),
+            DbInitError::Corrupt(msg) => write!(f, "database file is corrupt: {msg}"),
+            DbInitError::MigrationFailed {
+                version,
+                description,
+            } => write!(f, "migration {version} ({description}) failed"),
+            DbInitError::SchemaConflict(conflict) => write!(
+                f,
+                "existing schema conflicts with migration {} ({}): {}",
+                conflict.version, conflict.description, conflict.error
+            ),
+            DbInitError::Other(err) => write!(f, "database error: {err}"),
+        }
+    }
 }
 
-/*
-Example of how to insert a conversation
+impl std::error::Error for DbInitError {}
+
+impl From<sqlx::Error> for DbInitError {
+    fn from(err: sqlx::Error) -> Self {
+        if let sqlx::Error::Database(db_err) = &err {
+            let primary_code = db_err
+                .code()
+                .and_then(|code| code.parse::<i64>().ok())
+                .map(|code| code & 0xff);
+            match primary_code {
+                Some(SQLITE_BUSY) | Some(SQLITE_LOCKED) => return DbInitError::Locked,
+                Some(SQLITE_CORRUPT) | Some(SQLITE_NOTADB) => {
+                    return DbInitError::Corrupt(db_err.message().to_string())
+                }
+                _ => {}
+            }
+        }
+        DbInitError::Other(err)
+    }
+}
+
+/// Connection pragmas applied to every pooled connection.
+#[derive(Debug, Clone)]
+pub struct DbOptions {
+    /// WAL lets readers proceed while a long write (e.g. an import) is in flight.
+    pub journal_mode: SqliteJournalMode,
+    /// `NORMAL` is durable under WAL except for the last commits on power loss.
+    pub synchronous: SqliteSynchronous,
+    /// How long a connection waits on a lock before failing with SQLITE_BUSY.
+    pub busy_timeout: Duration,
+    /// Enforces `messages.conversation_id -> conversations.id`.
+    pub foreign_keys: bool,
+}
+
+impl Default for DbOptions {
+    fn default() -> Self {
+        Self {
+            journal_mode: SqliteJournalMode::Wal,
+            synchronous: SqliteSynchronous::Normal,
+            busy_timeout: Duration::from_secs(5),
+            foreign_keys: true,
+        }
+    }
+}
+
+/// Opens (creating if needed) the app database and verifies it is usable.
+///
+/// Connecting alone succeeds even for a damaged file, so this also reads the schema,
+/// checks that no migration was left in a failed state, and that pending migrations
+/// apply cleanly to the existing tables.
+pub async fn init_pool(db_file: &Path, options: &DbOptions) -> Result<SqlitePool, DbInitError> {
+    let connect_options = SqliteConnectOptions::new()
+        .filename(db_file)
+        .create_if_missing(true)
+        .journal_mode(options.journal_mode)
+        .synchronous(options.synchronous)
+        .busy_timeout(options.busy_timeout)
+        .foreign_keys(options.foreign_keys);
+    let pool = SqlitePool::connect_with(connect_options).await?;
+
+    sqlx::query("SELECT COUNT(*) FROM sqlite_master")
+        .fetch_one(&pool)
+        .await?;
+
+    if let Some((version, description)) = find_failed_migration(&pool).await? {
+        return Err(DbInitError::MigrationFailed {
+            version,
+            description,
+        });
+    }
+
+    if let Some(conflict) = check_schema(&pool).await?.conflict {
+        return Err(DbInitError::SchemaConflict(conflict));
+    }
+
+    Ok(pool)
+}
+
+/// A pending migration that fails against the current schema.
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaConflict {
+    pub version: i64,
+    pub description: String,
+    pub error: String,
+    /// The app tables exist but `_sqlx_migrations` does not, i.e. the schema was
+    /// created outside the migration framework.
+    pub legacy: bool,
+}
 
-pub async fn insert_conversation(
+/// Migration state of the database as seen by `check_schema`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaCheck {
+    /// Tables exist that no recorded migration created.
+    pub legacy: bool,
+    /// Highest successfully applied migration, if any are recorded.
+    pub applied_version: Option<i64>,
+    /// Migrations the SQL plugin will apply on the next load.
+    pub pending: Vec<i64>,
+    pub conflict: Option<SchemaConflict>,
+}
+
+/// Dry-runs the pending migrations inside a transaction that is always rolled back, so
+/// a schema the migrations cannot apply to is reported up front instead of failing
+/// inside the SQL plugin with a bare SQLite error.
+pub async fn check_schema(pool: &SqlitePool) -> Result<SchemaCheck, sqlx::Error> {
+    let has_migrations_table: Option<String> = sqlx::query_scalar(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations'",
+    )
+    .fetch_optional(pool)
+    .await?;
+    let applied: Vec<i64> = if has_migrations_table.is_some() {
+        sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success = 1")
+            .fetch_all(pool)
+            .await?
+    } else {
+        Vec::new()
+    };
+    let has_app_tables: Option<String> = sqlx::query_scalar(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'conversations'",
+    )
+    .fetch_optional(pool)
+    .await?;
+    let legacy = has_migrations_table.is_none() && has_app_tables.is_some();
+
+    let pending: Vec<_> = crate::migrations::migrations()
+        .into_iter()
+        .filter(|m| !applied.contains(&m.version))
+        .collect();
+    let mut conflict = None;
+    if !pending.is_empty() {
+        let mut tx = pool.begin().await?;
+        for migration in &pending {
+            if let Err(err) = sqlx::raw_sql(migration.sql).execute(&mut *tx).await {
+                conflict = Some(SchemaConflict {
+                    version: migration.version,
+                    description: migration.description.to_string(),
+                    error: err.to_string(),
+                    legacy,
+                });
+                break;
+            }
+        }
+        tx.rollback().await?;
+    }
+
+    Ok(SchemaCheck {
+        legacy,
+        applied_version: applied.iter().copied().max(),
+        pending: pending.iter().map(|m| m.version).collect(),
+        conflict,
+    })
+}
+
+/// Returns the first migration the SQL plugin recorded as unsuccessful, if any.
+async fn find_failed_migration(pool: &SqlitePool) -> Result<Option<(i64, String)>, sqlx::Error> {
+    let has_table: Option<String> = sqlx::query_scalar(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations'",
+    )
+    .fetch_optional(pool)
+    .await?;
+    if has_table.is_none() {
+        return Ok(None);
+    }
+
+    sqlx::query_as(
+        "SELECT version, description FROM _sqlx_migrations WHERE success = 0 ORDER BY version LIMIT 1",
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+/// Moves a damaged database (and its `-wal`/`-shm` side files) to a timestamped
+/// `.corrupt-*` sibling so the next launch starts with a fresh file. Returns the backup path.
+pub fn backup_corrupt_db(db_file: &Path) -> std::io::Result<PathBuf> {
+    let stamp = chrono::Utc::now().format("%Y%m%d%H%M%S");
+    let backup = PathBuf::from(format!("{}.corrupt-{stamp}", db_file.display()));
+    std::fs::rename(db_file, &backup)?;
+    for suffix in ["-wal", "-shm"] {
+        let side = PathBuf::from(format!("{}{suffix}", db_file.display()));
+        if side.exists() {
+            let side_backup = PathBuf::from(format!("{}{suffix}", backup.display()));
+            if let Err(err) = std::fs::rename(&side, &side_backup) {
+                log::warn!("failed to move {:?} aside: {err}", side);
+            }
+        }
+    }
+    Ok(backup)
+}
+
+/// A row from the `conversations` table.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Conversation {
+    pub id: i64,
+    pub title: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Database file size before and after `optimize_database`.
+#[derive(Debug, Clone, Serialize)]
+pub struct OptimizeReport {
+    pub size_before_bytes: i64,
+    pub size_after_bytes: i64,
+}
+
+/// Statements run by `optimize_database`, in order. `VACUUM` cannot run inside a
+/// transaction, so each statement executes on its own in autocommit mode.
+const OPTIMIZE_STATEMENTS: &[&str] = &[
+    "PRAGMA optimize",
+    "INSERT INTO messages_fts(messages_fts) VALUES('optimize')",
+    "INSERT INTO conversations_fts(conversations_fts) VALUES('optimize')",
+    "VACUUM",
+    // Fold the WAL back into the main file so the reclaimed space shows up on disk.
+    "PRAGMA wal_checkpoint(TRUNCATE)",
+];
+
+/// Merges FTS index segments, refreshes query planner stats, and vacuums the database.
+pub async fn optimize_database(pool: &SqlitePool) -> Result<OptimizeReport, String> {
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    let size_before_bytes = database_size_bytes(&mut conn).await?;
+    for statement in OPTIMIZE_STATEMENTS {
+        sqlx::query(statement)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| format!("{statement}: {e}"))?;
+    }
+    let size_after_bytes = database_size_bytes(&mut conn).await?;
+    Ok(OptimizeReport {
+        size_before_bytes,
+        size_after_bytes,
+    })
+}
+
+async fn database_size_bytes(conn: &mut sqlx::SqliteConnection) -> Result<i64, String> {
+    sqlx::query_scalar("SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()")
+        .fetch_one(conn)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Default number of characters kept for the sidebar preview of the last message.
+pub const CONVERSATION_PREVIEW_CHARS: u32 = 120;
+
+/// Sidebar row: conversation metadata plus message count and a preview of the last message.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ConversationSummary {
+    pub id: i64,
+    pub title: Option<String>,
+    pub updated_at: String,
+    pub message_count: i64,
+    pub preview: Option<String>,
+}
+
+/// Lists every conversation with its message count and the first `preview_chars`
+/// characters of its latest message, newest first, in a single query.
+pub async fn list_conversation_summaries(
+    pool: &SqlitePool,
+    preview_chars: u32,
+) -> Result<Vec<ConversationSummary>, String> {
+    sqlx::query_as::<_, ConversationSummary>(
+        "SELECT c.id, c.title, c.updated_at,
+                (SELECT COUNT(*) FROM messages m WHERE m.conversation_id = c.id) AS message_count,
+                (SELECT substr(m.content, 1, ?) FROM messages m
+                  WHERE m.conversation_id = c.id
+                  ORDER BY m.id DESC LIMIT 1) AS preview
+         FROM conversations c
+         ORDER BY c.updated_at DESC, c.id DESC",
+    )
+    .bind(preview_chars)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Forks `conversation_id` into a new conversation holding every message up to and
+/// including `from_message_id`. Copies keep their order and original `created_at`;
+/// the FTS triggers index them like any other insert.
+pub async fn branch_conversation(
     pool: &SqlitePool,
-    title: &str,
-) -> Result<Conversation, sqlx::Error> {
-    let now = chrono::Utc::now().to_rfc3339();
-    let row: SqliteRow = sqlx::query(
-        "INSERT INTO conversations (title, created_at, updated_at) VALUES (?, ?, ?) RETURNING id",
+    conversation_id: i64,
+    from_message_id: i64,
+    new_title: Option<&str>,
+) -> Result<Conversation, String> {
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+
+    let source_title: Option<String> =
+        sqlx::query_scalar("SELECT title FROM conversations WHERE id = ?")
+            .bind(conversation_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "conversation not found".to_string())?;
+
+    let message_exists: Option<i64> =
+        sqlx::query_scalar("SELECT id FROM messages WHERE id = ? AND conversation_id = ?")
+            .bind(from_message_id)
+            .bind(conversation_id)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+    if message_exists.is_none() {
+        return Err("message not found in conversation".into());
+    }
+
+    let title = new_title.map(str::to_string).or(source_title);
+    let conversation: Conversation = sqlx::query_as(
+        "INSERT INTO conversations (title, system_prompt, max_history_messages, generation_params)
+         SELECT ?, system_prompt, max_history_messages, generation_params
+         FROM conversations WHERE id = ?
+         RETURNING id, title, created_at, updated_at",
     )
     .bind(title)
-    .bind(&now)
-    .bind(&now)
+    .bind(conversation_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    // Copy in id order so each tool result's parent is already copied and can be
+    // pointed at its new id
+    let rows: Vec<BranchedRow> = sqlx::query_as(
+        "SELECT id, role, content, reasoning, status, created_at, tool_name, tool_call_id,
+                tool_arguments, parent_message_id, metadata
+         FROM messages
+         WHERE conversation_id = ? AND id <= ?
+         ORDER BY id ASC",
+    )
+    .bind(conversation_id)
+    .bind(from_message_id)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    let mut new_ids: HashMap<i64, i64> = HashMap::with_capacity(rows.len());
+    for row in rows {
+        let parent_message_id = row
+            .parent_message_id
+            .and_then(|parent| new_ids.get(&parent).copied());
+        let new_id: i64 = sqlx::query_scalar(
+            "INSERT INTO messages (conversation_id, role, content, reasoning, status, created_at,
+                                   tool_name, tool_call_id, tool_arguments, parent_message_id,
+                                   metadata)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) RETURNING id",
+        )
+        .bind(conversation.id)
+        .bind(&row.role)
+        .bind(&row.content)
+        .bind(&row.reasoning)
+        .bind(&row.status)
+        .bind(&row.created_at)
+        .bind(&row.tool_name)
+        .bind(&row.tool_call_id)
+        .bind(&row.tool_arguments)
+        .bind(parent_message_id)
+        .bind(&row.metadata)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+        new_ids.insert(row.id, new_id);
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(conversation)
+}
+
+/// A message as copied by `branch_conversation`.
+#[derive(sqlx::FromRow)]
+struct BranchedRow {
+    id: i64,
+    role: String,
+    content: String,
+    reasoning: Option<String>,
+    status: String,
+    created_at: String,
+    tool_name: Option<String>,
+    tool_call_id: Option<String>,
+    tool_arguments: Option<String>,
+    parent_message_id: Option<i64>,
+    metadata: Option<String>,
+}
+
+/// Returns the conversation's own system prompt; `None` means it uses the global default.
+pub async fn get_conversation_system_prompt(
+    pool: &SqlitePool,
+    conversation_id: i64,
+) -> Result<Option<String>, String> {
+    sqlx::query_scalar::<_, Option<String>>("SELECT system_prompt FROM conversations WHERE id = ?")
+        .bind(conversation_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "conversation not found".to_string())
+}
+
+/// Sets the conversation's system prompt. `None` reverts to the global default and an
+/// empty string disables the system message for this conversation.
+pub async fn set_conversation_system_prompt(
+    pool: &SqlitePool,
+    conversation_id: i64,
+    prompt: Option<&str>,
+) -> Result<(), String> {
+    let result = sqlx::query("UPDATE conversations SET system_prompt = ? WHERE id = ?")
+        .bind(prompt)
+        .bind(conversation_id)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    if result.rows_affected() == 0 {
+        return Err("conversation not found".into());
+    }
+    Ok(())
+}
+
+/// The system prompt to send for a conversation: its own prompt, else the global one
+/// from `app_settings`. Returns `None` when the result is empty (no system message).
+pub async fn effective_system_prompt(
+    pool: &SqlitePool,
+    conversation_id: i64,
+) -> Result<Option<String>, String> {
+    let prompt = match get_conversation_system_prompt(pool, conversation_id).await? {
+        Some(prompt) => Some(prompt),
+        None => settings::get_setting::<String>(pool, Setting::SystemPrompt).await?,
+    };
+    Ok(prompt.filter(|p| !p.trim().is_empty()))
+}
+
+/// Returns the conversation's own history limit; `None` means it uses the global one.
+pub async fn get_conversation_max_history(
+    pool: &SqlitePool,
+    conversation_id: i64,
+) -> Result<Option<i64>, String> {
+    sqlx::query_scalar::<_, Option<i64>>(
+        "SELECT max_history_messages FROM conversations WHERE id = ?",
+    )
+    .bind(conversation_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| "conversation not found".to_string())
+}
+
+/// Sets how many stored messages are sent with each request for a conversation.
+/// `None` reverts to the global limit and `0` sends the whole history.
+pub async fn set_conversation_max_history(
+    pool: &SqlitePool,
+    conversation_id: i64,
+    max: Option<i64>,
+) -> Result<(), String> {
+    if let Some(max) = max {
+        models::validate_max_history(max)?;
+    }
+    let result = sqlx::query("UPDATE conversations SET max_history_messages = ? WHERE id = ?")
+        .bind(max)
+        .bind(conversation_id)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    if result.rows_affected() == 0 {
+        return Err("conversation not found".into());
+    }
+    Ok(())
+}
+
+/// The history limit for a conversation: its own, else the global one. `None` when
+/// neither is set or the effective value is `0` (no limit).
+pub async fn effective_max_history(
+    pool: &SqlitePool,
+    conversation_id: i64,
+) -> Result<Option<usize>, String> {
+    let max = match get_conversation_max_history(pool, conversation_id).await? {
+        Some(max) => Some(max),
+        None => settings::get_setting::<i64>(pool, Setting::MaxHistoryMessages).await?,
+    };
+    Ok(max
+        .and_then(|max| usize::try_from(max).ok())
+        .filter(|max| *max > 0))
+}
+
+/// Returns the conversation's own generation params; `None` means it uses the
+/// global defaults.
+pub async fn get_conversation_generation_params(
+    pool: &SqlitePool,
+    conversation_id: i64,
+) -> Result<Option<GenerationParams>, String> {
+    let raw = sqlx::query_scalar::<_, Option<String>>(
+        "SELECT generation_params FROM conversations WHERE id = ?",
+    )
+    .bind(conversation_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| "conversation not found".to_string())?;
+    raw.map(|raw| {
+        serde_json::from_str(&raw).map_err(|e| format!("invalid stored generation params: {e}"))
+    })
+    .transpose()
+}
+
+/// Validates and stores the conversation's generation params. `None` reverts to the
+/// global defaults.
+pub async fn set_conversation_generation_params(
+    pool: &SqlitePool,
+    conversation_id: i64,
+    params: Option<&GenerationParams>,
+) -> Result<(), String> {
+    let raw = match params {
+        Some(params) => {
+            params.validate()?;
+            Some(serde_json::to_string(params).map_err(|e| e.to_string())?)
+        }
+        None => None,
+    };
+    let result = sqlx::query("UPDATE conversations SET generation_params = ? WHERE id = ?")
+        .bind(raw)
+        .bind(conversation_id)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    if result.rows_affected() == 0 {
+        return Err("conversation not found".into());
+    }
+    Ok(())
+}
+
+/// The generation params to send for a conversation: its own, with unset fields
+/// taken from the global defaults.
+pub async fn effective_generation_params(
+    pool: &SqlitePool,
+    conversation_id: i64,
+) -> Result<GenerationParams, String> {
+    let own = get_conversation_generation_params(pool, conversation_id)
+        .await?
+        .unwrap_or_default();
+    Ok(own.merged_over(&GenerationParams::global_defaults(pool).await?))
+}
+
+/// Inserts a message after checking that `reasoning` is allowed for `role`, and
+/// returns its id. The database enforces the same rules for writes from the frontend.
+/// `metadata` is stored as JSON text and never sent to providers.
+pub async fn add_message(
+    pool: &SqlitePool,
+    conversation_id: i64,
+    role: Role,
+    content: &str,
+    reasoning: Option<&str>,
+    metadata: Option<&serde_json::Value>,
+) -> Result<i64, String> {
+    models::validate_reasoning(role, reasoning)?;
+    if let Some(metadata) = metadata {
+        models::validate_metadata(metadata)?;
+    }
+    sqlx::query_scalar(
+        "INSERT INTO messages (conversation_id, role, content, reasoning, metadata) VALUES (?, ?, ?, ?, ?) RETURNING id",
+    )
+    .bind(conversation_id)
+    .bind(role.as_str())
+    .bind(content)
+    .bind(reasoning)
+    .bind(metadata.map(|m| m.to_string()))
     .fetch_one(pool)
-    .await?;
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Stores the result of tool call `tool_call_id` as a `tool` message following the
+/// assistant message that requested it, and returns the new message id. `arguments`
+/// are the call's JSON arguments, replayed with the assistant message's `tool_calls`.
+pub async fn append_tool_result(
+    pool: &SqlitePool,
+    assistant_message_id: i64,
+    tool_call_id: &str,
+    tool_name: &str,
+    arguments: Option<&serde_json::Value>,
+    content: &str,
+) -> Result<i64, String> {
+    if tool_call_id.trim().is_empty() {
+        return Err("tool_call_id must not be empty".to_string());
+    }
+    let (conversation_id, role): (i64, String) =
+        sqlx::query_as("SELECT conversation_id, role FROM messages WHERE id = ?")
+            .bind(assistant_message_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "message not found".to_string())?;
+    if role != Role::Assistant.as_str() {
+        return Err(format!(
+            "tool results must follow an assistant message, not '{role}'"
+        ));
+    }
+    sqlx::query_scalar(
+        "INSERT INTO messages (conversation_id, role, content, tool_name, tool_call_id, tool_arguments, parent_message_id)
+         VALUES (?, ?, ?, ?, ?, ?, ?) RETURNING id",
+    )
+    .bind(conversation_id)
+    .bind(Role::Tool.as_str())
+    .bind(content)
+    .bind(tool_name)
+    .bind(tool_call_id)
+    .bind(arguments.map(|a| a.to_string()))
+    .bind(assistant_message_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Stored messages to send with a chat request after the history limit is applied.
+/// The system prompt is not a stored message and never counts toward the limit.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProviderHistory {
+    pub messages: Vec<serde_json::Value>,
+    /// Ids of the stored messages in `messages`, in order.
+    pub included_message_ids: Vec<i64>,
+    /// Older messages left out by the limit; the database keeps them.
+    pub omitted: usize,
+}
+
+/// A stored message with the columns needed to replay it to the model.
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct HistoryRow {
+    id: i64,
+    role: String,
+    content: String,
+    tool_call_id: Option<String>,
+    tool_name: Option<String>,
+    tool_arguments: Option<String>,
+    parent_message_id: Option<i64>,
+}
+
+/// Stored messages to send back to the model: replies still being generated or that
+/// failed are left out.
+const HISTORY_ROWS_SQL: &str =
+    "SELECT id, role, content, tool_call_id, tool_name, tool_arguments, parent_message_id
+     FROM messages WHERE conversation_id = ? AND status NOT IN ('pending', 'error')
+     ORDER BY id";
+
+impl ProviderHistory {
+    /// Renders `rows`, keeping only the newest allowed by `max` (see
+    /// `models::history_start`). Assistant messages get `tool_calls` rebuilt from the
+    /// tool results that answer them, and tool results whose requesting assistant
+    /// message is not sent are dropped, since the chat API rejects either alone.
+    fn from_rows(rows: &[HistoryRow], max: Option<usize>) -> Result<Self, String> {
+        let roles: Vec<&str> = rows.iter().map(|row| row.role.as_str()).collect();
+        let start = models::history_start(&roles, max);
+        let kept = &rows[start..];
+        let is_tool = |row: &HistoryRow| row.role == Role::Tool.as_str();
+        let answered = |row: &HistoryRow| {
+            row.tool_call_id.is_some()
+                && kept.iter().any(|parent| {
+                    Some(parent.id) == row.parent_message_id
+                        && parent.role == Role::Assistant.as_str()
+                })
+        };
+        let sent: Vec<&HistoryRow> = kept
+            .iter()
+            .filter(|row| !is_tool(row) || answered(row))
+            .collect();
+        let messages = sent
+            .iter()
+            .map(|row| {
+                let tool_calls = sent
+                    .iter()
+                    .filter(|result| is_tool(result) && result.parent_message_id == Some(row.id))
+                    .filter_map(|result| {
+                        Some(models::ToolCall {
+                            id: result.tool_call_id.clone()?,
+                            name: result.tool_name.clone().unwrap_or_default(),
+                            arguments: result
+                                .tool_arguments
+                                .clone()
+                                .unwrap_or_else(|| "{}".to_string()),
+                        })
+                    })
+                    .collect();
+                models::ChatMessage {
+                    role: row.role.clone(),
+                    content: row.content.clone(),
+                    tool_call_id: row.tool_call_id.clone(),
+                    tool_calls,
+                }
+                .to_api_message()
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            messages,
+            included_message_ids: sent.iter().map(|row| row.id).collect(),
+            omitted: start,
+        })
+    }
+}
+
+/// Returns the conversation's messages rendered for the next chat request, trimmed to
+/// its effective history limit.
+pub async fn provider_history(
+    pool: &SqlitePool,
+    conversation_id: i64,
+) -> Result<ProviderHistory, String> {
+    let rows = history_rows(pool, conversation_id).await?;
+    let max = effective_max_history(pool, conversation_id).await?;
+    ProviderHistory::from_rows(&rows, max)
+}
+
+/// Like `provider_history`, but with every stored message regardless of the limit.
+pub async fn full_provider_history(
+    pool: &SqlitePool,
+    conversation_id: i64,
+) -> Result<ProviderHistory, String> {
+    ProviderHistory::from_rows(&history_rows(pool, conversation_id).await?, None)
+}
+
+async fn history_rows(pool: &SqlitePool, conversation_id: i64) -> Result<Vec<HistoryRow>, String> {
+    sqlx::query_as(HISTORY_ROWS_SQL)
+        .bind(conversation_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// A conversation cut at its last user message, ready to be sent again.
+#[derive(Debug, Clone)]
+pub struct LastTurn {
+    /// Every stored message after the user message: the reply to replace, its tool
+    /// exchange and any failed attempts.
+    pub reply_message_ids: Vec<i64>,
+    /// Messages up to and including the user message, trimmed to the history limit.
+    pub history: ProviderHistory,
+}
 
-    let id: i64 = row.get("id");
-    Ok(Conversation {
+/// Returns the conversation up to its last user message, dropping the reply (and any
+/// tool exchange) that followed it, trimmed to the conversation's history limit.
+pub async fn last_turn(pool: &SqlitePool, conversation_id: i64) -> Result<LastTurn, String> {
+    let rows = history_rows(pool, conversation_id).await?;
+    let last_user = rows
+        .iter()
+        .rposition(|row| row.role == Role::User.as_str())
+        .ok_or_else(|| "the conversation has no user message to regenerate from".to_string())?;
+    let user_message_id = rows[last_user].id;
+    let reply_message_ids = sqlx::query_scalar(
+        "SELECT id FROM messages WHERE conversation_id = ? AND id > ? ORDER BY id",
+    )
+    .bind(conversation_id)
+    .bind(user_message_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    let max = effective_max_history(pool, conversation_id).await?;
+    Ok(LastTurn {
+        reply_message_ids,
+        history: ProviderHistory::from_rows(&rows[..=last_user], max)?,
+    })
+}
+
+/// Inserts an empty `pending` assistant message for a reply being regenerated and
+/// returns the status change to broadcast. The reply it replaces stays until
+/// `replace_reply` succeeds.
+pub async fn insert_pending_reply(
+    pool: &SqlitePool,
+    conversation_id: i64,
+    metadata: &serde_json::Value,
+) -> Result<MessageStatusChange, String> {
+    models::validate_metadata(metadata)?;
+    let id = sqlx::query_scalar(
+        "INSERT INTO messages (conversation_id, role, content, metadata, status) VALUES (?, ?, '', ?, ?) RETURNING id",
+    )
+    .bind(conversation_id)
+    .bind(Role::Assistant.as_str())
+    .bind(metadata.to_string())
+    .bind(MessageStatus::Pending.as_str())
+    .fetch_one(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(MessageStatusChange {
         id,
-        title: title.to_string(),
-        created_at: now.clone(),
-        updated_at: now,
+        conversation_id,
+        status: MessageStatus::Pending,
     })
 }
-*/
 
-/*
-Example of how to list conversations
+/// Completes the pending reply `reply_id` with `content` and the model's `reasoning`,
+/// and deletes the messages it
+/// replaces (`LastTurn::reply_message_ids`), in one transaction. Returns the status
+/// change to broadcast.
+pub async fn replace_reply(
+    pool: &SqlitePool,
+    reply_id: i64,
+    content: &str,
+    reasoning: Option<&str>,
+    replaced_ids: &[i64],
+) -> Result<MessageStatusChange, String> {
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let conversation_id: i64 = sqlx::query_scalar(
+        "UPDATE messages SET content = ?, reasoning = ?, status = ? WHERE id = ? AND status = ? RETURNING conversation_id",
+    )
+    .bind(content)
+    .bind(reasoning)
+    .bind(MessageStatus::Complete.as_str())
+    .bind(reply_id)
+    .bind(MessageStatus::Pending.as_str())
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| "the regenerated reply is no longer pending".to_string())?;
+    if !replaced_ids.is_empty() {
+        let placeholders = vec!["?"; replaced_ids.len()].join(", ");
+        let sql =
+            format!("DELETE FROM messages WHERE conversation_id = ? AND id IN ({placeholders})");
+        let mut delete = sqlx::query(&sql).bind(conversation_id);
+        for id in replaced_ids {
+            delete = delete.bind(id);
+        }
+        delete.execute(&mut *tx).await.map_err(|e| e.to_string())?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(MessageStatusChange {
+        id: reply_id,
+        conversation_id,
+        status: MessageStatus::Complete,
+    })
+}
+
+/// Event carrying one `SearchResultPage` of a streamed message search.
+pub const SEARCH_RESULT_EVENT: &str = "search-result";
+/// Terminal event of a streamed message search, carrying `SearchDone`.
+pub const SEARCH_DONE_EVENT: &str = "search-done";
+/// Matches fetched per page of a streamed search.
+pub const SEARCH_PAGE_SIZE: u32 = 50;
+/// Default and maximum caps on the matches a streamed search returns.
+pub const SEARCH_DEFAULT_MAX_RESULTS: u32 = 500;
+pub const SEARCH_MAX_RESULTS_LIMIT: u32 = 5000;
+
+/// A message matching a full-text search, with the matched terms marked in `snippet`.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct MessageSearchHit {
+    pub id: i64,
+    pub conversation_id: i64,
+    pub snippet: String,
+}
+
+/// Payload of `SEARCH_RESULT_EVENT`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResultPage {
+    pub search_id: u64,
+    pub hits: Vec<MessageSearchHit>,
+}
 
-pub async fn list_conversations(pool: &SqlitePool) -> Result<Vec<Conversation>, sqlx::Error> {
-    let rows = sqlx::query(
-        "SELECT id, title, created_at, updated_at FROM conversations ORDER BY updated_at DESC",
+/// Payload of `SEARCH_DONE_EVENT`. `truncated` means the result cap was reached.
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchDone {
+    pub search_id: u64,
+    pub total: u32,
+    pub truncated: bool,
+    pub error: Option<String>,
+}
+
+/// Builds an FTS5 prefix query matching every whitespace-separated term, the same
+/// way the conversation list search does. Terms are quoted so FTS operators in user
+/// input are matched literally. `None` when there is nothing to search for.
+pub fn fts_prefix_query(search: &str) -> Option<String> {
+    let terms: Vec<String> = search
+        .split_whitespace()
+        .map(|t| t.replace(['"', '\''], ""))
+        .filter(|t| !t.is_empty())
+        .map(|t| format!("\"{t}\"*"))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" AND "))
+}
+
+/// Returns up to `limit` messages matching `fts_query`, newest first. Pass the last
+/// id of the previous page as `before_id` to continue.
+pub async fn search_messages_page(
+    pool: &SqlitePool,
+    fts_query: &str,
+    before_id: Option<i64>,
+    limit: u32,
+) -> Result<Vec<MessageSearchHit>, String> {
+    sqlx::query_as::<_, MessageSearchHit>(
+        "SELECT rowid AS id, conversation_id,
+                snippet(messages_fts, 0, '[', ']', '…', 16) AS snippet
+         FROM messages_fts
+         WHERE messages_fts MATCH ? AND rowid < ?
+         ORDER BY rowid DESC
+         LIMIT ?",
     )
+    .bind(fts_query)
+    .bind(before_id.unwrap_or(i64::MAX))
+    .bind(limit)
     .fetch_all(pool)
-    .await?;
-    Ok(rows
-        .into_iter()
-        .map(|row| Conversation {
-            id: row.get("id"),
-            title: row.get::<String, _>("title"),
-            created_at: row.get::<String, _>("created_at"),
-            updated_at: row.get::<String, _>("updated_at"),
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Upper bound on the messages loaded on each side of a `message_context` target.
+pub const MESSAGE_CONTEXT_MAX: u32 = 100;
+
+/// A message returned by `message_context`; `is_target` marks the requested one.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ContextMessage {
+    pub id: i64,
+    pub conversation_id: i64,
+    pub role: String,
+    pub content: String,
+    pub reasoning: Option<String>,
+    pub created_at: String,
+    pub is_target: bool,
+}
+
+/// Loads message `message_id` with up to `before` earlier and `after` later messages
+/// from its conversation, in id order. Each side is capped at `MESSAGE_CONTEXT_MAX`.
+/// The `(conversation_id)` index already orders entries by rowid, so both sides are
+/// index range scans.
+pub async fn message_context(
+    pool: &SqlitePool,
+    message_id: i64,
+    before: u32,
+    after: u32,
+) -> Result<Vec<ContextMessage>, String> {
+    let conversation_id: i64 =
+        sqlx::query_scalar("SELECT conversation_id FROM messages WHERE id = ?")
+            .bind(message_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "message not found".to_string())?;
+    sqlx::query_as::<_, ContextMessage>(
+        "SELECT id, conversation_id, role, content, reasoning, created_at, id = ?1 AS is_target
+         FROM messages
+         WHERE id IN (
+             SELECT id FROM (SELECT id FROM messages WHERE conversation_id = ?2 AND id < ?1
+                             ORDER BY id DESC LIMIT ?3)
+             UNION ALL SELECT ?1
+             UNION ALL
+             SELECT id FROM (SELECT id FROM messages WHERE conversation_id = ?2 AND id > ?1
+                             ORDER BY id LIMIT ?4)
+         )
+         ORDER BY id",
+    )
+    .bind(message_id)
+    .bind(conversation_id)
+    .bind(before.min(MESSAGE_CONTEXT_MAX))
+    .bind(after.min(MESSAGE_CONTEXT_MAX))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Messages loaded per page when exporting a conversation to a file.
+pub const EXPORT_PAGE_SIZE: u32 = 200;
+
+/// A message as written by `conversation_export`.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ExportMessage {
+    pub id: i64,
+    pub role: String,
+    pub content: String,
+    pub reasoning: Option<String>,
+    pub tool_name: Option<String>,
+    pub tool_call_id: Option<String>,
+    /// JSON text of the arguments of a tool call.
+    pub tool_arguments: Option<String>,
+    /// JSON text of the message's metadata.
+    pub metadata: Option<String>,
+    pub status: String,
+    pub created_at: String,
+}
+
+/// Loads a conversation's metadata row.
+pub async fn get_conversation(
+    pool: &SqlitePool,
+    conversation_id: i64,
+) -> Result<Conversation, String> {
+    sqlx::query_as::<_, Conversation>(
+        "SELECT id, title, created_at, updated_at FROM conversations WHERE id = ?",
+    )
+    .bind(conversation_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| "conversation not found".to_string())
+}
+
+/// Number of messages stored for a conversation.
+pub async fn conversation_message_count(
+    pool: &SqlitePool,
+    conversation_id: i64,
+) -> Result<i64, String> {
+    sqlx::query_scalar("SELECT COUNT(*) FROM messages WHERE conversation_id = ?")
+        .bind(conversation_id)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Returns up to `limit` messages of a conversation in id order. Pass the last id of
+/// the previous page as `after_id` to continue.
+pub async fn export_messages_page(
+    pool: &SqlitePool,
+    conversation_id: i64,
+    after_id: Option<i64>,
+    limit: u32,
+) -> Result<Vec<ExportMessage>, String> {
+    sqlx::query_as::<_, ExportMessage>(
+        "SELECT id, role, content, reasoning, tool_name, tool_call_id, tool_arguments,
+                metadata, status, created_at
+         FROM messages
+         WHERE conversation_id = ? AND id > ?
+         ORDER BY id
+         LIMIT ?",
+    )
+    .bind(conversation_id)
+    .bind(after_id.unwrap_or(0))
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Returns `(id, content)` for each of `ids` that exists, in id order.
+pub async fn message_contents(
+    pool: &SqlitePool,
+    ids: &[i64],
+) -> Result<Vec<(i64, String)>, String> {
+    let ids = serde_json::to_string(ids).map_err(|e| e.to_string())?;
+    sqlx::query_as(
+        "SELECT id, content FROM messages WHERE id IN (SELECT value FROM json_each(?)) ORDER BY id",
+    )
+    .bind(ids)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Stores the encoded embedding `model` computed for message `id` from `content`.
+/// Returns false, storing nothing, if the message no longer has that content.
+pub async fn set_message_embedding(
+    pool: &SqlitePool,
+    id: i64,
+    content: &str,
+    model: &str,
+    embedding: &[u8],
+) -> Result<bool, String> {
+    let result = sqlx::query(
+        "UPDATE messages SET embedding = ?, embedding_model = ? WHERE id = ? AND content = ?",
+    )
+    .bind(embedding)
+    .bind(model)
+    .bind(id)
+    .bind(content)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Returns `(id, conversation_id, embedding)` for every message embedded with `model`.
+pub async fn message_embeddings(
+    pool: &SqlitePool,
+    model: &str,
+) -> Result<Vec<(i64, i64, Vec<u8>)>, String> {
+    sqlx::query_as(
+        "SELECT id, conversation_id, embedding FROM messages \
+         WHERE embedding IS NOT NULL AND embedding_model = ?",
+    )
+    .bind(model)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Event emitted with a `MessageStatusChange` whenever a message's status changes.
+pub const MESSAGE_STATUS_CHANGED_EVENT: &str = "message-status-changed";
+
+/// Lifecycle of a message, stored in `messages.status`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageStatus {
+    /// Saved, waiting for generation to start.
+    Pending,
+    /// Tokens are being generated into the message.
+    Streaming,
+    /// Generation finished (also the column default).
+    Complete,
+    /// Generation failed; the message may be retried.
+    Error,
+}
+
+impl MessageStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MessageStatus::Pending => "pending",
+            MessageStatus::Streaming => "streaming",
+            MessageStatus::Complete => "complete",
+            MessageStatus::Error => "error",
+        }
+    }
+}
+
+impl std::str::FromStr for MessageStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "pending" => Ok(MessageStatus::Pending),
+            "streaming" => Ok(MessageStatus::Streaming),
+            "complete" => Ok(MessageStatus::Complete),
+            "error" => Ok(MessageStatus::Error),
+            other => Err(format!(
+                "invalid message status '{other}': expected pending, streaming, complete or error"
+            )),
+        }
+    }
+}
+
+/// Payload of `MESSAGE_STATUS_CHANGED_EVENT`.
+#[derive(Debug, Clone, Serialize)]
+pub struct MessageStatusChange {
+    pub id: i64,
+    pub conversation_id: i64,
+    pub status: MessageStatus,
+}
+
+/// Sets `messages.status` for message `id` and returns the change to broadcast.
+pub async fn set_message_status(
+    pool: &SqlitePool,
+    id: i64,
+    status: MessageStatus,
+) -> Result<MessageStatusChange, String> {
+    let conversation_id: i64 =
+        sqlx::query_scalar("UPDATE messages SET status = ? WHERE id = ? RETURNING conversation_id")
+            .bind(status.as_str())
+            .bind(id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "message not found".to_string())?;
+    Ok(MessageStatusChange {
+        id,
+        conversation_id,
+        status,
+    })
+}
+
+/// Replaces the `app_settings` row with a fresh one, so every setting reverts to its
+/// column default.
+pub async fn reset_app_settings(pool: &SqlitePool) -> Result<(), String> {
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    sqlx::query("DELETE FROM app_settings")
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    sqlx::query("INSERT INTO app_settings (id) VALUES (1)")
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    tx.commit().await.map_err(|e| e.to_string())
+}
+
+/// Deletes every conversation and its messages; the FTS triggers drop their index
+/// entries. Returns the number of conversations deleted.
+pub async fn delete_all_conversations(pool: &SqlitePool) -> Result<u64, String> {
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    sqlx::query("DELETE FROM messages")
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    let deleted = sqlx::query("DELETE FROM conversations")
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?
+        .rows_affected();
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(deleted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    /// In-memory pool with every app migration applied.
+    async fn test_pool() -> SqlitePool {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        for migration in crate::migrations::migrations() {
+            sqlx::raw_sql(migration.sql).execute(&pool).await.unwrap();
+        }
+        pool
+    }
+
+    async fn insert_message(
+        pool: &SqlitePool,
+        conversation_id: i64,
+        role: &str,
+        content: &str,
+    ) -> i64 {
+        sqlx::query_scalar(
+            "INSERT INTO messages (conversation_id, role, content) VALUES (?, ?, ?) RETURNING id",
+        )
+        .bind(conversation_id)
+        .bind(role)
+        .bind(content)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn record_applied_migrations(pool: &SqlitePool) {
+        sqlx::query(
+            "CREATE TABLE _sqlx_migrations (version BIGINT PRIMARY KEY, description TEXT NOT NULL, success BOOLEAN NOT NULL)",
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        for migration in crate::migrations::migrations() {
+            sqlx::query(
+                "INSERT INTO _sqlx_migrations (version, description, success) VALUES (?, ?, 1)",
+            )
+            .bind(migration.version)
+            .bind(migration.description)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn check_schema_accepts_empty_and_fully_migrated_databases() {
+        let empty = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let check = check_schema(&empty).await.unwrap();
+        assert!(!check.legacy);
+        assert_eq!(check.pending.len(), crate::migrations::migrations().len());
+        assert!(check.conflict.is_none());
+        let tables: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sqlite_master")
+            .fetch_one(&empty)
+            .await
+            .unwrap();
+        assert_eq!(tables, 0, "the dry run must be rolled back");
+
+        let migrated = test_pool().await;
+        record_applied_migrations(&migrated).await;
+        let check = check_schema(&migrated).await.unwrap();
+        assert!(check.pending.is_empty());
+        assert!(check.conflict.is_none());
+    }
+
+    #[tokio::test]
+    async fn check_schema_reports_conflicts_with_legacy_tables() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::raw_sql(
+            "CREATE TABLE conversations (id INTEGER PRIMARY KEY, title TEXT, created_at TEXT, updated_at TEXT);
+             CREATE TABLE messages (id INTEGER PRIMARY KEY, conversation_id INTEGER, role TEXT, content TEXT, reasoning TEXT);",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let check = check_schema(&pool).await.unwrap();
+        assert!(check.legacy);
+        let conflict = check.conflict.expect("duplicate reasoning column");
+        assert_eq!(conflict.version, 4);
+        assert!(conflict.legacy);
+    }
+
+    #[tokio::test]
+    async fn init_pool_reports_non_database_file_as_corrupt() {
+        let dir = std::env::temp_dir().join(format!("openchat-db-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_file = dir.join("garbage.db");
+        std::fs::write(&db_file, vec![0x42u8; 4096]).unwrap();
+
+        let err = init_pool(&db_file, &DbOptions::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, DbInitError::Corrupt(_)), "unexpected: {err}");
+
+        let backup = backup_corrupt_db(&db_file).unwrap();
+        assert!(!db_file.exists());
+        assert!(backup.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn branch_copies_messages_up_to_and_including_target() {
+        let pool = test_pool().await;
+        let source: i64 = sqlx::query_scalar(
+            "INSERT INTO conversations (title) VALUES ('Original') RETURNING id",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        insert_message(&pool, source, "user", "first question").await;
+        let target = insert_message(&pool, source, "assistant", "first answer").await;
+        insert_message(&pool, source, "user", "diverging question").await;
+        sqlx::query("UPDATE messages SET metadata = '{\"model\":\"m\"}' WHERE id = ?")
+            .bind(target)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let branched = branch_conversation(&pool, source, target, None)
+            .await
+            .unwrap();
+        assert_ne!(branched.id, source);
+        assert_eq!(branched.title.as_deref(), Some("Original"));
+
+        let contents: Vec<String> = sqlx::query_scalar(
+            "SELECT content FROM messages WHERE conversation_id = ? ORDER BY id",
+        )
+        .bind(branched.id)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(contents, vec!["first question", "first answer"]);
+        let metadata: Vec<Option<String>> = sqlx::query_scalar(
+            "SELECT metadata FROM messages WHERE conversation_id = ? ORDER BY id",
+        )
+        .bind(branched.id)
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(metadata, vec![None, Some(r#"{"model":"m"}"#.to_string())]);
+
+        let fts_hits: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM messages_fts WHERE messages_fts MATCH 'first' AND conversation_id = ?",
+        )
+        .bind(branched.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(fts_hits, 2);
+    }
+
+    #[tokio::test]
+    async fn branch_keeps_tool_exchanges_replayable() {
+        let pool = test_pool().await;
+        let source: i64 =
+            sqlx::query_scalar("INSERT INTO conversations (title) VALUES ('Tools') RETURNING id")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        insert_message(&pool, source, "user", "weather?").await;
+        let call = insert_message(&pool, source, "assistant", "checking").await;
+        let args = serde_json::json!({ "city": "Oslo" });
+        let result = append_tool_result(&pool, call, "call_1", "get_weather", Some(&args), "sunny")
+            .await
+            .unwrap();
+
+        let branched = branch_conversation(&pool, source, result, None)
+            .await
+            .unwrap();
+        let original = provider_history(&pool, source).await.unwrap();
+        let copy = provider_history(&pool, branched.id).await.unwrap();
+        assert_eq!(copy.messages, original.messages);
+        assert_eq!(copy.messages.len(), 3);
+        assert!(copy
+            .included_message_ids
+            .iter()
+            .all(|id| !original.included_message_ids.contains(id)));
+    }
+
+    #[tokio::test]
+    async fn branch_rejects_message_from_other_conversation() {
+        let pool = test_pool().await;
+        let a: i64 =
+            sqlx::query_scalar("INSERT INTO conversations (title) VALUES ('A') RETURNING id")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        let b: i64 =
+            sqlx::query_scalar("INSERT INTO conversations (title) VALUES ('B') RETURNING id")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        let foreign = insert_message(&pool, b, "user", "hello").await;
+
+        let err = branch_conversation(&pool, a, foreign, Some("Fork"))
+            .await
+            .unwrap_err();
+        assert_eq!(err, "message not found in conversation");
+    }
+
+    #[tokio::test]
+    async fn provider_history_keeps_the_newest_messages_within_the_limit() {
+        let pool = test_pool().await;
+        let conversation: i64 =
+            sqlx::query_scalar("INSERT INTO conversations (title) VALUES ('Chat') RETURNING id")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        let first = insert_message(&pool, conversation, "user", "weather?").await;
+        let call = insert_message(&pool, conversation, "assistant", "checking").await;
+        let result = append_tool_result(&pool, call, "call_1", "get_weather", None, "sunny")
+            .await
+            .unwrap();
+        let answer = insert_message(&pool, conversation, "assistant", "it is sunny").await;
+
+        let history = provider_history(&pool, conversation).await.unwrap();
+        assert_eq!(
+            history.included_message_ids,
+            vec![first, call, result, answer]
+        );
+        assert_eq!(history.omitted, 0);
+
+        settings::set_setting(&pool, Setting::MaxHistoryMessages, Some(3_i64))
+            .await
+            .unwrap();
+        let history = provider_history(&pool, conversation).await.unwrap();
+        assert_eq!(history.included_message_ids, vec![call, result, answer]);
+        assert_eq!(history.omitted, 1);
+
+        // A tool result is never sent without the assistant message that requested it.
+        set_conversation_max_history(&pool, conversation, Some(2))
+            .await
+            .unwrap();
+        let history = provider_history(&pool, conversation).await.unwrap();
+        assert_eq!(history.included_message_ids, vec![answer]);
+        assert_eq!(history.omitted, 3);
+
+        set_conversation_max_history(&pool, conversation, Some(0))
+            .await
+            .unwrap();
+        assert_eq!(
+            provider_history(&pool, conversation).await.unwrap().omitted,
+            0
+        );
+        assert!(set_conversation_max_history(&pool, conversation, Some(-1))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn effective_system_prompt_prefers_conversation_then_global() {
+        let pool = test_pool().await;
+        let conversation_id: i64 =
+            sqlx::query_scalar("INSERT INTO conversations (title) VALUES ('a') RETURNING id")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        settings::set_setting(&pool, Setting::SystemPrompt, Some("global".to_string()))
+            .await
+            .unwrap();
+
+        let effective = effective_system_prompt(&pool, conversation_id)
+            .await
+            .unwrap();
+        assert_eq!(effective.as_deref(), Some("global"));
+
+        set_conversation_system_prompt(&pool, conversation_id, Some("pirate"))
+            .await
+            .unwrap();
+        let effective = effective_system_prompt(&pool, conversation_id)
+            .await
+            .unwrap();
+        assert_eq!(effective.as_deref(), Some("pirate"));
+
+        set_conversation_system_prompt(&pool, conversation_id, Some(""))
+            .await
+            .unwrap();
+        assert_eq!(
+            effective_system_prompt(&pool, conversation_id)
+                .await
+                .unwrap(),
+            None
+        );
+        assert!(set_conversation_system_prompt(&pool, 999, None)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn set_message_status_updates_row_and_rejects_unknown_ids() {
+        let pool = test_pool().await;
+        let conversation: i64 =
+            sqlx::query_scalar("INSERT INTO conversations (title) VALUES ('s') RETURNING id")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        let id = insert_message(&pool, conversation, "assistant", "").await;
+
+        let change = set_message_status(&pool, id, MessageStatus::Streaming)
+            .await
+            .unwrap();
+        assert_eq!(change.conversation_id, conversation);
+        let stored: String = sqlx::query_scalar("SELECT status FROM messages WHERE id = ?")
+            .bind(id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(
+            stored.parse::<MessageStatus>(),
+            Ok(MessageStatus::Streaming)
+        );
+
+        assert!(set_message_status(&pool, id + 1, MessageStatus::Error)
+            .await
+            .is_err());
+        assert!("done".parse::<MessageStatus>().is_err());
+    }
+
+    #[tokio::test]
+    async fn reset_clears_settings_and_conversations() {
+        let pool = test_pool().await;
+        crate::settings::set_setting(
+            &pool,
+            crate::settings::Setting::SystemPrompt,
+            Some("be brief".to_string()),
+        )
+        .await
+        .unwrap();
+        let conversation: i64 =
+            sqlx::query_scalar("INSERT INTO conversations (title) VALUES ('Chat') RETURNING id")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        insert_message(&pool, conversation, "user", "searchable text").await;
+
+        reset_app_settings(&pool).await.unwrap();
+        let prompt: Option<String> =
+            crate::settings::get_setting(&pool, crate::settings::Setting::SystemPrompt)
+                .await
+                .unwrap();
+        assert_eq!(prompt, None);
+
+        assert_eq!(delete_all_conversations(&pool).await.unwrap(), 1);
+        let remaining: i64 = sqlx::query_scalar(
+            "SELECT (SELECT COUNT(*) FROM messages) + (SELECT COUNT(*) FROM messages_fts WHERE messages_fts MATCH 'searchable')",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(remaining, 0);
+    }
+
+    #[tokio::test]
+    async fn rejects_unknown_roles_and_reasoning_outside_assistant_messages() {
+        let pool = test_pool().await;
+        let conversation: i64 =
+            sqlx::query_scalar("INSERT INTO conversations (title) VALUES ('Chat') RETURNING id")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+
+        add_message(
+            &pool,
+            conversation,
+            Role::Assistant,
+            "answer",
+            Some("thoughts"),
+            None,
+        )
+        .await
+        .unwrap();
+        let err = add_message(
+            &pool,
+            conversation,
+            Role::User,
+            "question",
+            Some("thoughts"),
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert!(
+            err.contains("only allowed on assistant"),
+            "unexpected: {err}"
+        );
+        assert!("moderator".parse::<Role>().is_err());
+
+        // Writes that bypass `add_message` hit the same rules in the database.
+        let raw = sqlx::query(
+            "INSERT INTO messages (conversation_id, role, content) VALUES (?, 'moderator', 'x')",
+        )
+        .bind(conversation)
+        .execute(&pool)
+        .await;
+        assert!(raw.is_err());
+        let raw = sqlx::query(
+            "INSERT INTO messages (conversation_id, role, content, reasoning) VALUES (?, 'user', 'x', 'y')",
+        )
+        .bind(conversation)
+        .execute(&pool)
+        .await;
+        assert!(raw.is_err());
+    }
+
+    /// Returns the metadata stored on message `message_id`, if any.
+    async fn message_metadata(
+        pool: &SqlitePool,
+        message_id: i64,
+    ) -> Result<Option<serde_json::Value>, String> {
+        let raw: Option<Option<String>> =
+            sqlx::query_scalar("SELECT metadata FROM messages WHERE id = ?")
+                .bind(message_id)
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| e.to_string())?;
+        let raw = raw.ok_or_else(|| "message not found".to_string())?;
+        raw.map(|text| {
+            serde_json::from_str(&text).map_err(|e| format!("invalid message metadata: {e}"))
         })
-        .collect())
+        .transpose()
+    }
+
+    #[tokio::test]
+    async fn stores_message_metadata_objects() {
+        let pool = test_pool().await;
+        let conversation: i64 =
+            sqlx::query_scalar("INSERT INTO conversations (title) VALUES ('Chat') RETURNING id")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        let metadata = serde_json::json!({ "attachments": [{ "path": "/tmp/notes.md" }] });
+
+        let id = add_message(
+            &pool,
+            conversation,
+            Role::User,
+            "see attached",
+            None,
+            Some(&metadata),
+        )
+        .await
+        .unwrap();
+        assert_eq!(message_metadata(&pool, id).await.unwrap(), Some(metadata));
+
+        let plain = add_message(&pool, conversation, Role::User, "hi", None, None)
+            .await
+            .unwrap();
+        assert_eq!(message_metadata(&pool, plain).await.unwrap(), None);
+
+        let err = add_message(
+            &pool,
+            conversation,
+            Role::User,
+            "x",
+            None,
+            Some(&serde_json::json!(["not", "an", "object"])),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.contains("JSON object"), "unexpected: {err}");
+    }
+
+    #[tokio::test]
+    async fn replace_reply_swaps_everything_after_the_last_user_message() {
+        let pool = test_pool().await;
+        let conversation: i64 =
+            sqlx::query_scalar("INSERT INTO conversations (title) VALUES ('Chat') RETURNING id")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        add_message(&pool, conversation, Role::User, "first", None, None)
+            .await
+            .unwrap();
+        add_message(&pool, conversation, Role::Assistant, "one", None, None)
+            .await
+            .unwrap();
+        let question = add_message(&pool, conversation, Role::User, "second", None, None)
+            .await
+            .unwrap();
+        let answer = add_message(&pool, conversation, Role::Assistant, "two", None, None)
+            .await
+            .unwrap();
+        append_tool_result(&pool, answer, "call_1", "lookup", None, "result")
+            .await
+            .unwrap();
+
+        let turn = last_turn(&pool, conversation).await.unwrap();
+        assert!(turn.reply_message_ids.iter().all(|id| *id > question));
+        assert_eq!(turn.history.messages.len(), 3);
+        assert_eq!(turn.history.messages[2]["content"], "second");
+
+        assert_eq!(turn.reply_message_ids.len(), 2);
+
+        // A failed attempt stays out of the history and leaves the old reply alone.
+        let metadata = serde_json::json!({ "provider": "openai", "model": "gpt-4o" });
+        let failed = insert_pending_reply(&pool, conversation, &metadata)
+            .await
+            .unwrap();
+        set_message_status(&pool, failed.id, MessageStatus::Error)
+            .await
+            .unwrap();
+        let messages = provider_history(&pool, conversation)
+            .await
+            .unwrap()
+            .messages;
+        assert_eq!(messages.len(), 5);
+        assert_eq!(messages[3]["content"], "two");
+
+        let turn = last_turn(&pool, conversation).await.unwrap();
+        assert_eq!(turn.reply_message_ids.len(), 3);
+        let pending = insert_pending_reply(&pool, conversation, &metadata)
+            .await
+            .unwrap();
+        let done = replace_reply(
+            &pool,
+            pending.id,
+            "retry",
+            Some("thought again"),
+            &turn.reply_message_ids,
+        )
+        .await
+        .unwrap();
+        assert_eq!(done.status, MessageStatus::Complete);
+        let messages = provider_history(&pool, conversation)
+            .await
+            .unwrap()
+            .messages;
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[3]["content"], "retry");
+        let reasoning: Option<String> =
+            sqlx::query_scalar("SELECT reasoning FROM messages WHERE id = ?")
+                .bind(pending.id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(reasoning.as_deref(), Some("thought again"));
+        assert_eq!(
+            message_metadata(&pool, pending.id).await.unwrap(),
+            Some(metadata)
+        );
+        assert!(replace_reply(&pool, pending.id, "again", None, &[])
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn tool_results_follow_their_assistant_message_and_render_for_the_api() {
+        let pool = test_pool().await;
+        let conversation: i64 =
+            sqlx::query_scalar("INSERT INTO conversations (title) VALUES ('Chat') RETURNING id")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        let question = insert_message(&pool, conversation, "user", "weather?").await;
+        let call = insert_message(&pool, conversation, "assistant", "checking").await;
+
+        let args = serde_json::json!({ "city": "Oslo" });
+        append_tool_result(&pool, call, "call_1", "get_weather", Some(&args), "sunny")
+            .await
+            .unwrap();
+        assert!(
+            append_tool_result(&pool, question, "call_2", "get_weather", None, "rain")
+                .await
+                .is_err()
+        );
+
+        let messages = provider_history(&pool, conversation)
+            .await
+            .unwrap()
+            .messages;
+        assert_eq!(
+            messages[1],
+            serde_json::json!({
+                "role": "assistant",
+                "content": "checking",
+                "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": { "name": "get_weather", "arguments": r#"{"city":"Oslo"}"# },
+                }],
+            })
+        );
+        assert_eq!(
+            messages[2],
+            serde_json::json!({ "role": "tool", "tool_call_id": "call_1", "content": "sunny" })
+        );
+    }
+
+    #[tokio::test]
+    async fn search_pages_newest_first_and_quotes_terms() {
+        let pool = test_pool().await;
+        let conversation: i64 =
+            sqlx::query_scalar("INSERT INTO conversations (title) VALUES ('Chat') RETURNING id")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        let mut ids = Vec::new();
+        for i in 0..5 {
+            ids.push(
+                insert_message(&pool, conversation, "user", &format!("rust-lang tip {i}")).await,
+            );
+        }
+        insert_message(&pool, conversation, "user", "unrelated").await;
+
+        let query = fts_prefix_query("  rust-lang  ti ").unwrap();
+        let first = search_messages_page(&pool, &query, None, 3).await.unwrap();
+        let rest = search_messages_page(&pool, &query, first.last().map(|h| h.id), 3)
+            .await
+            .unwrap();
+        let found: Vec<i64> = first.iter().chain(&rest).map(|h| h.id).collect();
+        ids.reverse();
+        assert_eq!(found, ids);
+        assert!(first[0].snippet.contains('['));
+        assert_eq!(fts_prefix_query(" \"' "), None);
+    }
+
+    #[tokio::test]
+    async fn embeddings_are_per_model_and_dropped_on_edit() {
+        let pool = test_pool().await;
+        let conversation: i64 =
+            sqlx::query_scalar("INSERT INTO conversations (title) VALUES ('Search') RETURNING id")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        let a = insert_message(&pool, conversation, "user", "first").await;
+        let b = insert_message(&pool, conversation, "user", "second").await;
+        assert!(
+            set_message_embedding(&pool, a, "first", "m1", &[1, 2, 3, 4])
+                .await
+                .unwrap()
+        );
+        assert!(
+            set_message_embedding(&pool, b, "second", "m2", &[5, 6, 7, 8])
+                .await
+                .unwrap()
+        );
+        assert!(!set_message_embedding(&pool, b, "stale", "m1", &[0; 4])
+            .await
+            .unwrap());
+
+        let ids =
+            |rows: Vec<(i64, i64, Vec<u8>)>| rows.into_iter().map(|r| r.0).collect::<Vec<_>>();
+        assert_eq!(ids(message_embeddings(&pool, "m1").await.unwrap()), [a]);
+        assert_eq!(ids(message_embeddings(&pool, "m2").await.unwrap()), [b]);
+
+        sqlx::query("UPDATE messages SET status = 'complete' WHERE id = ?")
+            .bind(a)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(ids(message_embeddings(&pool, "m1").await.unwrap()), [a]);
+        sqlx::query("UPDATE messages SET content = 'edited' WHERE id = ?")
+            .bind(a)
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert!(message_embeddings(&pool, "m1").await.unwrap().is_empty());
+        let model: Option<String> =
+            sqlx::query_scalar("SELECT embedding_model FROM messages WHERE id = ?")
+                .bind(a)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(model, None);
+    }
+
+    #[tokio::test]
+    async fn message_context_stays_within_the_conversation() {
+        let pool = test_pool().await;
+        let mut ids = Vec::new();
+        for title in ["A", "B"] {
+            let conversation: i64 =
+                sqlx::query_scalar("INSERT INTO conversations (title) VALUES (?) RETURNING id")
+                    .bind(title)
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap();
+            for i in 0..4 {
+                ids.push(insert_message(&pool, conversation, "user", &format!("{title}{i}")).await);
+            }
+        }
+
+        let context = message_context(&pool, ids[1], 5, 1).await.unwrap();
+        let contents: Vec<&str> = context.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["A0", "A1", "A2"]);
+        let targets: Vec<bool> = context.iter().map(|m| m.is_target).collect();
+        assert_eq!(targets, [false, true, false]);
+        assert!(message_context(&pool, -1, 1, 1).await.is_err());
+    }
 }
-*/
@@ -0,0 +1,144 @@
+//! Message embeddings for semantic search. Vectors come from the MLC server's
+//! OpenAI-compatible `/v1/embeddings` endpoint and are stored per message as
+//! little-endian `f32` BLOBs, alongside the model that produced them; similarity is
+//! computed here, not in SQLite.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Per-request timeout for an embeddings call.
+const EMBEDDINGS_TIMEOUT: Duration = Duration::from_secs(60);
+/// Inputs sent per embeddings request.
+pub const EMBEDDING_BATCH_SIZE: usize = 32;
+
+/// A semantic search match.
+#[derive(Debug, Clone, Serialize)]
+pub struct SemanticHit {
+    pub id: i64,
+    pub conversation_id: i64,
+    pub score: f32,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingsResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+/// Embeds `inputs` with `model` via `{base_url}/embeddings`, returning vectors in
+/// input order.
+pub async fn embed_texts(
+    base_url: &str,
+    model: &str,
+    inputs: &[String],
+) -> Result<Vec<Vec<f32>>, String> {
+    let resp = crate::http_client::shared_client()
+        .post(format!("{base_url}/embeddings"))
+        .timeout(EMBEDDINGS_TIMEOUT)
+        .json(&serde_json::json!({ "model": model, "input": inputs }))
+        .send()
+        .await
+        .map_err(|e| format!("embeddings request failed: {e}"))?;
+    if !resp.status().is_success() {
+        return Err(format!(
+            "embeddings request failed: HTTP {} (the loaded model may not support embeddings)",
+            resp.status()
+        ));
+    }
+    let mut body: EmbeddingsResponse = resp
+        .json()
+        .await
+        .map_err(|e| format!("invalid embeddings response: {e}"))?;
+    if body.data.len() != inputs.len() {
+        return Err(format!(
+            "embeddings response has {} vectors for {} inputs",
+            body.data.len(),
+            inputs.len()
+        ));
+    }
+    body.data.sort_by_key(|d| d.index);
+    Ok(body.data.into_iter().map(|d| d.embedding).collect())
+}
+
+/// Serializes a vector for the `messages.embedding` column.
+pub fn encode_vector(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+/// Parses a stored vector; `None` if the BLOB isn't a whole number of `f32`s.
+pub fn decode_vector(bytes: &[u8]) -> Option<Vec<f32>> {
+    let chunks = bytes.chunks_exact(4);
+    if !chunks.remainder().is_empty() {
+        return None;
+    }
+    Some(
+        chunks
+            .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+            .collect(),
+    )
+}
+
+/// Cosine similarity, or `None` for mismatched dimensions or a zero vector.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> Option<f32> {
+    if a.len() != b.len() || a.is_empty() {
+        return None;
+    }
+    let (mut dot, mut norm_a, mut norm_b) = (0.0f32, 0.0f32, 0.0f32);
+    for (x, y) in a.iter().zip(b) {
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    let denom = norm_a.sqrt() * norm_b.sqrt();
+    (denom > 0.0).then(|| dot / denom)
+}
+
+/// Scores every `(id, conversation_id, embedding)` row against `query` and returns
+/// the `k` best, highest first. Callers pass only rows embedded with the query's
+/// model; rows with unreadable vectors or a different dimension are skipped.
+pub fn top_k(query: &[f32], rows: Vec<(i64, i64, Vec<u8>)>, k: usize) -> Vec<SemanticHit> {
+    let mut hits: Vec<SemanticHit> = rows
+        .into_iter()
+        .filter_map(|(id, conversation_id, blob)| {
+            let score = cosine_similarity(query, &decode_vector(&blob)?)?;
+            Some(SemanticHit {
+                id,
+                conversation_id,
+                score,
+            })
+        })
+        .collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(k);
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_vector, encode_vector, top_k};
+
+    #[test]
+    fn ranks_stored_vectors_by_cosine_similarity() {
+        assert_eq!(
+            decode_vector(&encode_vector(&[1.5, -2.0])).unwrap(),
+            [1.5, -2.0]
+        );
+        assert_eq!(decode_vector(&[0, 1, 2]), None);
+
+        let rows = vec![
+            (1, 10, encode_vector(&[0.0, 1.0])),
+            (2, 10, encode_vector(&[1.0, 0.1])),
+            (3, 11, encode_vector(&[1.0, 0.0, 0.0])),
+            (4, 11, encode_vector(&[0.0, 0.0])),
+        ];
+        let hits = top_k(&[1.0, 0.0], rows, 5);
+        let ids: Vec<i64> = hits.iter().map(|h| h.id).collect();
+        assert_eq!(ids, [2, 1]);
+        assert!(hits[0].score > 0.99);
+    }
+}
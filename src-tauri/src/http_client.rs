@@ -0,0 +1,26 @@
+//! Process-wide `reqwest::Client` so health polling, Hugging Face lookups and MCP HTTP
+//! sessions share one connection pool. Callers set per-request timeouts.
+
+use once_cell::sync::Lazy;
+use std::time::Duration;
+
+/// Upper bound on establishing a TCP/TLS connection; request timeouts are per call.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Idle pooled connections are closed after this long.
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+static SHARED_CLIENT: Lazy<reqwest::Client> = Lazy::new(|| {
+    reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+        .build()
+        .unwrap_or_else(|e| {
+            log::warn!("http_client: failed to build configured client, using defaults - {e}");
+            reqwest::Client::new()
+        })
+});
+
+/// Returns the shared client. Cloning it is cheap and shares the pool.
+pub fn shared_client() -> &'static reqwest::Client {
+    &SHARED_CLIENT
+}
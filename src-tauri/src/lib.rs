@@ -2,21 +2,48 @@
 
 // --- Standard library imports ---
 use std::fs;
+use std::time::Duration;
 
 // --- External crate imports ---
 use std::sync::Arc;
 
-use tauri::{Manager, RunEvent, WindowEvent};
+use tauri::{Emitter, Manager, RunEvent, WindowEvent};
 
 // --- Internal module imports ---
+mod chat_provider;
 mod commands;
+mod conversation_export;
 mod db;
+mod embeddings;
+mod http_client;
+mod log_export;
 pub mod mcp;
 mod menu;
 mod migrations;
 mod mlc_server;
+mod model_card;
+mod model_catalog;
 mod model_download;
 mod model_store;
+mod models;
+mod process_info;
+mod safe_mode;
+mod secrets;
+mod self_test;
+mod settings;
+mod telemetry;
+mod theme;
+
+/// Subdirectory of app data holding the opt-in telemetry log.
+const TELEMETRY_DIR_NAME: &str = "telemetry";
+
+/// Longest app exit waits for the server and MCP children to stop gracefully before
+/// force-killing them.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Longest each MCP child gets to close before it is force-killed; shorter than
+/// `SHUTDOWN_TIMEOUT` so the MCP shutdown reports before the overall deadline.
+const MCP_CHILD_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
 
 /// Name of the SQLite database file used by the app.
 const DB_FILE_NAME: &str = "chatchat3.db";
@@ -50,6 +77,16 @@ pub fn run() {
                 .build(),
         )
         .setup(|app| {
+            // Safe mode skips auto-starting the server and auto-connecting MCP sessions
+            let safe_mode = safe_mode::SafeMode::from_env();
+            if safe_mode.0 {
+                log::warn!(
+                    "Safe mode is active ({}); skipping server auto-start and MCP auto-connect",
+                    safe_mode::SAFE_MODE_ENV
+                );
+            }
+            app.manage(safe_mode);
+
             // --- Database setup ---
             let app_data_dir = app
                 .path()
@@ -57,15 +94,105 @@ pub fn run() {
                 .map_err(|e| format!("Failed to get app data dir: {e}"))?;
             setup_sqlite_pool(app, &app_data_dir)?;
 
+            // Apply a relocated model cache before anything resolves model paths
+            let pool = app.state::<sqlx::SqlitePool>().inner().clone();
+            tauri::async_runtime::block_on(model_store::load_hub_dir_override(&pool));
+
+            // Move a plaintext OpenAI key from older versions into the secret store
+            let secrets_pool = pool.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = secrets::migrate_legacy_openai_api_key(&secrets_pool).await {
+                    log::warn!("Failed to migrate the legacy OpenAI API key: {e}");
+                }
+            });
+
+            // Opt-in telemetry log; stays off until the stored flag says otherwise
+            app.manage(telemetry::TelemetryLog::new(
+                app_data_dir.join(TELEMETRY_DIR_NAME),
+            ));
+            let telemetry_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                telemetry::load_enabled(&telemetry_handle).await;
+            });
+
+            // Apply the stored theme to the window chrome
+            let theme_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                theme::load_theme(&theme_handle).await;
+            });
+
+            // Registry of in-flight and paused model downloads
+            app.manage(Arc::new(model_download::DownloadRegistry::default()));
+
             // Set up MLC server manager in app state
             let handle = app.handle().clone();
             let manager: Arc<crate::mlc_server::MLCServerManager> =
                 Arc::new(crate::mlc_server::MLCServerManager::new(handle));
             app.manage(manager);
 
-            // Set up MCP manager state
-            let mcp_manager = crate::mcp::McpManager::new();
-            app.manage(mcp_manager);
+            // Set up MCP manager state, forwarding stderr and server logs to the frontend and
+            // connection changes to the telemetry log
+            let mcp_events_handle = app.handle().clone();
+            let mcp_manager =
+                crate::mcp::McpManager::with_event_sink(Arc::new(move |event| match event {
+                    crate::mcp::events::McpEvent::Stderr(payload) => {
+                        let _ = mcp_events_handle
+                            .emit(crate::mcp::events::MCP_SERVER_STDERR_EVENT, payload);
+                    }
+                    crate::mcp::events::McpEvent::Log(payload) => {
+                        let _ = mcp_events_handle.emit(crate::mcp::events::MCP_LOG_EVENT, payload);
+                    }
+                    crate::mcp::events::McpEvent::Connected { id } => telemetry::record(
+                        &mcp_events_handle,
+                        telemetry::TelemetryEvent::McpConnected { server_id: id },
+                    ),
+                    crate::mcp::events::McpEvent::Disconnected { id } => telemetry::record(
+                        &mcp_events_handle,
+                        telemetry::TelemetryEvent::McpDisconnected { server_id: id },
+                    ),
+                }));
+            app.manage(mcp_manager.clone());
+
+            // Restore opt-in MCP debug mode and the session cap, then warm-connect enabled
+            // servers if opted in (never in safe mode). Failures are reported per server and never block
+            // startup.
+            let autoconnect_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                match settings::get_setting::<bool>(&pool, settings::Setting::McpDebugEnabled).await
+                {
+                    Ok(enabled) => mcp_manager.set_debug(enabled.unwrap_or(false)),
+                    Err(e) => log::debug!("mcp: settings unavailable - {e}"),
+                }
+                if let Ok(Some(max)) =
+                    settings::get_setting::<i64>(&pool, settings::Setting::McpMaxSessions).await
+                {
+                    mcp_manager.set_max_sessions(max.max(1) as usize);
+                }
+                if safe_mode.0 {
+                    return;
+                }
+                let autoconnect =
+                    settings::get_setting::<bool>(&pool, settings::Setting::McpAutoconnectOnLaunch)
+                        .await;
+                if !matches!(autoconnect, Ok(Some(true))) {
+                    return;
+                }
+                log::info!("mcp: connecting enabled servers at launch");
+                let report = |result: &crate::mcp::McpReconnectResult| {
+                    let _ =
+                        autoconnect_handle.emit(crate::mcp::events::MCP_AUTOCONNECT_EVENT, result);
+                };
+                match crate::mcp::session::connect_enabled_mcp_servers(&mcp_manager, &pool, report)
+                    .await
+                {
+                    Ok(results) => log::info!(
+                        "mcp: connected {}/{} servers at launch",
+                        results.iter().filter(|r| r.ok).count(),
+                        results.len()
+                    ),
+                    Err(e) => log::warn!("mcp: autoconnect at launch failed - {e}"),
+                }
+            });
 
             // --- Application menu ---
             menu::MenuManager::setup_app_menu(app)?;
@@ -77,14 +204,131 @@ pub fn run() {
             commands::mlc_get_status,
             commands::mlc_start,
             commands::mlc_restart,
+            commands::mlc_has_been_ready,
+            commands::mlc_get_load_progress,
+            commands::mlc_get_log_level,
+            commands::mlc_set_log_level,
+            commands::mlc_get_max_tokens,
+            commands::mlc_set_max_tokens,
+            commands::mlc_get_sampling_defaults,
+            commands::mlc_set_sampling_defaults,
+            commands::mlc_get_host,
+            commands::mlc_set_host,
+            commands::mlc_get_watchdog_enabled,
+            commands::mlc_set_watchdog_enabled,
+            commands::mlc_request_started,
+            commands::mlc_request_finished,
+            commands::mlc_get_kill_orphans,
+            commands::mlc_set_kill_orphans,
+            commands::mlc_admin_request,
+            commands::diagnose_port,
+            commands::find_orphaned_servers,
+            commands::kill_orphaned_servers,
+            // System health
+            commands::get_system_health,
+            commands::self_test,
+            // Conversations
+            commands::branch_conversation,
+            commands::export_conversation_to_file,
+            commands::set_message_status,
+            commands::get_conversation_summaries,
+            commands::add_message,
+            commands::regenerate_with,
+            commands::append_tool_result,
+            commands::get_provider_messages,
+            commands::get_provider_history,
+            commands::count_conversation_tokens,
+            commands::get_conversation_max_history,
+            commands::set_conversation_max_history,
+            commands::get_conversation_generation_params,
+            commands::get_effective_generation_params,
+            commands::set_conversation_generation_params,
+            commands::get_max_history_messages,
+            commands::set_max_history_messages,
+            commands::search_messages_stream,
+            commands::get_message_context,
+            commands::embed_message,
+            commands::embed_messages,
+            commands::semantic_search,
+            commands::get_conversation_system_prompt,
+            commands::set_conversation_system_prompt,
+            commands::get_effective_system_prompt,
+            // Database maintenance
+            commands::optimize_database,
+            commands::check_database_schema,
+            // App reset
+            commands::reset_app_state,
             // MCP commands
             commands::mcp_check_server,
+            commands::mcp_transport_schemas,
             commands::mcp_list_tools,
+            commands::mcp_list_all_tools,
             commands::mcp_call_tool,
+            commands::mcp_call_by_qualified_name,
+            commands::mcp_complete,
+            commands::mcp_refresh_credentials,
+            commands::mcp_reconnect_all,
+            commands::mcp_reconnect_stale,
+            commands::mcp_disconnect_disabled,
+            commands::mcp_capabilities,
+            commands::mcp_set_log_level,
+            commands::mcp_last_error,
+            commands::mcp_measure_latency,
+            commands::mcp_last_exchange,
+            commands::mcp_get_debug_enabled,
+            commands::mcp_set_debug_enabled,
+            commands::mcp_get_autoconnect_on_launch,
+            commands::mcp_set_autoconnect_on_launch,
+            commands::mcp_get_max_sessions,
+            commands::mcp_set_max_sessions,
+            commands::set_server_priority,
+            commands::import_mcp_servers,
+            commands::export_mcp_servers,
+            // Secrets
+            commands::set_secret,
+            commands::has_secret,
+            commands::delete_secret,
+            commands::set_openai_api_key,
+            commands::clear_openai_api_key,
+            commands::has_openai_api_key,
+            commands::test_cloud_provider,
+            commands::get_openai_max_attempts,
+            commands::set_openai_max_attempts,
+            // Build features
+            commands::local_inference_available,
+            commands::is_safe_mode,
             // Environment variables
             commands::get_env_var,
-            // Model download
+            // Models
+            commands::get_model_info,
+            commands::verify_model,
+            commands::repair_model,
+            commands::get_hub_dir,
+            commands::move_model_cache,
+            commands::resolve_model_path,
+            commands::list_recommended_models,
+            commands::fetch_model_card,
+            commands::check_model_compatibility,
             commands::download_model,
+            commands::abort_and_remove_download,
+            commands::cancel_download,
+            commands::pause_download,
+            commands::resume_download,
+            commands::get_download_status,
+            commands::get_download_bandwidth_limit,
+            commands::set_download_bandwidth_limit,
+            // Log export
+            commands::export_logs,
+            // Telemetry
+            commands::get_telemetry_enabled,
+            commands::set_telemetry_enabled,
+            commands::export_telemetry_log,
+            commands::get_provider_request_logging,
+            commands::set_provider_request_logging,
+            commands::log_chat_request,
+            // Appearance
+            commands::get_theme,
+            commands::set_theme,
         ])
         .on_menu_event(|app, event| {
             menu::MenuManager::handle_menu_event(app, event.id().as_ref());
@@ -110,25 +354,103 @@ fn setup_sqlite_pool(app: &mut tauri::App, app_data_dir: &std::path::Path) -> Re
     // Ensure the app data directory exists
     fs::create_dir_all(app_data_dir).map_err(|e| format!("Failed to create app data dir: {e}"))?;
     let db_file = app_data_dir.join(DB_FILE_NAME);
-    let pool = tauri::async_runtime::block_on(db::init_pool(&db_file))
-        .map_err(|_| "Failed to create SqlitePool".to_string())?;
+    let pool = tauri::async_runtime::block_on(db::init_pool(&db_file, &db::DbOptions::default()))
+        .map_err(|err| handle_db_init_error(&db_file, &err))?;
     app.manage(pool);
     Ok(())
 }
 
+/// Logs a database startup failure and returns a user-facing explanation. A corrupt
+/// file is moved aside so the next launch can start with a fresh database.
+fn handle_db_init_error(db_file: &std::path::Path, err: &db::DbInitError) -> String {
+    let message = match err {
+        db::DbInitError::Locked => format!(
+            "The chat database at {} is locked by another process. Quit any other running copy of OpenChat and try again.",
+            db_file.display()
+        ),
+        db::DbInitError::Corrupt(_) => match db::backup_corrupt_db(db_file) {
+            Ok(backup) => format!(
+                "The chat database was damaged ({err}). It has been moved to {} and a new database will be created the next time OpenChat starts.",
+                backup.display()
+            ),
+            Err(backup_err) => format!(
+                "The chat database at {} is damaged ({err}) and could not be backed up: {backup_err}",
+                db_file.display()
+            ),
+        },
+        db::DbInitError::SchemaConflict(conflict) if conflict.legacy => format!(
+            "The chat database at {} was created by an older version of OpenChat and cannot be upgraded ({err}). Move the file elsewhere to start with a new database; your data in it is untouched.",
+            db_file.display()
+        ),
+        db::DbInitError::SchemaConflict(_) => format!(
+            "The chat database at {} does not match what this version of OpenChat expects ({err}). Your data was left untouched.",
+            db_file.display()
+        ),
+        db::DbInitError::MigrationFailed { .. } => format!(
+            "Upgrading the chat database failed: {err}. Your data at {} was left untouched.",
+            db_file.display()
+        ),
+        db::DbInitError::Other(_) => format!("Failed to open the chat database: {err}"),
+    };
+    log::error!("{message}");
+    message
+}
+
 /// Handles cleanup when the main window is destroyed (shuts down server).
 fn handle_window_destroyed(_window: &tauri::Window) {
     log::info!("Window destroyed...");
 }
 
-/// Handles cleanup when the application is exiting (shuts down server).
+/// Handles cleanup when the application is exiting (shuts down the server and MCP
+/// children). Never blocks longer than `SHUTDOWN_TIMEOUT`: whatever has not stopped by
+/// then is killed by pid.
 fn handle_app_exit(app: &tauri::AppHandle) {
-    log::info!("App exiting; stopping MLC server...");
-    if let Some(state) = app.try_state::<Arc<crate::mlc_server::MLCServerManager>>() {
-        // Block until stop completes to ensure process is terminated
-        let manager: Arc<crate::mlc_server::MLCServerManager> = state.inner().clone();
-        let _ = tauri::async_runtime::block_on(async move {
-            let _ = manager.stop().await;
-        });
+    log::info!("App exiting; stopping MLC server and MCP servers...");
+    let server = app
+        .try_state::<Arc<crate::mlc_server::MLCServerManager>>()
+        .map(|state| state.inner().clone());
+    let mcp = app
+        .try_state::<Arc<crate::mcp::McpManager>>()
+        .map(|state| state.inner().clone());
+
+    // Taken before the graceful stop releases the handles
+    let mut pids: Vec<u32> = server
+        .as_ref()
+        .and_then(|s| s.child_pid())
+        .into_iter()
+        .collect();
+    if let Some(mcp) = &mcp {
+        pids.extend(mcp.child_pids());
+    }
+
+    let stopped = tauri::async_runtime::block_on(async move {
+        let graceful = async {
+            let stop_server = async {
+                if let Some(server) = &server {
+                    let _ = server.stop().await;
+                }
+            };
+            let stop_mcp = async {
+                if let Some(mcp) = &mcp {
+                    if let Ok(report) = mcp.shutdown(MCP_CHILD_SHUTDOWN_TIMEOUT).await {
+                        log::info!(
+                            "MCP shutdown: {} closed, {} force-killed",
+                            report.closed,
+                            report.force_killed
+                        );
+                    }
+                }
+            };
+            tokio::join!(stop_server, stop_mcp);
+        };
+        tokio::time::timeout(SHUTDOWN_TIMEOUT, graceful)
+            .await
+            .is_ok()
+    });
+    if !stopped {
+        log::warn!("Shutdown did not finish within {SHUTDOWN_TIMEOUT:?}; force-killing {pids:?}");
+        for pid in pids {
+            process_info::force_kill(pid);
+        }
     }
 }
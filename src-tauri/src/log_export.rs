@@ -0,0 +1,202 @@
+//! Bundles app logs, recent model server output and system info into one zip for bug
+//! reports. Everything written is passed through `redact_secrets` first.
+
+use serde::Serialize;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+/// Replaces a redacted value.
+const REDACTED: &str = "[REDACTED]";
+/// Key fragments whose `key=value` / `key: value` values are redacted.
+const SECRET_KEY_HINTS: &[&str] = &[
+    "token",
+    "secret",
+    "password",
+    "passwd",
+    "api_key",
+    "apikey",
+    "api-key",
+    "authorization",
+];
+/// Prefixes of well-known API key formats (OpenAI, Hugging Face, GitHub, Anthropic).
+const SECRET_VALUE_PREFIXES: &[&str] = &["sk-", "hf_", "ghp_", "gho_", "github_pat_"];
+
+/// What `write_log_archive` put in the zip.
+#[derive(Debug, Clone, Serialize)]
+pub struct LogExportSummary {
+    pub path: String,
+    pub files: Vec<String>,
+}
+
+/// Writes `dest` as a zip holding every `*.log` file in `log_dir` (under `logs/`), the
+/// recent model server output and `system-info.json`.
+pub fn write_log_archive(
+    dest: &Path,
+    log_dir: &Path,
+    server_output: &[String],
+    system_info: &serde_json::Value,
+) -> Result<LogExportSummary, String> {
+    let file = fs::File::create(dest).map_err(|e| format!("Failed to create {dest:?}: {e}"))?;
+    let mut zip = ZipWriter::new(file);
+    let mut files = Vec::new();
+    let mut add = |zip: &mut ZipWriter<fs::File>, name: String, text: &str| {
+        zip.start_file(name.as_str(), SimpleFileOptions::default())
+            .and_then(|_| Ok(zip.write_all(redact_secrets(text).as_bytes())?))
+            .map_err(|e| format!("Failed to write {name} to the archive: {e}"))?;
+        files.push(name);
+        Ok::<(), String>(())
+    };
+
+    let mut log_files: Vec<_> = match fs::read_dir(log_dir) {
+        Ok(entries) => entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "log"))
+            .collect(),
+        Err(e) => {
+            log::warn!("export_logs: cannot read log dir {log_dir:?} - {e}");
+            Vec::new()
+        }
+    };
+    log_files.sort();
+    for path in log_files {
+        let Some(name) = path.file_name().map(|n| n.to_string_lossy().into_owned()) else {
+            continue;
+        };
+        match fs::read(&path) {
+            Ok(bytes) => add(
+                &mut zip,
+                format!("logs/{name}"),
+                &String::from_utf8_lossy(&bytes),
+            )?,
+            Err(e) => log::warn!("export_logs: skipping {path:?} - {e}"),
+        }
+    }
+
+    add(
+        &mut zip,
+        "mlc-server-recent.log".to_string(),
+        &server_output.join("\n"),
+    )?;
+    let info = serde_json::to_string_pretty(system_info).map_err(|e| e.to_string())?;
+    add(&mut zip, "system-info.json".to_string(), &info)?;
+
+    zip.finish()
+        .map_err(|e| format!("Failed to finish the archive: {e}"))?;
+    Ok(LogExportSummary {
+        path: dest.display().to_string(),
+        files,
+    })
+}
+
+/// Best-effort scrub of obvious secrets: bearer tokens, well-known API key formats and
+/// values of `key=value` / `key: value` pairs whose key looks secret.
+pub fn redact_secrets(text: &str) -> String {
+    text.lines().map(redact_line).collect::<Vec<_>>().join("\n")
+}
+
+fn redact_line(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut redact_next = false;
+    for word in line.split_inclusive(' ') {
+        let token = word.trim_end_matches(' ');
+        let trailing = &word[token.len()..];
+        if token.is_empty() {
+            out.push_str(word);
+            continue;
+        }
+        if redact_next {
+            redact_next = false;
+            out.push_str(REDACTED);
+        } else if let Some(redacted) = redact_token(token) {
+            out.push_str(&redacted);
+        } else {
+            out.push_str(token);
+        }
+        out.push_str(trailing);
+
+        let lower = token.to_ascii_lowercase();
+        let key = lower.trim_end_matches(':').trim_matches('"');
+        if lower == "bearer" || (lower.ends_with(':') && is_secret_key(key)) {
+            redact_next = true;
+        }
+    }
+    out
+}
+
+/// Redacts a single whitespace-free token, or `None` to keep it.
+fn redact_token(token: &str) -> Option<String> {
+    let bare = token.trim_matches(|c: char| matches!(c, '"' | '\'' | ',' | ';'));
+    if SECRET_VALUE_PREFIXES
+        .iter()
+        .any(|p| bare.starts_with(p) && bare.len() > p.len() + 8)
+    {
+        return Some(REDACTED.to_string());
+    }
+    let (key, _) = token.split_once('=')?;
+    is_secret_key(&key.to_ascii_lowercase()).then(|| format!("{key}={REDACTED}"))
+}
+
+fn is_secret_key(key: &str) -> bool {
+    SECRET_KEY_HINTS.iter().any(|hint| key.contains(hint))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{redact_secrets, write_log_archive};
+    use std::io::Read;
+
+    #[test]
+    fn redacts_tokens_keys_and_secret_assignments() {
+        let text = "Authorization: Bearer abc.def.ghi\n\
+                    using key sk-proj-0123456789abcdef for hf_ABCDEFGHIJKLMNOP\n\
+                    env HF_TOKEN=hf_secretvalue123 PORT=8000\n\
+                    \"api_key\": \"xyz\"";
+        assert_eq!(
+            redact_secrets(text),
+            "Authorization: [REDACTED] [REDACTED]\n\
+             using key [REDACTED] for [REDACTED]\n\
+             env HF_TOKEN=[REDACTED] PORT=8000\n\
+             \"api_key\": [REDACTED]"
+        );
+    }
+
+    #[test]
+    fn archive_contains_redacted_logs_and_system_info() {
+        let dir = std::env::temp_dir().join(format!("openchat-log-export-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("OpenChat.log"), "started with token=abc123\n").unwrap();
+        std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+        let dest = dir.join("logs.zip");
+
+        let summary = write_log_archive(
+            &dest,
+            &dir,
+            &["[mlx-server] ready".to_string()],
+            &serde_json::json!({ "os": "test" }),
+        )
+        .unwrap();
+        assert_eq!(
+            summary.files,
+            [
+                "logs/OpenChat.log",
+                "mlc-server-recent.log",
+                "system-info.json"
+            ]
+        );
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&dest).unwrap()).unwrap();
+        let mut log = String::new();
+        archive
+            .by_name("logs/OpenChat.log")
+            .unwrap()
+            .read_to_string(&mut log)
+            .unwrap();
+        assert_eq!(log, "started with token=[REDACTED]");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,4 +1,6 @@
 pub const MCP_JSONRPC_VERSION: &str = "2.0";
+/// JSON-RPC error code for a method the receiver does not implement.
+pub const MCP_JSONRPC_METHOD_NOT_FOUND: i64 = -32601;
 /// MCP protocol version understood by this client. Align with server expectations.
 /// See https://spec.modelcontextprotocol.io for the latest.
 pub const MCP_PROTOCOL_VERSION: &str = "2025-06-18";
@@ -6,8 +8,55 @@ pub const MCP_PROTOCOL_VERSION: &str = "2025-06-18";
 pub const MCP_METHOD_INITIALIZE: &str = "initialize";
 pub const MCP_METHOD_TOOLS_LIST: &str = "tools/list";
 pub const MCP_METHOD_TOOLS_CALL: &str = "tools/call";
+pub const MCP_METHOD_PING: &str = "ping";
+pub const MCP_METHOD_COMPLETION_COMPLETE: &str = "completion/complete";
+pub const MCP_METHOD_LOGGING_SET_LEVEL: &str = "logging/setLevel";
 pub const MCP_NOTIFICATION_INITIALIZED: &str = "notifications/initialized";
+/// Log message sent by a server that advertised the `logging` capability.
+pub const MCP_NOTIFICATION_MESSAGE: &str = "notifications/message";
+
+/// Levels accepted by `logging/setLevel`, least to most severe (RFC 5424 names).
+pub const MCP_LOG_LEVELS: [&str; 8] = [
+    "debug",
+    "info",
+    "notice",
+    "warning",
+    "error",
+    "critical",
+    "alert",
+    "emergency",
+];
 
 pub const MCP_DEFAULT_CONNECT_TIMEOUT_MS: u64 = 5_000;
 pub const MCP_DEFAULT_LIST_TOOLS_TIMEOUT_MS: u64 = 5_000;
 pub const MCP_DEFAULT_TOOL_CALL_TIMEOUT_MS: u64 = 20_000;
+pub const MCP_DEFAULT_PING_TIMEOUT_MS: u64 = 2_000;
+pub const MCP_DEFAULT_COMPLETION_TIMEOUT_MS: u64 = 5_000;
+pub const MCP_DEFAULT_SET_LOG_LEVEL_TIMEOUT_MS: u64 = 5_000;
+
+/// Prefix of errors meaning the server's pipe closed (it exited or restarted), as opposed
+/// to an RPC-level error. See `transport::is_transport_closed`.
+pub const MCP_TRANSPORT_CLOSED: &str = "transport closed";
+/// Prefix of errors meaning the server did not answer within the request's timeout.
+/// See `transport::is_timeout`.
+pub const MCP_REQUEST_TIMED_OUT: &str = "request timed out";
+
+/// Default cap on live sessions; the least recently used is evicted beyond it.
+pub const MCP_DEFAULT_MAX_SESSIONS: usize = 16;
+/// Largest cap on live sessions accepted by `mcp_set_max_sessions`.
+pub const MCP_MAX_SESSIONS_LIMIT: usize = 256;
+
+/// Samples taken by `mcp_measure_latency` by default, and the most it accepts.
+pub const MCP_DEFAULT_LATENCY_SAMPLES: u32 = 5;
+pub const MCP_MAX_LATENCY_SAMPLES: u32 = 50;
+
+/// Servers connected at once by `reconnect_all_mcp_sessions`.
+pub const MCP_RECONNECT_CONCURRENCY: usize = 4;
+
+/// Stderr lines forwarded per server per second before the rest are suppressed.
+pub const MCP_STDERR_MAX_LINES_PER_SEC: u32 = 50;
+/// Longer stderr lines are truncated to this many bytes.
+pub const MCP_STDERR_MAX_LINE_BYTES: usize = 2_048;
+
+/// Each side of an exchange kept in debug mode is truncated to this many bytes.
+pub const MCP_DEBUG_EXCHANGE_MAX_BYTES: usize = 64 * 1024;
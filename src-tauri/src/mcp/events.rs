@@ -0,0 +1,94 @@
+//! Events raised by MCP sessions for the host application to forward (e.g. as Tauri
+//! events). Kept free of Tauri types so the MCP module stays host-agnostic.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Event name the host should use for `McpEvent::Stderr`.
+pub const MCP_SERVER_STDERR_EVENT: &str = "mcp-server-stderr";
+
+/// One stderr line from a stdio MCP server.
+#[derive(Serialize, Debug, Clone)]
+pub struct McpStderrLine {
+    pub id: i64,
+    pub line: String,
+}
+
+/// Event name for each server's `McpReconnectResult` while connecting at launch.
+pub const MCP_AUTOCONNECT_EVENT: &str = "mcp-autoconnect";
+
+/// Event name the host should use for `McpEvent::Log`.
+pub const MCP_LOG_EVENT: &str = "mcp-log";
+
+/// One `notifications/message` log entry from an MCP server.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct McpLogMessage {
+    pub id: i64,
+    pub level: String,
+    pub logger: Option<String>,
+    /// The notification's `data`; strings as-is, anything else as compact JSON.
+    pub message: String,
+}
+
+impl McpLogMessage {
+    /// Builds the log entry for server `id` from `notifications/message` params.
+    pub fn from_params(id: i64, params: &Value) -> Self {
+        let message = match params.get("data") {
+            Some(Value::String(s)) => s.clone(),
+            Some(Value::Null) | None => String::new(),
+            Some(other) => other.to_string(),
+        };
+        McpLogMessage {
+            id,
+            level: params
+                .get("level")
+                .and_then(|v| v.as_str())
+                .unwrap_or("info")
+                .to_string(),
+            logger: params
+                .get("logger")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+            message,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum McpEvent {
+    Stderr(McpStderrLine),
+    /// A server sent a log notification.
+    Log(McpLogMessage),
+    /// A session for server `id` was established.
+    Connected {
+        id: i64,
+    },
+    /// The session for server `id` was dropped, evicted or replaced.
+    Disconnected {
+        id: i64,
+    },
+}
+
+/// Callback invoked for every `McpEvent`.
+pub type McpEventSink = Arc<dyn Fn(McpEvent) + Send + Sync>;
+
+#[cfg(test)]
+mod tests {
+    use super::McpLogMessage;
+    use serde_json::json;
+
+    #[test]
+    fn log_messages_stringify_structured_data() {
+        let log = McpLogMessage::from_params(
+            3,
+            &json!({ "level": "error", "logger": "db", "data": { "rows": 2 } }),
+        );
+        assert_eq!(log.level, "error");
+        assert_eq!(log.logger.as_deref(), Some("db"));
+        assert_eq!(log.message, r#"{"rows":2}"#);
+
+        let log = McpLogMessage::from_params(3, &json!({ "level": "info", "data": "started" }));
+        assert_eq!((log.logger, log.message.as_str()), (None, "started"));
+    }
+}
@@ -0,0 +1,150 @@
+//! Export of saved MCP servers in the `mcpServers` JSON shape understood by
+//! Claude Desktop and `import_mcp_servers`.
+
+use serde_json::{json, Map, Value};
+use sqlx::SqlitePool;
+
+use crate::mcp::serde_utils::{
+    merge_auth_header, parse_mcp_json_object, parse_mcp_json_object_opt, parse_mcp_string_array,
+};
+use crate::mcp::store::{fetch_all_mcp_servers, DbMcpServerEntry};
+
+/// Placeholder written in place of env and header values when redacting.
+pub const REDACTED_VALUE: &str = "<redacted>";
+
+/// Returns all saved servers as `{ "mcpServers": { name: entry } }`. Disabled servers are
+/// included with `"disabled": true`. With `redact_secrets`, env and header values
+/// (including the merged `auth` token) are replaced by `REDACTED_VALUE`.
+pub async fn export_mcp_servers(pool: &SqlitePool, redact_secrets: bool) -> Result<Value, String> {
+    let rows = fetch_all_mcp_servers(pool).await?;
+    Ok(build_mcp_servers_json(&rows, redact_secrets))
+}
+
+pub fn build_mcp_servers_json(rows: &[DbMcpServerEntry], redact_secrets: bool) -> Value {
+    let mut servers = Map::new();
+    for row in rows {
+        let s = &row.server;
+        let mut entry = Map::new();
+        if s.transport == "stdio" {
+            entry.insert("command".into(), json!(s.command.as_deref().unwrap_or("")));
+            entry.insert(
+                "args".into(),
+                json!(parse_mcp_string_array(s.args.as_deref())),
+            );
+            let env = parse_mcp_json_object(s.env.as_deref());
+            entry.insert("env".into(), redact(env, redact_secrets));
+            if let Some(env_file) = s.env_file.as_deref().filter(|f| !f.trim().is_empty()) {
+                entry.insert("envFile".into(), json!(env_file));
+            }
+            if let Some(cwd) = s.cwd.as_deref().filter(|c| !c.trim().is_empty()) {
+                entry.insert("cwd".into(), json!(cwd));
+            }
+        } else {
+            entry.insert("url".into(), json!(s.url.as_deref().unwrap_or("")));
+            let headers = merge_auth_header(
+                parse_mcp_json_object_opt(s.headers.as_deref()).as_ref(),
+                s.auth.as_deref(),
+            );
+            if let Some(headers) = headers {
+                entry.insert("headers".into(), redact(headers, redact_secrets));
+            }
+        }
+        entry.insert("disabled".into(), json!(s.enabled == 0));
+
+        // Names are not unique in the table; keep every server by suffixing its id.
+        let key = if servers.contains_key(&row.name) {
+            format!("{} ({})", row.name, row.id)
+        } else {
+            row.name.clone()
+        };
+        servers.insert(key, Value::Object(entry));
+    }
+    json!({ "mcpServers": servers })
+}
+
+fn redact(mut value: Value, redact_secrets: bool) -> Value {
+    if redact_secrets {
+        if let Some(obj) = value.as_object_mut() {
+            for v in obj.values_mut() {
+                *v = json!(REDACTED_VALUE);
+            }
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_mcp_servers_json, REDACTED_VALUE};
+    use crate::mcp::import::parse_mcp_servers_json;
+    use crate::mcp::store::{DbMcpServer, DbMcpServerEntry};
+
+    fn entry(id: i64, name: &str, server: DbMcpServer) -> DbMcpServerEntry {
+        DbMcpServerEntry {
+            id,
+            name: name.to_string(),
+            priority: 0,
+            server,
+        }
+    }
+
+    fn server(transport: &str, enabled: i64) -> DbMcpServer {
+        DbMcpServer {
+            transport: transport.to_string(),
+            command: None,
+            args: None,
+            env: None,
+            env_file: None,
+            cwd: None,
+            url: None,
+            headers: None,
+            auth: None,
+            heartbeat_sec: None,
+            connect_timeout_ms: None,
+            enabled,
+        }
+    }
+
+    #[test]
+    fn exports_round_trip_through_import_and_redacts_secrets() {
+        let rows = vec![
+            entry(
+                1,
+                "fs",
+                DbMcpServer {
+                    command: Some("npx".into()),
+                    args: Some(r#"["-y","server"]"#.into()),
+                    env: Some(r#"{"TOKEN":"secret"}"#.into()),
+                    ..server("stdio", 1)
+                },
+            ),
+            entry(
+                2,
+                "remote",
+                DbMcpServer {
+                    url: Some("https://example.com/mcp".into()),
+                    auth: Some("Bearer abc".into()),
+                    ..server("http", 0)
+                },
+            ),
+        ];
+
+        let plain = build_mcp_servers_json(&rows, false);
+        assert_eq!(plain["mcpServers"]["fs"]["env"]["TOKEN"], "secret");
+        assert_eq!(plain["mcpServers"]["remote"]["disabled"], true);
+        assert_eq!(
+            plain["mcpServers"]["remote"]["headers"]["Authorization"],
+            "Bearer abc"
+        );
+
+        let imported = parse_mcp_servers_json(&plain.to_string()).unwrap();
+        assert!(imported.iter().all(|(_, r)| r.is_ok()));
+
+        let redacted = build_mcp_servers_json(&rows, true);
+        assert_eq!(redacted["mcpServers"]["fs"]["env"]["TOKEN"], REDACTED_VALUE);
+        assert_eq!(
+            redacted["mcpServers"]["remote"]["headers"]["Authorization"],
+            REDACTED_VALUE
+        );
+    }
+}
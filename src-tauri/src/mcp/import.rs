@@ -0,0 +1,254 @@
+//! Import of MCP server definitions in the `mcpServers` JSON shape used by
+//! Claude Desktop and similar tools.
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sqlx::SqlitePool;
+
+/// A server entry mapped onto the `mcp_servers` columns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewMcpServer {
+    pub name: String,
+    pub enabled: bool,
+    pub transport: &'static str,
+    pub command: Option<String>,
+    pub args: Option<String>,
+    pub env: Option<String>,
+    pub env_file: Option<String>,
+    pub cwd: Option<String>,
+    pub url: Option<String>,
+    pub headers: Option<String>,
+}
+
+/// A named entry and either its mapped row or a validation error.
+pub type ParsedEntry = (String, Result<NewMcpServer, String>);
+
+#[derive(Serialize, Debug, Clone)]
+pub struct McpImportEntryError {
+    pub name: String,
+    pub error: String,
+}
+
+/// Outcome of `import_mcp_servers`: created row ids, names skipped because a server
+/// with that name already exists, and per-entry validation errors.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct McpImportResult {
+    pub created_ids: Vec<i64>,
+    pub skipped: Vec<String>,
+    pub errors: Vec<McpImportEntryError>,
+}
+
+/// Parses an `mcpServers` block. Accepts either `{ "mcpServers": { ... } }` or the inner
+/// object directly. Returns one `(name, result)` pair per entry.
+pub fn parse_mcp_servers_json(json: &str) -> Result<Vec<ParsedEntry>, String> {
+    let root: Value = serde_json::from_str(json).map_err(|e| format!("invalid JSON: {e}"))?;
+    let servers = match root.get("mcpServers") {
+        Some(inner) => inner,
+        None => &root,
+    };
+    let servers = servers
+        .as_object()
+        .ok_or_else(|| "expected an object of servers keyed by name".to_string())?;
+    Ok(servers
+        .iter()
+        .map(|(name, entry)| (name.trim().to_string(), parse_entry(name, entry)))
+        .collect())
+}
+
+fn parse_entry(name: &str, entry: &Value) -> Result<NewMcpServer, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("server name is empty".into());
+    }
+    let obj = entry
+        .as_object()
+        .ok_or_else(|| "entry must be an object".to_string())?;
+    let enabled = !obj
+        .get("disabled")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+
+    match (obj.get("command"), obj.get("url")) {
+        (Some(_), Some(_)) => Err("entry has both `command` and `url`".into()),
+        (None, None) => Err("entry needs either `command` or `url`".into()),
+        (Some(command), None) => {
+            let command = command
+                .as_str()
+                .map(str::trim)
+                .filter(|c| !c.is_empty())
+                .ok_or_else(|| "`command` must be a non-empty string".to_string())?;
+            let args = match obj.get("args") {
+                None | Some(Value::Null) => Vec::new(),
+                Some(Value::Array(items)) => items
+                    .iter()
+                    .map(|a| a.as_str().map(str::to_string))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| "`args` must be an array of strings".to_string())?,
+                Some(_) => return Err("`args` must be an array of strings".into()),
+            };
+            let env = string_map(obj.get("env"), "env")?;
+            let env_file = optional_path(obj.get("envFile"), "envFile")?;
+            let cwd = optional_path(obj.get("cwd"), "cwd")?;
+            Ok(NewMcpServer {
+                name: name.to_string(),
+                enabled,
+                transport: "stdio",
+                command: Some(command.to_string()),
+                args: Some(Value::from(args).to_string()),
+                env: Some(Value::Object(env).to_string()),
+                env_file,
+                cwd,
+                url: None,
+                headers: None,
+            })
+        }
+        (None, Some(url)) => {
+            let url = url
+                .as_str()
+                .map(str::trim)
+                .ok_or_else(|| "`url` must be a string".to_string())?;
+            let parsed = reqwest::Url::parse(url).map_err(|e| format!("invalid `url`: {e}"))?;
+            if !matches!(parsed.scheme(), "http" | "https") {
+                return Err(format!("unsupported url scheme: {}", parsed.scheme()));
+            }
+            let headers = string_map(obj.get("headers"), "headers")?;
+            Ok(NewMcpServer {
+                name: name.to_string(),
+                enabled,
+                transport: "http",
+                command: None,
+                args: None,
+                env: None,
+                env_file: None,
+                cwd: None,
+                url: Some(url.to_string()),
+                headers: Some(Value::Object(headers).to_string()),
+            })
+        }
+    }
+}
+
+/// Validates an optional path string; blank paths count as absent.
+fn optional_path(value: Option<&Value>, field: &str) -> Result<Option<String>, String> {
+    match value {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(path)) => Ok(Some(path.trim().to_string()).filter(|p| !p.is_empty())),
+        Some(_) => Err(format!("`{field}` must be a string")),
+    }
+}
+
+/// Validates an optional `{ "KEY": "value" }` object.
+fn string_map(value: Option<&Value>, field: &str) -> Result<Map<String, Value>, String> {
+    match value {
+        None | Some(Value::Null) => Ok(Map::new()),
+        Some(Value::Object(map)) if map.values().all(Value::is_string) => Ok(map.clone()),
+        Some(_) => Err(format!("`{field}` must be an object of string values")),
+    }
+}
+
+/// Parses `json` and inserts every valid entry whose name is not already taken.
+pub async fn import_mcp_servers(pool: &SqlitePool, json: &str) -> Result<McpImportResult, String> {
+    let entries = parse_mcp_servers_json(json)?;
+    let mut result = McpImportResult::default();
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    for (name, parsed) in entries {
+        let server = match parsed {
+            Ok(server) => server,
+            Err(error) => {
+                result.errors.push(McpImportEntryError { name, error });
+                continue;
+            }
+        };
+        let exists: Option<i64> = sqlx::query_scalar("SELECT id FROM mcp_servers WHERE name = ?")
+            .bind(&server.name)
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        if exists.is_some() {
+            result.skipped.push(server.name);
+            continue;
+        }
+        let id: i64 = sqlx::query_scalar(
+            "INSERT INTO mcp_servers (name, enabled, transport, command, args, env, env_file, cwd, url, headers) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?) RETURNING id",
+        )
+        .bind(&server.name)
+        .bind(server.enabled as i64)
+        .bind(server.transport)
+        .bind(&server.command)
+        .bind(&server.args)
+        .bind(&server.env)
+        .bind(&server.env_file)
+        .bind(&server.cwd)
+        .bind(&server.url)
+        .bind(&server.headers)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+        result.created_ids.push(id);
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_mcp_servers_json;
+
+    #[test]
+    fn parses_stdio_and_http_entries_and_reports_invalid_ones() {
+        let input = r#"{
+            "mcpServers": {
+                "filesystem": {
+                    "command": "npx",
+                    "args": ["-y", "@modelcontextprotocol/server-filesystem", "/tmp"],
+                    "env": { "DEBUG": "1" },
+                    "envFile": "~/.config/fs-server.env",
+                    "cwd": "/srv/fs"
+                },
+                "remote": { "url": "https://example.com/mcp", "disabled": true },
+                "both": { "command": "x", "url": "https://example.com" },
+                "bad_args": { "command": "x", "args": [1, 2] },
+                "ftp": { "url": "ftp://example.com" }
+            }
+        }"#;
+
+        let entries = parse_mcp_servers_json(input).unwrap();
+        assert_eq!(entries.len(), 5);
+        let get = |name: &str| {
+            entries
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, r)| r.clone())
+                .unwrap()
+        };
+
+        let fs = get("filesystem").unwrap();
+        assert_eq!(fs.transport, "stdio");
+        assert_eq!(fs.command.as_deref(), Some("npx"));
+        assert_eq!(
+            fs.args.as_deref(),
+            Some(r#"["-y","@modelcontextprotocol/server-filesystem","/tmp"]"#)
+        );
+        assert_eq!(fs.env.as_deref(), Some(r#"{"DEBUG":"1"}"#));
+        assert_eq!(fs.env_file.as_deref(), Some("~/.config/fs-server.env"));
+        assert_eq!(fs.cwd.as_deref(), Some("/srv/fs"));
+        assert!(fs.enabled);
+
+        let remote = get("remote").unwrap();
+        assert_eq!(remote.transport, "http");
+        assert_eq!(remote.url.as_deref(), Some("https://example.com/mcp"));
+        assert!(!remote.enabled);
+
+        assert!(get("both").is_err());
+        assert!(get("bad_args").is_err());
+        assert!(get("ftp").is_err());
+    }
+
+    #[test]
+    fn accepts_bare_server_map_and_rejects_non_objects() {
+        let entries = parse_mcp_servers_json(r#"{ "a": { "command": "a" } }"#).unwrap();
+        assert_eq!(entries[0].0, "a");
+        assert!(parse_mcp_servers_json("[]").is_err());
+        assert!(parse_mcp_servers_json("not json").is_err());
+    }
+}
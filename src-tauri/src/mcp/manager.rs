@@ -1,46 +1,346 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 
+use crate::mcp::constants::{MCP_DEBUG_EXCHANGE_MAX_BYTES, MCP_DEFAULT_MAX_SESSIONS};
+use crate::mcp::events::{McpEvent, McpEventSink, McpLogMessage, McpStderrLine};
 use crate::mcp::transport::{
-    create_http_session, parse_tools_array, spawn_stdio_session, McpSession, McpTransport,
+    create_http_session, is_session_lost, is_unsent_request, parse_completion, parse_tools_array,
+    spawn_stdio_session, McpSession, McpTransport, NotificationSink, StderrLineSink,
+};
+use crate::mcp::types::{
+    McpCapabilities, McpCompletion, McpCompletionArgument, McpCompletionRef, McpExchange,
+    McpLatency, McpServerError, McpSessionHealth, McpShutdownReport, McpToolInfo,
 };
-use crate::mcp::types::McpToolInfo;
 
 // (check_server is re-exported from mod.rs directly)
 
+/// How a stdio session is launched, kept so it can be respawned if the server exits.
+#[derive(Clone)]
+pub struct StdioLaunch {
+    pub command: String,
+    pub args: Vec<String>,
+    pub env: serde_json::Value,
+    /// `.env`-style file read on every spawn; explicit `env` entries win over it.
+    pub env_file: Option<String>,
+    pub cwd: Option<String>,
+    pub connect_timeout_ms: u64,
+}
+
+/// A cached session. Its own lock keeps one request/response exchange on the wire at a
+/// time, while the map lock is only held to look sessions up, so different servers
+/// are used concurrently.
+type SharedSession = Arc<tokio::sync::Mutex<McpSession>>;
+
 /// High-level manager that caches `McpSession`s keyed by id and exposes
 /// convenience operations. Thin wrapper over transport helpers.
 pub struct McpManager {
-    pub(super) sessions: tokio::sync::Mutex<std::collections::HashMap<i64, McpSession>>,
+    pub(super) sessions: tokio::sync::Mutex<std::collections::HashMap<i64, SharedSession>>,
+    /// Tools from the most recent `list_tools` per session, used for argument validation.
+    tools: tokio::sync::Mutex<std::collections::HashMap<i64, Vec<McpToolInfo>>>,
+    /// Per-server guard held while a session is created, so concurrent `ensure_*`
+    /// callers for one id wait for a single connect instead of racing their own.
+    connecting: std::sync::Mutex<HashMap<i64, Arc<tokio::sync::Mutex<()>>>>,
+    /// Launch parameters of cached stdio sessions, for transparent respawn.
+    stdio_launches: tokio::sync::Mutex<std::collections::HashMap<i64, StdioLaunch>>,
+    /// When each cached session was last inserted or sent a request.
+    last_used: std::sync::Mutex<HashMap<i64, Instant>>,
+    max_sessions: AtomicUsize,
+    /// Child process id per cached stdio session, readable without awaiting any session
+    /// lock so a stuck session can still be force-killed on exit.
+    child_pids: std::sync::Mutex<HashMap<i64, u32>>,
+    /// Last failure per server id, cleared by the next successful operation.
+    last_errors: std::sync::Mutex<HashMap<i64, McpServerError>>,
+    /// Whether `last_exchanges` is filled; off unless the user opts in.
+    debug: AtomicBool,
+    last_exchanges: std::sync::Mutex<HashMap<i64, McpExchange>>,
+    event_sink: Option<McpEventSink>,
 }
 
 impl McpManager {
     /// Creates a new, empty manager instance.
     pub fn new() -> Arc<Self> {
+        Self::build(None)
+    }
+
+    /// Creates a manager that reports session events (such as stderr lines) to `sink`.
+    pub fn with_event_sink(sink: McpEventSink) -> Arc<Self> {
+        Self::build(Some(sink))
+    }
+
+    fn build(event_sink: Option<McpEventSink>) -> Arc<Self> {
         Arc::new(Self {
             sessions: tokio::sync::Mutex::new(std::collections::HashMap::new()),
+            tools: tokio::sync::Mutex::new(std::collections::HashMap::new()),
+            connecting: std::sync::Mutex::new(HashMap::new()),
+            stdio_launches: tokio::sync::Mutex::new(std::collections::HashMap::new()),
+            last_used: std::sync::Mutex::new(HashMap::new()),
+            child_pids: std::sync::Mutex::new(HashMap::new()),
+            max_sessions: AtomicUsize::new(MCP_DEFAULT_MAX_SESSIONS),
+            last_errors: std::sync::Mutex::new(HashMap::new()),
+            debug: AtomicBool::new(false),
+            last_exchanges: std::sync::Mutex::new(HashMap::new()),
+            event_sink,
         })
     }
 
-    /// Ensures a stdio session exists for `id`, creating it if needed and sending initialize.
-    pub async fn ensure_stdio(
+    /// Sets the cap on live sessions (at least 1). Takes effect when the next session
+    /// is created; evicted sessions reconnect on their next use via `ensure_*`.
+    pub fn set_max_sessions(&self, max: usize) {
+        self.max_sessions.store(max.max(1), Ordering::SeqCst);
+    }
+
+    /// Returns the last recorded error for server `id`, if its latest operation failed.
+    pub fn last_error(&self, id: i64) -> Option<McpServerError> {
+        self.last_errors.lock().ok()?.get(&id).cloned()
+    }
+
+    /// Records a failed operation on `id`, or clears the stored error on success.
+    pub fn record_outcome<T>(&self, id: i64, result: &Result<T, String>) {
+        let Ok(mut errors) = self.last_errors.lock() else {
+            return;
+        };
+        match result {
+            Ok(_) => {
+                errors.remove(&id);
+            }
+            Err(error) => {
+                errors.insert(
+                    id,
+                    McpServerError {
+                        id,
+                        error: error.clone(),
+                        at: chrono::Utc::now(),
+                    },
+                );
+            }
+        }
+    }
+
+    /// Turns retention of the last raw exchange per server on or off. Turning it off
+    /// discards everything captured so far.
+    pub fn set_debug(&self, enabled: bool) {
+        self.debug.store(enabled, Ordering::Relaxed);
+        if !enabled {
+            if let Ok(mut exchanges) = self.last_exchanges.lock() {
+                exchanges.clear();
+            }
+        }
+    }
+
+    /// The last request/response sent to `id` while debug mode was on.
+    pub fn last_exchange(&self, id: i64) -> Option<McpExchange> {
+        self.last_exchanges.lock().ok()?.get(&id).cloned()
+    }
+
+    fn record_exchange(
         &self,
         id: i64,
-        command: &str,
-        args: &[String],
-        env: &serde_json::Value,
-        cwd: Option<&str>,
-        connect_timeout_ms: u64,
-    ) -> Result<(), String> {
-        let mut sessions = self.sessions.lock().await;
-        if sessions.contains_key(&id) {
+        method: &str,
+        params: &serde_json::Value,
+        result: &Result<serde_json::Value, String>,
+    ) {
+        if !self.debug.load(Ordering::Relaxed) {
+            return;
+        }
+        let (ok, response) = match result {
+            Ok(value) => (true, debug_json(value)),
+            Err(e) => (false, truncate_debug(crate::log_export::redact_secrets(e))),
+        };
+        let exchange = McpExchange {
+            id,
+            method: method.to_string(),
+            request: debug_json(params),
+            response,
+            ok,
+            at: chrono::Utc::now(),
+        };
+        if let Ok(mut exchanges) = self.last_exchanges.lock() {
+            exchanges.insert(id, exchange);
+        }
+    }
+
+    fn touch(&self, id: i64) {
+        if let Ok(mut last_used) = self.last_used.lock() {
+            last_used.insert(id, Instant::now());
+        }
+    }
+
+    /// Ids to evict, least recently used first, so that `sessions` fits the cap.
+    /// `keep` (the session just inserted) is never chosen.
+    fn lru_victims(&self, sessions: &HashMap<i64, SharedSession>, keep: i64) -> Vec<i64> {
+        let max = self.max_sessions.load(Ordering::SeqCst);
+        let excess = sessions.len().saturating_sub(max);
+        if excess == 0 {
+            return Vec::new();
+        }
+        let last_used = self.last_used.lock().map(|m| m.clone()).unwrap_or_default();
+        let mut candidates: Vec<(Option<Instant>, i64)> = sessions
+            .keys()
+            .filter(|id| **id != keep)
+            .map(|id| (last_used.get(id).copied(), *id))
+            .collect();
+        candidates.sort();
+        candidates
+            .into_iter()
+            .take(excess)
+            .map(|(_, id)| id)
+            .collect()
+    }
+
+    fn emit(&self, event: McpEvent) {
+        if let Some(sink) = &self.event_sink {
+            sink(event);
+        }
+    }
+
+    /// Wraps the event sink as a stderr sink tagged with session `id`.
+    fn stderr_sink(&self, id: i64) -> Option<StderrLineSink> {
+        let sink = self.event_sink.clone()?;
+        Some(Arc::new(move |line| {
+            sink(McpEvent::Stderr(McpStderrLine { id, line }))
+        }))
+    }
+
+    /// Wraps the event sink as a notification sink tagged with session `id`, raising
+    /// `McpEvent::Log` for `notifications/message`. Other notifications are only logged.
+    fn notification_sink(&self, id: i64) -> Option<NotificationSink> {
+        let sink = self.event_sink.clone()?;
+        Some(Arc::new(move |method, params| {
+            if method == crate::mcp::constants::MCP_NOTIFICATION_MESSAGE {
+                sink(McpEvent::Log(McpLogMessage::from_params(id, params)));
+            } else {
+                log::debug!("mcp: session {id} sent notification {method}");
+            }
+        }))
+    }
+
+    /// The guard serializing session creation for `id`.
+    fn connect_guard(&self, id: i64) -> Arc<tokio::sync::Mutex<()>> {
+        let mut connecting = self
+            .connecting
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        connecting.entry(id).or_default().clone()
+    }
+
+    /// Ensures a stdio session exists for `id`, creating it if needed and sending initialize.
+    pub async fn ensure_stdio(&self, id: i64, launch: StdioLaunch) -> Result<(), String> {
+        let guard = self.connect_guard(id);
+        let _connecting = guard.lock().await;
+        if self.sessions.lock().await.contains_key(&id) {
             return Ok(());
         }
-        let session =
-            spawn_stdio_session(command, args, Some(env), cwd, connect_timeout_ms).await?;
-        sessions.insert(id, session);
+        let spawned = self.spawn_stdio(id, &launch).await;
+        self.record_outcome(id, &spawned);
+        let session = spawned?;
+        self.stdio_launches.lock().await.insert(id, launch);
+        self.insert_session(id, session).await;
         Ok(())
     }
 
+    async fn spawn_stdio(&self, id: i64, launch: &StdioLaunch) -> Result<McpSession, String> {
+        let mut session = spawn_stdio_session(
+            &launch.command,
+            &launch.args,
+            Some(&launch.env),
+            launch.env_file.as_deref(),
+            launch.cwd.as_deref(),
+            launch.connect_timeout_ms,
+            self.stderr_sink(id),
+        )
+        .await?;
+        session.set_notification_sink(self.notification_sink(id));
+        Ok(session)
+    }
+
+    /// Returns the cached session for `id` without holding the map lock.
+    async fn session(&self, id: i64) -> Result<SharedSession, String> {
+        self.sessions
+            .lock()
+            .await
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| "not connected".to_string())
+    }
+
+    /// Replaces the stdio session `failed` for `id` with a freshly spawned and initialized
+    /// one. If a concurrent caller already replaced it, the replacement is kept.
+    async fn respawn_stdio(&self, id: i64, failed: &SharedSession) -> Result<(), String> {
+        let launch = self
+            .stdio_launches
+            .lock()
+            .await
+            .get(&id)
+            .cloned()
+            .ok_or("not connected")?;
+        let old = {
+            let mut sessions = self.sessions.lock().await;
+            match sessions.get(&id) {
+                Some(current) if Arc::ptr_eq(current, failed) => sessions.remove(&id),
+                Some(_) => return Ok(()),
+                None => None,
+            }
+        };
+        if let Some(old) = old {
+            let _ = old.lock().await.kill_child().await;
+            self.emit(McpEvent::Disconnected { id });
+        }
+        let session = self.spawn_stdio(id, &launch).await?;
+        self.insert_session(id, session).await;
+        Ok(())
+    }
+
+    /// Sends a request on session `id`, recording the outcome as the server's last error.
+    /// If a stdio server's pipe was already closed when the request was written (it
+    /// exited or restarted itself), respawns it and retries once. Failures after the
+    /// request went out (EOF while reading, timeouts) and RPC errors are returned
+    /// as-is, since the server may already have acted on the request.
+    async fn send(
+        &self,
+        id: i64,
+        method: &str,
+        params: serde_json::Value,
+        timeout_ms: u64,
+    ) -> Result<serde_json::Value, String> {
+        let captured = self.debug.load(Ordering::Relaxed).then(|| params.clone());
+        let result = self.send_with_respawn(id, method, params, timeout_ms).await;
+        self.record_outcome(id, &result);
+        if let Some(params) = captured {
+            self.record_exchange(id, method, &params, &result);
+        }
+        result
+    }
+
+    async fn send_with_respawn(
+        &self,
+        id: i64,
+        method: &str,
+        params: serde_json::Value,
+        timeout_ms: u64,
+    ) -> Result<serde_json::Value, String> {
+        self.touch(id);
+        let session = self.session(id).await?;
+        let first = session
+            .lock()
+            .await
+            .send(method, params.clone(), timeout_ms)
+            .await;
+        match first {
+            Err(e)
+                if is_unsent_request(&e) && self.stdio_launches.lock().await.contains_key(&id) =>
+            {
+                log::warn!("mcp: session {id} {e}; respawning server and retrying {method}");
+                self.respawn_stdio(id, &session).await?;
+                let session = self.session(id).await?;
+                let mut session = session.lock().await;
+                session.send(method, params, timeout_ms).await
+            }
+            other => other,
+        }
+    }
+
     /// Ensures an http session exists for `id`, creating it if needed and sending initialize.
     pub async fn ensure_http(
         &self,
@@ -49,27 +349,356 @@ impl McpManager {
         headers: Option<&serde_json::Value>,
         connect_timeout_ms: u64,
     ) -> Result<(), String> {
-        let mut sessions = self.sessions.lock().await;
-        if sessions.contains_key(&id) {
+        let guard = self.connect_guard(id);
+        let _connecting = guard.lock().await;
+        if self.sessions.lock().await.contains_key(&id) {
             return Ok(());
         }
-        let session = create_http_session(url, headers, connect_timeout_ms).await?;
-        sessions.insert(id, session);
+        let created = create_http_session(url, headers, connect_timeout_ms).await;
+        self.record_outcome(id, &created);
+        self.insert_session(id, created?).await;
         Ok(())
     }
 
+    /// Caches a freshly connected session. Sessions are established without holding the
+    /// lock so servers connect in parallel; if another caller won the race for `id`,
+    /// the newer session is discarded.
+    async fn insert_session(&self, id: i64, mut session: McpSession) {
+        let mut sessions = self.sessions.lock().await;
+        if sessions.contains_key(&id) {
+            drop(sessions);
+            if let Err(e) = session.kill_child().await {
+                log::warn!("mcp: failed to kill duplicate session {id} child - {e}");
+            }
+            return;
+        }
+        if let (Some(pid), Ok(mut pids)) = (session.pid(), self.child_pids.lock()) {
+            pids.insert(id, pid);
+        }
+        sessions.insert(id, Arc::new(tokio::sync::Mutex::new(session)));
+        self.touch(id);
+        self.emit(McpEvent::Connected { id });
+
+        let evicted: Vec<(i64, SharedSession)> = self
+            .lru_victims(&sessions, id)
+            .into_iter()
+            .filter_map(|victim| sessions.remove(&victim).map(|s| (victim, s)))
+            .collect();
+        drop(sessions);
+        for (victim, session) in evicted {
+            log::info!("mcp: session limit reached; evicting least recently used session {victim}");
+            self.forget(victim).await;
+            if let Err(e) = session.lock().await.kill_child().await {
+                log::warn!("mcp: failed to kill evicted session {victim} child - {e}");
+            }
+            self.emit(McpEvent::Disconnected { id: victim });
+        }
+    }
+
+    /// Clears cached tools, launch parameters and usage for `id`.
+    async fn forget(&self, id: i64) {
+        self.tools.lock().await.remove(&id);
+        if let Ok(mut exchanges) = self.last_exchanges.lock() {
+            exchanges.remove(&id);
+        }
+        self.stdio_launches.lock().await.remove(&id);
+        if let Ok(mut last_used) = self.last_used.lock() {
+            last_used.remove(&id);
+        }
+        if let Ok(mut pids) = self.child_pids.lock() {
+            pids.remove(&id);
+        }
+    }
+
+    /// Process ids of the stdio server children, without waiting on any async lock.
+    pub fn child_pids(&self) -> Vec<u32> {
+        self.child_pids
+            .lock()
+            .map(|pids| pids.values().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Ids of every cached session, ascending.
+    pub async fn session_ids(&self) -> Vec<i64> {
+        let mut ids: Vec<i64> = self.sessions.lock().await.keys().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Removes the cached session for `id`, killing its child process if it has one.
+    /// Returns whether a session was cached.
+    pub async fn drop_session(&self, id: i64) -> bool {
+        self.forget(id).await;
+        let removed = self.sessions.lock().await.remove(&id);
+        match removed {
+            Some(session) => {
+                if let Err(e) = session.lock().await.kill_child().await {
+                    log::warn!("mcp: failed to kill session {id} child - {e}");
+                }
+                self.emit(McpEvent::Disconnected { id });
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Removes every cached session, killing stdio children. Returns the dropped ids.
+    pub async fn drop_all_sessions(&self) -> Vec<i64> {
+        let (drained, _) = self.take_all_sessions().await;
+        let mut ids = Vec::with_capacity(drained.len());
+        for (id, session) in drained {
+            if let Err(e) = session.lock().await.kill_child().await {
+                log::warn!("mcp: failed to kill session {id} child - {e}");
+            }
+            self.emit(McpEvent::Disconnected { id });
+            ids.push(id);
+        }
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Closes every cached session on a spawned task, waiting at most
+    /// `per_child_timeout` for each child to stop before force-killing it by pid.
+    /// Sessions close concurrently, so the whole shutdown is bounded by roughly one
+    /// timeout. Await the handle from async code, or `block_on` it (with an outer
+    /// timeout) from a synchronous exit path; must be called within a Tokio runtime.
+    pub fn shutdown(
+        self: &Arc<Self>,
+        per_child_timeout: std::time::Duration,
+    ) -> tokio::task::JoinHandle<McpShutdownReport> {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            let (drained, pids) = manager.take_all_sessions().await;
+            let mut closing = tokio::task::JoinSet::new();
+            for (id, session) in drained {
+                let pid = pids.get(&id).copied();
+                closing.spawn(async move {
+                    let stopped = tokio::time::timeout(per_child_timeout, async {
+                        session.lock().await.kill_child().await
+                    })
+                    .await;
+                    match stopped {
+                        Ok(Ok(())) => return (id, false),
+                        Ok(Err(e)) => log::warn!("mcp: failed to kill session {id} child - {e}"),
+                        Err(_) => log::warn!(
+                            "mcp: session {id} did not close within {per_child_timeout:?}"
+                        ),
+                    }
+                    match pid {
+                        Some(pid) => {
+                            crate::process_info::force_kill(pid);
+                            (id, true)
+                        }
+                        None => (id, false),
+                    }
+                });
+            }
+
+            let mut report = McpShutdownReport::default();
+            while let Some(joined) = closing.join_next().await {
+                let Ok((id, forced)) = joined else {
+                    continue;
+                };
+                manager.emit(McpEvent::Disconnected { id });
+                if forced {
+                    report.force_killed += 1;
+                } else {
+                    report.closed += 1;
+                }
+            }
+            report
+        })
+    }
+
+    /// Empties the session cache and its bookkeeping, returning the sessions and the
+    /// child pids they had.
+    async fn take_all_sessions(&self) -> (Vec<(i64, SharedSession)>, HashMap<i64, u32>) {
+        self.tools.lock().await.clear();
+        self.stdio_launches.lock().await.clear();
+        if let Ok(mut last_used) = self.last_used.lock() {
+            last_used.clear();
+        }
+        let pids = self
+            .child_pids
+            .lock()
+            .map(|mut pids| std::mem::take(&mut *pids))
+            .unwrap_or_default();
+        let drained = self.sessions.lock().await.drain().collect();
+        (drained, pids)
+    }
+
+    /// Reports liveness for every cached session, ordered by id. By default this only
+    /// inspects local state; with `probe` each session is also sent a `ping`. Only a
+    /// closed transport or a timeout marks it dead: an error response (e.g. a server
+    /// without `ping`) still shows the server is there.
+    pub async fn session_health(&self, probe: bool, timeout_ms: u64) -> Vec<McpSessionHealth> {
+        let sessions: Vec<(i64, SharedSession)> = self
+            .sessions
+            .lock()
+            .await
+            .iter()
+            .map(|(id, s)| (*id, s.clone()))
+            .collect();
+        let mut health = Vec::with_capacity(sessions.len());
+        for (id, session) in sessions {
+            let mut session = session.lock().await;
+            let mut alive = session.is_alive();
+            if alive && probe {
+                alive = session
+                    .send(
+                        crate::mcp::constants::MCP_METHOD_PING,
+                        serde_json::json!({}),
+                        timeout_ms,
+                    )
+                    .await
+                    .err()
+                    .is_none_or(|e| !is_session_lost(&e));
+            }
+            health.push(McpSessionHealth {
+                id,
+                connected: true,
+                alive,
+            });
+        }
+        health.sort_by_key(|h| h.id);
+        health
+    }
+
+    /// Sends `ping` to server `id`.
+    pub async fn ping(&self, id: i64, timeout_ms: u64) -> Result<(), String> {
+        self.send(
+            id,
+            crate::mcp::constants::MCP_METHOD_PING,
+            serde_json::json!({}),
+            timeout_ms,
+        )
+        .await
+        .map(|_| ())
+    }
+
+    /// Times `samples` round trips over the cached session for `id`. Uses `ping`,
+    /// falling back to `tools/list` for servers that reject it; fails only if neither
+    /// succeeds.
+    pub async fn measure_latency(
+        &self,
+        id: i64,
+        samples: u32,
+        timeout_ms: u64,
+    ) -> Result<McpLatency, String> {
+        use crate::mcp::constants::{MCP_METHOD_PING, MCP_METHOD_TOOLS_LIST};
+        let mut method = MCP_METHOD_PING;
+        let mut durations = Vec::with_capacity(samples as usize);
+        for n in 0..samples.max(1) {
+            let started = Instant::now();
+            let result = self
+                .send(id, method, serde_json::json!({}), timeout_ms)
+                .await;
+            match result {
+                Err(e) if n == 0 && method == MCP_METHOD_PING => {
+                    log::debug!("mcp: session {id} ping failed ({e}); timing tools/list instead");
+                    method = MCP_METHOD_TOOLS_LIST;
+                    let started = Instant::now();
+                    self.send(id, method, serde_json::json!({}), timeout_ms)
+                        .await?;
+                    durations.push(started.elapsed());
+                }
+                result => {
+                    result?;
+                    durations.push(started.elapsed());
+                }
+            }
+        }
+        let (min_ms, median_ms, max_ms) = latency_stats(&mut durations);
+        Ok(McpLatency {
+            id,
+            method: method.to_string(),
+            samples: durations.len() as u32,
+            min_ms,
+            median_ms,
+            max_ms,
+        })
+    }
+
     /// Lists available tools for `id`.
     pub async fn list_tools(&self, id: i64, timeout_ms: u64) -> Result<Vec<McpToolInfo>, String> {
-        let mut sessions = self.sessions.lock().await;
-        let s = sessions.get_mut(&id).ok_or("not connected")?;
-        let result = s
+        let result = self
             .send(
+                id,
                 crate::mcp::constants::MCP_METHOD_TOOLS_LIST,
                 serde_json::json!({}),
                 timeout_ms,
             )
             .await?;
-        Ok(parse_tools_array(&result))
+        let tools = parse_tools_array(&result);
+        self.tools.lock().await.insert(id, tools.clone());
+        Ok(tools)
+    }
+
+    /// What server `id` advertised when its current session was initialized.
+    pub async fn capabilities(&self, id: i64) -> Result<McpCapabilities, String> {
+        Ok(self.session(id).await?.lock().await.capabilities())
+    }
+
+    /// Asks server `id` to send log notifications at `level` and above.
+    pub async fn set_log_level(&self, id: i64, level: &str, timeout_ms: u64) -> Result<(), String> {
+        self.send(
+            id,
+            crate::mcp::constants::MCP_METHOD_LOGGING_SET_LEVEL,
+            serde_json::json!({ "level": level }),
+            timeout_ms,
+        )
+        .await
+        .map(|_| ())
+    }
+
+    /// Asks server `id` to complete `argument` of the prompt or resource `reference`.
+    /// Servers that did not advertise the `completions` capability get no request and
+    /// yield no suggestions.
+    pub async fn complete_argument(
+        &self,
+        id: i64,
+        reference: &McpCompletionRef,
+        argument: &McpCompletionArgument,
+        timeout_ms: u64,
+    ) -> Result<McpCompletion, String> {
+        let supported = self.session(id).await?.lock().await.supports_completions();
+        if !supported {
+            return Ok(McpCompletion::default());
+        }
+        let result = self
+            .send(
+                id,
+                crate::mcp::constants::MCP_METHOD_COMPLETION_COMPLETE,
+                serde_json::json!({ "ref": reference, "argument": argument }),
+                timeout_ms,
+            )
+            .await?;
+        Ok(parse_completion(&result))
+    }
+
+    /// Checks `args` against the cached `inputSchema` of `tool`, listing tools first if
+    /// none are cached. Tools without a schema accept any arguments.
+    pub async fn validate_tool_args(
+        &self,
+        id: i64,
+        tool: &str,
+        args: &serde_json::Value,
+        timeout_ms: u64,
+    ) -> Result<(), String> {
+        let cached = self.tools.lock().await.get(&id).cloned();
+        let tools = match cached {
+            Some(tools) => tools,
+            None => self.list_tools(id, timeout_ms).await?,
+        };
+        let info = tools
+            .iter()
+            .find(|t| t.name == tool)
+            .ok_or_else(|| format!("unknown tool: {tool}"))?;
+        match &info.input_schema {
+            Some(schema) => crate::mcp::schema::validate_tool_args(schema, args)
+                .map_err(|e| format!("invalid arguments for {tool}: {e}")),
+            None => Ok(()),
+        }
     }
 
     /// Calls a tool for `id` with JSON args; returns concatenated text content.
@@ -80,10 +709,9 @@ impl McpManager {
         args: serde_json::Value,
         timeout_ms: u64,
     ) -> Result<String, String> {
-        let mut sessions = self.sessions.lock().await;
-        let s = sessions.get_mut(&id).ok_or("not connected")?;
-        let result = s
+        let result = self
             .send(
+                id,
                 crate::mcp::constants::MCP_METHOD_TOOLS_CALL,
                 serde_json::json!({ "name": tool, "arguments": args }),
                 timeout_ms,
@@ -115,4 +743,343 @@ impl McpManager {
     }
 }
 
+/// Min, median and max of `durations` in milliseconds; zeros when empty.
+fn latency_stats(durations: &mut [std::time::Duration]) -> (f64, f64, f64) {
+    if durations.is_empty() {
+        return (0.0, 0.0, 0.0);
+    }
+    durations.sort();
+    let ms = |d: std::time::Duration| d.as_secs_f64() * 1000.0;
+    let len = durations.len();
+    // The two middle samples coincide when `len` is odd
+    let median = (ms(durations[(len - 1) / 2]) + ms(durations[len / 2])) / 2.0;
+    (ms(durations[0]), median, ms(durations[len - 1]))
+}
+
+/// Pretty-prints `value` (one key per line, so `redact_secrets` sees `"key": value`
+/// pairs), redacts it and caps its size.
+fn debug_json(value: &serde_json::Value) -> String {
+    let text = serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string());
+    truncate_debug(crate::log_export::redact_secrets(&text))
+}
+
+fn truncate_debug(mut text: String) -> String {
+    if text.len() > MCP_DEBUG_EXCHANGE_MAX_BYTES {
+        let mut end = MCP_DEBUG_EXCHANGE_MAX_BYTES;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+        text.push_str("\n... (truncated)");
+    }
+    text
+}
+
 // Re-exports handled by parent mod
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::{McpManager, StdioLaunch};
+    use crate::mcp::types::McpShutdownReport;
+
+    /// Minimal stdio MCP server that answers every request with `{"content":"ok"}`.
+    /// Given a count as `$1`, it exits after that many requests, like a server that
+    /// restarts itself when idle.
+    const FAKE_SERVER: &str = r#"n=0
+while read -r line; do
+  id=$(printf '%s' "$line" | sed -n 's/.*"id":\([0-9]*\).*/\1/p')
+  [ -z "$id" ] && continue
+  printf '{"jsonrpc":"2.0","id":%s,"result":{"content":"ok"}}\n' "$id"
+  n=$((n+1))
+  [ -n "$1" ] && [ "$n" -ge "$1" ] && exit 0
+done"#;
+
+    /// Stdio MCP server that echoes each request's tool name back as its content,
+    /// pausing before it answers so overlapping requests would be noticed.
+    const ECHO_NAME_SERVER: &str = r#"while read -r line; do
+  id=$(printf '%s' "$line" | sed -n 's/.*"id":\([0-9]*\).*/\1/p')
+  [ -z "$id" ] && continue
+  name=$(printf '%s' "$line" | sed -n 's/.*"name":"\([^"]*\)".*/\1/p')
+  sleep 0.01
+  printf '{"jsonrpc":"2.0","id":%s,"result":{"content":"%s"}}\n' "$id" "$name"
+done"#;
+
+    /// Stdio MCP server that asks the client for its roots before answering a tool call,
+    /// then reports the error code it got back as the call's content.
+    const ROOTS_REQUEST_SERVER: &str = r#"while read -r line; do
+  id=$(printf '%s' "$line" | sed -n 's/.*"id":\([0-9]*\).*/\1/p')
+  [ -z "$id" ] && continue
+  case "$line" in
+    *'"tools/call"'*)
+      printf '{"jsonrpc":"2.0","id":"srv-1","method":"roots/list"}\n'
+      read -r reply
+      code=$(printf '%s' "$reply" | sed -n 's/.*"code":\(-[0-9]*\).*/\1/p')
+      printf '{"jsonrpc":"2.0","id":%s,"result":{"content":"%s"}}\n' "$id" "$code" ;;
+    *) printf '{"jsonrpc":"2.0","id":%s,"result":{}}\n' "$id" ;;
+  esac
+done"#;
+
+    fn launch(command: &str, args: &[String], connect_timeout_ms: u64) -> StdioLaunch {
+        StdioLaunch {
+            command: command.to_string(),
+            args: args.to_vec(),
+            env: serde_json::json!({}),
+            env_file: None,
+            cwd: None,
+            connect_timeout_ms,
+        }
+    }
+
+    fn fake_server_args(exit_after: Option<u32>) -> Vec<String> {
+        let mut args = vec!["-c".to_string(), FAKE_SERVER.to_string(), "sh".to_string()];
+        args.extend(exit_after.map(|n| n.to_string()));
+        args
+    }
+
+    async fn connect(manager: &McpManager, id: i64, exit_after: Option<u32>) {
+        let args = fake_server_args(exit_after);
+        manager
+            .ensure_stdio(id, launch("/bin/sh", &args, 5_000))
+            .await
+            .unwrap();
+    }
+
+    /// Whether `pid` is a live (non-zombie) process, per `ps`.
+    fn is_running(pid: u32) -> bool {
+        std::process::Command::new("ps")
+            .args(["-o", "stat=", "-p", &pid.to_string()])
+            .output()
+            .map(|o| {
+                let stat = String::from_utf8_lossy(&o.stdout);
+                let stat = stat.trim();
+                !stat.is_empty() && !stat.starts_with('Z')
+            })
+            .unwrap_or(false)
+    }
+
+    #[tokio::test]
+    async fn shutdown_force_kills_children_that_do_not_close_in_time() {
+        let manager = McpManager::new();
+        connect(&manager, 1, None).await;
+        connect(&manager, 2, None).await;
+        let pids = manager.child_pids();
+        assert_eq!(pids.len(), 2);
+
+        // A request in flight on session 2 keeps its lock, so it cannot close itself.
+        let stuck = manager.sessions.lock().await.get(&2).cloned().unwrap();
+        let _in_flight = stuck.lock().await;
+
+        let report = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            manager.shutdown(std::time::Duration::from_millis(200)),
+        )
+        .await
+        .expect("shutdown exceeded its deadline")
+        .unwrap();
+        assert_eq!(
+            report,
+            McpShutdownReport {
+                closed: 1,
+                force_killed: 1
+            }
+        );
+        assert!(manager.session_ids().await.is_empty());
+        assert!(manager.child_pids().is_empty());
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(2);
+        while pids.iter().any(|pid| is_running(*pid)) && std::time::Instant::now() < deadline {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert!(
+            pids.iter().all(|pid| !is_running(*pid)),
+            "children still running"
+        );
+    }
+
+    #[tokio::test]
+    async fn call_tool_respawns_stdio_server_after_it_exits() {
+        let manager = McpManager::new();
+        // Exits after initialize + one call.
+        connect(&manager, 1, Some(2)).await;
+
+        let first = manager
+            .call_tool(1, "echo", serde_json::json!({}), 5_000)
+            .await;
+        assert_eq!(first.as_deref(), Ok("ok"));
+
+        // Once the server has exited, the next call must respawn it transparently.
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        while manager.session_health(false, 0).await[0].alive
+            && std::time::Instant::now() < deadline
+        {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        let second = manager
+            .call_tool(1, "echo", serde_json::json!({}), 5_000)
+            .await;
+        assert_eq!(second.as_deref(), Ok("ok"));
+        manager.drop_session(1).await;
+    }
+
+    #[tokio::test]
+    async fn evicts_least_recently_used_session_over_the_cap() {
+        let manager = McpManager::new();
+        manager.set_max_sessions(2);
+        connect(&manager, 1, None).await;
+        connect(&manager, 2, None).await;
+
+        // Using session 1 makes session 2 the least recently used.
+        manager
+            .call_tool(1, "echo", serde_json::json!({}), 5_000)
+            .await
+            .unwrap();
+        connect(&manager, 3, None).await;
+
+        let ids: Vec<i64> = manager
+            .session_health(false, 0)
+            .await
+            .iter()
+            .map(|h| h.id)
+            .collect();
+        assert_eq!(ids, vec![1, 3]);
+        manager.drop_all_sessions().await;
+    }
+
+    #[tokio::test]
+    async fn records_last_error_until_the_next_success() {
+        let manager = McpManager::new();
+        let failed = manager
+            .ensure_stdio(7, launch("/nonexistent/mcp-server", &[], 1_000))
+            .await;
+        assert!(failed.is_err());
+        assert_eq!(manager.last_error(7).map(|e| e.id), Some(7));
+
+        connect(&manager, 7, None).await;
+        assert!(manager.last_error(7).is_none());
+        manager.drop_session(7).await;
+    }
+
+    #[test]
+    fn latency_stats_use_the_middle_samples() {
+        use std::time::Duration;
+        let mut odd = [30, 10, 20].map(Duration::from_millis);
+        assert_eq!(super::latency_stats(&mut odd), (10.0, 20.0, 30.0));
+        let mut even = [40, 10, 20, 30].map(Duration::from_millis);
+        assert_eq!(super::latency_stats(&mut even), (10.0, 25.0, 40.0));
+    }
+
+    #[tokio::test]
+    async fn measures_latency_over_the_cached_session() {
+        let manager = McpManager::new();
+        connect(&manager, 5, None).await;
+        let latency = manager.measure_latency(5, 3, 5_000).await.unwrap();
+        assert_eq!((latency.method.as_str(), latency.samples), ("ping", 3));
+        assert!(latency.min_ms <= latency.median_ms && latency.median_ms <= latency.max_ms);
+        manager.drop_session(5).await;
+    }
+
+    #[tokio::test]
+    async fn keeps_the_last_exchange_only_in_debug_mode() {
+        let manager = McpManager::new();
+        connect(&manager, 4, None).await;
+        let args = serde_json::json!({ "api_key": "hunter2", "query": "rust" });
+
+        manager
+            .call_tool(4, "search", args.clone(), 5_000)
+            .await
+            .unwrap();
+        assert!(manager.last_exchange(4).is_none());
+
+        manager.set_debug(true);
+        manager.call_tool(4, "search", args, 5_000).await.unwrap();
+        let exchange = manager.last_exchange(4).unwrap();
+        assert_eq!(exchange.method, "tools/call");
+        assert!(exchange.ok);
+        assert!(exchange.request.contains("rust"));
+        assert!(!exchange.request.contains("hunter2"));
+
+        manager.set_debug(false);
+        assert!(manager.last_exchange(4).is_none());
+        manager.drop_session(4).await;
+    }
+
+    #[tokio::test]
+    async fn server_requests_are_answered_with_method_not_found() {
+        let manager = McpManager::new();
+        let args = vec![
+            "-c".to_string(),
+            ROOTS_REQUEST_SERVER.to_string(),
+            "sh".to_string(),
+        ];
+        manager
+            .ensure_stdio(10, launch("/bin/sh", &args, 5_000))
+            .await
+            .unwrap();
+
+        let result = manager
+            .call_tool(10, "search", serde_json::json!({}), 5_000)
+            .await;
+        assert_eq!(result.as_deref(), Ok("-32601"));
+        manager.drop_session(10).await;
+    }
+
+    #[tokio::test]
+    async fn concurrent_ensures_for_one_server_spawn_it_once() {
+        let manager = McpManager::new();
+        let log = std::env::temp_dir().join(format!("openchat-mcp-spawns-{}", std::process::id()));
+        let _ = std::fs::remove_file(&log);
+        let script = format!("echo spawned >> '{}'\n{FAKE_SERVER}", log.display());
+        let args = vec!["-c".to_string(), script, "sh".to_string()];
+
+        let ensures: Vec<_> = (0..4)
+            .map(|_| {
+                let (manager, args) = (manager.clone(), args.clone());
+                tokio::spawn(async move {
+                    manager
+                        .ensure_stdio(11, launch("/bin/sh", &args, 5_000))
+                        .await
+                })
+            })
+            .collect();
+        for ensure in ensures {
+            ensure.await.unwrap().unwrap();
+        }
+
+        let spawns = std::fs::read_to_string(&log).unwrap();
+        assert_eq!(spawns.lines().count(), 1);
+        manager.drop_session(11).await;
+        let _ = std::fs::remove_file(&log);
+    }
+
+    #[tokio::test]
+    async fn concurrent_calls_on_one_session_are_serialized() {
+        let manager = McpManager::new();
+        let args = vec![
+            "-c".to_string(),
+            ECHO_NAME_SERVER.to_string(),
+            "sh".to_string(),
+        ];
+        manager
+            .ensure_stdio(9, launch("/bin/sh", &args, 5_000))
+            .await
+            .unwrap();
+
+        let calls: Vec<_> = (0..8)
+            .map(|n| {
+                let manager = manager.clone();
+                tokio::spawn(async move {
+                    let tool = format!("tool{n}");
+                    let result = manager
+                        .call_tool(9, &tool, serde_json::json!({}), 5_000)
+                        .await;
+                    (tool, result)
+                })
+            })
+            .collect();
+        for call in calls {
+            let (tool, result) = call.await.unwrap();
+            assert_eq!(result.as_deref(), Ok(tool.as_str()));
+        }
+        manager.drop_session(9).await;
+    }
+}
@@ -5,16 +5,27 @@
 //! - `McpSession` transport (STDIO/HTTP)
 //! - `check_server` best-effort connectivity probe
 //! - `McpToolInfo`/`McpCheckResult` data types
+//! - `transport_schemas` describing the fields each transport accepts
+//! - `import`/`export` of `mcpServers` JSON blocks shared with other MCP clients
 
 pub mod constants;
+pub mod events;
+pub mod export;
+pub mod import;
+pub mod schema;
 pub mod serde_utils;
 pub mod session; // DB-backed session ensure (existing)
 pub mod store; // DB store helpers (existing)
+pub mod transports;
 
 mod manager;
 mod transport;
 mod types;
 
-pub use manager::McpManager;
+pub use manager::{McpManager, StdioLaunch};
 pub use transport::{check_server, TransportConfig};
-pub use types::{McpCheckResult, McpToolInfo};
+pub use types::{
+    McpCapabilities, McpCheckResult, McpCompletion, McpCompletionArgument, McpCompletionRef,
+    McpExchange, McpLatency, McpReconnectResult, McpServerError, McpServerTool, McpSessionHealth,
+    McpToolCatalog, McpToolInfo, McpToolListFailure,
+};
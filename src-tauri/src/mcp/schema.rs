@@ -0,0 +1,132 @@
+//! Minimal JSON Schema checks for tool arguments.
+//!
+//! Covers the subset MCP tool schemas use in practice: `type` (single or list),
+//! `required`, `properties`, `items` and `enum`. Unknown keywords are ignored, so
+//! a schema we don't fully understand never rejects valid input.
+
+use serde_json::Value;
+
+/// Validates `args` against a tool's `inputSchema`. Returns every problem found,
+/// joined into one message, e.g. `args.path: expected string, got number`.
+pub fn validate_tool_args(schema: &Value, args: &Value) -> Result<(), String> {
+    let mut errors = Vec::new();
+    validate_value(schema, args, "args", &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+fn validate_value(schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    if let Some(expected) = schema.get("type") {
+        let allowed: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|t| matches_type(t, value)) {
+            errors.push(format!(
+                "{path}: expected {}, got {}",
+                allowed.join(" or "),
+                type_name(value)
+            ));
+            return;
+        }
+    }
+
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        if !options.contains(value) {
+            errors.push(format!(
+                "{path}: must be one of {}",
+                Value::from(options.clone())
+            ));
+        }
+    }
+
+    if let Some(obj) = value.as_object() {
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !obj.contains_key(key) {
+                    errors.push(format!("{path}.{key}: required"));
+                }
+            }
+        }
+        if let Some(props) = schema.get("properties").and_then(Value::as_object) {
+            for (key, prop_schema) in props {
+                if let Some(v) = obj.get(key) {
+                    validate_value(prop_schema, v, &format!("{path}.{key}"), errors);
+                }
+            }
+        }
+    }
+
+    if let (Some(items), Some(arr)) = (schema.get("items"), value.as_array()) {
+        for (i, item) in arr.iter().enumerate() {
+            validate_value(items, item, &format!("{path}[{i}]"), errors);
+        }
+    }
+}
+
+fn matches_type(expected: &str, value: &Value) -> bool {
+    match expected {
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        // JSON Schema counts any number with a zero fractional part (e.g. `3.0`) as an integer.
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "null" => value.is_null(),
+        // Unknown type keywords are not enforced.
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::validate_tool_args;
+    use serde_json::json;
+
+    #[test]
+    fn reports_missing_required_and_type_mismatches() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string" },
+                "limit": { "type": "integer" },
+                "mode": { "enum": ["read", "write"] },
+                "tags": { "type": "array", "items": { "type": "string" } }
+            },
+            "required": ["path"]
+        });
+
+        assert!(validate_tool_args(&schema, &json!({ "path": "/tmp", "limit": 3 })).is_ok());
+        assert!(validate_tool_args(&schema, &json!({ "path": "/tmp", "limit": 3.0 })).is_ok());
+
+        let err = validate_tool_args(
+            &schema,
+            &json!({ "limit": 1.5, "mode": "delete", "tags": ["a", 1] }),
+        )
+        .unwrap_err();
+        assert!(err.contains("args.path: required"));
+        assert!(err.contains("args.limit: expected integer, got number"));
+        assert!(err.contains("args.mode: must be one of"));
+        assert!(err.contains("args.tags[1]: expected string, got number"));
+
+        assert!(validate_tool_args(&schema, &json!("oops")).is_err());
+    }
+}
@@ -2,12 +2,21 @@ use std::sync::Arc;
 
 use sqlx::SqlitePool;
 
-use crate::mcp::constants::MCP_DEFAULT_CONNECT_TIMEOUT_MS;
+use crate::mcp::constants::{
+    MCP_DEFAULT_CONNECT_TIMEOUT_MS, MCP_DEFAULT_LIST_TOOLS_TIMEOUT_MS, MCP_DEFAULT_PING_TIMEOUT_MS,
+    MCP_RECONNECT_CONCURRENCY,
+};
 use crate::mcp::serde_utils::{
     merge_auth_header, parse_mcp_json_object, parse_mcp_json_object_opt, parse_mcp_string_array,
 };
-use crate::mcp::store::{fetch_mcp_server, DbMcpServer};
-use crate::mcp::McpManager;
+use crate::mcp::store::{
+    fetch_all_mcp_servers, fetch_mcp_server, list_enabled_mcp_server_ids, list_enabled_mcp_servers,
+    DbMcpServer,
+};
+use crate::mcp::{
+    McpManager, McpReconnectResult, McpServerTool, McpToolCatalog, McpToolInfo, McpToolListFailure,
+    StdioLaunch,
+};
 
 type ResultT<T> = Result<T, String>;
 
@@ -15,6 +24,19 @@ pub async fn ensure_mcp_session(
     id: i64,
     manager: &Arc<McpManager>,
     pool: &SqlitePool,
+) -> ResultT<()> {
+    let result = ensure_mcp_session_inner(id, manager, pool).await;
+    if result.is_err() {
+        // Successes are recorded by the manager only when a session is actually created.
+        manager.record_outcome(id, &result);
+    }
+    result
+}
+
+async fn ensure_mcp_session_inner(
+    id: i64,
+    manager: &Arc<McpManager>,
+    pool: &SqlitePool,
 ) -> ResultT<()> {
     let row = fetch_mcp_server(pool, id).await?;
     let connect_ms: u64 = normalize_connect_timeout(row.connect_timeout_ms);
@@ -24,6 +46,248 @@ pub async fn ensure_mcp_session(
     }
 }
 
+/// Drops every cached session and reconnects all enabled servers from the DB, at most
+/// `MCP_RECONNECT_CONCURRENCY` at a time. Returns one result per enabled server, by id.
+pub async fn reconnect_all_mcp_sessions(
+    manager: &Arc<McpManager>,
+    pool: &SqlitePool,
+) -> ResultT<Vec<McpReconnectResult>> {
+    manager.drop_all_sessions().await;
+    let ids = list_enabled_mcp_server_ids(pool).await?;
+    reconnect_servers(manager, pool, ids, |_| {}).await
+}
+
+/// Connects every enabled server that has no cached session yet, at most
+/// `MCP_RECONNECT_CONCURRENCY` at a time, so the first tool call skips spawn and
+/// `initialize`. `on_result` sees each server's outcome as soon as it is known.
+pub async fn connect_enabled_mcp_servers(
+    manager: &Arc<McpManager>,
+    pool: &SqlitePool,
+    on_result: impl Fn(&McpReconnectResult),
+) -> ResultT<Vec<McpReconnectResult>> {
+    let ids = list_enabled_mcp_server_ids(pool).await?;
+    reconnect_servers(manager, pool, ids, on_result).await
+}
+
+/// Pings every cached session and reconnects the enabled servers whose session no
+/// longer answers, as happens to HTTP sessions and stdio children across a system
+/// sleep. Dead sessions of disabled or deleted servers are only dropped. Returns one
+/// result per reconnected server, by id.
+pub async fn reconnect_stale_mcp_sessions(
+    manager: &Arc<McpManager>,
+    pool: &SqlitePool,
+) -> ResultT<Vec<McpReconnectResult>> {
+    let stale: Vec<i64> = manager
+        .session_health(true, MCP_DEFAULT_PING_TIMEOUT_MS)
+        .await
+        .into_iter()
+        .filter(|health| !health.alive)
+        .map(|health| health.id)
+        .collect();
+    if stale.is_empty() {
+        return Ok(Vec::new());
+    }
+    let enabled: std::collections::HashSet<i64> = list_enabled_mcp_server_ids(pool)
+        .await?
+        .into_iter()
+        .collect();
+    let mut ids = Vec::with_capacity(stale.len());
+    for id in stale {
+        manager.drop_session(id).await;
+        if enabled.contains(&id) {
+            ids.push(id);
+        }
+    }
+    log::info!("mcp: reconnecting stale sessions {ids:?}");
+    reconnect_servers(manager, pool, ids, |_| {}).await
+}
+
+/// Connects each of `ids`, at most `MCP_RECONNECT_CONCURRENCY` at a time, passing each
+/// outcome to `on_result` as it completes. Returns one result per server, by id.
+async fn reconnect_servers(
+    manager: &Arc<McpManager>,
+    pool: &SqlitePool,
+    ids: Vec<i64>,
+    on_result: impl Fn(&McpReconnectResult),
+) -> ResultT<Vec<McpReconnectResult>> {
+    let permits = Arc::new(tokio::sync::Semaphore::new(MCP_RECONNECT_CONCURRENCY));
+    let mut tasks = tokio::task::JoinSet::new();
+    for id in ids {
+        let (manager, pool, permits) = (manager.clone(), pool.clone(), permits.clone());
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            let result = ensure_mcp_session(id, &manager, &pool).await;
+            McpReconnectResult {
+                id,
+                ok: result.is_ok(),
+                error: result.err(),
+            }
+        });
+    }
+
+    let mut results = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        let result = joined.map_err(|e| e.to_string())?;
+        on_result(&result);
+        results.push(result);
+    }
+    results.sort_by_key(|r| r.id);
+    Ok(results)
+}
+
+/// Lists the tools of every enabled server, connecting lazily and at most
+/// `MCP_RECONNECT_CONCURRENCY` at a time. A server that fails is reported in
+/// `failed` rather than failing the whole call.
+pub async fn list_all_mcp_tools(
+    manager: &Arc<McpManager>,
+    pool: &SqlitePool,
+) -> ResultT<McpToolCatalog> {
+    let servers = list_enabled_mcp_servers(pool).await?;
+
+    let permits = Arc::new(tokio::sync::Semaphore::new(MCP_RECONNECT_CONCURRENCY));
+    let mut tasks = tokio::task::JoinSet::new();
+    for (rank, (id, name)) in servers.into_iter().enumerate() {
+        let (manager, pool, permits) = (manager.clone(), pool.clone(), permits.clone());
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            let tools = match ensure_mcp_session(id, &manager, &pool).await {
+                Ok(()) => {
+                    manager
+                        .list_tools(id, MCP_DEFAULT_LIST_TOOLS_TIMEOUT_MS)
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+            (rank, (id, name, tools))
+        });
+    }
+
+    let mut listed = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        listed.push(joined.map_err(|e| e.to_string())?);
+    }
+    // Keep the priority order of the server list
+    listed.sort_by_key(|(rank, _)| *rank);
+    Ok(build_tool_catalog(
+        listed.into_iter().map(|(_, listed)| listed).collect(),
+    ))
+}
+
+/// Flattens per-server results, keeping the first tool of each (server, name) pair.
+fn build_tool_catalog(listed: Vec<(i64, String, ResultT<Vec<McpToolInfo>>)>) -> McpToolCatalog {
+    let mut catalog = McpToolCatalog::default();
+    let mut seen = std::collections::HashSet::new();
+    for (server_id, server_name, result) in listed {
+        match result {
+            Ok(tools) => {
+                for tool in tools {
+                    if seen.insert((server_id, tool.name.clone())) {
+                        catalog.tools.push(McpServerTool {
+                            server_id,
+                            server_name: server_name.clone(),
+                            tool,
+                        });
+                    }
+                }
+            }
+            Err(error) => catalog.failed.push(McpToolListFailure {
+                server_id,
+                server_name,
+                error,
+            }),
+        }
+    }
+    catalog
+}
+
+/// Resolves `"serverName.toolName"` to a server id and tool name. When several servers
+/// match, the one with the highest priority wins. Fails if no server matches, if the
+/// best matches tie on priority, or if the winning server is disabled.
+pub async fn resolve_qualified_tool_name(
+    pool: &SqlitePool,
+    qualified: &str,
+) -> ResultT<(i64, String)> {
+    let servers: Vec<ServerRow> = fetch_all_mcp_servers(pool)
+        .await?
+        .into_iter()
+        .map(|entry| {
+            (
+                entry.id,
+                entry.name,
+                entry.server.enabled != 0,
+                entry.priority,
+            )
+        })
+        .collect();
+    resolve_qualified_name(&servers, qualified)
+}
+
+/// A server as seen by name resolution: `(id, name, enabled, priority)`.
+type ServerRow = (i64, String, bool, i64);
+
+/// Matches `qualified` against server rows. Server
+/// names may themselves contain dots, so every server whose name is a dot-terminated
+/// prefix is a candidate. Disabled servers are dropped when an enabled one also
+/// matches; of the rest, only the highest-priority candidates are considered.
+fn resolve_qualified_name(servers: &[ServerRow], qualified: &str) -> ResultT<(i64, String)> {
+    let mut matches: Vec<(&ServerRow, &str)> = servers
+        .iter()
+        .filter_map(|server| {
+            let tool = qualified
+                .strip_prefix(server.1.as_str())?
+                .strip_prefix('.')?;
+            (!tool.is_empty()).then_some((server, tool))
+        })
+        .collect();
+    if matches.iter().any(|(server, _)| server.2) {
+        matches.retain(|(server, _)| server.2);
+    }
+    if let Some(top) = matches.iter().map(|(server, _)| server.3).max() {
+        matches.retain(|(server, _)| server.3 == top);
+    }
+    match matches.as_slice() {
+        [] => Err(format!(
+            "no MCP server matches '{qualified}' (expected \"serverName.toolName\")"
+        )),
+        [((id, name, enabled, _), tool)] => {
+            if !enabled {
+                return Err(format!("MCP server '{name}' is disabled"));
+            }
+            Ok((*id, tool.to_string()))
+        }
+        many => {
+            let names: Vec<String> = many
+                .iter()
+                .map(|((id, name, _, _), _)| format!("'{name}' (id {id})"))
+                .collect();
+            Err(format!(
+                "'{qualified}' is ambiguous; it matches servers {}",
+                names.join(", ")
+            ))
+        }
+    }
+}
+
+/// Drops cached sessions whose server is disabled or no longer exists, killing stdio
+/// children. Returns the dropped ids, ascending.
+pub async fn disconnect_disabled_mcp_sessions(
+    manager: &Arc<McpManager>,
+    pool: &SqlitePool,
+) -> ResultT<Vec<i64>> {
+    let enabled: std::collections::HashSet<i64> = list_enabled_mcp_server_ids(pool)
+        .await?
+        .into_iter()
+        .collect();
+    let mut dropped = Vec::new();
+    for id in manager.session_ids().await {
+        if !enabled.contains(&id) && manager.drop_session(id).await {
+            log::info!("mcp: disconnected session {id}; server is disabled or removed");
+            dropped.push(id);
+        }
+    }
+    Ok(dropped)
+}
+
 enum Transport {
     Stdio,
     Http,
@@ -56,18 +320,15 @@ async fn ensure_stdio_from_row(
         .command
         .as_deref()
         .ok_or_else(|| "missing command".to_string())?;
-    let args_vec = parse_mcp_string_array(row.args.as_deref());
-    let env_val = parse_mcp_json_object(row.env.as_deref());
-    manager
-        .ensure_stdio(
-            id,
-            command,
-            &args_vec,
-            &env_val,
-            row.cwd.as_deref(),
-            connect_ms,
-        )
-        .await
+    let launch = StdioLaunch {
+        command: command.to_string(),
+        args: parse_mcp_string_array(row.args.as_deref()),
+        env: parse_mcp_json_object(row.env.as_deref()),
+        env_file: row.env_file.clone(),
+        cwd: row.cwd.clone(),
+        connect_timeout_ms: connect_ms,
+    };
+    manager.ensure_stdio(id, launch).await
 }
 
 async fn ensure_http_from_row(
@@ -89,3 +350,78 @@ async fn ensure_http_from_row(
         .ensure_http(id, url, headers_val.as_ref(), connect_ms)
         .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool(name: &str) -> McpToolInfo {
+        McpToolInfo {
+            name: name.to_string(),
+            description: None,
+            input_schema: None,
+        }
+    }
+
+    #[test]
+    fn resolves_qualified_tool_names() {
+        let servers = vec![
+            (1, "files".to_string(), true, 0),
+            (2, "web.search".to_string(), true, 0),
+            (3, "web".to_string(), true, 0),
+            (4, "old".to_string(), false, 0),
+            (5, "dup".to_string(), true, 0),
+            (6, "dup".to_string(), true, 0),
+            (7, "pick".to_string(), true, 0),
+            (8, "pick".to_string(), true, 2),
+            (9, "pick".to_string(), true, 1),
+            (10, "live".to_string(), false, 5),
+            (11, "live".to_string(), true, 0),
+        ];
+        assert_eq!(
+            resolve_qualified_name(&servers, "files.read.all"),
+            Ok((1, "read.all".to_string()))
+        );
+        assert_eq!(
+            resolve_qualified_name(&servers, "web.fetch"),
+            Ok((3, "fetch".to_string()))
+        );
+        let err = resolve_qualified_name(&servers, "web.search.query").unwrap_err();
+        assert!(err.contains("ambiguous"), "unexpected: {err}");
+        let err = resolve_qualified_name(&servers, "dup.x").unwrap_err();
+        assert!(err.contains("ambiguous"), "unexpected: {err}");
+        assert_eq!(
+            resolve_qualified_name(&servers, "pick.x"),
+            Ok((8, "x".to_string()))
+        );
+        assert_eq!(
+            resolve_qualified_name(&servers, "live.x"),
+            Ok((11, "x".to_string()))
+        );
+        let err = resolve_qualified_name(&servers, "old.tool").unwrap_err();
+        assert!(err.contains("disabled"), "unexpected: {err}");
+        assert!(resolve_qualified_name(&servers, "files.").is_err());
+        assert!(resolve_qualified_name(&servers, "missing.tool").is_err());
+    }
+
+    #[test]
+    fn tool_catalog_dedups_per_server_and_reports_failures() {
+        let catalog = build_tool_catalog(vec![
+            (
+                1,
+                "files".into(),
+                Ok(vec![tool("read"), tool("read"), tool("write")]),
+            ),
+            (2, "web".into(), Err("connect timed out".into())),
+            (3, "notes".into(), Ok(vec![tool("read")])),
+        ]);
+        let names: Vec<_> = catalog
+            .tools
+            .iter()
+            .map(|t| format!("{}.{}", t.server_name, t.tool.name))
+            .collect();
+        assert_eq!(names, ["files.read", "files.write", "notes.read"]);
+        assert_eq!(catalog.failed.len(), 1);
+        assert_eq!(catalog.failed[0].server_id, 2);
+    }
+}
@@ -1,7 +1,7 @@
 use sqlx::SqlitePool;
 
 pub const SELECT_MCP_SERVER_BY_ID: &str =
-    "SELECT transport, command, args, env, cwd, url, headers, auth, heartbeat_sec, connect_timeout_ms, enabled FROM mcp_servers WHERE id = ?";
+    "SELECT transport, command, args, env, env_file, cwd, url, headers, auth, heartbeat_sec, connect_timeout_ms, enabled FROM mcp_servers WHERE id = ?";
 
 #[derive(sqlx::FromRow)]
 pub struct DbMcpServer {
@@ -9,6 +9,8 @@ pub struct DbMcpServer {
     pub command: Option<String>,
     pub args: Option<String>,
     pub env: Option<String>,
+    /// Path of a `.env`-style file whose variables are loaded under `env` at spawn time.
+    pub env_file: Option<String>,
     pub cwd: Option<String>,
     pub url: Option<String>,
     pub headers: Option<String>,
@@ -18,6 +20,62 @@ pub struct DbMcpServer {
     pub enabled: i64,
 }
 
+pub const SELECT_ALL_MCP_SERVERS: &str =
+    "SELECT id, name, priority, transport, command, args, env, env_file, cwd, url, headers, auth, heartbeat_sec, connect_timeout_ms, enabled FROM mcp_servers ORDER BY priority DESC, id";
+
+/// A stored server together with its id, display name and priority.
+#[derive(sqlx::FromRow)]
+pub struct DbMcpServerEntry {
+    pub id: i64,
+    pub name: String,
+    pub priority: i64,
+    #[sqlx(flatten)]
+    pub server: DbMcpServer,
+}
+
+/// Returns every saved server, enabled or not, highest priority first.
+pub async fn fetch_all_mcp_servers(pool: &SqlitePool) -> Result<Vec<DbMcpServerEntry>, String> {
+    sqlx::query_as::<_, DbMcpServerEntry>(SELECT_ALL_MCP_SERVERS)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+pub async fn list_enabled_mcp_server_ids(pool: &SqlitePool) -> Result<Vec<i64>, String> {
+    sqlx::query_scalar("SELECT id FROM mcp_servers WHERE enabled = 1 ORDER BY priority DESC, id")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Ids and names of enabled servers, highest priority first.
+pub async fn list_enabled_mcp_servers(pool: &SqlitePool) -> Result<Vec<(i64, String)>, String> {
+    sqlx::query_as("SELECT id, name FROM mcp_servers WHERE enabled = 1 ORDER BY priority DESC, id")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Sets a server's priority. Higher values take precedence when tool names overlap.
+pub async fn set_mcp_server_priority(
+    pool: &SqlitePool,
+    id: i64,
+    priority: i64,
+) -> Result<(), String> {
+    let result = sqlx::query(
+        "UPDATE mcp_servers SET priority = ?, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+    )
+    .bind(priority)
+    .bind(id)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    if result.rows_affected() == 0 {
+        return Err("server not found".into());
+    }
+    Ok(())
+}
+
 pub async fn fetch_mcp_server(pool: &SqlitePool, id: i64) -> Result<DbMcpServer, String> {
     let row_opt: Option<DbMcpServer> = sqlx::query_as::<_, DbMcpServer>(SELECT_MCP_SERVER_BY_ID)
         .bind(id)
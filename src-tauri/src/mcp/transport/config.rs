@@ -7,6 +7,7 @@ pub enum TransportConfig<'a> {
         command: &'a str,
         args: &'a [String],
         env: Option<&'a serde_json::Value>,
+        env_file: Option<&'a str>,
         cwd: Option<&'a str>,
         connect_timeout_ms: u64,
         list_tools_timeout_ms: u64,
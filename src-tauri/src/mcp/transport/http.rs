@@ -4,7 +4,6 @@ use crate::mcp::constants::{
     MCP_METHOD_INITIALIZE, MCP_NOTIFICATION_INITIALIZED, MCP_PROTOCOL_VERSION,
 };
 use crate::mcp::transport::session::{McpSession, McpTransport};
-use tokio::time::Duration;
 
 /// Creates initialization parameters for MCP session
 fn init_params() -> serde_json::Value {
@@ -15,27 +14,21 @@ fn init_params() -> serde_json::Value {
     })
 }
 
-/// Builds an HTTP client with specified timeout
-fn build_http_client(timeout_ms: u64) -> Result<reqwest::Client, String> {
-    reqwest::Client::builder()
-        .timeout(Duration::from_millis(timeout_ms))
-        .build()
-        .map_err(|e| e.to_string())
-}
-
 /// Creates a new HTTP-based MCP session
 pub async fn create_http_session(
     url: &str,
     headers: Option<&serde_json::Value>,
     connect_timeout_ms: u64,
 ) -> Result<McpSession, String> {
-    let client = build_http_client(connect_timeout_ms)?;
+    // Requests carry their own timeouts, so sessions share the app-wide pool.
+    let client = crate::http_client::shared_client().clone();
     let mut session = McpSession::new_http(client, url.to_string(), headers.cloned());
 
     // Send initialize request and wait for response
-    let _ = session
+    let result = session
         .send(MCP_METHOD_INITIALIZE, init_params(), connect_timeout_ms)
         .await?;
+    session.set_initialize_result(result);
 
     // Send notifications/initialized notification (no response expected)
     session
@@ -7,13 +7,15 @@ pub mod config;
 pub mod http;
 pub mod parsing;
 pub mod session;
+pub mod stderr;
 pub mod stdio;
 pub mod validation;
 
 // Re-export main types and functions for backwards compatibility
 pub use config::TransportConfig;
 pub use http::create_http_session;
-pub use parsing::parse_tools_array;
-pub use session::{McpSession, McpTransport};
+pub use parsing::{parse_completion, parse_tools_array};
+pub use session::{is_session_lost, is_unsent_request, McpSession, McpTransport, NotificationSink};
+pub use stderr::StderrLineSink;
 pub use stdio::spawn_stdio_session;
 pub use validation::check_server;
@@ -1,19 +1,47 @@
 //! Response parsing utilities for MCP protocol
 
-use crate::mcp::types::McpToolInfo;
+use crate::mcp::types::{McpCompletion, McpToolInfo};
 
-/// Parses the tools array from an MCP tools/list response
+/// Tools parsed from a tools/list response, with the names of duplicates that were
+/// dropped.
+pub struct ParsedTools {
+    pub tools: Vec<McpToolInfo>,
+    pub duplicates: Vec<String>,
+}
+
+/// Parses the tools array from an MCP tools/list response. Duplicate names are
+/// dropped (the first wins) and logged, since calls to them would be ambiguous.
 pub fn parse_tools_array(result_value: &serde_json::Value) -> Vec<McpToolInfo> {
+    let parsed = parse_tools_list(result_value);
+    if !parsed.duplicates.is_empty() {
+        log::warn!(
+            "mcp: tools/list returned duplicate tool names {:?}; keeping the first of each",
+            parsed.duplicates
+        );
+    }
+    parsed.tools
+}
+
+/// Like `parse_tools_array`, but reports the duplicate names instead of logging them.
+pub fn parse_tools_list(result_value: &serde_json::Value) -> ParsedTools {
     let tools = result_value
         .get("tools")
         .and_then(|t| t.as_array())
         .cloned()
         .unwrap_or_default();
     let mut out = Vec::with_capacity(tools.len());
+    let mut seen = std::collections::HashSet::new();
+    let mut duplicates = Vec::new();
     for tool in tools.iter() {
         let Some(name) = tool.get("name").and_then(|n| n.as_str()) else {
             continue;
         };
+        if !seen.insert(name) {
+            if !duplicates.iter().any(|d| d == name) {
+                duplicates.push(name.to_string());
+            }
+            continue;
+        }
         let description = tool
             .get("description")
             .and_then(|d| d.as_str())
@@ -29,12 +57,41 @@ pub fn parse_tools_array(result_value: &serde_json::Value) -> Vec<McpToolInfo> {
             input_schema,
         });
     }
-    out
+    ParsedTools {
+        tools: out,
+        duplicates,
+    }
+}
+
+/// Parses the `completion` object of a `completion/complete` response. Non-string
+/// values are skipped; a missing object yields no suggestions.
+pub fn parse_completion(result_value: &serde_json::Value) -> McpCompletion {
+    let Some(completion) = result_value.get("completion") else {
+        return McpCompletion::default();
+    };
+    let values = completion
+        .get("values")
+        .and_then(|v| v.as_array())
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    McpCompletion {
+        values,
+        total: completion.get("total").and_then(|t| t.as_u64()),
+        has_more: completion
+            .get("hasMore")
+            .and_then(|h| h.as_bool())
+            .unwrap_or(false),
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::parse_tools_array;
+    use super::{parse_completion, parse_tools_array, parse_tools_list};
     use serde_json::json;
 
     #[test]
@@ -78,4 +135,34 @@ mod tests {
         assert_eq!(tools[2].name, "no_schema");
         assert!(tools[2].input_schema.is_none());
     }
+
+    #[test]
+    fn parse_tools_list_keeps_the_first_of_duplicate_names() {
+        let parsed = parse_tools_list(&json!({
+            "tools": [
+                { "name": "search", "description": "first" },
+                { "name": "fetch" },
+                { "name": "search", "description": "second" },
+                { "name": "search" }
+            ]
+        }));
+        let names: Vec<_> = parsed.tools.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["search", "fetch"]);
+        assert_eq!(parsed.tools[0].description.as_deref(), Some("first"));
+        assert_eq!(parsed.duplicates, vec!["search"]);
+    }
+
+    #[test]
+    fn parse_completion_reads_values_and_paging() {
+        let parsed = parse_completion(&json!({
+            "completion": { "values": ["python", "pytorch", 3], "total": 10, "hasMore": true }
+        }));
+        assert_eq!(parsed.values, vec!["python", "pytorch"]);
+        assert_eq!(parsed.total, Some(10));
+        assert!(parsed.has_more);
+
+        let empty = parse_completion(&json!({}));
+        assert!(empty.values.is_empty());
+        assert!(!empty.has_more);
+    }
 }
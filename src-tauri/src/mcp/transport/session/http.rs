@@ -5,7 +5,7 @@ use async_trait::async_trait;
 use log::{debug, warn};
 use tokio::time::Duration;
 
-use super::McpTransport;
+use super::{http_error, McpTransport};
 
 /// HTTP-based MCP session
 #[derive(Debug)]
@@ -14,6 +14,7 @@ pub struct HttpSession {
     url: String,
     headers: Option<serde_json::Value>,
     next_id: u64,
+    pub(super) initialize_result: Option<serde_json::Value>,
 }
 
 impl HttpSession {
@@ -24,6 +25,7 @@ impl HttpSession {
             url,
             headers,
             next_id: 0,
+            initialize_result: None,
         }
     }
 }
@@ -69,9 +71,9 @@ impl McpTransport for HttpSession {
         }
 
         // Send request and get response
-        let resp = request.send().await.map_err(|e| e.to_string())?;
+        let resp = request.send().await.map_err(http_error)?;
         let status = resp.status();
-        let body_text = resp.text().await.map_err(|e| e.to_string())?;
+        let body_text = resp.text().await.map_err(http_error)?;
 
         if !status.is_success() {
             warn!(
@@ -138,7 +140,7 @@ impl McpTransport for HttpSession {
         }
 
         // Send notification and get response (but don't expect meaningful response)
-        let resp = request.send().await.map_err(|e| e.to_string())?;
+        let resp = request.send().await.map_err(http_error)?;
         let status = resp.status();
 
         if !status.is_success() {
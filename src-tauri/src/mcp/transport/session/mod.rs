@@ -4,6 +4,80 @@ pub mod http;
 pub mod stdio;
 
 use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::mcp::constants::{MCP_REQUEST_TIMED_OUT, MCP_TRANSPORT_CLOSED};
+use crate::mcp::types::McpCapabilities;
+
+/// Receives `(method, params)` for each notification a server sends.
+pub type NotificationSink = Arc<dyn Fn(&str, &serde_json::Value) + Send + Sync>;
+
+/// Builds an error marking the transport as closed, e.g. `transport closed: broken pipe`.
+pub fn transport_closed_error(detail: impl std::fmt::Display) -> String {
+    format!("{MCP_TRANSPORT_CLOSED}: {detail}")
+}
+
+/// Whether `err` came from `transport_closed_error`, i.e. the pipe broke rather than
+/// the server answering with an error.
+pub fn is_transport_closed(err: &str) -> bool {
+    err.starts_with(MCP_TRANSPORT_CLOSED)
+}
+
+/// Builds a transport-closed error for a request that could not be written, so the
+/// server never saw it, e.g. `transport closed: request not sent - broken pipe`.
+pub fn unsent_request_error(detail: impl std::fmt::Display) -> String {
+    transport_closed_error(format!("{UNSENT_REQUEST} - {detail}"))
+}
+
+/// Whether `err` came from `unsent_request_error`. Only such requests are safe to
+/// retry: after a read EOF or timeout the server may already have acted on them.
+pub fn is_unsent_request(err: &str) -> bool {
+    err.strip_prefix(MCP_TRANSPORT_CLOSED)
+        .and_then(|rest| rest.strip_prefix(": "))
+        .is_some_and(|rest| rest.starts_with(UNSENT_REQUEST))
+}
+
+const UNSENT_REQUEST: &str = "request not sent";
+
+/// Builds an error for a request the server did not answer in time, e.g.
+/// `request timed out: read`.
+pub fn timeout_error(detail: impl std::fmt::Display) -> String {
+    format!("{MCP_REQUEST_TIMED_OUT}: {detail}")
+}
+
+/// Whether `err` came from `timeout_error`.
+pub fn is_timeout(err: &str) -> bool {
+    err.starts_with(MCP_REQUEST_TIMED_OUT)
+}
+
+/// Whether `err` means the session is gone (the transport closed or the server
+/// stopped answering). Any other error, such as a JSON-RPC error response, came
+/// from a live server.
+pub fn is_session_lost(err: &str) -> bool {
+    is_transport_closed(err) || is_timeout(err)
+}
+
+/// Maps a reqwest error to a transport error: timeouts and connection failures mean
+/// the session is lost, anything else keeps its message.
+fn http_error(err: reqwest::Error) -> String {
+    if err.is_timeout() {
+        timeout_error(err)
+    } else if err.is_connect() {
+        transport_closed_error(err)
+    } else {
+        err.to_string()
+    }
+}
+
+/// I/O errors that mean the other end of the pipe has gone away.
+fn is_pipe_closed(err: &std::io::Error) -> bool {
+    matches!(
+        err.kind(),
+        std::io::ErrorKind::BrokenPipe
+            | std::io::ErrorKind::ConnectionReset
+            | std::io::ErrorKind::UnexpectedEof
+    )
+}
 
 /// Transport-agnostic interface for MCP communication
 #[async_trait]
@@ -93,6 +167,82 @@ impl McpSession {
         McpSession::Http(http::HttpSession::new(client, url, headers))
     }
 
+    /// Records the server's response to `initialize`.
+    pub fn set_initialize_result(&mut self, result: serde_json::Value) {
+        match self {
+            McpSession::Stdio(session) => session.initialize_result = Some(result),
+            McpSession::Http(session) => session.initialize_result = Some(result),
+        }
+    }
+
+    /// The server's response to `initialize`, if it sent one.
+    pub fn initialize_result(&self) -> Option<&serde_json::Value> {
+        match self {
+            McpSession::Stdio(session) => session.initialize_result.as_ref(),
+            McpSession::Http(session) => session.initialize_result.as_ref(),
+        }
+    }
+
+    /// Protocol version the server agreed to during `initialize`.
+    pub fn protocol_version(&self) -> Option<&str> {
+        self.initialize_result()?
+            .get("protocolVersion")
+            .and_then(|v| v.as_str())
+    }
+
+    /// Whether the server advertised `capability` during `initialize`.
+    fn has_capability(&self, capability: &str) -> bool {
+        self.initialize_result()
+            .and_then(|r| r.get("capabilities"))
+            .and_then(|c| c.get(capability))
+            .is_some_and(|v| !v.is_null())
+    }
+
+    /// Whether the server advertised the `completions` capability during `initialize`.
+    pub fn supports_completions(&self) -> bool {
+        self.has_capability("completions")
+    }
+
+    /// The capabilities and protocol version from the stored `initialize` response.
+    /// A respawned or reconnected session stores a fresh response, so this always
+    /// reflects the current connection.
+    pub fn capabilities(&self) -> McpCapabilities {
+        McpCapabilities {
+            initialized: self.initialize_result().is_some(),
+            protocol_version: self.protocol_version().map(str::to_string),
+            supports_tools: self.has_capability("tools"),
+            supports_resources: self.has_capability("resources"),
+            supports_prompts: self.has_capability("prompts"),
+            supports_logging: self.has_capability("logging"),
+            supports_completions: self.supports_completions(),
+        }
+    }
+
+    /// Routes notifications from the server to `sink`. Only STDIO sessions read
+    /// server-initiated messages; HTTP sessions ignore the sink.
+    pub fn set_notification_sink(&mut self, sink: Option<NotificationSink>) {
+        if let McpSession::Stdio(session) = self {
+            session.notification_sink = sink;
+        }
+    }
+
+    /// Cheap liveness check from local state: whether the STDIO child is still running.
+    /// HTTP sessions are stateless and always report alive.
+    pub fn is_alive(&mut self) -> bool {
+        match self {
+            McpSession::Stdio(session) => session.is_alive(),
+            McpSession::Http(_) => true,
+        }
+    }
+
+    /// Process id of the server child for STDIO sessions.
+    pub fn pid(&self) -> Option<u32> {
+        match self {
+            McpSession::Stdio(session) => session.pid(),
+            McpSession::Http(_) => None,
+        }
+    }
+
     /// Kills the child process if this is a STDIO session
     pub async fn kill_child(&mut self) -> Result<(), String> {
         match self {
@@ -101,3 +251,47 @@ impl McpSession {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        is_session_lost, is_unsent_request, timeout_error, transport_closed_error,
+        unsent_request_error, McpSession,
+    };
+
+    #[test]
+    fn only_closed_transports_and_timeouts_lose_the_session() {
+        assert!(is_session_lost(&transport_closed_error("broken pipe")));
+        assert!(is_session_lost(&unsent_request_error("broken pipe")));
+        assert!(is_session_lost(&timeout_error("read")));
+        assert!(!is_session_lost("Method not found"));
+    }
+
+    #[test]
+    fn only_unwritten_requests_count_as_unsent() {
+        assert!(is_unsent_request(&unsent_request_error("broken pipe")));
+        assert!(!is_unsent_request(&transport_closed_error(
+            "server closed stdout"
+        )));
+        assert!(!is_unsent_request(&timeout_error("read")));
+    }
+
+    #[test]
+    fn capabilities_come_from_the_initialize_result() {
+        let mut session =
+            McpSession::new_http(reqwest::Client::new(), "http://localhost".into(), None);
+        let caps = session.capabilities();
+        assert!(!caps.initialized);
+        assert!(!caps.supports_tools);
+
+        session.set_initialize_result(serde_json::json!({
+            "protocolVersion": "2025-06-18",
+            "capabilities": { "tools": { "listChanged": true }, "logging": {}, "prompts": null }
+        }));
+        let caps = session.capabilities();
+        assert!(caps.initialized);
+        assert_eq!(caps.protocol_version.as_deref(), Some("2025-06-18"));
+        assert!(caps.supports_tools && caps.supports_logging);
+        assert!(!caps.supports_prompts && !caps.supports_resources && !caps.supports_completions);
+    }
+}
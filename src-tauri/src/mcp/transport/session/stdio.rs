@@ -1,20 +1,35 @@
 //! STDIO session implementation for MCP
 
-use crate::mcp::constants::MCP_JSONRPC_VERSION;
+use crate::mcp::constants::{MCP_JSONRPC_METHOD_NOT_FOUND, MCP_JSONRPC_VERSION};
 use async_trait::async_trait;
 use log::{debug, error, warn};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::time::{timeout, Duration};
+use tokio::time::{timeout, timeout_at, Duration, Instant};
 
-use super::McpTransport;
+use super::{
+    is_pipe_closed, timeout_error, transport_closed_error, unsent_request_error, McpTransport,
+    NotificationSink,
+};
 
 /// STDIO-based MCP session
-#[derive(Debug)]
 pub struct StdioSession {
     child: tokio::process::Child,
     stdin: tokio::process::ChildStdin,
     reader: BufReader<tokio::process::ChildStdout>,
     next_id: u64,
+    pub(super) initialize_result: Option<serde_json::Value>,
+    /// Receives notifications the server interleaves with responses.
+    pub(super) notification_sink: Option<NotificationSink>,
+}
+
+impl std::fmt::Debug for StdioSession {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StdioSession")
+            .field("child", &self.child)
+            .field("next_id", &self.next_id)
+            .field("initialize_result", &self.initialize_result)
+            .finish_non_exhaustive()
+    }
 }
 
 impl StdioSession {
@@ -29,6 +44,56 @@ impl StdioSession {
             stdin,
             reader,
             next_id: 0,
+            initialize_result: None,
+            notification_sink: None,
+        }
+    }
+
+    /// Returns whether the child process is still running, without blocking.
+    pub fn is_alive(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+
+    /// OS process id of the child, while it has not been reaped.
+    pub fn pid(&self) -> Option<u32> {
+        self.child.id()
+    }
+
+    /// Answers a server-to-client request with a JSON-RPC "method not found" error.
+    /// The client offers no such capabilities, and a server left waiting may stall.
+    async fn reply_method_not_found(
+        &mut self,
+        id: serde_json::Value,
+        method: &str,
+        deadline: Instant,
+    ) -> Result<(), String> {
+        let reply = serde_json::json!({
+            "jsonrpc": MCP_JSONRPC_VERSION,
+            "id": id,
+            "error": {
+                "code": MCP_JSONRPC_METHOD_NOT_FOUND,
+                "message": format!("Method not found: {method}"),
+            },
+        });
+        let mut line = serde_json::to_string(&reply).map_err(|e| e.to_string())?;
+        line.push('\n');
+
+        let write_res = timeout_at(deadline, async {
+            self.stdin.write_all(line.as_bytes()).await?;
+            self.stdin.flush().await
+        })
+        .await;
+        match write_res {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(e)) if is_pipe_closed(&e) => {
+                warn!("mcp.send(stdio): stdin closed - {}", e);
+                Err(transport_closed_error(e))
+            }
+            Ok(Err(e)) => {
+                error!("mcp.send(stdio): write error - {}", e);
+                Err(e.to_string())
+            }
+            Err(_) => Err(timeout_error("write")),
         }
     }
 
@@ -64,6 +129,10 @@ impl McpTransport for StdioSession {
         let mut line = serde_json::to_string(&req).map_err(|e| e.to_string())?;
         line.push('\n');
 
+        if !self.is_alive() {
+            warn!("mcp.send(stdio): server exited before {}", method);
+            return Err(unsent_request_error("server exited"));
+        }
         let write_res = timeout(Duration::from_millis(timeout_ms), async {
             self.stdin.write_all(line.as_bytes()).await?;
             self.stdin.flush().await
@@ -72,38 +141,66 @@ impl McpTransport for StdioSession {
 
         match write_res {
             Ok(Ok(())) => {}
+            Ok(Err(e)) if is_pipe_closed(&e) => {
+                warn!("mcp.send(stdio): stdin closed - {}", e);
+                return Err(unsent_request_error(e));
+            }
             Ok(Err(e)) => {
                 error!("mcp.send(stdio): write error - {}", e);
                 return Err(e.to_string());
             }
             Err(_) => {
                 warn!("mcp.send(stdio): write timeout (timeout_ms={})", timeout_ms);
-                return Err("write timeout".to_string());
+                return Err(timeout_error("write"));
             }
         }
 
-        // Read response
-        let mut buf = String::new();
-        let read_res = timeout(
-            Duration::from_millis(timeout_ms),
-            self.reader.read_line(&mut buf),
-        )
-        .await;
+        // Read lines until our response arrives, passing notifications to the sink
+        let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+        let v = loop {
+            let mut buf = String::new();
+            let read_res = timeout_at(deadline, self.reader.read_line(&mut buf)).await;
 
-        match read_res {
-            Ok(Ok(_)) => {}
-            Ok(Err(e)) => {
-                error!("mcp.send(stdio): read error - {}", e);
-                return Err(e.to_string());
+            match read_res {
+                Ok(Ok(0)) => {
+                    warn!("mcp.send(stdio): server closed stdout");
+                    return Err(transport_closed_error("server closed stdout"));
+                }
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) if is_pipe_closed(&e) => {
+                    warn!("mcp.send(stdio): stdout closed - {}", e);
+                    return Err(transport_closed_error(e));
+                }
+                Ok(Err(e)) => {
+                    error!("mcp.send(stdio): read error - {}", e);
+                    return Err(e.to_string());
+                }
+                Err(_) => {
+                    warn!("mcp.send(stdio): read timeout (timeout_ms={})", timeout_ms);
+                    return Err(timeout_error("read"));
+                }
             }
-            Err(_) => {
-                warn!("mcp.send(stdio): read timeout (timeout_ms={})", timeout_ms);
-                return Err("read timeout".to_string());
+
+            let v: serde_json::Value = serde_json::from_str(&buf).map_err(|e| e.to_string())?;
+            match (v.get("id"), v.get("method").and_then(|m| m.as_str())) {
+                (None, Some(method)) => {
+                    if let Some(sink) = &self.notification_sink {
+                        sink(method, v.get("params").unwrap_or(&serde_json::Value::Null));
+                    }
+                }
+                (Some(id), Some(method)) => {
+                    debug!("mcp.send(stdio): declining server request {}", method);
+                    self.reply_method_not_found(id.clone(), method, deadline)
+                        .await?;
+                }
+                (Some(id), None) if id.as_u64().is_some_and(|id| id != self.next_id) => {
+                    debug!("mcp.send(stdio): skipping stale response id={}", id);
+                }
+                _ => break v,
             }
-        }
+        };
 
-        // Parse and validate response
-        let v: serde_json::Value = serde_json::from_str(&buf).map_err(|e| e.to_string())?;
+        // Validate response
         if let Some(err) = v.get("error") {
             let msg = err
                 .get("message")
@@ -150,6 +247,10 @@ impl McpTransport for StdioSession {
 
         match write_res {
             Ok(Ok(())) => Ok(()),
+            Ok(Err(e)) if is_pipe_closed(&e) => {
+                warn!("mcp.send_notification(stdio): stdin closed - {}", e);
+                Err(transport_closed_error(e))
+            }
             Ok(Err(e)) => {
                 error!("mcp.send_notification(stdio): write error - {}", e);
                 Err(e.to_string())
@@ -159,7 +260,7 @@ impl McpTransport for StdioSession {
                     "mcp.send_notification(stdio): write timeout (timeout_ms={})",
                     timeout_ms
                 );
-                Err("write timeout".to_string())
+                Err(timeout_error("write"))
             }
         }
     }
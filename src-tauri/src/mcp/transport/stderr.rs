@@ -0,0 +1,122 @@
+//! Relays a stdio MCP server's stderr line by line, with a per-second line budget
+//! so a chatty server can't flood the IPC channel.
+
+use log::debug;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::ChildStderr;
+
+use crate::mcp::constants::{MCP_STDERR_MAX_LINES_PER_SEC, MCP_STDERR_MAX_LINE_BYTES};
+
+/// Receives each relayed stderr line.
+pub type StderrLineSink = Arc<dyn Fn(String) + Send + Sync>;
+
+/// Fixed one-second window limiter. Lines over budget are counted and reported as a
+/// single summary line when the next window opens.
+struct LineRateLimiter {
+    window_start: Instant,
+    admitted: u32,
+    suppressed: u32,
+}
+
+impl LineRateLimiter {
+    fn new(now: Instant) -> Self {
+        Self {
+            window_start: now,
+            admitted: 0,
+            suppressed: 0,
+        }
+    }
+
+    /// Returns the lines to emit for `line` arriving at `now`: possibly a summary of
+    /// suppressed lines from the previous window, then the line itself if in budget.
+    fn admit(&mut self, line: String, now: Instant) -> Vec<String> {
+        let mut out = Vec::new();
+        if now.duration_since(self.window_start) >= Duration::from_secs(1) {
+            if self.suppressed > 0 {
+                out.push(format!("[{} stderr lines suppressed]", self.suppressed));
+            }
+            self.window_start = now;
+            self.admitted = 0;
+            self.suppressed = 0;
+        }
+        if self.admitted < MCP_STDERR_MAX_LINES_PER_SEC {
+            self.admitted += 1;
+            out.push(line);
+        } else {
+            self.suppressed += 1;
+        }
+        out
+    }
+}
+
+/// Truncates `line` to the byte limit on a char boundary.
+fn clamp_line(mut line: String) -> String {
+    if line.len() > MCP_STDERR_MAX_LINE_BYTES {
+        let mut end = MCP_STDERR_MAX_LINE_BYTES;
+        while !line.is_char_boundary(end) {
+            end -= 1;
+        }
+        line.truncate(end);
+        line.push('…');
+    }
+    line
+}
+
+/// Reads `stderr` until EOF. With a sink, lines are forwarded (rate limited); without
+/// one they are only logged at debug level. Draining always happens so the child
+/// never blocks on a full pipe; invalid UTF-8 is replaced rather than ending the relay.
+pub fn spawn_stderr_relay(stderr: ChildStderr, sink: Option<StderrLineSink>) {
+    tokio::spawn(async move {
+        let mut reader = BufReader::new(stderr);
+        let mut buf = Vec::new();
+        let mut limiter = LineRateLimiter::new(Instant::now());
+        loop {
+            buf.clear();
+            match reader.read_until(b'\n', &mut buf).await {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {}
+            }
+            let line = String::from_utf8_lossy(&buf);
+            let line = line.trim_end();
+            if line.is_empty() {
+                continue;
+            }
+            debug!("mcp.stderr: {line}");
+            if let Some(sink) = &sink {
+                for out in limiter.admit(clamp_line(line.to_string()), Instant::now()) {
+                    sink(out);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clamp_line, LineRateLimiter};
+    use crate::mcp::constants::{MCP_STDERR_MAX_LINES_PER_SEC, MCP_STDERR_MAX_LINE_BYTES};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn limiter_suppresses_over_budget_and_reports_next_window() {
+        let start = Instant::now();
+        let mut limiter = LineRateLimiter::new(start);
+        let emitted: usize = (0..MCP_STDERR_MAX_LINES_PER_SEC + 5)
+            .map(|i| limiter.admit(format!("line {i}"), start).len())
+            .sum();
+        assert_eq!(emitted, MCP_STDERR_MAX_LINES_PER_SEC as usize);
+
+        let next = limiter.admit("after".into(), start + Duration::from_secs(1));
+        assert_eq!(next, vec!["[5 stderr lines suppressed]", "after"]);
+    }
+
+    #[test]
+    fn clamp_line_truncates_on_char_boundary() {
+        let long = "é".repeat(MCP_STDERR_MAX_LINE_BYTES);
+        let clamped = clamp_line(long);
+        assert!(clamped.len() <= MCP_STDERR_MAX_LINE_BYTES + '…'.len_utf8());
+        assert!(clamped.ends_with('…'));
+    }
+}
@@ -4,7 +4,9 @@ use crate::mcp::constants::{
     MCP_METHOD_INITIALIZE, MCP_NOTIFICATION_INITIALIZED, MCP_PROTOCOL_VERSION,
 };
 use crate::mcp::transport::session::{McpSession, McpTransport};
+use crate::mcp::transport::stderr::{spawn_stderr_relay, StderrLineSink};
 use log::{error, info, warn};
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use tokio::io::BufReader;
 use tokio::process::Command;
@@ -74,8 +76,50 @@ fn build_stdio_command(command: &str, args: &[String]) -> Command {
     }
 }
 
-/// Applies environment variables and working directory to a command
-fn apply_env_and_cwd(cmd: &mut Command, env: Option<&serde_json::Value>, cwd: Option<&str>) {
+/// Resolves an `env_file` setting: a leading `~` is the user's home directory and a
+/// relative path is taken from the server's `cwd`. A relative path without a `cwd`
+/// is rejected rather than read from wherever the app happens to run.
+fn resolve_env_file(path: &str, cwd: Option<&str>) -> Result<PathBuf, String> {
+    let expanded = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => {
+            let home = home::home_dir()
+                .ok_or_else(|| format!("cannot expand '{path}': no home directory"))?;
+            home.join(rest.trim_start_matches(['/', '\\']))
+        }
+        _ => PathBuf::from(path),
+    };
+    if expanded.is_absolute() {
+        return Ok(expanded);
+    }
+    match cwd.map(str::trim).filter(|cwd| !cwd.is_empty()) {
+        Some(cwd) => Ok(Path::new(cwd).join(expanded)),
+        None => Err(format!(
+            "env file '{path}' is relative; use an absolute path or set the server's working directory"
+        )),
+    }
+}
+
+/// Reads `KEY=value` pairs from a `.env`-style file at `path`, resolved as in
+/// `resolve_env_file`. Comments, `export` prefixes and single or double quotes are
+/// handled by `dotenvy`. Values are never logged.
+pub fn load_env_file(path: &str, cwd: Option<&str>) -> Result<Vec<(String, String)>, String> {
+    let path = resolve_env_file(path, cwd)?;
+    if !path.is_file() {
+        return Err(format!("env file not found: {}", path.display()));
+    }
+    dotenvy::from_path_iter(&path)
+        .and_then(|iter| iter.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("invalid env file {}: {e}", path.display()))
+}
+
+/// Applies environment variables and working directory to a command. Variables from
+/// `env_file` are applied first so explicit `env` entries override them.
+fn apply_env_and_cwd(
+    cmd: &mut Command,
+    env: Option<&serde_json::Value>,
+    env_file: Option<&str>,
+    cwd: Option<&str>,
+) -> Result<(), String> {
     if let Some(cwd_val) = cwd {
         if cwd_val.trim().is_empty() {
             info!("mcp: cwd is empty string; ignoring current_dir");
@@ -83,6 +127,15 @@ fn apply_env_and_cwd(cmd: &mut Command, env: Option<&serde_json::Value>, cwd: Op
             cmd.current_dir(cwd_val);
         }
     }
+    if let Some(env_file) = env_file.filter(|p| !p.trim().is_empty()) {
+        let vars = load_env_file(env_file, cwd)?;
+        info!(
+            "mcp: loaded {} variables from env file '{}'",
+            vars.len(),
+            env_file
+        );
+        cmd.envs(vars);
+    }
     if let Some(env_obj) = env.and_then(|v| v.as_object()) {
         for (k, val) in env_obj.iter() {
             if let Some(s) = val.as_str() {
@@ -90,21 +143,25 @@ fn apply_env_and_cwd(cmd: &mut Command, env: Option<&serde_json::Value>, cwd: Op
             }
         }
     }
+    Ok(())
 }
 
-/// Spawns a new STDIO-based MCP session
+/// Spawns a new STDIO-based MCP session. Stderr is always drained; lines go to
+/// `stderr_sink` when one is given.
 pub async fn spawn_stdio_session(
     command: &str,
     args: &[String],
     env: Option<&serde_json::Value>,
+    env_file: Option<&str>,
     cwd: Option<&str>,
     connect_timeout_ms: u64,
+    stderr_sink: Option<StderrLineSink>,
 ) -> Result<McpSession, String> {
     let mut cmd = build_stdio_command(command, args);
     cmd.stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());
-    apply_env_and_cwd(&mut cmd, env, cwd);
+    apply_env_and_cwd(&mut cmd, env, env_file, cwd)?;
     let mut child = timeout(Duration::from_millis(connect_timeout_ms), async {
         cmd.spawn()
     })
@@ -129,17 +186,21 @@ pub async fn spawn_stdio_session(
     log::debug!("mcp: stdio spawned child process (pid={:?})", child.id());
     let stdin = child.stdin.take().ok_or("no stdin")?;
     let stdout = child.stdout.take().ok_or("no stdout")?;
+    if let Some(stderr) = child.stderr.take() {
+        spawn_stderr_relay(stderr, stderr_sink);
+    }
     let mut session = McpSession::new_stdio(child, stdin, BufReader::new(stdout));
 
     // Send initialize request and wait for response
-    if let Err(e) = session
+    match session
         .send(MCP_METHOD_INITIALIZE, init_params(), connect_timeout_ms)
         .await
     {
-        warn!(
+        Ok(result) => session.set_initialize_result(result),
+        Err(e) => warn!(
             "mcp: failed to send initialize request - {}, continuing anyway",
             e
-        );
+        ),
     }
 
     // Send notifications/initialized notification (no response expected)
@@ -155,3 +216,54 @@ pub async fn spawn_stdio_session(
 
     Ok(session)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{load_env_file, resolve_env_file};
+
+    #[test]
+    fn loads_env_files_with_comments_and_quotes() {
+        let path =
+            std::env::temp_dir().join(format!("openchat-mcp-env-{}.env", std::process::id()));
+        std::fs::write(
+            &path,
+            "# API credentials\nTOKEN=abc123\nexport REGION=\"eu west\"\nNOTE='a # b'\n\n",
+        )
+        .unwrap();
+        let vars = load_env_file(path.to_str().unwrap(), None).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            vars,
+            vec![
+                ("TOKEN".to_string(), "abc123".to_string()),
+                ("REGION".to_string(), "eu west".to_string()),
+                ("NOTE".to_string(), "a # b".to_string()),
+            ]
+        );
+        assert!(load_env_file("/nonexistent/openchat.env", None).is_err());
+    }
+
+    #[test]
+    fn env_file_paths_expand_home_and_follow_the_server_cwd() {
+        let home = home::home_dir().unwrap();
+        assert_eq!(
+            resolve_env_file("~/.config/fs-server.env", None).unwrap(),
+            home.join(".config/fs-server.env")
+        );
+        let cwd = std::env::temp_dir();
+        assert_eq!(
+            resolve_env_file("server.env", cwd.to_str()).unwrap(),
+            cwd.join("server.env")
+        );
+        assert_eq!(
+            resolve_env_file("~user/server.env", cwd.to_str()).unwrap(),
+            cwd.join("~user/server.env")
+        );
+        assert!(resolve_env_file("server.env", None).is_err());
+        let absolute = cwd.join("server.env");
+        assert_eq!(
+            resolve_env_file(absolute.to_str().unwrap(), Some("/elsewhere")).unwrap(),
+            absolute
+        );
+    }
+}
@@ -1,13 +1,76 @@
 //! Server validation and connectivity checking for MCP
 
-use crate::mcp::constants::MCP_METHOD_TOOLS_LIST;
+use crate::mcp::constants::{MCP_METHOD_TOOLS_LIST, MCP_PROTOCOL_VERSION};
 use crate::mcp::transport::config::TransportConfig;
 use crate::mcp::transport::http::create_http_session;
-use crate::mcp::transport::parsing::parse_tools_array;
-use crate::mcp::transport::session::McpTransport;
+use crate::mcp::transport::parsing::{parse_tools_list, ParsedTools};
+use crate::mcp::transport::session::{McpSession, McpTransport};
 use crate::mcp::transport::stdio::spawn_stdio_session;
 use crate::mcp::types::McpCheckResult;
 use log::{info, warn};
+use std::time::Instant;
+
+const TRANSPORT_STDIO: &str = "stdio";
+const TRANSPORT_HTTP: &str = "http";
+
+/// Builds the partial-success warning for a check that connected and listed tools.
+/// Returns `None` when nothing noteworthy happened.
+fn check_warning(
+    initialized: bool,
+    protocol_version: Option<&str>,
+    tools_count: usize,
+) -> Option<String> {
+    let mut warnings = Vec::new();
+    if !initialized {
+        warnings.push("Server did not respond to initialize".to_string());
+    }
+    if let Some(version) = protocol_version.filter(|v| *v != MCP_PROTOCOL_VERSION) {
+        warnings.push(format!("Protocol version downgraded to {version}"));
+    }
+    if tools_count == 0 {
+        warnings.push("Connected but no tools exposed".to_string());
+    }
+    if warnings.is_empty() {
+        None
+    } else {
+        Some(warnings.join("; "))
+    }
+}
+
+fn session_warning(session: &McpSession, parsed: &ParsedTools) -> Option<String> {
+    let warning = check_warning(
+        session.initialize_result().is_some(),
+        session.protocol_version(),
+        parsed.tools.len(),
+    );
+    if parsed.duplicates.is_empty() {
+        return warning;
+    }
+    let duplicates = format!(
+        "Duplicate tool names ignored: {}",
+        parsed.duplicates.join(", ")
+    );
+    Some(match warning {
+        Some(warning) => format!("{warning}; {duplicates}"),
+        None => duplicates,
+    })
+}
+
+fn elapsed_ms(started: Instant) -> u64 {
+    started.elapsed().as_millis().try_into().unwrap_or(u64::MAX)
+}
+
+fn failed_check(transport: &str, latency_ms: u64, error: String) -> McpCheckResult {
+    McpCheckResult {
+        ok: false,
+        transport: transport.to_string(),
+        latency_ms,
+        tools_count: None,
+        tools: None,
+        warning: None,
+        error: Some(error),
+    }
+}
 
 /// Best-effort helper that attempts to connect and list tools for a given transport configuration.
 pub async fn check_server(config: TransportConfig<'_>) -> McpCheckResult {
@@ -16,33 +79,32 @@ pub async fn check_server(config: TransportConfig<'_>) -> McpCheckResult {
             command,
             args,
             env,
+            env_file,
             cwd,
             connect_timeout_ms,
             list_tools_timeout_ms,
         } => {
             if command.trim().is_empty() {
-                return McpCheckResult {
-                    ok: false,
-                    tools_count: None,
-                    tools: None,
-                    warning: None,
-                    error: Some("Command cannot be empty".into()),
-                };
+                return failed_check(TRANSPORT_STDIO, 0, "Command cannot be empty".into());
             }
             info!("mcp.check: stdio connect (cmd='{}', args_count={}, cwd={:?}, connect_timeout_ms={}, list_tools_timeout_ms={})", command, args.len(), cwd, connect_timeout_ms, list_tools_timeout_ms);
-            let mut session =
-                match spawn_stdio_session(command, args, env, cwd, connect_timeout_ms).await {
-                    Ok(s) => s,
-                    Err(e) => {
-                        return McpCheckResult {
-                            ok: false,
-                            tools_count: None,
-                            tools: None,
-                            warning: None,
-                            error: Some(e),
-                        };
-                    }
-                };
+            let started = Instant::now();
+            let mut session = match spawn_stdio_session(
+                command,
+                args,
+                env,
+                env_file,
+                cwd,
+                connect_timeout_ms,
+                None,
+            )
+            .await
+            {
+                Ok(s) => s,
+                Err(e) => {
+                    return failed_check(TRANSPORT_STDIO, elapsed_ms(started), e);
+                }
+            };
             let tools_res = session
                 .send(
                     MCP_METHOD_TOOLS_LIST,
@@ -51,26 +113,34 @@ pub async fn check_server(config: TransportConfig<'_>) -> McpCheckResult {
                 )
                 .await;
             let tools = match tools_res {
-                Ok(v) => parse_tools_array(&v),
+                Ok(v) => parse_tools_list(&v),
                 Err(_) => {
                     warn!("mcp.check: tools/list failed over stdio");
                     let _ = session.kill_child().await;
-                    return McpCheckResult {
-                        ok: false,
-                        tools_count: None,
-                        tools: None,
-                        warning: None,
-                        error: Some("Failed to request tools/list".into()),
-                    };
+                    return failed_check(
+                        TRANSPORT_STDIO,
+                        elapsed_ms(started),
+                        "Failed to request tools/list".into(),
+                    );
                 }
             };
+            let latency_ms = elapsed_ms(started);
             let _ = session.kill_child().await;
-            info!("mcp.check: stdio ok - tools_count={}", tools.len());
+            let warning = session_warning(&session, &tools);
+            let tools = tools.tools;
+            info!(
+                "mcp.check: stdio ok - tools_count={} latency_ms={} warning={:?}",
+                tools.len(),
+                latency_ms,
+                warning
+            );
             McpCheckResult {
                 ok: true,
+                transport: TRANSPORT_STDIO.to_string(),
+                latency_ms,
                 tools_count: Some(tools.len() as u32),
                 tools: Some(tools),
-                warning: None,
+                warning,
                 error: None,
             }
         }
@@ -81,16 +151,11 @@ pub async fn check_server(config: TransportConfig<'_>) -> McpCheckResult {
             list_tools_timeout_ms,
         } => {
             info!("mcp.check: http connect (url='{}', connect_timeout_ms={}, list_tools_timeout_ms={})", url, connect_timeout_ms, list_tools_timeout_ms);
+            let started = Instant::now();
             let mut session = match create_http_session(url, headers, connect_timeout_ms).await {
                 Ok(s) => s,
                 Err(e) => {
-                    return McpCheckResult {
-                        ok: false,
-                        tools_count: None,
-                        tools: None,
-                        warning: None,
-                        error: Some(e),
-                    };
+                    return failed_check(TRANSPORT_HTTP, elapsed_ms(started), e);
                 }
             };
             let tools_res = session
@@ -101,26 +166,57 @@ pub async fn check_server(config: TransportConfig<'_>) -> McpCheckResult {
                 )
                 .await;
             let tools = match tools_res {
-                Ok(v) => parse_tools_array(&v),
+                Ok(v) => parse_tools_list(&v),
                 Err(e) => {
                     warn!("mcp.check: http tools/list failed: {}", e);
-                    return McpCheckResult {
-                        ok: false,
-                        tools_count: None,
-                        tools: None,
-                        warning: None,
-                        error: Some(format!("Failed HTTP tools/list: {}", e)),
-                    };
+                    return failed_check(
+                        TRANSPORT_HTTP,
+                        elapsed_ms(started),
+                        format!("Failed HTTP tools/list: {}", e),
+                    );
                 }
             };
-            info!("mcp.check: http ok - tools_count={}", tools.len());
+            let latency_ms = elapsed_ms(started);
+            let warning = session_warning(&session, &tools);
+            let tools = tools.tools;
+            info!(
+                "mcp.check: http ok - tools_count={} latency_ms={} warning={:?}",
+                tools.len(),
+                latency_ms,
+                warning
+            );
             McpCheckResult {
                 ok: true,
+                transport: TRANSPORT_HTTP.to_string(),
+                latency_ms,
                 tools_count: Some(tools.len() as u32),
                 tools: Some(tools),
-                warning: None,
+                warning,
                 error: None,
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::check_warning;
+    use crate::mcp::constants::MCP_PROTOCOL_VERSION;
+
+    #[test]
+    fn check_warning_reports_partial_success_cases() {
+        assert_eq!(check_warning(true, Some(MCP_PROTOCOL_VERSION), 3), None);
+        assert_eq!(
+            check_warning(true, Some(MCP_PROTOCOL_VERSION), 0).as_deref(),
+            Some("Connected but no tools exposed")
+        );
+        assert_eq!(
+            check_warning(true, Some("2024-11-05"), 0).as_deref(),
+            Some("Protocol version downgraded to 2024-11-05; Connected but no tools exposed")
+        );
+        assert_eq!(
+            check_warning(false, None, 2).as_deref(),
+            Some("Server did not respond to initialize")
+        );
+    }
+}
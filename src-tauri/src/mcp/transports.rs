@@ -0,0 +1,113 @@
+//! Describes the fields each MCP transport needs, so the settings form can be
+//! generated from the backend rather than hardcoding stdio/http knowledge.
+
+use serde::Serialize;
+
+/// Value type of a transport field as stored in `mcp_servers`.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum McpFieldType {
+    String,
+    StringArray,
+    /// JSON object of string values (env vars, headers).
+    StringMap,
+    Integer,
+}
+
+/// A single configurable field of a transport.
+#[derive(Serialize, Debug, Clone)]
+pub struct McpTransportField {
+    pub name: &'static str,
+    #[serde(rename = "type")]
+    pub field_type: McpFieldType,
+    pub required: bool,
+    pub description: &'static str,
+}
+
+/// Fields accepted by one transport, keyed by the `transport` column value.
+#[derive(Serialize, Debug, Clone)]
+pub struct McpTransportSchema {
+    pub transport: &'static str,
+    pub fields: Vec<McpTransportField>,
+}
+
+const fn field(
+    name: &'static str,
+    field_type: McpFieldType,
+    required: bool,
+    description: &'static str,
+) -> McpTransportField {
+    McpTransportField {
+        name,
+        field_type,
+        required,
+        description,
+    }
+}
+
+/// Every supported transport and its fields. Timeouts shared by all transports are
+/// listed on each so a form can be rendered from a single entry.
+pub fn transport_schemas() -> Vec<McpTransportSchema> {
+    use McpFieldType::*;
+    let common = [field(
+        "connect_timeout_ms",
+        Integer,
+        false,
+        "Milliseconds to wait for the server to initialize",
+    )];
+
+    let stdio = [
+        field("command", String, true, "Executable to launch"),
+        field("args", StringArray, false, "Command-line arguments"),
+        field("env", StringMap, false, "Extra environment variables"),
+        field(
+            "env_file",
+            String,
+            false,
+            "Path to a .env file loaded under env",
+        ),
+        field("cwd", String, false, "Working directory"),
+    ];
+    let http = [
+        field("url", String, true, "Server endpoint URL"),
+        field("headers", StringMap, false, "Extra request headers"),
+        field("auth", String, false, "Authorization header value"),
+        field(
+            "heartbeat_sec",
+            Integer,
+            false,
+            "Seconds between keep-alive pings",
+        ),
+    ];
+
+    vec![
+        McpTransportSchema {
+            transport: "stdio",
+            fields: stdio.into_iter().chain(common.clone()).collect(),
+        },
+        McpTransportSchema {
+            transport: "http",
+            fields: http.into_iter().chain(common).collect(),
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::transport_schemas;
+    use crate::mcp::store::SELECT_ALL_MCP_SERVERS;
+
+    #[test]
+    fn every_field_is_a_stored_column() {
+        for schema in transport_schemas() {
+            for field in &schema.fields {
+                assert!(
+                    SELECT_ALL_MCP_SERVERS.contains(field.name),
+                    "{}.{} is not an mcp_servers column",
+                    schema.transport,
+                    field.name
+                );
+            }
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 /// Basic metadata describing an MCP tool, including optional input schema.
 #[derive(Serialize, Debug, Clone)]
@@ -9,12 +9,138 @@ pub struct McpToolInfo {
     pub input_schema: Option<serde_json::Value>,
 }
 
+/// Liveness of a cached MCP session.
+#[derive(Serialize, Debug, Clone)]
+pub struct McpSessionHealth {
+    pub id: i64,
+    pub connected: bool,
+    pub alive: bool,
+}
+
+/// Most recent failure of an operation on a server, kept until one succeeds.
+#[derive(Serialize, Debug, Clone)]
+pub struct McpServerError {
+    pub id: i64,
+    pub error: String,
+    pub at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A tool in the unified list from `mcp_list_all_tools`, with the server offering it.
+#[derive(Serialize, Debug, Clone)]
+pub struct McpServerTool {
+    pub server_id: i64,
+    pub server_name: String,
+    #[serde(flatten)]
+    pub tool: McpToolInfo,
+}
+
+/// A server whose tools could not be listed by `mcp_list_all_tools`.
+#[derive(Serialize, Debug, Clone)]
+pub struct McpToolListFailure {
+    pub server_id: i64,
+    pub server_name: String,
+    pub error: String,
+}
+
+/// Tools of every enabled server, plus the servers that failed.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct McpToolCatalog {
+    pub tools: Vec<McpServerTool>,
+    pub failed: Vec<McpToolListFailure>,
+}
+
+/// Round-trip latency of lightweight requests to a server, in milliseconds.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct McpLatency {
+    pub id: i64,
+    /// `ping`, or `tools/list` for servers that do not answer pings.
+    pub method: String,
+    pub samples: u32,
+    pub min_ms: f64,
+    pub median_ms: f64,
+    pub max_ms: f64,
+}
+
+/// Last request/response pair sent to a server, kept only in debug mode. Both sides
+/// are pretty-printed JSON with obvious secrets redacted and may be truncated.
+#[derive(Serialize, Debug, Clone)]
+pub struct McpExchange {
+    pub id: i64,
+    pub method: String,
+    pub request: String,
+    /// The result on success, the error message otherwise.
+    pub response: String,
+    pub ok: bool,
+    pub at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Outcome of (re)connecting one server, e.g. in `reconnect_all_mcp_sessions` or at launch.
+#[derive(Serialize, Debug, Clone)]
+pub struct McpReconnectResult {
+    pub id: i64,
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+/// Features a connected server advertised in its `initialize` response. Everything is
+/// `false` when the server never answered `initialize`.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct McpCapabilities {
+    pub initialized: bool,
+    pub protocol_version: Option<String>,
+    pub supports_tools: bool,
+    pub supports_resources: bool,
+    pub supports_prompts: bool,
+    pub supports_logging: bool,
+    pub supports_completions: bool,
+}
+
 /// Result for a best-effort server check (connect + list tools).
 #[derive(Serialize, Debug, Clone)]
 pub struct McpCheckResult {
     pub ok: bool,
+    /// Transport the check ran over (`stdio` or `http`).
+    pub transport: String,
+    /// Milliseconds from spawn/connect until `tools/list` completed (or failed).
+    pub latency_ms: u64,
     pub tools_count: Option<u32>,
     pub tools: Option<Vec<McpToolInfo>>,
     pub warning: Option<String>,
     pub error: Option<String>,
 }
+
+/// What a `completion/complete` request completes an argument of.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum McpCompletionRef {
+    #[serde(rename = "ref/prompt")]
+    Prompt { name: String },
+    #[serde(rename = "ref/resource")]
+    Resource { uri: String },
+}
+
+/// The argument being completed and what has been typed so far.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct McpCompletionArgument {
+    pub name: String,
+    pub value: String,
+}
+
+/// Outcome of `McpManager::shutdown`.
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct McpShutdownReport {
+    /// Sessions whose child (if any) was stopped within the per-child timeout.
+    pub closed: usize,
+    /// Sessions whose child had to be force-killed by pid after the timeout.
+    pub force_killed: usize,
+}
+
+/// Suggestions returned by `completion/complete`.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct McpCompletion {
+    pub values: Vec<String>,
+    /// Total number of matches, when the server reports more than it returned.
+    pub total: Option<u64>,
+    #[serde(rename = "hasMore")]
+    pub has_more: bool,
+}
@@ -11,6 +11,7 @@ pub mod menu_ids {
     pub const RELOAD: &str = "reload";
     pub const NEW_CHAT: &str = "new_chat";
     pub const OPEN_SETTINGS: &str = "open_settings";
+    pub const TOGGLE_THEME: &str = "toggle_theme";
 }
 
 /// Manages the application's menu system
@@ -101,7 +102,7 @@ impl MenuManager {
             .map_err(|e| format!("Failed to build Edit submenu: {e}"))
     }
 
-    /// Creates the View menu with reload functionality
+    /// Creates the View menu with reload and theme toggle items
     fn build_view_menu(app: &App) -> Result<tauri::menu::Submenu<tauri::Wry>, String> {
         let reload_item = MenuItemBuilder::new("Reload")
             .id(menu_ids::RELOAD)
@@ -109,8 +110,14 @@ impl MenuManager {
             .build(app)
             .map_err(|e| format!("Failed to build Reload menu item: {e}"))?;
 
+        let toggle_theme_item = MenuItemBuilder::new("Toggle Theme")
+            .id(menu_ids::TOGGLE_THEME)
+            .accelerator("CmdOrCtrl+Shift+L")
+            .build(app)
+            .map_err(|e| format!("Failed to build Toggle Theme menu item: {e}"))?;
+
         SubmenuBuilder::new(app, "View")
-            .items(&[&reload_item])
+            .items(&[&reload_item, &toggle_theme_item])
             .build()
             .map_err(|e| format!("Failed to build View submenu: {e}"))
     }
@@ -124,6 +131,7 @@ impl MenuManager {
             menu_ids::RELOAD => Self::handle_reload(app),
             menu_ids::NEW_CHAT => Self::handle_new_chat(app),
             menu_ids::OPEN_SETTINGS => Self::handle_open_settings(app),
+            menu_ids::TOGGLE_THEME => Self::handle_toggle_theme(app),
             _ => {
                 // Handle any other menu events if needed
                 log::debug!("Unhandled menu event: {}", event_id);
@@ -152,6 +160,16 @@ impl MenuManager {
         }
     }
 
+    /// Handles the toggle theme menu action (light, dark, system in turn)
+    fn handle_toggle_theme(app: &AppHandle) {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = crate::theme::toggle_theme(&app).await {
+                log::error!("Failed to toggle theme: {}", e);
+            }
+        });
+    }
+
     /// Sends a custom event to the frontend
     fn send_frontend_event(window: &WebviewWindow, event_name: &str) {
         let script = format!("window.dispatchEvent(new CustomEvent('{}'))", event_name);
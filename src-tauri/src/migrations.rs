@@ -63,5 +63,163 @@ pub fn migrations() -> Vec<Migration> {
             sql: include_str!("../migrations/010_add_model_to_app_settings.sql"),
             kind: MigrationKind::Up,
         },
+        Migration {
+            version: 11,
+            description: "add_mlc_log_level_to_app_settings",
+            sql: include_str!("../migrations/011_add_mlc_log_level_to_app_settings.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 12,
+            description: "add_mlc_max_tokens_to_app_settings",
+            sql: include_str!("../migrations/012_add_mlc_max_tokens_to_app_settings.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 13,
+            description: "add_mlc_sampling_to_app_settings",
+            sql: include_str!("../migrations/013_add_mlc_sampling_to_app_settings.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 14,
+            description: "add_system_prompt_to_conversations",
+            sql: include_str!("../migrations/014_add_system_prompt_to_conversations.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 15,
+            description: "add_openai_api_key_to_app_settings",
+            sql: include_str!("../migrations/015_add_openai_api_key_to_app_settings.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 16,
+            description: "create_secrets",
+            sql: include_str!("../migrations/016_create_secrets.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 17,
+            description: "add_mlc_host_to_app_settings",
+            sql: include_str!("../migrations/017_add_mlc_host_to_app_settings.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 18,
+            description: "add_download_bandwidth_limit_to_app_settings",
+            sql: include_str!("../migrations/018_add_download_bandwidth_limit_to_app_settings.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 19,
+            description: "add_telemetry_enabled_to_app_settings",
+            sql: include_str!("../migrations/019_add_telemetry_enabled_to_app_settings.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 20,
+            description: "validate_message_role",
+            sql: include_str!("../migrations/020_validate_message_role.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 21,
+            description: "add_tool_columns_to_messages",
+            sql: include_str!("../migrations/021_add_tool_columns_to_messages.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 22,
+            description: "add_embedding_to_messages",
+            sql: include_str!("../migrations/022_add_embedding_to_messages.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 23,
+            description: "add_mlc_watchdog_enabled_to_app_settings",
+            sql: include_str!("../migrations/023_add_mlc_watchdog_enabled_to_app_settings.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 24,
+            description: "add_model_cache_dir_to_app_settings",
+            sql: include_str!("../migrations/024_add_model_cache_dir_to_app_settings.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 25,
+            description: "add_metadata_to_messages",
+            sql: include_str!("../migrations/025_add_metadata_to_messages.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 26,
+            description: "add_theme_to_app_settings",
+            sql: include_str!("../migrations/026_add_theme_to_app_settings.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 27,
+            description: "add_mcp_debug_enabled_to_app_settings",
+            sql: include_str!("../migrations/027_add_mcp_debug_enabled_to_app_settings.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 28,
+            description: "add_provider_request_logging_to_app_settings",
+            sql: include_str!("../migrations/028_add_provider_request_logging_to_app_settings.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 29,
+            description: "add_priority_to_mcp_servers",
+            sql: include_str!("../migrations/029_add_priority_to_mcp_servers.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 30,
+            description: "add_mlc_kill_orphans_to_app_settings",
+            sql: include_str!("../migrations/030_add_mlc_kill_orphans_to_app_settings.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 31,
+            description: "add_max_history_messages",
+            sql: include_str!("../migrations/031_add_max_history_messages.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 32,
+            description: "add_generation_params_to_conversations",
+            sql: include_str!("../migrations/032_add_generation_params_to_conversations.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 33,
+            description: "add_env_file_to_mcp_servers",
+            sql: include_str!("../migrations/033_add_env_file_to_mcp_servers.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 34,
+            description: "add_mcp_autoconnect_on_launch_to_app_settings",
+            sql: include_str!(
+                "../migrations/034_add_mcp_autoconnect_on_launch_to_app_settings.sql"
+            ),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 35,
+            description: "add_mcp_max_sessions_to_app_settings",
+            sql: include_str!("../migrations/035_add_mcp_max_sessions_to_app_settings.sql"),
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 36,
+            description: "add_openai_max_attempts_to_app_settings",
+            sql: include_str!("../migrations/036_add_openai_max_attempts_to_app_settings.sql"),
+            kind: MigrationKind::Up,
+        },
     ]
 }
@@ -1,14 +1,226 @@
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener};
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::{Duration, Instant};
 
 use serde::{Deserialize, Serialize};
-use tauri::{AppHandle, Emitter};
-use tauri_plugin_shell::{process::CommandEvent, ShellExt};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_shell::{
+    process::{CommandEvent, TerminatedPayload},
+    ShellExt,
+};
 use tokio::sync::{Mutex, RwLock};
 
+use crate::model_store::{
+    app_models_dir, is_model_cached, missing_model_files, resolve_model_path, ModelLocation,
+};
+use crate::process_info::{find_processes, force_kill, RunningProcess};
+use crate::settings::{self, Setting};
+use crate::telemetry::{self, TelemetryEvent};
+
 /// Event name emitted to the frontend whenever the status changes.
 pub const MLC_STATUS_CHANGED_EVENT: &str = "mlc-status-changed";
 
+/// Event emitted once per app session, the first time the server becomes HTTP ready.
+pub const MLC_FIRST_READY_EVENT: &str = "mlc-first-ready";
+
+/// Event carrying a `ModelVerification` while `start` checks the selected model's files.
+pub const MLC_MODEL_VERIFICATION_EVENT: &str = "mlc-model-verification";
+
+/// Event carrying `MlcLoadProgress` while a started server loads its model.
+pub const MLC_LOAD_PROGRESS_EVENT: &str = "mlc-load-progress";
+
+/// Stage reported before (or without) any recognizable progress output.
+const MLC_LOAD_STAGE_LOADING: &str = "loading";
+
+/// Log levels accepted by the sidecar's `--log-level` flag.
+pub const MLC_LOG_LEVELS: &[&str] = &["DEBUG", "INFO", "WARN", "ERROR"];
+
+/// Validates a `--max-tokens` value.
+pub fn validate_max_tokens(max_tokens: i64) -> Result<u32, String> {
+    u32::try_from(max_tokens)
+        .ok()
+        .filter(|v| *v > 0)
+        .ok_or_else(|| format!("max tokens must be a positive integer, got {max_tokens}"))
+}
+
+/// Status error set when the bundled server binary can't be found (broken install).
+pub const MLC_SIDECAR_MISSING_ERROR: &str =
+    "Model server component is missing; please reinstall OpenChat";
+
+/// Name of the bundled model server sidecar.
+const MLC_SIDECAR_NAME: &str = "openchat-mlx-server";
+
+/// Lines of server output kept in memory for log export.
+const MLC_RECENT_OUTPUT_LINES: usize = 1000;
+
+/// How long `openchat-mlx-server --help` may take when checking which flags it accepts.
+const MLC_HELP_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// How often the watchdog health-checks a server that should be ready.
+const MLC_WATCHDOG_INTERVAL: Duration = Duration::from_secs(15);
+/// How long a watchdog health check may take. Generous, since a server busy with a
+/// request outside the app can still be slow to list its models.
+const MLC_WATCHDOG_PROBE_TIMEOUT: Duration = Duration::from_secs(30);
+/// Timeout of the readiness checks while a server starts.
+const MLC_READINESS_PROBE_TIMEOUT: Duration = Duration::from_millis(800);
+/// Consecutive failed watchdog checks that trigger a restart.
+const MLC_WATCHDOG_FAILURE_THRESHOLD: u32 = 3;
+/// Delay before the first watchdog restart; doubles per consecutive restart.
+const MLC_WATCHDOG_BASE_BACKOFF: Duration = Duration::from_secs(5);
+/// Upper bound on the watchdog restart delay.
+const MLC_WATCHDOG_MAX_BACKOFF: Duration = Duration::from_secs(300);
+/// How long a request marked by the frontend holds off the watchdog unless it is
+/// finished first, so a mark left behind by a reloaded webview cannot suspend the
+/// watchdog for good.
+const MLC_REQUEST_LEASE_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Paths `admin_request` may reach on the server. An entry ending in `/` also allows
+/// any path below it.
+const MLC_ADMIN_PATHS: &[&str] = &[
+    "/health",
+    "/v1/models",
+    "/v1/models/",
+    "/v1/stats",
+    "/v1/admin/",
+];
+/// HTTP methods `admin_request` may use.
+const MLC_ADMIN_METHODS: &[&str] = &["GET", "POST", "PUT", "DELETE"];
+/// Timeout for one admin request; model loads can take a while.
+const MLC_ADMIN_REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Address the server binds to unless `mlc_host` is set.
+pub const MLC_DEFAULT_HOST: &str = "127.0.0.1";
+
+/// Validates a bind host, which must be a literal IPv4 or IPv6 address, and returns its
+/// canonical form.
+pub fn validate_host(host: &str) -> Result<String, String> {
+    host.trim()
+        .parse::<IpAddr>()
+        .map(|ip| ip.to_string())
+        .map_err(|_| format!("invalid host '{host}'; expected an IP address such as 127.0.0.1"))
+}
+
+/// Warning shown in the status when `host` makes the server reachable from other machines.
+fn network_exposure_warning(host: &str) -> Option<String> {
+    let ip: IpAddr = host.parse().ok()?;
+    (!ip.is_loopback()).then(|| {
+        format!("Model server is bound to {host} and reachable from other devices on the network")
+    })
+}
+
+/// Address used to reach a server bound to `host` from this machine: loopback when
+/// bound to all interfaces, otherwise the host itself.
+fn local_address(host: &str) -> IpAddr {
+    match host.parse::<IpAddr>() {
+        Ok(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+        Ok(ip) => ip,
+        Err(_) => IpAddr::V4(Ipv4Addr::LOCALHOST),
+    }
+}
+
+/// Validates a log level case-insensitively and returns its canonical form.
+pub fn normalize_log_level(level: &str) -> Result<String, String> {
+    let upper = level.trim().to_ascii_uppercase();
+    if MLC_LOG_LEVELS.contains(&upper.as_str()) {
+        Ok(upper)
+    } else {
+        Err(format!(
+            "invalid log level '{level}'; expected one of {}",
+            MLC_LOG_LEVELS.join(", ")
+        ))
+    }
+}
+
+/// Server-wide sampling defaults passed to the sidecar. `None` keeps the sidecar's own
+/// default; per-request parameters still override these.
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq)]
+pub struct SamplingDefaults {
+    #[serde(default)]
+    pub temperature: Option<f64>,
+    #[serde(default)]
+    pub top_p: Option<f64>,
+    #[serde(default)]
+    pub repetition_penalty: Option<f64>,
+}
+
+impl SamplingDefaults {
+    /// Checks each set value against its accepted range.
+    pub fn validate(&self) -> Result<(), String> {
+        check_range("temperature", self.temperature, 0.0, 2.0, true)?;
+        check_range("top_p", self.top_p, 0.0, 1.0, false)?;
+        check_range(
+            "repetition_penalty",
+            self.repetition_penalty,
+            0.0,
+            2.0,
+            false,
+        )?;
+        Ok(())
+    }
+
+    /// Reads the persisted defaults from `app_settings`.
+    pub async fn load(pool: &SqlitePool) -> Result<Self, String> {
+        Ok(Self {
+            temperature: settings::get_setting(pool, Setting::MlcTemperature).await?,
+            top_p: settings::get_setting(pool, Setting::MlcTopP).await?,
+            repetition_penalty: settings::get_setting(pool, Setting::MlcRepetitionPenalty).await?,
+        })
+    }
+
+    /// Validates and persists the defaults to `app_settings`.
+    pub async fn save(&self, pool: &SqlitePool) -> Result<(), String> {
+        self.validate()?;
+        settings::set_setting(pool, Setting::MlcTemperature, self.temperature).await?;
+        settings::set_setting(pool, Setting::MlcTopP, self.top_p).await?;
+        settings::set_setting(pool, Setting::MlcRepetitionPenalty, self.repetition_penalty).await
+    }
+
+    /// CLI flags and values for the set values.
+    fn to_args(&self) -> Vec<(&'static str, String)> {
+        [
+            ("--temperature", self.temperature),
+            ("--top-p", self.top_p),
+            ("--repetition-penalty", self.repetition_penalty),
+        ]
+        .into_iter()
+        .filter_map(|(flag, value)| value.map(|v| (flag, v.to_string())))
+        .collect()
+    }
+}
+
+/// Long flags (`--name`) mentioned in a command's `--help` output.
+fn parse_help_flags(help: &str) -> HashSet<String> {
+    help.split(|c: char| c.is_whitespace() || matches!(c, ',' | '[' | ']' | '=' | '|'))
+        .filter(|word| word.starts_with("--") && word.len() > 2)
+        .map(|word| {
+            word.trim_end_matches(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+                .to_string()
+        })
+        .collect()
+}
+
+/// Validates `value` lies in `(min, max]`, or `[min, max]` when `min_inclusive`.
+pub fn check_range(
+    name: &str,
+    value: Option<f64>,
+    min: f64,
+    max: f64,
+    min_inclusive: bool,
+) -> Result<(), String> {
+    let Some(v) = value else {
+        return Ok(());
+    };
+    let above_min = if min_inclusive { v >= min } else { v > min };
+    if v.is_finite() && above_min && v <= max {
+        Ok(())
+    } else {
+        let open = if min_inclusive { '[' } else { '(' };
+        Err(format!("{name} must be in {open}{min}, {max}], got {v}"))
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
 pub struct MLCServerStatus {
@@ -17,28 +229,226 @@ pub struct MLCServerStatus {
     pub port: Option<u16>,
     pub pid: Option<u32>,
     pub error: Option<String>,
+    /// Exit code of the last unexpected termination, if the process reported one.
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    /// Signal that terminated the process last time it exited unexpectedly (Unix only).
+    #[serde(default)]
+    pub exit_signal: Option<i32>,
+    /// Non-fatal notice, e.g. that the server is exposed on the network.
+    #[serde(default)]
+    pub warning: Option<String>,
+    /// Model configured in settings when the server was started.
+    #[serde(default)]
+    pub requested_model: Option<String>,
+    /// Model the server reports serving, read from `/v1/models` once it is ready.
+    #[serde(default)]
+    pub loaded_model: Option<String>,
+    /// Set when a model was requested but the server is not serving it.
+    #[serde(default)]
+    pub model_mismatch: bool,
+}
+
+/// Pre-flight check of the selected model's files, emitted as
+/// `MLC_MODEL_VERIFICATION_EVENT`. An incomplete model is only reported; the user
+/// repairs it with the `repair_model` command.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ModelVerification {
+    Verified { model: String },
+    Incomplete { model: String, missing: Vec<String> },
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MLCServerConfig {
     pub host: String,
     pub port: u16,
+    /// Passed as `--log-level`; `None` keeps the sidecar default.
+    #[serde(default)]
+    pub log_level: Option<String>,
+    /// Passed as `--max-tokens`; `None` keeps the sidecar default.
+    #[serde(default)]
+    pub max_tokens: Option<u32>,
+    /// Passed as `--temperature`, `--top-p` and `--repetition-penalty` when set.
+    #[serde(default)]
+    pub sampling: SamplingDefaults,
+    /// Restart the server when it stops answering health checks.
+    #[serde(default = "watchdog_enabled_default")]
+    pub watchdog_enabled: bool,
+    /// Model selected in settings, compared against what the server loads.
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Kill `openchat-mlx-server` processes left over from earlier runs before starting.
+    #[serde(default)]
+    pub kill_orphans: bool,
+}
+
+fn watchdog_enabled_default() -> bool {
+    false
 }
 
 impl Default for MLCServerConfig {
     fn default() -> Self {
         Self {
-            host: "127.0.0.1".to_string(),
+            host: MLC_DEFAULT_HOST.to_string(),
             port: 8000,
+            log_level: None,
+            max_tokens: None,
+            sampling: SamplingDefaults::default(),
+            watchdog_enabled: watchdog_enabled_default(),
+            model: None,
+            kill_orphans: false,
         }
     }
 }
 
+/// A chat request counted by `MLCServerManager::track_request`; finishes on drop.
+pub struct InFlightRequest(std::sync::Arc<MLCServerManager>);
+
+impl Drop for InFlightRequest {
+    fn drop(&mut self) {
+        let _ = self
+            .0
+            .in_flight_requests
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1));
+    }
+}
+
+/// Chat requests the frontend marked as in flight, each until it is finished or its
+/// lease of `timeout` runs out.
+#[derive(Debug)]
+struct RequestLeases {
+    timeout: Duration,
+    next_id: u64,
+    started: HashMap<u64, Instant>,
+}
+
+impl RequestLeases {
+    fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            next_id: 1,
+            started: HashMap::new(),
+        }
+    }
+
+    /// Starts a lease at `now` and returns its id.
+    fn start(&mut self, now: Instant) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.started.insert(id, now);
+        id
+    }
+
+    /// Ends lease `id`; unknown and expired ids are ignored.
+    fn finish(&mut self, id: u64) {
+        self.started.remove(&id);
+    }
+
+    /// Drops leases expired at `now` and returns whether any remain.
+    fn any_active(&mut self, now: Instant) -> bool {
+        let timeout = self.timeout;
+        self.started.retain(|id, started| {
+            let live = now.duration_since(*started) < timeout;
+            if !live {
+                log::warn!("chat request lease {id} expired without being finished");
+            }
+            live
+        });
+        !self.started.is_empty()
+    }
+}
+
 pub struct MLCServerManager {
     app_handle: AppHandle,
     status: Mutex<MLCServerStatus>,
     child: Mutex<Option<tauri_plugin_shell::process::CommandChild>>,
     config: RwLock<MLCServerConfig>,
+    has_been_ready: AtomicBool,
+    recent_output: std::sync::Arc<OutputRing>,
+    /// Watchdog restarts since the server last passed a watchdog check.
+    watchdog_restarts: AtomicU32,
+    /// Backend chat requests being served; the watchdog does not probe while any are.
+    in_flight_requests: AtomicU32,
+    /// Chat requests the frontend streams itself; also hold off the watchdog.
+    request_leases: std::sync::Mutex<RequestLeases>,
+    /// Latest load progress between process start and the first passed health check.
+    load_progress: std::sync::Mutex<Option<MlcLoadProgress>>,
+    /// Long flags the sidecar lists in its `--help`, once read.
+    sidecar_flags: Mutex<Option<HashSet<String>>>,
+}
+
+/// Model-load progress of a starting server.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MlcLoadProgress {
+    pub stage: String,
+    /// `None` when the server gives no percentage, e.g. before any marker was seen.
+    pub percent: Option<u8>,
+}
+
+/// Recognizes load-progress markers in a server output line: JSON lines of the form
+/// `{"event":"load_progress","stage":..,"percent":..}`, and tqdm bars such as
+/// `Loading weights:  40%|####      | 4/10`. Only the last carriage-return-separated
+/// redraw of a tqdm line counts.
+fn parse_load_progress(line: &str) -> Option<MlcLoadProgress> {
+    let line = line.rsplit('\r').find(|s| !s.trim().is_empty())?.trim();
+    if line.starts_with('{') {
+        let value: serde_json::Value = serde_json::from_str(line).ok()?;
+        if value.get("event").and_then(|e| e.as_str()) != Some("load_progress") {
+            return None;
+        }
+        return Some(MlcLoadProgress {
+            stage: value
+                .get("stage")
+                .and_then(|s| s.as_str())
+                .unwrap_or(MLC_LOAD_STAGE_LOADING)
+                .to_string(),
+            percent: value
+                .get("percent")
+                .and_then(|p| p.as_f64())
+                .map(|p| p.clamp(0.0, 100.0) as u8),
+        });
+    }
+
+    let (before_bar, _) = line.split_once("%|")?;
+    let (stage, percent) = match before_bar.rsplit_once(':') {
+        // Keep only the text after any log prefix such as "INFO:".
+        Some((desc, percent)) => (desc.rsplit(':').next().unwrap_or(desc).trim(), percent),
+        None => ("", before_bar),
+    };
+    let percent: u8 = percent.trim().parse().ok()?;
+    Some(MlcLoadProgress {
+        stage: if stage.is_empty() {
+            MLC_LOAD_STAGE_LOADING.to_string()
+        } else {
+            stage.to_string()
+        },
+        percent: Some(percent.min(100)),
+    })
+}
+
+/// The server's most recent output lines, oldest first, across restarts.
+#[derive(Default)]
+struct OutputRing {
+    lines: std::sync::Mutex<VecDeque<String>>,
+}
+
+impl OutputRing {
+    fn push(&self, line: String) {
+        if let Ok(mut lines) = self.lines.lock() {
+            if lines.len() == MLC_RECENT_OUTPUT_LINES {
+                lines.pop_front();
+            }
+            lines.push_back(line);
+        }
+    }
+
+    fn snapshot(&self) -> Vec<String> {
+        self.lines
+            .lock()
+            .map(|lines| lines.iter().cloned().collect())
+            .unwrap_or_default()
+    }
 }
 
 impl MLCServerManager {
@@ -49,14 +459,161 @@ impl MLCServerManager {
             status: Mutex::new(MLCServerStatus::default()),
             child: Mutex::new(None),
             config: RwLock::new(MLCServerConfig::default()),
+            has_been_ready: AtomicBool::new(false),
+            recent_output: Default::default(),
+            watchdog_restarts: AtomicU32::new(0),
+            in_flight_requests: AtomicU32::new(0),
+            request_leases: std::sync::Mutex::new(RequestLeases::new(MLC_REQUEST_LEASE_TIMEOUT)),
+            load_progress: std::sync::Mutex::new(None),
+            sidecar_flags: Mutex::new(None),
         }
     }
 
+    /// The sidecar command with the bundled python and the app's model cache set.
+    fn sidecar_command(&self) -> Result<tauri_plugin_shell::process::Command, String> {
+        let mut cmd = self
+            .app_handle
+            .shell()
+            .sidecar(MLC_SIDECAR_NAME)
+            .map_err(|e| format!("Failed to resolve openchat-mlx-server sidecar: {e}"))?;
+        // Optionally set bundled python sidecar path
+        let python_path = self.app_handle.shell().sidecar("python3").ok().map(|cmd| {
+            let std_cmd: std::process::Command = cmd.into();
+            std::path::PathBuf::from(std_cmd.get_program().to_owned())
+        });
+        if let Some(py) = python_path {
+            cmd = cmd.env("OPENCHAT_MLX_SERVER_PYTHON", py);
+        }
+        // Resolve repo ids from the same cache the app downloads into
+        Ok(cmd.env("HF_HUB_CACHE", crate::model_store::hub_dir().path))
+    }
+
+    /// Long flags the sidecar accepts, read from its `--help` on first use. Empty
+    /// (and retried next time) when the help cannot be read.
+    async fn sidecar_flags(&self) -> HashSet<String> {
+        let mut cached = self.sidecar_flags.lock().await;
+        if let Some(flags) = cached.as_ref() {
+            return flags.clone();
+        }
+        let help = async {
+            let output = self
+                .sidecar_command()?
+                .arg("--help")
+                .output()
+                .await
+                .map_err(|e| e.to_string())?;
+            Ok::<_, String>(String::from_utf8_lossy(&output.stdout).into_owned())
+        };
+        match tokio::time::timeout(MLC_HELP_TIMEOUT, help).await {
+            Ok(Ok(help)) => {
+                let flags = parse_help_flags(&help);
+                log::debug!("openchat-mlx-server flags: {flags:?}");
+                *cached = Some(flags.clone());
+                flags
+            }
+            Ok(Err(e)) => {
+                log::warn!("Failed to read openchat-mlx-server --help: {e}");
+                HashSet::new()
+            }
+            Err(_) => {
+                log::warn!("openchat-mlx-server --help timed out");
+                HashSet::new()
+            }
+        }
+    }
+
+    /// Returns the model-load progress while a started server is not yet ready.
+    pub fn load_progress(&self) -> Option<MlcLoadProgress> {
+        self.load_progress.lock().ok().and_then(|p| p.clone())
+    }
+
+    fn set_load_progress(&self, progress: Option<MlcLoadProgress>) {
+        if let Ok(mut current) = self.load_progress.lock() {
+            *current = progress.clone();
+        }
+        if let Some(progress) = progress {
+            let _ = self.app_handle.emit(MLC_LOAD_PROGRESS_EVENT, progress);
+        }
+    }
+
+    /// Emits progress for a server output line while the model is loading.
+    fn handle_output_line(&self, line: &str) {
+        let loading = self.load_progress.lock().is_ok_and(|p| p.is_some());
+        if !loading {
+            return;
+        }
+        if let Some(progress) = parse_load_progress(line) {
+            if self.load_progress().as_ref() != Some(&progress) {
+                self.set_load_progress(Some(progress));
+            }
+        }
+    }
+
+    /// Returns the server's most recent output lines (stdout, stderr and exit), oldest
+    /// first.
+    pub fn recent_output(&self) -> Vec<String> {
+        self.recent_output.snapshot()
+    }
+
+    /// Path the sidecar is launched from, when the shell plugin can resolve it.
+    fn sidecar_path(&self) -> Option<std::path::PathBuf> {
+        self.app_handle
+            .shell()
+            .sidecar(MLC_SIDECAR_NAME)
+            .ok()
+            .map(|cmd| {
+                let std_cmd: std::process::Command = cmd.into();
+                std::path::PathBuf::from(std_cmd.get_program())
+            })
+    }
+
+    /// Sidecar processes of the current user, other than the managed child, that were
+    /// left behind by a run that did not stop them. Only processes started from this
+    /// app's sidecar path whose parent is not another live app instance qualify.
+    pub async fn find_orphaned_servers(&self) -> Result<Vec<RunningProcess>, String> {
+        let Some(sidecar) = self.sidecar_path() else {
+            return Ok(Vec::new());
+        };
+        let current = self.child_pid();
+        tauri::async_runtime::spawn_blocking(move || {
+            find_processes(&sidecar)
+                .into_iter()
+                .filter(|p| Some(p.pid) != current)
+                .collect()
+        })
+        .await
+        .map_err(|e| e.to_string())
+    }
+
+    /// Force-kills the processes `find_orphaned_servers` reports and returns them.
+    pub async fn kill_orphaned_servers(&self) -> Result<Vec<RunningProcess>, String> {
+        let orphans = self.find_orphaned_servers().await?;
+        for orphan in &orphans {
+            log::warn!("Killing orphaned {} (pid={})", orphan.name, orphan.pid);
+            force_kill(orphan.pid);
+        }
+        Ok(orphans)
+    }
+
+    /// Process id of the running server, read without waiting so it is available even
+    /// when a stop is stuck holding the locks. `None` if it can't be determined now.
+    pub fn child_pid(&self) -> Option<u32> {
+        if let Ok(child) = self.child.try_lock() {
+            return child.as_ref().map(|c| c.pid());
+        }
+        self.status.try_lock().ok().and_then(|s| s.pid)
+    }
+
     /// Returns a snapshot of the current status.
     pub async fn get_status(&self) -> MLCServerStatus {
         self.status.lock().await.clone()
     }
 
+    /// Whether the server has reached HTTP readiness at least once since launch.
+    pub fn has_been_ready(&self) -> bool {
+        self.has_been_ready.load(Ordering::SeqCst)
+    }
+
     /// Updates internal status and emits an event to the frontend.
     async fn update_status_and_emit(&self, new_status: MLCServerStatus) {
         {
@@ -66,9 +623,194 @@ impl MLCServerManager {
         let _ = self.app_handle.emit(MLC_STATUS_CHANGED_EVENT, new_status);
     }
 
-    /// Performs a lightweight HTTP readiness check against `/v1/models`.
-    async fn health_check(&self, port: u16) -> anyhow::Result<()> {
-        http_get_models_reqwest(port).await
+    /// Refreshes persisted options from `app_settings`. Leaves the config unchanged if the
+    /// pool isn't managed yet or the settings can't be read (e.g. migrations pending).
+    pub async fn load_config_from_settings(&self) {
+        let Some(pool) = self
+            .app_handle
+            .try_state::<SqlitePool>()
+            .map(|s| s.inner().clone())
+        else {
+            return;
+        };
+        if let Err(err) = self.apply_settings(&pool).await {
+            log::warn!("Failed to read MLC settings: {err}");
+        }
+    }
+
+    async fn apply_settings(&self, pool: &SqlitePool) -> Result<(), String> {
+        let log_level = settings::get_setting::<String>(pool, Setting::MlcLogLevel).await?;
+        let max_tokens = settings::get_setting::<i64>(pool, Setting::MlcMaxTokens).await?;
+        let sampling = SamplingDefaults::load(pool).await?;
+        let host = settings::get_setting::<String>(pool, Setting::MlcHost).await?;
+        let watchdog_enabled =
+            settings::get_setting::<bool>(pool, Setting::MlcWatchdogEnabled).await?;
+        let model = settings::get_setting::<String>(pool, Setting::Model).await?;
+        let kill_orphans = settings::get_setting::<bool>(pool, Setting::MlcKillOrphans).await?;
+
+        let mut config = self.config.write().await;
+        config.host = host
+            .and_then(|h| valid_or_warn(validate_host(&h)))
+            .unwrap_or_else(|| MLC_DEFAULT_HOST.to_string());
+        config.log_level = log_level.and_then(|l| valid_or_warn(normalize_log_level(&l)));
+        config.max_tokens = max_tokens.and_then(|v| valid_or_warn(validate_max_tokens(v)));
+        config.sampling = valid_or_warn(sampling.validate().map(|_| sampling)).unwrap_or_default();
+        config.watchdog_enabled = watchdog_enabled.unwrap_or_else(watchdog_enabled_default);
+        config.model = model
+            .map(|m| m.trim().to_string())
+            .filter(|m| !m.is_empty());
+        config.kill_orphans = kill_orphans.unwrap_or(false);
+        Ok(())
+    }
+
+    /// Returns the base URL of the OpenAI-compatible API (`http://host:port/v1`) once
+    /// the server is HTTP-ready; otherwise `ServiceUnavailable` with the current status.
+    pub async fn require_ready(&self) -> Result<String, ServerCommandError> {
+        self.ready_addr().await.map(api_base_url)
+    }
+
+    /// Local address of the server once it is HTTP-ready; otherwise `ServiceUnavailable`
+    /// with the current status.
+    pub async fn ready_addr(&self) -> Result<SocketAddr, ServerCommandError> {
+        let status = self.get_status().await;
+        match status.port {
+            Some(port) if status.is_http_ready => {
+                let host = local_address(&self.config.read().await.host);
+                Ok(SocketAddr::new(host, port))
+            }
+            _ => Err(ServerCommandError::unavailable(status)),
+        }
+    }
+
+    /// Proxies an allowlisted admin request to the managed server and returns its JSON
+    /// (a string for non-JSON bodies, null for empty ones). Only sent when the server
+    /// is reachable over loopback.
+    pub async fn admin_request(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<serde_json::Value>,
+    ) -> Result<serde_json::Value, ServerCommandError> {
+        let method = validate_admin_request(method, path)?;
+        let status = self.get_status().await;
+        let port = match status.port {
+            Some(port) if status.is_http_ready => port,
+            _ => return Err(ServerCommandError::unavailable(status)),
+        };
+        let host = local_address(&self.config.read().await.host);
+        if !host.is_loopback() {
+            return Err("admin requests are only sent to a server bound to loopback".into());
+        }
+
+        let url = format!("http://{}{path}", SocketAddr::new(host, port));
+        let mut request = crate::http_client::shared_client()
+            .request(method, &url)
+            .timeout(MLC_ADMIN_REQUEST_TIMEOUT);
+        if let Some(body) = &body {
+            request = request.json(body);
+        }
+        let resp = request
+            .send()
+            .await
+            .map_err(|e| format!("admin request to {path} failed: {e}"))?;
+        let code = resp.status();
+        let text = resp
+            .text()
+            .await
+            .map_err(|e| format!("admin request to {path} failed: {e}"))?;
+        if !code.is_success() {
+            return Err(format!("admin request to {path} returned HTTP {code}: {text}").into());
+        }
+        if text.trim().is_empty() {
+            return Ok(serde_json::Value::Null);
+        }
+        Ok(serde_json::from_str(&text).unwrap_or(serde_json::Value::String(text)))
+    }
+
+    /// Checks that the selected model, when it is a cached Hugging Face repo, has every
+    /// file the server needs, failing with the missing files instead of letting the
+    /// server fail on a partial cache. Uncached repos and local paths are left to the
+    /// server.
+    async fn preflight_model(&self, model: &str) -> Result<(), String> {
+        let models_dir = match self.app_handle.path().app_data_dir() {
+            Ok(dir) => app_models_dir(&dir),
+            Err(e) => return Err(format!("Failed to get app data dir: {e}")),
+        };
+        let ModelLocation::HuggingFace { repo_id, cache_dir } =
+            resolve_model_path(model, &models_dir)
+        else {
+            return Ok(());
+        };
+        if !is_model_cached(&repo_id) {
+            return Ok(());
+        }
+
+        let missing = missing_files(cache_dir.clone()).await?;
+        if missing.is_empty() {
+            self.emit_verification(ModelVerification::Verified { model: repo_id });
+            return Ok(());
+        }
+        log::warn!("Model {repo_id} is missing {}", missing.join(", "));
+        let error = format!(
+            "Model {repo_id} is missing files ({}); repair it and start the server again",
+            missing.join(", ")
+        );
+        self.emit_verification(ModelVerification::Incomplete {
+            model: repo_id,
+            missing,
+        });
+        Err(error)
+    }
+
+    fn emit_verification(&self, verification: ModelVerification) {
+        let _ = self
+            .app_handle
+            .emit(MLC_MODEL_VERIFICATION_EVENT, verification);
+    }
+
+    /// Performs a lightweight HTTP readiness check against `/v1/models`, returning the
+    /// ids of the served models.
+    async fn health_check(&self, port: u16, timeout: Duration) -> anyhow::Result<Vec<String>> {
+        let host = local_address(&self.config.read().await.host);
+        http_get_models_reqwest(SocketAddr::new(host, port), timeout).await
+    }
+
+    /// Records that a chat request streamed by the frontend started and returns its
+    /// lease id for `request_finished`. The lease lapses after
+    /// `MLC_REQUEST_LEASE_TIMEOUT` if it is never finished.
+    pub fn request_started(&self) -> u64 {
+        let mut leases = self
+            .request_leases
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        leases.start(Instant::now())
+    }
+
+    /// Records that the chat request holding lease `lease` finished.
+    pub fn request_finished(&self, lease: u64) {
+        let mut leases = self
+            .request_leases
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        leases.finish(lease);
+    }
+
+    /// Counts a backend chat request as in flight until the returned guard is dropped.
+    pub fn track_request(self: &std::sync::Arc<Self>) -> InFlightRequest {
+        self.in_flight_requests.fetch_add(1, Ordering::SeqCst);
+        InFlightRequest(std::sync::Arc::clone(self))
+    }
+
+    /// Whether any backend request or unexpired frontend lease is in flight.
+    fn is_serving_requests(&self) -> bool {
+        if self.in_flight_requests.load(Ordering::SeqCst) > 0 {
+            return true;
+        }
+        let mut leases = self
+            .request_leases
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        leases.any_active(Instant::now())
     }
 
     /// Polls HTTP readiness up to 50 times (2s interval). Updates `is_http_ready` on success.
@@ -82,13 +824,29 @@ impl MLCServerManager {
                 return;
             };
 
-            match self.health_check(port).await {
-                Ok(_) => {
+            match self.health_check(port, MLC_READINESS_PROBE_TIMEOUT).await {
+                Ok(served) => {
                     let mut new_status = current_status.clone();
-                    if !new_status.is_http_ready {
+                    let (loaded_model, model_mismatch) =
+                        match_loaded_model(new_status.requested_model.as_deref(), &served);
+                    if model_mismatch {
+                        log::warn!(
+                            "openchat-mlx-server is serving {served:?}, not the requested model {:?}",
+                            new_status.requested_model
+                        );
+                    }
+                    if !new_status.is_http_ready
+                        || new_status.loaded_model != loaded_model
+                        || new_status.model_mismatch != model_mismatch
+                    {
                         new_status.is_http_ready = true;
                         new_status.error = None;
-                        self.update_status_and_emit(new_status).await;
+                        new_status.loaded_model = loaded_model;
+                        new_status.model_mismatch = model_mismatch;
+                        self.update_status_and_emit(new_status.clone()).await;
+                    }
+                    if !self.has_been_ready.swap(true, Ordering::SeqCst) {
+                        let _ = self.app_handle.emit(MLC_FIRST_READY_EVENT, new_status);
                     }
                     return;
                 }
@@ -108,6 +866,104 @@ impl MLCServerManager {
         }
     }
 
+    /// Enables or disables the hung-server watchdog for the running and future servers.
+    pub async fn set_watchdog_enabled(&self, enabled: bool) {
+        self.config.write().await.watchdog_enabled = enabled;
+    }
+
+    /// Health-checks server `pid` every `MLC_WATCHDOG_INTERVAL` until it stops or is
+    /// replaced. After `MLC_WATCHDOG_FAILURE_THRESHOLD` consecutive failures the server
+    /// is restarted, waiting longer before each consecutive restart. Every failure and
+    /// recovery is reflected in the emitted status. No probes are sent while chat
+    /// requests are in flight, and failures only count once the server has been ready,
+    /// so a busy or still-loading server is never restarted.
+    async fn run_watchdog(self: &std::sync::Arc<Self>, pid: u32) {
+        let mut failures = 0;
+        let mut was_ready = false;
+        loop {
+            tokio::time::sleep(MLC_WATCHDOG_INTERVAL).await;
+            let status = self.get_status().await;
+            let Some(port) = status.port.filter(|_| status.pid == Some(pid)) else {
+                return;
+            };
+            was_ready |= status.is_http_ready;
+            if !self.config.read().await.watchdog_enabled || self.is_serving_requests() {
+                failures = 0;
+                continue;
+            }
+
+            match self.health_check(port, MLC_WATCHDOG_PROBE_TIMEOUT).await {
+                Ok(_) if !was_ready => {
+                    was_ready = true;
+                    log::info!("watchdog: openchat-mlx-server (pid={pid}) finished loading");
+                    let mut ready = status;
+                    ready.is_http_ready = true;
+                    ready.error = None;
+                    self.update_status_and_emit(ready.clone()).await;
+                    if !self.has_been_ready.swap(true, Ordering::SeqCst) {
+                        let _ = self.app_handle.emit(MLC_FIRST_READY_EVENT, ready);
+                    }
+                }
+                Err(err) if !was_ready => {
+                    log::debug!("watchdog: pid={pid} is not ready yet: {err}");
+                }
+                Ok(_) => {
+                    self.watchdog_restarts.store(0, Ordering::SeqCst);
+                    if failures > 0 || !status.is_http_ready {
+                        log::info!("watchdog: openchat-mlx-server (pid={pid}) is responding again");
+                        let mut recovered = status;
+                        recovered.is_http_ready = true;
+                        recovered.error = None;
+                        self.update_status_and_emit(recovered.clone()).await;
+                        if !self.has_been_ready.swap(true, Ordering::SeqCst) {
+                            let _ = self.app_handle.emit(MLC_FIRST_READY_EVENT, recovered);
+                        }
+                    }
+                    failures = 0;
+                }
+                Err(err) => {
+                    failures += 1;
+                    log::warn!(
+                        "watchdog: health check failed for pid={pid} ({failures}/{MLC_WATCHDOG_FAILURE_THRESHOLD}): {err}"
+                    );
+                    let mut unhealthy = status;
+                    unhealthy.is_http_ready = false;
+                    if failures < MLC_WATCHDOG_FAILURE_THRESHOLD {
+                        unhealthy.error = Some(format!("Model server is not responding: {err}"));
+                        self.update_status_and_emit(unhealthy).await;
+                        continue;
+                    }
+
+                    let attempt = self.watchdog_restarts.fetch_add(1, Ordering::SeqCst);
+                    let delay = watchdog_backoff(attempt);
+                    unhealthy.error = Some(format!(
+                        "Model server stopped responding; restarting in {}s",
+                        delay.as_secs()
+                    ));
+                    self.update_status_and_emit(unhealthy).await;
+                    tokio::time::sleep(delay).await;
+                    if self.get_status().await.pid != Some(pid) {
+                        return;
+                    }
+                    log::warn!("watchdog: restarting unresponsive openchat-mlx-server (pid={pid})");
+                    self.restart_from_watchdog();
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Restarts on a separate task so the watchdog, which the new server's start
+    /// spawns again, isn't part of its own future type.
+    fn restart_from_watchdog(self: &std::sync::Arc<Self>) {
+        let manager = std::sync::Arc::clone(self);
+        tauri::async_runtime::spawn(async move {
+            if let Err(err) = manager.restart().await {
+                log::error!("watchdog: restart failed: {err}");
+            }
+        });
+    }
+
     /// Restarts the server by forcing a stop and then start, bypassing any short-circuit logic.
     pub async fn restart(self: &std::sync::Arc<Self>) -> Result<MLCServerStatus, String> {
         log::info!("Restarting MLX server (forced restart)");
@@ -129,46 +985,120 @@ impl MLCServerManager {
             return Ok(current_status);
         }
 
+        self.load_config_from_settings().await;
         let config = { self.config.read().await.clone() };
 
+        // Nothing of ours is running, so any sidecar left holding a port is an orphan
+        if config.kill_orphans {
+            if let Err(e) = self.kill_orphaned_servers().await {
+                log::warn!("Failed to clean up orphaned servers: {e}");
+            }
+        }
+
         // Find an available port near the desired one
         let desired_port = config.port;
-        let port = find_available_port(desired_port, 10)
-            .ok_or_else(|| format!("No available port found near {desired_port}"))?;
+        let bind_ip = local_address(&config.host);
+        let port = match find_available_port(bind_ip, desired_port, 10) {
+            Some(port) => port,
+            None => {
+                let occupant = tauri::async_runtime::spawn_blocking(move || {
+                    crate::process_info::port_occupant(desired_port)
+                })
+                .await
+                .ok()
+                .flatten();
+                return Err(match occupant {
+                    Some(occupant) => format!(
+                        "No available port found near {desired_port}; port {desired_port} is in use by {occupant}"
+                    ),
+                    None => format!("No available port found near {desired_port}"),
+                });
+            }
+        };
 
-        // Optionally set bundled python sidecar path
-        let python_path = self.app_handle.shell().sidecar("python3").ok().map(|cmd| {
-            let std_cmd: std::process::Command = cmd.into();
-            std::path::PathBuf::from(std_cmd.get_program().to_owned())
-        });
+        if let Err(searched) = self.check_sidecar_present() {
+            log::error!(
+                "{MLC_SIDECAR_NAME} sidecar not found; searched: {}",
+                searched
+                    .iter()
+                    .map(|p| p.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            let mut status = self.get_status().await;
+            status.is_running = false;
+            status.is_http_ready = false;
+            status.pid = None;
+            status.error = Some(MLC_SIDECAR_MISSING_ERROR.to_string());
+            self.update_status_and_emit(status).await;
+            return Err(MLC_SIDECAR_MISSING_ERROR.to_string());
+        }
+
+        if let Some(model) = config.model.as_deref() {
+            self.preflight_model(model).await?;
+        }
 
         log::info!(
-            "Starting openchat-mlx-server: host={} port={}",
+            "Starting openchat-mlx-server: host={} port={} log_level={:?} max_tokens={:?} sampling={:?}",
             config.host,
-            port
+            port,
+            config.log_level,
+            config.max_tokens,
+            config.sampling
         );
 
         // Build and spawn sidecar using Tauri's shell plugin
-        let mut sidecar_cmd = self
-            .app_handle
-            .shell()
-            .sidecar("openchat-mlx-server")
-            .map_err(|e| format!("Failed to resolve openchat-mlx-server sidecar: {e}"))?
-            .args(["--host", &config.host, "--port", &port.to_string()]);
+        let mut sidecar_cmd =
+            self.sidecar_command()?
+                .args(["--host", &config.host, "--port", &port.to_string()]);
 
-        if let Some(py) = python_path {
-            sidecar_cmd = sidecar_cmd.env("OPENCHAT_MLX_SERVER_PYTHON", py);
+        // Optional settings go only to sidecar builds that know the flag; older
+        // builds exit on unrecognised arguments
+        let mut optional_args = Vec::new();
+        if let Some(level) = &config.log_level {
+            optional_args.push(("--log-level", level.clone()));
+        }
+        if let Some(max_tokens) = config.max_tokens {
+            optional_args.push(("--max-tokens", max_tokens.to_string()));
+        }
+        optional_args.extend(config.sampling.to_args());
+        if !optional_args.is_empty() {
+            let supported = self.sidecar_flags().await;
+            for (flag, value) in optional_args {
+                if supported.contains(flag) {
+                    sidecar_cmd = sidecar_cmd.args([flag, value.as_str()]);
+                } else {
+                    log::warn!(
+                        "openchat-mlx-server does not accept {flag}; ignoring {flag} {value}"
+                    );
+                }
+            }
         }
 
         let (rx, child) = sidecar_cmd
             .spawn()
             .map_err(|e| format!("Failed to start openchat-mlx-server: {e}"))?;
 
-        // Drain and log stdout/stderr
-        spawn_command_log_relay("[mlx-server]", rx);
-
         let pid = child.pid();
 
+        // Drain and log stdout/stderr; reflect unexpected exits in the status
+        self.set_load_progress(Some(MlcLoadProgress {
+            stage: MLC_LOAD_STAGE_LOADING.to_string(),
+            percent: None,
+        }));
+        let manager = std::sync::Arc::clone(self);
+        let line_manager = std::sync::Arc::clone(self);
+        let output = std::sync::Arc::clone(&self.recent_output);
+        spawn_command_log_relay(
+            "[mlx-server]",
+            rx,
+            output,
+            move |line| line_manager.handle_output_line(line),
+            move |payload| async move {
+                manager.handle_terminated(pid, payload).await;
+            },
+        );
+
         // Save child handle
         {
             let mut guard = self.child.lock().await;
@@ -182,13 +1112,22 @@ impl MLCServerManager {
             port: Some(port),
             pid: Some(pid),
             error: None,
+            exit_code: None,
+            exit_signal: None,
+            warning: network_exposure_warning(&config.host),
+            requested_model: config.model.clone(),
+            loaded_model: None,
+            model_mismatch: false,
         };
         self.update_status_and_emit(new_status.clone()).await;
+        telemetry::record(&self.app_handle, TelemetryEvent::ServerStarted { port });
 
-        // Kick off health polling in the background
+        // Kick off health polling in the background, then watch for hangs
         let manager = std::sync::Arc::clone(self);
         tauri::async_runtime::spawn(async move {
             manager.poll_health_check().await;
+            manager.set_load_progress(None);
+            manager.run_watchdog(pid).await;
         });
 
         Ok(new_status)
@@ -224,6 +1163,7 @@ impl MLCServerManager {
             if let Err(err) = child.kill() {
                 log::warn!("Failed to kill child process: {err}");
             }
+            telemetry::record(&self.app_handle, TelemetryEvent::ServerStopped);
         }
 
         let mut status = self.status.lock().await.clone();
@@ -234,17 +1174,133 @@ impl MLCServerManager {
         Ok(())
     }
 
+    /// Checks that the sidecar binary exists where the shell plugin will launch it from.
+    /// On failure returns the paths that were searched.
+    fn check_sidecar_present(&self) -> Result<(), Vec<std::path::PathBuf>> {
+        match self.sidecar_path() {
+            Some(path) if path.is_file() => Ok(()),
+            Some(path) => Err(vec![path]),
+            None => Err(std::env::current_exe()
+                .ok()
+                .and_then(|exe| exe.parent().map(|dir| dir.join(MLC_SIDECAR_NAME)))
+                .into_iter()
+                .collect()),
+        }
+    }
+
+    /// Records an exit of process `pid` that `stop` did not initiate. `stop` takes the
+    /// child handle before killing it, so a missing or different handle means the exit
+    /// was expected (or belongs to a previous run) and is ignored.
+    async fn handle_terminated(&self, pid: u32, payload: TerminatedPayload) {
+        {
+            let mut child = self.child.lock().await;
+            if child.as_ref().map(|c| c.pid()) != Some(pid) {
+                return;
+            }
+            *child = None;
+        }
+        self.set_load_progress(None);
+
+        let reason = match (payload.code, payload.signal) {
+            (_, Some(signal)) => format!("signal {signal}"),
+            (Some(code), None) => format!("exit code {code}"),
+            (None, None) => "unknown status".to_string(),
+        };
+        log::error!("openchat-mlx-server (pid={pid}) exited unexpectedly: {reason}");
+        telemetry::record(
+            &self.app_handle,
+            TelemetryEvent::ServerCrashed {
+                exit_code: payload.code,
+                signal: payload.signal,
+            },
+        );
+
+        let mut status = self.status.lock().await.clone();
+        status.is_running = false;
+        status.is_http_ready = false;
+        status.pid = None;
+        status.exit_code = payload.code;
+        status.exit_signal = payload.signal;
+        status.error = Some(format!("MLC server exited unexpectedly ({reason})"));
+        self.update_status_and_emit(status).await;
+    }
+
     // Removed manual resource resolver; sidecar paths are resolved via Shell plugin.
 }
 
-/// Spawns a task that relays and logs CommandEvent output with a consistent prefix.
-fn spawn_command_log_relay(
+/// Error returned by commands that need a ready model server. Serialized with a `kind`
+/// tag so the frontend can show a uniform "starting up" state and retry on
+/// `service_unavailable`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ServerCommandError {
+    /// The server isn't running or isn't HTTP-ready yet.
+    ServiceUnavailable {
+        message: String,
+        status: MLCServerStatus,
+    },
+    /// Any other failure.
+    Failed { message: String },
+}
+
+impl ServerCommandError {
+    fn unavailable(status: MLCServerStatus) -> Self {
+        let message = if status.is_running {
+            "The local model server is starting up"
+        } else {
+            "The local model server is not running"
+        };
+        ServerCommandError::ServiceUnavailable {
+            message: message.to_string(),
+            status,
+        }
+    }
+}
+
+impl From<String> for ServerCommandError {
+    fn from(message: String) -> Self {
+        ServerCommandError::Failed { message }
+    }
+}
+
+impl From<&str> for ServerCommandError {
+    fn from(message: &str) -> Self {
+        message.to_string().into()
+    }
+}
+
+/// Delay before watchdog restart number `attempt` (0-based): doubling from
+/// `MLC_WATCHDOG_BASE_BACKOFF`, capped at `MLC_WATCHDOG_MAX_BACKOFF`.
+fn watchdog_backoff(attempt: u32) -> Duration {
+    MLC_WATCHDOG_BASE_BACKOFF
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MLC_WATCHDOG_MAX_BACKOFF)
+}
+
+/// Drops an invalid stored setting with a warning so it can't block startup.
+fn valid_or_warn<T>(value: Result<T, String>) -> Option<T> {
+    value
+        .map_err(|err| log::warn!("Ignoring stored MLC setting: {err}"))
+        .ok()
+}
+
+/// Spawns a task that relays and logs CommandEvent output with a consistent prefix,
+/// keeping a copy in `output`. `on_terminated` runs once when the process exits.
+fn spawn_command_log_relay<L, F, Fut>(
     prefix: impl Into<String>,
     rx: tauri::async_runtime::Receiver<CommandEvent>,
-) {
+    output: std::sync::Arc<OutputRing>,
+    on_line: L,
+    on_terminated: F,
+) where
+    L: Fn(&str) + Send + 'static,
+    F: FnOnce(TerminatedPayload) -> Fut + Send + 'static,
+    Fut: std::future::Future<Output = ()> + Send,
+{
     let prefix = prefix.into();
     tauri::async_runtime::spawn(async move {
         let mut rx = rx;
+        let mut on_terminated = Some(on_terminated);
         while let Some(event) = rx.recv().await {
             match event {
                 CommandEvent::Stdout(bytes) => {
@@ -252,6 +1308,8 @@ fn spawn_command_log_relay(
                         let line = line.trim_end_matches('\n');
                         if !line.is_empty() {
                             log::info!("{} {}", prefix, line);
+                            on_line(line);
+                            output.push(line.to_string());
                         }
                     }
                 }
@@ -260,11 +1318,14 @@ fn spawn_command_log_relay(
                         let line = line.trim_end_matches('\n');
                         if !line.is_empty() {
                             log::error!("{} {}", prefix, line);
+                            on_line(line);
+                            output.push(format!("stderr: {line}"));
                         }
                     }
                 }
                 CommandEvent::Error(err) => {
                     log::error!("{} error: {}", prefix, err);
+                    output.push(format!("error: {err}"));
                 }
                 CommandEvent::Terminated(payload) => {
                     log::info!(
@@ -273,6 +1334,13 @@ fn spawn_command_log_relay(
                         payload.code,
                         payload.signal
                     );
+                    output.push(format!(
+                        "terminated: code={:?} signal={:?}",
+                        payload.code, payload.signal
+                    ));
+                    if let Some(on_terminated) = on_terminated.take() {
+                        on_terminated(payload).await;
+                    }
                 }
                 _ => {}
             }
@@ -280,26 +1348,95 @@ fn spawn_command_log_relay(
     });
 }
 
-/// GET /v1/models with a short timeout; ensures a JSON response containing a `data` array.
-async fn http_get_models_reqwest(port: u16) -> anyhow::Result<()> {
-    let url = format!("http://127.0.0.1:{}/v1/models", port);
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_millis(800))
-        .build()?;
-    let resp = client.get(&url).send().await?;
+/// `missing_model_files` for `dir`, off the async runtime.
+async fn missing_files(dir: std::path::PathBuf) -> Result<Vec<String>, String> {
+    tauri::async_runtime::spawn_blocking(move || missing_model_files(&dir))
+        .await
+        .map_err(|e| format!("join error: {e}"))
+}
+
+/// Base URL of the OpenAI-compatible API served at `addr`.
+pub fn api_base_url(addr: SocketAddr) -> String {
+    format!("http://{addr}/v1")
+}
+
+/// GET /v1/models within `timeout`; ensures a JSON response containing a `data` array
+/// and returns the model ids listed in it.
+pub async fn http_get_models_reqwest(
+    addr: SocketAddr,
+    timeout: Duration,
+) -> anyhow::Result<Vec<String>> {
+    let url = format!("{}/models", api_base_url(addr));
+    let resp = crate::http_client::shared_client()
+        .get(&url)
+        .timeout(timeout)
+        .send()
+        .await?;
     if !resp.status().is_success() {
         anyhow::bail!("HTTP {}", resp.status());
     }
     let json: serde_json::Value = resp.json().await?;
-    match json.get("data") {
-        Some(value) if value.is_array() => Ok(()),
-        _ => anyhow::bail!("Missing or invalid `data` field in response"),
+    match json.get("data").and_then(|v| v.as_array()) {
+        Some(models) => Ok(models
+            .iter()
+            .filter_map(|m| m.get("id").and_then(|id| id.as_str()).map(str::to_string))
+            .collect()),
+        None => anyhow::bail!("Missing or invalid `data` field in response"),
+    }
+}
+
+/// Checks an admin request against `MLC_ADMIN_METHODS` and `MLC_ADMIN_PATHS`. Paths
+/// must be plain absolute paths: no query, fragment, `..` or empty segments.
+fn validate_admin_request(method: &str, path: &str) -> Result<reqwest::Method, String> {
+    let method = method.trim().to_ascii_uppercase();
+    if !MLC_ADMIN_METHODS.contains(&method.as_str()) {
+        return Err(format!(
+            "method '{method}' is not allowed; expected one of {}",
+            MLC_ADMIN_METHODS.join(", ")
+        ));
+    }
+    let plain = path.starts_with('/')
+        && !path.contains(['?', '#', '\\', '%'])
+        && path[1..]
+            .split('/')
+            .all(|segment| !matches!(segment, "" | "." | ".."));
+    let allowed = MLC_ADMIN_PATHS
+        .iter()
+        .any(|allowed| match allowed.strip_suffix('/') {
+            Some(_) => path.starts_with(allowed) && path.len() > allowed.len(),
+            None => path == *allowed,
+        });
+    if !plain || !allowed {
+        return Err(format!("path '{path}' is not an allowed server admin path"));
+    }
+    method
+        .parse()
+        .map_err(|_| format!("invalid method '{method}'"))
+}
+
+/// Picks the loaded model from the ids the server lists: the requested one if it is
+/// among them, otherwise the first. The second value is whether a requested model is
+/// missing. Ids match exactly or as a trailing path component, since the server may
+/// report a local path for a repo id. An empty list says nothing about what is loaded,
+/// so it is never a mismatch.
+fn match_loaded_model(requested: Option<&str>, served: &[String]) -> (Option<String>, bool) {
+    let Some(requested) = requested.filter(|_| !served.is_empty()) else {
+        return (served.first().cloned(), false);
+    };
+    let same = |id: &str| {
+        id == requested
+            || id.ends_with(&format!("/{requested}"))
+            || requested.ends_with(&format!("/{id}"))
+    };
+    match served.iter().find(|id| same(id)) {
+        Some(id) => (Some(id.clone()), false),
+        None => (served.first().cloned(), true),
     }
 }
 
-/// Attempts to find an available port by binding sequentially starting at `start` for `range` ports.
-fn find_available_port(start: u16, range: u16) -> Option<u16> {
-    let host = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1));
+/// Attempts to find an available port on `host` by binding sequentially starting at
+/// `start` for `range` ports.
+fn find_available_port(host: IpAddr, start: u16, range: u16) -> Option<u16> {
     for port in start..start.saturating_add(range) {
         let addr = SocketAddr::new(host, port);
         if TcpListener::bind(addr).is_ok() {
@@ -308,3 +1445,188 @@ fn find_available_port(start: u16, range: u16) -> Option<u16> {
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        local_address, match_loaded_model, network_exposure_warning, parse_help_flags,
+        parse_load_progress, validate_admin_request, validate_host, watchdog_backoff,
+        MLCServerStatus, MlcLoadProgress, RequestLeases, SamplingDefaults, ServerCommandError,
+        MLC_WATCHDOG_MAX_BACKOFF,
+    };
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn request_leases_end_when_finished_or_expired() {
+        let start = Instant::now();
+        let mut leases = RequestLeases::new(Duration::from_secs(60));
+        assert!(!leases.any_active(start));
+
+        let finished = leases.start(start);
+        let abandoned = leases.start(start);
+        assert_ne!(finished, abandoned);
+        leases.finish(finished);
+        assert!(leases.any_active(start + Duration::from_secs(59)));
+        assert!(!leases.any_active(start + Duration::from_secs(60)));
+
+        leases.finish(abandoned);
+        leases.finish(12345);
+        assert!(!leases.any_active(start));
+    }
+
+    #[test]
+    fn admin_requests_are_limited_to_allowlisted_paths_and_methods() {
+        assert_eq!(
+            validate_admin_request("get", "/v1/models"),
+            Ok(reqwest::Method::GET)
+        );
+        assert!(validate_admin_request("POST", "/v1/admin/unload").is_ok());
+        assert!(validate_admin_request("DELETE", "/v1/models/mlx-community/x").is_ok());
+        assert!(validate_admin_request("GET", "/health").is_ok());
+
+        assert!(validate_admin_request("PATCH", "/health").is_err());
+        assert!(validate_admin_request("GET", "/v1/admin/").is_err());
+        assert!(validate_admin_request("GET", "/v1/chat/completions").is_err());
+        assert!(validate_admin_request("GET", "/v1/admin/../../etc").is_err());
+        assert!(validate_admin_request("GET", "/v1/admin//x").is_err());
+        assert!(validate_admin_request("GET", "/health?x=1").is_err());
+        assert!(validate_admin_request("GET", "/v1/admin/%2e%2e/x").is_err());
+        assert!(validate_admin_request("GET", "health").is_err());
+        assert!(validate_admin_request("GET", "/healthz").is_err());
+    }
+
+    #[test]
+    fn watchdog_backoff_doubles_up_to_the_cap() {
+        assert_eq!(watchdog_backoff(0), Duration::from_secs(5));
+        assert_eq!(watchdog_backoff(2), Duration::from_secs(20));
+        assert_eq!(watchdog_backoff(10), MLC_WATCHDOG_MAX_BACKOFF);
+        assert_eq!(watchdog_backoff(u32::MAX), MLC_WATCHDOG_MAX_BACKOFF);
+    }
+
+    #[test]
+    fn flags_a_server_serving_a_different_model() {
+        let served = vec!["/models/mlx-community/Qwen3-4B-4bit".to_string()];
+        assert_eq!(
+            match_loaded_model(Some("mlx-community/Qwen3-4B-4bit"), &served),
+            (Some(served[0].clone()), false)
+        );
+        assert_eq!(
+            match_loaded_model(Some("mlx-community/Llama-3.2-1B"), &served),
+            (Some(served[0].clone()), true)
+        );
+        assert_eq!(
+            match_loaded_model(None, &served),
+            (Some(served[0].clone()), false)
+        );
+        assert_eq!(match_loaded_model(Some("a/b"), &[]), (None, false));
+    }
+
+    #[test]
+    fn service_unavailable_serializes_with_kind_and_status() {
+        let status = MLCServerStatus {
+            is_running: true,
+            is_http_ready: false,
+            port: Some(8000),
+            pid: Some(42),
+            error: None,
+            exit_code: None,
+            exit_signal: None,
+            warning: None,
+            ..Default::default()
+        };
+        let json = serde_json::to_value(ServerCommandError::unavailable(status)).unwrap();
+        assert_eq!(json["kind"], "service_unavailable");
+        assert_eq!(json["message"], "The local model server is starting up");
+        assert_eq!(json["status"]["port"], 8000);
+
+        let json = serde_json::to_value(ServerCommandError::from("boom")).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "kind": "failed", "message": "boom" })
+        );
+    }
+
+    #[test]
+    fn help_flags_are_read_from_argparse_usage() {
+        let help = "usage: openchat-mlx-server [-h] [--host HOST] [--port PORT]\n\
+                    \n  --log-level {DEBUG,INFO}, -l\n  --max-tokens=N  cap\n";
+        let flags = parse_help_flags(help);
+        for flag in ["--host", "--port", "--log-level", "--max-tokens"] {
+            assert!(flags.contains(flag), "{flag} missing from {flags:?}");
+        }
+        assert!(!flags.contains("--temperature"));
+    }
+
+    #[test]
+    fn sampling_defaults_validate_ranges_and_build_args() {
+        let defaults = SamplingDefaults {
+            temperature: Some(0.0),
+            top_p: Some(0.9),
+            repetition_penalty: None,
+        };
+        assert!(defaults.validate().is_ok());
+        assert_eq!(
+            defaults.to_args(),
+            vec![
+                ("--temperature", "0".to_string()),
+                ("--top-p", "0.9".to_string())
+            ]
+        );
+
+        let bad_top_p = SamplingDefaults {
+            top_p: Some(0.0),
+            ..Default::default()
+        };
+        assert!(bad_top_p.validate().is_err());
+        let bad_temperature = SamplingDefaults {
+            temperature: Some(f64::NAN),
+            ..Default::default()
+        };
+        assert!(bad_temperature.validate().is_err());
+    }
+
+    #[test]
+    fn validates_hosts_and_flags_network_exposure() {
+        assert_eq!(validate_host(" 0.0.0.0 ").as_deref(), Ok("0.0.0.0"));
+        assert_eq!(validate_host("::1").as_deref(), Ok("::1"));
+        assert!(validate_host("localhost").is_err());
+
+        assert!(network_exposure_warning("127.0.0.1").is_none());
+        assert!(network_exposure_warning("0.0.0.0").is_some());
+        assert_eq!(local_address("0.0.0.0").to_string(), "127.0.0.1");
+        assert_eq!(local_address("192.168.1.5").to_string(), "192.168.1.5");
+    }
+
+    #[test]
+    fn parses_load_progress_markers() {
+        let progress = |stage: &str, percent: Option<u8>| {
+            Some(MlcLoadProgress {
+                stage: stage.to_string(),
+                percent,
+            })
+        };
+        assert_eq!(
+            parse_load_progress(r#"{"event":"load_progress","stage":"weights","percent":42.5}"#),
+            progress("weights", Some(42))
+        );
+        assert_eq!(
+            parse_load_progress(
+                "Loading weights:  10%|#         | 1/10\rLoading weights:  40%|####      | 4/10"
+            ),
+            progress("Loading weights", Some(40))
+        );
+        assert_eq!(
+            parse_load_progress("INFO: Fetching 9 files: 100%|##########| 9/9"),
+            progress("Fetching 9 files", Some(100))
+        );
+        assert_eq!(
+            parse_load_progress(" 75%|#######5  | 3/4"),
+            progress("loading", Some(75))
+        );
+        assert_eq!(parse_load_progress(r#"{"event":"request"}"#), None);
+        assert_eq!(
+            parse_load_progress("Server listening on 127.0.0.1:8000"),
+            None
+        );
+    }
+}
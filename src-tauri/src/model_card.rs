@@ -0,0 +1,371 @@
+//! Model card preview: fetches just `README.md`, `config.json` and the file listing of
+//! a Hugging Face repo so the model picker can describe and size a model before
+//! committing to a download, and checks from the repo's file list whether the bundled
+//! MLX server can load it.
+
+use crate::http_client::shared_client;
+use crate::model_download::hf_endpoint;
+use crate::model_store::{validate_repo_id, MODEL_CONFIG_FILES, MODEL_TOKENIZER_FILES};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long a fetched card is reused before asking Hugging Face again.
+const MODEL_CARD_TTL: Duration = Duration::from_secs(10 * 60);
+/// Timeout of each file request.
+const MODEL_CARD_TIMEOUT: Duration = Duration::from_secs(10);
+/// Longer READMEs are truncated to this many bytes.
+const MODEL_CARD_MAX_README_BYTES: usize = 256 * 1024;
+
+/// README and config of a repo (either is `None` if the repo does not have it), plus
+/// its files with their sizes.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ModelCard {
+    pub repo_id: String,
+    pub readme: Option<String>,
+    pub config: Option<serde_json::Value>,
+    pub files: Vec<ModelCardFile>,
+    /// Sum of the known file sizes, in bytes.
+    pub total_size: u64,
+}
+
+/// One file of the repo; `size` is in bytes, `None` if the listing omits it.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ModelCardFile {
+    pub path: String,
+    pub size: Option<u64>,
+}
+
+static CARD_CACHE: Lazy<Mutex<HashMap<String, (Instant, ModelCard)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Returns the model card of `repo_id`, from cache if fetched in the last
+/// `MODEL_CARD_TTL`. Fails if the repo has neither file or cannot be reached.
+pub async fn fetch_model_card(repo_id: &str) -> Result<ModelCard, String> {
+    validate_repo_id(repo_id)?;
+    if let Some(card) = cached(repo_id, Instant::now()) {
+        return Ok(card);
+    }
+    let (info, readme, config) = tokio::join!(
+        fetch_repo_info(repo_id),
+        fetch_file(repo_id, "README.md"),
+        fetch_file(repo_id, "config.json")
+    );
+    let (info, readme, config) = (info?, readme?, config?);
+    if readme.is_none() && config.is_none() {
+        return Err(format!(
+            "{repo_id} has no README.md or config.json, or is not a public model repository"
+        ));
+    }
+    let card = ModelCard {
+        repo_id: repo_id.to_string(),
+        readme: readme.map(truncate_readme),
+        config: config
+            .map(|text| {
+                serde_json::from_str(&text).map_err(|e| format!("invalid config.json: {e}"))
+            })
+            .transpose()?,
+        total_size: info.siblings.iter().filter_map(|f| f.size).sum(),
+        files: info
+            .siblings
+            .into_iter()
+            .map(|f| ModelCardFile {
+                path: f.rfilename,
+                size: f.size,
+            })
+            .collect(),
+    };
+    if let Ok(mut cache) = CARD_CACHE.lock() {
+        cache.retain(|_, (at, _)| at.elapsed() < MODEL_CARD_TTL);
+        cache.insert(repo_id.to_string(), (Instant::now(), card.clone()));
+    }
+    Ok(card)
+}
+
+/// Whether a repo can be loaded by the bundled MLX server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompatibilityVerdict {
+    Compatible,
+    Incompatible,
+    /// Has usable weights but no MLX markers; loads only if the architecture is supported.
+    Unknown,
+}
+
+/// Result of `check_model_compatibility`.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ModelCompatibility {
+    pub repo_id: String,
+    pub verdict: CompatibilityVerdict,
+    pub reason: String,
+}
+
+/// The parts of the Hugging Face model API response the compatibility check uses.
+#[derive(Debug, Default, Deserialize)]
+struct RepoInfo {
+    #[serde(default)]
+    library_name: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    siblings: Vec<RepoFile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RepoFile {
+    rfilename: String,
+    /// Only present when the listing is requested with `blobs=true`.
+    #[serde(default)]
+    size: Option<u64>,
+}
+
+/// Decides from the repo's file list, tags and `config.json` (not its weights) whether
+/// the MLX server can load `repo_id`.
+pub async fn check_model_compatibility(repo_id: &str) -> Result<ModelCompatibility, String> {
+    validate_repo_id(repo_id)?;
+    let info = fetch_repo_info(repo_id).await?;
+    let config = if info.siblings.iter().any(|f| f.rfilename == "config.json") {
+        fetch_file(repo_id, "config.json")
+            .await?
+            .and_then(|text| serde_json::from_str(&text).ok())
+    } else {
+        None
+    };
+    let (verdict, reason) = assess_compatibility(&info, config.as_ref());
+    Ok(ModelCompatibility {
+        repo_id: repo_id.to_string(),
+        verdict,
+        reason,
+    })
+}
+
+fn assess_compatibility(
+    info: &RepoInfo,
+    config: Option<&serde_json::Value>,
+) -> (CompatibilityVerdict, String) {
+    use CompatibilityVerdict::*;
+    let has = |name: &str| info.siblings.iter().any(|f| f.rfilename == name);
+    let has_ext = |ext: &str| info.siblings.iter().any(|f| f.rfilename.ends_with(ext));
+
+    if !MODEL_CONFIG_FILES.iter().any(|name| has(name)) {
+        return (Incompatible, "the repo has no config.json".into());
+    }
+    if !MODEL_TOKENIZER_FILES.iter().any(|name| has(name)) {
+        return (
+            Incompatible,
+            "the repo has no tokenizer.json or tokenizer.model".into(),
+        );
+    }
+    if has("ndarray-cache.json") || has("tensor-cache.json") {
+        return (Compatible, "MLC weight shards".into());
+    }
+    if !has_ext(".safetensors") {
+        let reason = if has_ext(".gguf") {
+            "only GGUF weights; look for an MLX conversion of this model"
+        } else {
+            "no safetensors weights (PyTorch .bin checkpoints are not supported)"
+        };
+        return (Incompatible, reason.into());
+    }
+    let is_mlx = |value: &str| value.eq_ignore_ascii_case("mlx");
+    if info.library_name.as_deref().is_some_and(is_mlx) || info.tags.iter().any(|t| is_mlx(t)) {
+        return (Compatible, "tagged as an MLX model".into());
+    }
+    if let Some(bits) = config.and_then(|c| c.pointer("/quantization/bits")) {
+        return (
+            Compatible,
+            format!("MLX quantization metadata ({bits}-bit)"),
+        );
+    }
+    if let Some(method) = config
+        .and_then(|c| c.pointer("/quantization_config/quant_method"))
+        .and_then(|m| m.as_str())
+    {
+        return (
+            Incompatible,
+            format!("quantized with {method}, which MLX cannot load"),
+        );
+    }
+    (
+        Unknown,
+        "safetensors weights without MLX markers; an unconverted model only loads if MLX supports its architecture"
+            .into(),
+    )
+}
+
+/// Fetches the repo's tags and file list, with file sizes, from the model API.
+async fn fetch_repo_info(repo_id: &str) -> Result<RepoInfo, String> {
+    let url = format!("{}/api/models/{repo_id}?blobs=true", hf_endpoint());
+    let resp = hf_request(&url)
+        .send()
+        .await
+        .map_err(|e| format!("failed to look up {repo_id}: {e}"))?;
+    let status = resp.status();
+    if matches!(status.as_u16(), 401 | 404) {
+        return Err(format!(
+            "{repo_id} was not found or is not a public model repository"
+        ));
+    }
+    if !status.is_success() {
+        return Err(format!("failed to look up {repo_id}: HTTP {status}"));
+    }
+    resp.json()
+        .await
+        .map_err(|e| format!("invalid model info for {repo_id}: {e}"))
+}
+
+fn hf_request(url: &str) -> reqwest::RequestBuilder {
+    let request = shared_client().get(url).timeout(MODEL_CARD_TIMEOUT);
+    match std::env::var("HF_TOKEN") {
+        Ok(token) => request.bearer_auth(token),
+        Err(_) => request,
+    }
+}
+
+fn cached(repo_id: &str, now: Instant) -> Option<ModelCard> {
+    let cache = CARD_CACHE.lock().ok()?;
+    let (at, card) = cache.get(repo_id)?;
+    (now.duration_since(*at) < MODEL_CARD_TTL).then(|| card.clone())
+}
+
+/// Downloads `file` from the repo's main branch; `None` if it does not exist.
+async fn fetch_file(repo_id: &str, file: &str) -> Result<Option<String>, String> {
+    let url = format!("{}/{repo_id}/resolve/main/{file}", hf_endpoint());
+    let resp = hf_request(&url)
+        .send()
+        .await
+        .map_err(|e| format!("failed to fetch {file} for {repo_id}: {e}"))?;
+    let status = resp.status();
+    // Missing files and missing (or private) repos both answer 401/404
+    if matches!(status.as_u16(), 401 | 404) {
+        return Ok(None);
+    }
+    if !status.is_success() {
+        return Err(format!(
+            "failed to fetch {file} for {repo_id}: HTTP {status}"
+        ));
+    }
+    resp.text()
+        .await
+        .map(Some)
+        .map_err(|e| format!("failed to read {file} for {repo_id}: {e}"))
+}
+
+fn truncate_readme(mut readme: String) -> String {
+    if readme.len() > MODEL_CARD_MAX_README_BYTES {
+        let mut end = MODEL_CARD_MAX_README_BYTES;
+        while !readme.is_char_boundary(end) {
+            end -= 1;
+        }
+        readme.truncate(end);
+    }
+    readme
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        assess_compatibility, cached, validate_repo_id, CompatibilityVerdict, ModelCard, RepoFile,
+        RepoInfo, CARD_CACHE, MODEL_CARD_TTL,
+    };
+    use std::time::Instant;
+
+    fn repo(files: &[&str], tags: &[&str]) -> RepoInfo {
+        RepoInfo {
+            library_name: None,
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            siblings: files
+                .iter()
+                .map(|f| RepoFile {
+                    rfilename: f.to_string(),
+                    size: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn assesses_mlx_compatibility_from_files_and_config() {
+        let base = ["config.json", "tokenizer.json", "model.safetensors"];
+        let verdict = |info: &RepoInfo, config: Option<serde_json::Value>| {
+            assess_compatibility(info, config.as_ref()).0
+        };
+        assert_eq!(
+            verdict(&repo(&base, &["mlx"]), None),
+            CompatibilityVerdict::Compatible
+        );
+        let mlx_quant = serde_json::json!({ "quantization": { "group_size": 64, "bits": 4 } });
+        assert_eq!(
+            verdict(&repo(&base, &[]), Some(mlx_quant)),
+            CompatibilityVerdict::Compatible
+        );
+        let gptq = serde_json::json!({ "quantization_config": { "quant_method": "gptq" } });
+        assert_eq!(
+            verdict(&repo(&base, &[]), Some(gptq)),
+            CompatibilityVerdict::Incompatible
+        );
+        assert_eq!(
+            verdict(&repo(&base, &[]), None),
+            CompatibilityVerdict::Unknown
+        );
+        assert_eq!(
+            verdict(
+                &repo(
+                    &["config.json", "tokenizer.json", "model-Q4_K_M.gguf"],
+                    &["mlx"]
+                ),
+                None
+            ),
+            CompatibilityVerdict::Incompatible
+        );
+        assert_eq!(
+            verdict(
+                &repo(&["tokenizer.json", "model.safetensors"], &["mlx"]),
+                None
+            ),
+            CompatibilityVerdict::Incompatible
+        );
+    }
+
+    #[test]
+    fn accepts_only_owner_and_name_repo_ids() {
+        assert!(validate_repo_id("mlx-community/Qwen3-4B-4bit").is_ok());
+        for bad in ["Qwen3", "a/b/c", "../etc", "a/..", "owner/na me", "/name"] {
+            assert!(validate_repo_id(bad).is_err(), "{bad} should be rejected");
+        }
+    }
+
+    #[test]
+    fn reads_file_sizes_from_the_blob_listing() {
+        let info: RepoInfo = serde_json::from_value(serde_json::json!({
+            "siblings": [
+                { "rfilename": "config.json", "size": 1024 },
+                { "rfilename": "model.safetensors", "size": 2_000_000_000u64 },
+                { "rfilename": "README.md" }
+            ]
+        }))
+        .unwrap();
+        let sizes: Vec<Option<u64>> = info.siblings.iter().map(|f| f.size).collect();
+        assert_eq!(sizes, vec![Some(1024), Some(2_000_000_000), None]);
+    }
+
+    #[test]
+    fn cached_cards_expire_after_the_ttl() {
+        let card = ModelCard {
+            repo_id: "test/card".to_string(),
+            readme: Some("# Card".to_string()),
+            config: None,
+            files: Vec::new(),
+            total_size: 0,
+        };
+        let fetched = Instant::now();
+        CARD_CACHE
+            .lock()
+            .unwrap()
+            .insert(card.repo_id.clone(), (fetched, card.clone()));
+        assert_eq!(cached("test/card", fetched), Some(card));
+        assert_eq!(cached("test/card", fetched + MODEL_CARD_TTL), None);
+    }
+}
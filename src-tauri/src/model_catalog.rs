@@ -0,0 +1,85 @@
+//! Curated MLX models offered in the model picker, so users don't need to know exact
+//! Hugging Face repo ids. Sizes are approximate download sizes.
+
+use serde::Serialize;
+
+use crate::model_store::is_model_cached;
+
+/// A catalog entry, with whether it is already in the local Hugging Face cache.
+#[derive(Debug, Clone, Serialize)]
+pub struct RecommendedModel {
+    pub repo_id: &'static str,
+    pub display_name: &'static str,
+    pub approx_size_bytes: u64,
+    pub context_length: u32,
+    pub cached: bool,
+}
+
+const GB: u64 = 1_000_000_000;
+
+/// `(repo_id, display_name, approx_size_bytes, context_length)`, smallest first.
+const CATALOG: &[(&str, &str, u64, u32)] = &[
+    (
+        "mlx-community/Llama-3.2-3B-Instruct-4bit",
+        "Llama 3.2 3B Instruct (4-bit)",
+        2 * GB,
+        131_072,
+    ),
+    (
+        "mlx-community/Qwen3-4B-4bit",
+        "Qwen3 4B (4-bit)",
+        23 * GB / 10,
+        40_960,
+    ),
+    (
+        "mlx-community/Qwen3-8B-4bit",
+        "Qwen3 8B (4-bit)",
+        46 * GB / 10,
+        40_960,
+    ),
+    (
+        "mlx-community/Qwen3-14B-4bit",
+        "Qwen3 14B (4-bit)",
+        83 * GB / 10,
+        40_960,
+    ),
+    (
+        "lmstudio-community/Qwen3-30B-A3B-Instruct-2507-MLX-4bit",
+        "Qwen3 30B A3B Instruct 2507 (4-bit)",
+        172 * GB / 10,
+        262_144,
+    ),
+];
+
+/// Returns the curated models, marking those already downloaded. Touches the
+/// filesystem, so call it from a blocking context.
+pub fn list_recommended_models() -> Vec<RecommendedModel> {
+    CATALOG
+        .iter()
+        .map(
+            |&(repo_id, display_name, approx_size_bytes, context_length)| RecommendedModel {
+                repo_id,
+                display_name,
+                approx_size_bytes,
+                context_length,
+                cached: is_model_cached(repo_id),
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CATALOG;
+    use std::collections::HashSet;
+
+    #[test]
+    fn catalog_repo_ids_are_unique_and_well_formed() {
+        let mut seen = HashSet::new();
+        for (repo_id, _, size, context) in CATALOG {
+            assert!(seen.insert(*repo_id), "duplicate {repo_id}");
+            assert_eq!(repo_id.split('/').count(), 2, "bad repo id {repo_id}");
+            assert!(*size > 0 && *context > 0);
+        }
+    }
+}
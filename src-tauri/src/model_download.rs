@@ -1,13 +1,22 @@
-use crate::model_store::{is_model_cached, model_cache_dir, model_downloading_dir};
+use crate::http_client::shared_client;
+use crate::model_store::{
+    is_flat_model_dir, is_model_cached, model_cache_dir, model_downloading_dir,
+};
+use crate::settings::{self, Setting};
+use crate::telemetry::{self, TelemetryEvent};
 use hf_download::{DownloadConfig, HfDownloader, ProgressEvent, RepoType};
 use log::{debug, error, info, warn};
 use serde::Serialize;
+use std::collections::BTreeMap;
 use std::path::Path;
 use std::sync::{
     atomic::{AtomicU64, Ordering},
-    Arc,
+    Arc, Mutex,
 };
-use tauri::{AppHandle, Emitter};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
 
 #[derive(Debug, Serialize, Clone)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -27,6 +36,8 @@ pub enum DownloadProgressPayload {
         path: String,
         bytes: u64,
         progress_percent: u8,
+        /// Measured transfer rate, after any bandwidth cap.
+        bytes_per_sec: u64,
     },
     FileCompleted {
         repo_id: String,
@@ -42,11 +53,616 @@ pub enum DownloadProgressPayload {
         files_downloaded: usize,
         bytes_downloaded: u64,
     },
+    /// The repo id does not exist on Hugging Face.
+    RepoNotFound {
+        repo_id: String,
+    },
+    /// The repo exists but is private or gated for the current credentials.
+    AccessDenied {
+        repo_id: String,
+        status: u16,
+    },
+    /// The download was aborted and its partial files removed.
+    Cancelled {
+        repo_id: String,
+    },
+    /// The download was stopped with its partial files kept for `resume_download`.
+    Paused {
+        repo_id: String,
+    },
+    /// A paused download was restarted.
+    Resumed {
+        repo_id: String,
+    },
+}
+
+/// Successful result of `ensure_hf_model_cached`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadOutcome {
+    /// The model was already in the cache.
+    Cached,
+    /// The model was downloaded by this call.
+    Downloaded,
+    /// Another call is already downloading the model; its progress events cover
+    /// this request too.
+    AlreadyDownloading,
+}
+
+/// Error returned by `ensure_hf_model_cached` when the download was aborted.
+pub const DOWNLOAD_CANCELLED: &str = "download cancelled";
+
+/// Error returned by `ensure_hf_model_cached` when the download was paused.
+pub const DOWNLOAD_PAUSED: &str = "download paused";
+
+/// Default Hugging Face endpoint; overridable with `HF_ENDPOINT` like the HF tooling.
+const HF_DEFAULT_ENDPOINT: &str = "https://huggingface.co";
+
+/// Outcome of the pre-download repo lookup.
+#[derive(Debug, PartialEq)]
+enum RepoLookup {
+    Found,
+    NotFound,
+    AccessDenied(u16),
+    /// Any other status; the download itself will report the problem.
+    Unknown(u16),
+}
+
+/// Hugging Face answers unauthenticated requests for missing repos with 401, so the
+/// `X-Error-Code` header is what separates "does not exist" from "not allowed".
+fn classify_repo_lookup(status: u16, error_code: Option<&str>) -> RepoLookup {
+    match (status, error_code) {
+        (200..=299, _) => RepoLookup::Found,
+        (_, Some("RepoNotFound")) | (404, _) => RepoLookup::NotFound,
+        (_, Some("GatedRepo")) | (401 | 403, _) => RepoLookup::AccessDenied(status),
+        _ => RepoLookup::Unknown(status),
+    }
+}
+
+/// Hugging Face endpoint honouring `HF_ENDPOINT`, without a trailing slash.
+pub fn hf_endpoint() -> String {
+    let endpoint = std::env::var("HF_ENDPOINT").unwrap_or_else(|_| HF_DEFAULT_ENDPOINT.into());
+    endpoint.trim_end_matches('/').to_string()
+}
+
+/// Queries the Hugging Face model API for `repo_id`. Network failures are treated as
+/// unknown so an offline lookup never blocks a download that might still resume.
+async fn lookup_repo(repo_id: &str) -> RepoLookup {
+    let url = format!("{}/api/models/{repo_id}", hf_endpoint());
+    let mut request = shared_client()
+        .get(&url)
+        .timeout(std::time::Duration::from_secs(10));
+    if let Ok(token) = std::env::var("HF_TOKEN") {
+        request = request.bearer_auth(token);
+    }
+    match request.send().await {
+        Ok(resp) => {
+            let error_code = resp
+                .headers()
+                .get("x-error-code")
+                .and_then(|v| v.to_str().ok());
+            classify_repo_lookup(resp.status().as_u16(), error_code)
+        }
+        Err(e) => {
+            warn!("lookup_repo[{repo_id}]: request failed - {e}");
+            RepoLookup::Unknown(0)
+        }
+    }
+}
+
+/// Download bandwidth cap in bytes per second; 0 means unlimited. Read on every
+/// transfer so a change applies to downloads already in progress.
+static BANDWIDTH_LIMIT: AtomicU64 = AtomicU64::new(0);
+
+/// Sets the download bandwidth cap (`None` removes it).
+pub fn set_bandwidth_limit(bytes_per_sec: Option<u64>) {
+    BANDWIDTH_LIMIT.store(bytes_per_sec.unwrap_or(0), Ordering::Relaxed);
+}
+
+/// Validates a stored or requested bandwidth cap.
+pub fn validate_bandwidth_limit(bytes_per_sec: i64) -> Result<u64, String> {
+    u64::try_from(bytes_per_sec)
+        .ok()
+        .filter(|v| *v > 0)
+        .ok_or_else(|| format!("bandwidth limit must be a positive number of bytes per second, got {bytes_per_sec}"))
+}
+
+/// Loads the persisted bandwidth cap, keeping the current one if settings can't be read.
+async fn load_bandwidth_limit(app: &AppHandle) {
+    let Some(pool) = app.try_state::<sqlx::SqlitePool>() else {
+        return;
+    };
+    match settings::get_setting::<i64>(&pool, Setting::DownloadMaxBytesPerSec).await {
+        Ok(value) => set_bandwidth_limit(value.and_then(|v| validate_bandwidth_limit(v).ok())),
+        Err(e) => debug!("load_bandwidth_limit: settings unavailable - {e}"),
+    }
+}
+
+/// Rate measurement window; the reported rate is that of the last full window.
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Measures transfer rate over fixed windows and computes the pause needed to stay
+/// under a bandwidth cap.
+struct TransferRate {
+    window_start: Instant,
+    window_bytes: u64,
+    last_rate: Option<u64>,
+}
+
+impl TransferRate {
+    fn new(now: Instant) -> Self {
+        Self {
+            window_start: now,
+            window_bytes: 0,
+            last_rate: None,
+        }
+    }
+
+    /// Records `bytes` transferred at `now`. Returns how long to pause so the window
+    /// stays under `limit` bytes/sec (0 = unlimited), and the rate to report.
+    fn record(&mut self, bytes: u64, now: Instant, limit: u64) -> (Duration, u64) {
+        let mut elapsed = now.saturating_duration_since(self.window_start);
+        if elapsed >= RATE_WINDOW {
+            self.last_rate = Some((self.window_bytes as f64 / elapsed.as_secs_f64()) as u64);
+            self.window_start = now;
+            self.window_bytes = 0;
+            elapsed = Duration::ZERO;
+        }
+        self.window_bytes += bytes;
+
+        let delay = if limit > 0 {
+            Duration::from_secs_f64(self.window_bytes as f64 / limit as f64).saturating_sub(elapsed)
+        } else {
+            Duration::ZERO
+        };
+        let rate = self.last_rate.unwrap_or_else(|| {
+            let span = (elapsed + delay).max(Duration::from_millis(100));
+            (self.window_bytes as f64 / span.as_secs_f64()) as u64
+        });
+        (delay, rate)
+    }
+}
+
+/// Pause the bandwidth limit calls for, handed from the progress callback to the task
+/// driving the download, which sleeps it off between polls of the download.
+#[derive(Default)]
+struct OwedDelay {
+    delay: Mutex<Duration>,
+    notify: tokio::sync::Notify,
+}
+
+impl OwedDelay {
+    /// Records a pause computed by `TransferRate::record`. Later pauses in the same
+    /// window already include earlier ones, so the longest wins.
+    fn owe(&self, delay: Duration) {
+        if let Ok(mut owed) = self.delay.lock() {
+            *owed = (*owed).max(delay);
+        }
+        self.notify.notify_one();
+    }
+
+    fn take(&self) -> Duration {
+        self.delay
+            .lock()
+            .map(|mut owed| std::mem::take(&mut *owed))
+            .unwrap_or_default()
+    }
+}
+
+/// Minimum spacing of `BytesTransferred` events for one download (~10/sec).
+const PROGRESS_EMIT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Byte progress to report in one `BytesTransferred` event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CoalescedProgress {
+    bytes: u64,
+    progress_percent: u8,
+    bytes_per_sec: u64,
+}
+
+/// Coalesces per-chunk progress into at most one event per `PROGRESS_EMIT_INTERVAL`.
+/// Bytes from suppressed chunks carry over, so the emitted byte counts always sum to
+/// what was transferred once `flush` has been called.
+struct ProgressThrottle {
+    last_emit: Option<Instant>,
+    pending: Option<CoalescedProgress>,
+}
+
+impl ProgressThrottle {
+    fn new() -> Self {
+        Self {
+            last_emit: None,
+            pending: None,
+        }
+    }
+
+    /// Adds a chunk; returns the coalesced progress when an event is due.
+    fn record(&mut self, chunk: CoalescedProgress, now: Instant) -> Option<CoalescedProgress> {
+        let pending = self
+            .pending
+            .get_or_insert(CoalescedProgress { bytes: 0, ..chunk });
+        pending.bytes += chunk.bytes;
+        pending.progress_percent = chunk.progress_percent;
+        pending.bytes_per_sec = chunk.bytes_per_sec;
+        let due = self
+            .last_emit
+            .is_none_or(|last| now.saturating_duration_since(last) >= PROGRESS_EMIT_INTERVAL);
+        if !due {
+            return None;
+        }
+        self.last_emit = Some(now);
+        self.pending.take()
+    }
+
+    /// Returns progress not yet emitted, e.g. when a file finishes.
+    fn flush(&mut self) -> Option<CoalescedProgress> {
+        self.pending.take()
+    }
+}
+
+/// Stage of a download as reported by `download_status`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadPhase {
+    /// Checking the repo and preparing the `.downloading` directory.
+    Preparing,
+    Downloading,
+    /// Moving the finished download into the cache.
+    Finalizing,
+    /// Stopped by `pause_download`; partial files are kept.
+    Paused,
+}
+
+/// Snapshot of one active or paused download.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct DownloadStatus {
+    pub repo_id: String,
+    pub phase: DownloadPhase,
+    pub bytes_downloaded: u64,
+    /// Unknown until the repo's file list has been fetched.
+    pub total_bytes: Option<u64>,
+}
+
+/// Cancellation token, progress counters and completion signal shared between a
+/// download, whoever stops it, and status queries.
+struct DownloadControl {
+    token: CancellationToken,
+    /// Why `token` was cancelled; the first request wins.
+    stop_reason: Mutex<Option<StopRequest>>,
+    total_bytes: AtomicU64,
+    downloaded_bytes: AtomicU64,
+    phase: Mutex<DownloadPhase>,
+    finished: watch::Sender<bool>,
+}
+
+impl DownloadControl {
+    fn new() -> Self {
+        Self {
+            token: CancellationToken::new(),
+            stop_reason: Mutex::new(None),
+            total_bytes: AtomicU64::new(0),
+            downloaded_bytes: AtomicU64::new(0),
+            phase: Mutex::new(DownloadPhase::Preparing),
+            finished: watch::Sender::new(false),
+        }
+    }
+
+    fn set_phase(&self, phase: DownloadPhase) {
+        if let Ok(mut current) = self.phase.lock() {
+            *current = phase;
+        }
+    }
+
+    fn status(&self, repo_id: &str) -> DownloadStatus {
+        let total = self.total_bytes.load(Ordering::Relaxed);
+        DownloadStatus {
+            repo_id: repo_id.to_string(),
+            phase: self
+                .phase
+                .lock()
+                .map(|p| *p)
+                .unwrap_or(DownloadPhase::Downloading),
+            bytes_downloaded: self.downloaded_bytes.load(Ordering::Relaxed),
+            total_bytes: (total > 0).then_some(total),
+        }
+    }
+
+    fn stop(&self, request: StopRequest) {
+        if let Ok(mut reason) = self.stop_reason.lock() {
+            reason.get_or_insert(request);
+        }
+        self.token.cancel();
+    }
+
+    fn stopped_by(&self, request: StopRequest) -> bool {
+        self.token.is_cancelled()
+            && self
+                .stop_reason
+                .lock()
+                .is_ok_and(|reason| *reason == Some(request))
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.stopped_by(StopRequest::Cancel)
+    }
+
+    fn is_paused(&self) -> bool {
+        self.stopped_by(StopRequest::Pause)
+    }
+
+    /// The error a stopped download returns, or `None` if it was not stopped.
+    fn stop_error(&self) -> Option<String> {
+        if self.is_cancelled() {
+            Some(DOWNLOAD_CANCELLED.to_string())
+        } else if self.is_paused() {
+            Some(DOWNLOAD_PAUSED.to_string())
+        } else {
+            None
+        }
+    }
+}
+
+/// How a download is stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StopRequest {
+    Cancel,
+    Pause,
+}
+
+/// App-wide record of in-flight downloads (by repo id) and paused ones awaiting
+/// `resume_download`. Every download-control command goes through it.
+#[derive(Default)]
+pub struct DownloadRegistry {
+    active: Mutex<BTreeMap<String, Arc<DownloadControl>>>,
+    /// Paused downloads with their progress when paused.
+    paused: Mutex<BTreeMap<String, DownloadStatus>>,
+}
+
+impl DownloadRegistry {
+    /// Registers `repo_id` as downloading, or returns `None` if it already is. Checking
+    /// and inserting under one lock keeps two callers from both writing into the same
+    /// `.downloading` directory.
+    fn register(self: &Arc<Self>, repo_id: &str) -> Option<DownloadRegistration> {
+        let control = Arc::new(DownloadControl::new());
+        let mut active = self.active.lock().ok()?;
+        if active.contains_key(repo_id) {
+            return None;
+        }
+        active.insert(repo_id.to_string(), control.clone());
+        Some(DownloadRegistration {
+            registry: self.clone(),
+            repo_id: repo_id.to_string(),
+            control,
+        })
+    }
+
+    /// Returns the repo ids currently being downloaded, sorted.
+    pub fn active(&self) -> Vec<String> {
+        self.active
+            .lock()
+            .map(|active| active.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns active downloads followed by paused ones, each sorted by repo id, so a
+    /// freshly mounted UI can show progress without having seen earlier events.
+    pub fn status(&self) -> Vec<DownloadStatus> {
+        let mut status: Vec<DownloadStatus> = self
+            .active
+            .lock()
+            .map(|active| {
+                active
+                    .iter()
+                    .map(|(repo_id, control)| control.status(repo_id))
+                    .collect()
+            })
+            .unwrap_or_default();
+        if let Ok(paused) = self.paused.lock() {
+            status.extend(paused.values().cloned());
+        }
+        status
+    }
+
+    /// Stops the active download of `repo_id` and waits until its task has finished.
+    /// Returns the stopped download's control, or `None` if none was active.
+    async fn stop(&self, repo_id: &str, request: StopRequest) -> Option<Arc<DownloadControl>> {
+        let control = self
+            .active
+            .lock()
+            .ok()
+            .and_then(|active| active.get(repo_id).cloned())?;
+        control.stop(request);
+        let mut finished = control.finished.subscribe();
+        let _ = finished.wait_for(|done| *done).await;
+        Some(control)
+    }
+
+    /// Cancels the active download of `repo_id`; returns whether one was active.
+    async fn cancel(&self, repo_id: &str) -> bool {
+        self.stop(repo_id, StopRequest::Cancel).await.is_some()
+    }
+
+    /// Pauses the active download of `repo_id` and records it as resumable; returns
+    /// whether one was active.
+    async fn pause(&self, repo_id: &str) -> bool {
+        let Some(control) = self.stop(repo_id, StopRequest::Pause).await else {
+            return false;
+        };
+        if let Ok(mut paused) = self.paused.lock() {
+            let status = DownloadStatus {
+                phase: DownloadPhase::Paused,
+                ..control.status(repo_id)
+            };
+            paused.insert(repo_id.to_string(), status);
+        }
+        true
+    }
+
+    /// Removes `repo_id` from the paused set; returns whether it was there.
+    fn take_paused(&self, repo_id: &str) -> bool {
+        self.paused
+            .lock()
+            .map(|mut paused| paused.remove(repo_id).is_some())
+            .unwrap_or(false)
+    }
+}
+
+/// Marks a repo as downloading for as long as it is alive; dropping it deregisters
+/// the download and wakes anyone waiting for it to stop.
+struct DownloadRegistration {
+    registry: Arc<DownloadRegistry>,
+    repo_id: String,
+    control: Arc<DownloadControl>,
+}
+
+impl Drop for DownloadRegistration {
+    fn drop(&mut self) {
+        if let Ok(mut active) = self.registry.active.lock() {
+            if active
+                .get(&self.repo_id)
+                .is_some_and(|c| Arc::ptr_eq(c, &self.control))
+            {
+                active.remove(&self.repo_id);
+            }
+        }
+        self.control.finished.send_replace(true);
+    }
+}
+
+fn registry(app: &AppHandle) -> Arc<DownloadRegistry> {
+    app.state::<Arc<DownloadRegistry>>().inner().clone()
+}
+
+/// Stops the active download of `repo_id` but keeps its `.downloading` directory so
+/// `resume_download` can continue from the files already fetched. Returns whether a
+/// download was active.
+pub async fn pause_download(app: &AppHandle, repo_id: &str) -> Result<bool, String> {
+    if !registry(app).pause(repo_id).await {
+        return Ok(false);
+    }
+    info!("pause_download[{repo_id}]: paused");
+    let _ = app.emit(
+        "mlc-download-progress",
+        DownloadProgressPayload::Paused {
+            repo_id: repo_id.to_string(),
+        },
+    );
+    Ok(true)
+}
+
+/// Restarts a paused download and waits for it like `ensure_hf_model_cached`. Only one
+/// caller can claim a paused download, so concurrent resumes never start it twice.
+pub async fn resume_download(app: &AppHandle, repo_id: &str) -> Result<DownloadOutcome, String> {
+    let registry = registry(app);
+    if registry.active().iter().any(|id| id == repo_id) {
+        return Err(format!("{repo_id} is already downloading"));
+    }
+    if !registry.take_paused(repo_id) {
+        return Err(format!("no paused download for {repo_id}"));
+    }
+    info!("resume_download[{repo_id}]: resuming");
+    let _ = app.emit(
+        "mlc-download-progress",
+        DownloadProgressPayload::Resumed {
+            repo_id: repo_id.to_string(),
+        },
+    );
+    ensure_hf_model_cached(app, repo_id).await
+}
+
+/// Cancels the download of `repo_id` (active or paused) and waits for it to stop,
+/// keeping its partial files for a later download to reuse. Emits a `Cancelled`
+/// progress event and returns whether anything was cancelled.
+pub async fn cancel_download(app: &AppHandle, repo_id: &str) -> Result<bool, String> {
+    let registry = registry(app);
+    let was_active = registry.cancel(repo_id).await;
+    let was_paused = registry.take_paused(repo_id);
+    if !was_active && !was_paused {
+        return Ok(false);
+    }
+    info!("cancel_download[{repo_id}]: cancelled");
+    let _ = app.emit(
+        "mlc-download-progress",
+        DownloadProgressPayload::Cancelled {
+            repo_id: repo_id.to_string(),
+        },
+    );
+    Ok(true)
+}
+
+/// Aborts the download of `repo_id`, waits for it to stop and removes its
+/// `.downloading` directory, then emits a `Cancelled` progress event. A no-op returning
+/// `false` when nothing is downloading and no partial files exist.
+pub async fn abort_and_remove_download(app: &AppHandle, repo_id: &str) -> Result<bool, String> {
+    let registry = registry(app);
+    let was_active = registry.cancel(repo_id).await;
+    let was_paused = registry.take_paused(repo_id);
+
+    let downloading_dir = model_downloading_dir(repo_id);
+    let had_partial = downloading_dir.exists();
+    if had_partial {
+        info!("abort_download[{repo_id}]: removing {:?}", downloading_dir);
+        tauri::async_runtime::spawn_blocking(move || std::fs::remove_dir_all(&downloading_dir))
+            .await
+            .map_err(|e| format!("join error: {e}"))?
+            .map_err(|e| format!("failed to remove partial download: {e}"))?;
+    }
+
+    if !was_active && !was_paused && !had_partial {
+        return Ok(false);
+    }
+    let _ = app.emit(
+        "mlc-download-progress",
+        DownloadProgressPayload::Cancelled {
+            repo_id: repo_id.to_string(),
+        },
+    );
+    Ok(true)
+}
+
+/// Re-downloads the files a cached model is missing. The cache directory is moved back
+/// to `.downloading` so the download reuses the files already there, then promoted
+/// again as usual. Emits the regular `mlc-download-progress` events. Only flat caches
+/// written by the app are repaired; a Hugging Face CLI cache is never moved.
+pub async fn repair_cached_model(app: &AppHandle, repo_id: &str) -> Result<(), String> {
+    if registry(app)
+        .active()
+        .iter()
+        .any(|active| active == repo_id)
+    {
+        return Err(format!("{repo_id} is already downloading"));
+    }
+    let final_dir = model_cache_dir(repo_id);
+    if !is_flat_model_dir(&final_dir) {
+        return Err(format!(
+            "{repo_id} uses the Hugging Face CLI cache layout; repair it with `huggingface-cli download {repo_id}`"
+        ));
+    }
+    let downloading_dir = model_downloading_dir(repo_id);
+    info!("repair_cached_model[{repo_id}]: moving {final_dir:?} to {downloading_dir:?}");
+    tauri::async_runtime::spawn_blocking(move || {
+        if downloading_dir.exists() {
+            std::fs::remove_dir_all(&downloading_dir)?;
+        }
+        std::fs::rename(&final_dir, &downloading_dir)
+    })
+    .await
+    .map_err(|e| format!("join error: {e}"))?
+    .map_err(|e| format!("failed to prepare {repo_id} for repair: {e}"))?;
+
+    match ensure_hf_model_cached(app, repo_id).await? {
+        DownloadOutcome::AlreadyDownloading => Err(format!("{repo_id} is already downloading")),
+        _ => Ok(()),
+    }
 }
 
 /// Ensure the Hugging Face model is present in the MLC cache directory; if not, download it.
 /// Emits `mlc-download-progress` events with a tagged JSON payload for UI progress.
-pub async fn ensure_hf_model_cached(app: &AppHandle, repo_id: &str) -> Result<(), String> {
+/// A repo is only downloaded by one caller at a time; others return `AlreadyDownloading`.
+pub async fn ensure_hf_model_cached(
+    app: &AppHandle,
+    repo_id: &str,
+) -> Result<DownloadOutcome, String> {
     let final_dir = model_cache_dir(repo_id);
     let downloading_dir = model_downloading_dir(repo_id);
     info!(
@@ -68,7 +684,98 @@ pub async fn ensure_hf_model_cached(app: &AppHandle, repo_id: &str) -> Result<()
             }
         }
         info!("ensure_hf_model_cached: model already cached for {repo_id}");
-        return Ok(());
+        return Ok(DownloadOutcome::Cached);
+    }
+
+    let Some(active) = registry(app).register(repo_id) else {
+        info!("ensure_hf_model_cached: {repo_id} is already downloading");
+        return Ok(DownloadOutcome::AlreadyDownloading);
+    };
+    let started = Instant::now();
+    telemetry::record(
+        app,
+        TelemetryEvent::DownloadStarted {
+            repo_id: repo_id.to_string(),
+        },
+    );
+    let result = download_uncached_model(app, active, &final_dir, &downloading_dir).await;
+    let repo_id = repo_id.to_string();
+    let duration_ms = started.elapsed().as_millis() as u64;
+    telemetry::record(
+        app,
+        match &result {
+            Ok(()) => TelemetryEvent::DownloadCompleted {
+                repo_id,
+                duration_ms,
+            },
+            Err(e) if e == DOWNLOAD_CANCELLED => TelemetryEvent::DownloadCancelled {
+                repo_id,
+                duration_ms,
+            },
+            Err(e) if e == DOWNLOAD_PAUSED => TelemetryEvent::DownloadPaused {
+                repo_id,
+                duration_ms,
+            },
+            Err(_) => TelemetryEvent::DownloadFailed {
+                repo_id,
+                duration_ms,
+            },
+        },
+    );
+    result.map(|()| DownloadOutcome::Downloaded)
+}
+
+/// Downloads the repo registered by `active` into `downloading_dir` and promotes it to
+/// `final_dir`. The registration is released when this returns.
+async fn download_uncached_model(
+    app: &AppHandle,
+    active: DownloadRegistration,
+    final_dir: &Path,
+    downloading_dir: &Path,
+) -> Result<(), String> {
+    let repo_id = active.repo_id.as_str();
+    // Starting over picks up a paused download's partial files too.
+    active.registry.take_paused(repo_id);
+    load_bandwidth_limit(app).await;
+
+    let lookup = tokio::select! {
+        lookup = lookup_repo(repo_id) => lookup,
+        _ = active.control.token.cancelled() => {
+            return Err(active.control.stop_error().unwrap_or_else(|| DOWNLOAD_CANCELLED.to_string()));
+        }
+    };
+    match lookup {
+        RepoLookup::Found => {}
+        RepoLookup::NotFound => {
+            warn!("ensure_hf_model_cached: repo not found on Hugging Face: {repo_id}");
+            let _ = app.emit(
+                "mlc-download-progress",
+                DownloadProgressPayload::RepoNotFound {
+                    repo_id: repo_id.to_string(),
+                },
+            );
+            return Err(format!(
+                "Model repository not found on Hugging Face: {repo_id}"
+            ));
+        }
+        RepoLookup::AccessDenied(status) => {
+            warn!("ensure_hf_model_cached: access denied for {repo_id} (HTTP {status})");
+            let _ = app.emit(
+                "mlc-download-progress",
+                DownloadProgressPayload::AccessDenied {
+                    repo_id: repo_id.to_string(),
+                    status,
+                },
+            );
+            return Err(format!(
+                "Access to {repo_id} was denied (HTTP {status}); the repository may be private or gated"
+            ));
+        }
+        RepoLookup::Unknown(status) => {
+            debug!(
+                "ensure_hf_model_cached: repo lookup inconclusive for {repo_id} (status {status})"
+            );
+        }
     }
 
     let cfg = DownloadConfig::default();
@@ -80,34 +787,59 @@ pub async fn ensure_hf_model_cached(app: &AppHandle, repo_id: &str) -> Result<()
             .map_err(|e| format!("failed to create cache parent dir: {e}"))?;
     }
     // Ensure downloading directory exists (resume-friendly)
-    std::fs::create_dir_all(&downloading_dir)
+    std::fs::create_dir_all(downloading_dir)
         .map_err(|e| format!("failed to create downloading dir: {e}"))?;
 
-    // hf_download currently provides blocking and async; use blocking in a blocking task to avoid holding the async runtime.
+    // Download and promote on a blocking thread; promotion moves directories with
+    // blocking filesystem calls.
     let app_clone = app.clone();
     let repo_id_owned = repo_id.to_string();
     let repo_id_for_completed = repo_id_owned.clone();
     let repo_id_for_download = repo_id.to_string();
-    let downloading_owned = downloading_dir.clone();
-    let final_owned = final_dir.clone();
-    tauri::async_runtime::spawn_blocking(move || {
+    let downloading_owned = downloading_dir.to_path_buf();
+    let final_owned = final_dir.to_path_buf();
+    let control = active.control.clone();
+    let outcome = tauri::async_runtime::spawn_blocking(move || {
         let progress_app = app_clone.clone();
 
-        // Shared counters to compute and log percentage progress without excessive spam
-        let total_bytes_to_download = Arc::new(AtomicU64::new(0));
-        let downloaded_bytes = Arc::new(AtomicU64::new(0));
+        // Counters live on the download's control so `download_status` sees them too;
+        // the last logged percent keeps progress logging from spamming.
+        let counters_cb = control.clone();
         let last_logged_percent = Arc::new(AtomicU64::new(0));
 
-        let total_bytes_to_download_cb = total_bytes_to_download.clone();
-        let downloaded_bytes_cb = downloaded_bytes.clone();
         let last_logged_percent_cb = last_logged_percent.clone();
+        let transfer_rate = Mutex::new(TransferRate::new(Instant::now()));
+        let owed_delay = Arc::new(OwedDelay::default());
+        let owed_delay_cb = owed_delay.clone();
+        let progress_throttle = Arc::new(Mutex::new(ProgressThrottle::new()));
+        let emit_bytes_app = progress_app.clone();
+        let emit_bytes_repo_id = repo_id_owned.clone();
+        let emit_bytes = move |path: String, progress: CoalescedProgress| {
+            let _ = emit_bytes_app.emit(
+                "mlc-download-progress",
+                DownloadProgressPayload::BytesTransferred {
+                    repo_id: emit_bytes_repo_id.clone(),
+                    path,
+                    bytes: progress.bytes,
+                    progress_percent: progress.progress_percent,
+                    bytes_per_sec: progress.bytes_per_sec,
+                },
+            );
+        };
+        let flush_throttle = progress_throttle.clone();
+        let flush_emit = emit_bytes.clone();
+        let flush_progress = move |path: &str| {
+            if let Some(progress) = flush_throttle.lock().ok().and_then(|mut t| t.flush()) {
+                flush_emit(path.to_string(), progress);
+            }
+        };
 
-        let progress = move |evt: ProgressEvent| match evt {
+        let report = move |evt: ProgressEvent| match evt {
             ProgressEvent::RepoDiscovered {
                 num_files,
                 total_bytes,
             } => {
-                total_bytes_to_download_cb.store(total_bytes, Ordering::Relaxed);
+                counters_cb.total_bytes.store(total_bytes, Ordering::Relaxed);
                 info!(
                     "download[{repo_id_owned}]: discovered repo - files={num_files} total_bytes={total_bytes}"
                 );
@@ -121,9 +853,9 @@ pub async fn ensure_hf_model_cached(app: &AppHandle, repo_id: &str) -> Result<()
                 );
             }
             ProgressEvent::BytesTransferred { path, bytes } => {
-                let total = total_bytes_to_download_cb.load(Ordering::Relaxed);
+                let total = counters_cb.total_bytes.load(Ordering::Relaxed);
+                let current = counters_cb.downloaded_bytes.fetch_add(bytes as u64, Ordering::Relaxed) + bytes as u64;
                 let progress_percent = if total > 0 {
-                    let current = downloaded_bytes_cb.fetch_add(bytes as u64, Ordering::Relaxed) + bytes as u64;
                     let percent = (((current as f64) / (total as f64)) * 100.0).floor() as u64;
                     let last = last_logged_percent_cb.load(Ordering::Relaxed);
                     if percent > last {
@@ -136,18 +868,31 @@ pub async fn ensure_hf_model_cached(app: &AppHandle, repo_id: &str) -> Result<()
                 } else {
                     0
                 };
-                let _ = progress_app.emit(
-                    "mlc-download-progress",
-                    DownloadProgressPayload::BytesTransferred {
-                        repo_id: repo_id_owned.clone(),
-                        path,
-                        bytes: bytes as u64,
-                        progress_percent,
-                    },
-                );
+                let limit = BANDWIDTH_LIMIT.load(Ordering::Relaxed);
+                let (delay, bytes_per_sec) = transfer_rate
+                    .lock()
+                    .map(|mut rate| rate.record(bytes as u64, Instant::now(), limit))
+                    .unwrap_or_default();
+                let chunk = CoalescedProgress {
+                    bytes: bytes as u64,
+                    progress_percent,
+                    bytes_per_sec,
+                };
+                let due = progress_throttle
+                    .lock()
+                    .ok()
+                    .and_then(|mut t| t.record(chunk, Instant::now()));
+                if let Some(progress) = due {
+                    emit_bytes(path, progress);
+                }
+                // Not polling the download while the delay is served backpressures it.
+                if !delay.is_zero() {
+                    owed_delay_cb.owe(delay);
+                }
             }
             ProgressEvent::FileCompleted { path } => {
                 debug!("download[{repo_id_owned}]: file completed - {path}");
+                flush_progress(&path);
                 let _ = progress_app.emit(
                     "mlc-download-progress",
                     DownloadProgressPayload::FileCompleted {
@@ -158,6 +903,7 @@ pub async fn ensure_hf_model_cached(app: &AppHandle, repo_id: &str) -> Result<()
             }
             ProgressEvent::FileFailed { path, error } => {
                 warn!("download[{repo_id_owned}]: file failed - {path} - {error}");
+                flush_progress(&path);
                 let _ = progress_app.emit(
                     "mlc-download-progress",
                     DownloadProgressPayload::FileFailed {
@@ -180,22 +926,54 @@ pub async fn ensure_hf_model_cached(app: &AppHandle, repo_id: &str) -> Result<()
             }
         };
 
-        info!("download[{repo_id_for_download}]: starting blocking download into {:?}", downloading_owned);
-
-        let summary = match downloader.blocking_download_repo(
-            &repo_id_for_download,
-            RepoType::Model,
-            "main",
-            Path::new(&downloading_owned),
-            progress,
-        ) {
-            Ok(s) => s,
-            Err(e) => {
+        info!("download[{repo_id_for_download}]: starting download into {:?}", downloading_owned);
+        control.set_phase(DownloadPhase::Downloading);
+
+        // Dropping the download future stops it, so cancelling or pausing only has to
+        // win the race against it; partial files stay for a later resume. Bandwidth
+        // pauses race the token too, so a throttled download still stops at once.
+        let token = control.token.clone();
+        let downloaded = tauri::async_runtime::block_on(async {
+            let download = downloader.download_repo(
+                &repo_id_for_download,
+                RepoType::Model,
+                "main",
+                Path::new(&downloading_owned),
+                report,
+            );
+            tokio::pin!(download);
+            loop {
+                tokio::select! {
+                    biased;
+                    _ = token.cancelled() => break None,
+                    _ = owed_delay.notify.notified() => {
+                        tokio::select! {
+                            _ = tokio::time::sleep(owed_delay.take()) => {}
+                            _ = token.cancelled() => break None,
+                        }
+                    }
+                    result = &mut download => break Some(result),
+                }
+            }
+        });
+        let summary = match downloaded {
+            Some(Ok(s)) => s,
+            Some(Err(e)) => {
                 error!("download[{repo_id_for_download}]: error during download - {e}");
                 return Err(format!("download error: {e}"));
             }
+            None => {
+                return Err(control
+                    .stop_error()
+                    .unwrap_or_else(|| DOWNLOAD_CANCELLED.to_string()))
+            }
         };
 
+        if let Some(stopped) = control.stop_error() {
+            return Err(stopped);
+        }
+        control.set_phase(DownloadPhase::Finalizing);
+
         // Atomically promote the downloading dir to the final cache dir.
         // If the final dir already exists (e.g., previous run completed), clean up the downloading dir.
         if final_owned.exists() {
@@ -234,8 +1012,13 @@ pub async fn ensure_hf_model_cached(app: &AppHandle, repo_id: &str) -> Result<()
         );
         Ok::<(), String>(())
     })
-    .await
-    .map_err(|e| {
+    .await;
+
+    if let Some(stopped) = active.control.stop_error() {
+        info!("ensure_hf_model_cached: {stopped} for {repo_id}");
+        return Err(stopped);
+    }
+    outcome.map_err(|e| {
         error!("ensure_hf_model_cached[{repo_id}]: join error - {e}");
         format!("join error: {e}")
     })??;
@@ -243,3 +1026,196 @@ pub async fn ensure_hf_model_cached(app: &AppHandle, repo_id: &str) -> Result<()
     debug!("ensure_hf_model_cached: finished for {repo_id}");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        classify_repo_lookup, CoalescedProgress, DownloadPhase, DownloadRegistry, OwedDelay,
+        ProgressThrottle, RepoLookup, TransferRate, DOWNLOAD_CANCELLED, PROGRESS_EMIT_INTERVAL,
+    };
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn classifies_hugging_face_repo_lookup_responses() {
+        assert_eq!(classify_repo_lookup(200, None), RepoLookup::Found);
+        assert_eq!(classify_repo_lookup(404, None), RepoLookup::NotFound);
+        assert_eq!(
+            classify_repo_lookup(401, Some("RepoNotFound")),
+            RepoLookup::NotFound
+        );
+        assert_eq!(
+            classify_repo_lookup(403, Some("GatedRepo")),
+            RepoLookup::AccessDenied(403)
+        );
+        assert_eq!(
+            classify_repo_lookup(401, None),
+            RepoLookup::AccessDenied(401)
+        );
+        assert_eq!(classify_repo_lookup(503, None), RepoLookup::Unknown(503));
+    }
+
+    /// Stands in for a download task: runs until stopped (returning the stop error)
+    /// or until `complete` fires, then deregisters.
+    fn spawn_download(
+        registry: &Arc<DownloadRegistry>,
+        repo_id: &str,
+        complete: Option<tokio::sync::oneshot::Receiver<()>>,
+    ) -> tokio::task::JoinHandle<Result<(), String>> {
+        let registration = registry.register(repo_id).unwrap();
+        tokio::spawn(async move {
+            registration.control.set_phase(DownloadPhase::Downloading);
+            let finished = async {
+                match complete {
+                    Some(complete) => {
+                        let _ = complete.await;
+                    }
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                _ = registration.control.token.cancelled() => {
+                    Err(registration.control.stop_error().unwrap())
+                }
+                _ = finished => Ok(()),
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn start_then_cancel_stops_and_deregisters_the_download() {
+        let registry = Arc::new(DownloadRegistry::default());
+        let repo_id = "test/cancel";
+        assert!(!registry.cancel(repo_id).await);
+
+        let download = spawn_download(&registry, repo_id, None);
+        assert_eq!(registry.active(), vec![repo_id.to_string()]);
+        assert!(registry.cancel(repo_id).await);
+        assert!(registry.active().is_empty());
+        assert_eq!(download.await.unwrap(), Err(DOWNLOAD_CANCELLED.to_string()));
+        assert!(registry.status().is_empty());
+    }
+
+    #[tokio::test]
+    async fn start_then_complete_deregisters_without_stopping() {
+        let registry = Arc::new(DownloadRegistry::default());
+        let repo_id = "test/complete";
+        let (complete, completed) = tokio::sync::oneshot::channel();
+        let download = spawn_download(&registry, repo_id, Some(completed));
+        assert_eq!(registry.status()[0].repo_id, repo_id);
+
+        complete.send(()).unwrap();
+        assert_eq!(download.await.unwrap(), Ok(()));
+        assert!(registry.active().is_empty());
+        assert!(!registry.cancel(repo_id).await, "nothing left to cancel");
+    }
+
+    #[test]
+    fn download_status_reports_shared_counters() {
+        let registry = Arc::new(DownloadRegistry::default());
+        let repo_id = "test/status";
+        let registration = registry.register(repo_id).unwrap();
+        registration
+            .control
+            .total_bytes
+            .store(100, Ordering::Relaxed);
+        registration
+            .control
+            .downloaded_bytes
+            .store(40, Ordering::Relaxed);
+        registration.control.set_phase(DownloadPhase::Downloading);
+
+        let status = registry.status();
+        assert_eq!(status[0].phase, DownloadPhase::Downloading);
+        assert_eq!(status[0].bytes_downloaded, 40);
+        assert_eq!(status[0].total_bytes, Some(100));
+        drop(registration);
+        assert!(registry.status().is_empty());
+    }
+
+    #[test]
+    fn a_repo_is_registered_as_downloading_once() {
+        let registry = Arc::new(DownloadRegistry::default());
+        let repo_id = "test/dedup";
+        let first = registry.register(repo_id).unwrap();
+        assert!(registry.register(repo_id).is_none());
+        drop(first);
+        assert!(registry.register(repo_id).is_some());
+    }
+
+    #[tokio::test]
+    async fn pausing_stops_without_cancelling() {
+        let registry = Arc::new(DownloadRegistry::default());
+        let repo_id = "test/pause";
+        let download = spawn_download(&registry, repo_id, None);
+
+        assert!(registry.pause(repo_id).await);
+        assert_eq!(
+            download.await.unwrap(),
+            Err(super::DOWNLOAD_PAUSED.to_string())
+        );
+        let status = registry.status();
+        assert_eq!(status.len(), 1);
+        assert_eq!(status[0].phase, DownloadPhase::Paused);
+
+        assert!(registry.take_paused(repo_id));
+        assert!(
+            !registry.take_paused(repo_id),
+            "a paused download is claimed once"
+        );
+    }
+
+    #[test]
+    fn progress_throttle_coalesces_bytes_between_emissions() {
+        let start = Instant::now();
+        let chunk = |bytes, progress_percent| CoalescedProgress {
+            bytes,
+            progress_percent,
+            bytes_per_sec: 0,
+        };
+        let mut throttle = ProgressThrottle::new();
+        assert_eq!(throttle.record(chunk(10, 1), start), Some(chunk(10, 1)));
+        assert_eq!(throttle.record(chunk(20, 2), start), None);
+        assert_eq!(
+            throttle.record(chunk(30, 3), start + PROGRESS_EMIT_INTERVAL / 2),
+            None
+        );
+        assert_eq!(
+            throttle.record(chunk(40, 4), start + PROGRESS_EMIT_INTERVAL),
+            Some(chunk(90, 4))
+        );
+        assert_eq!(
+            throttle.record(chunk(5, 5), start + PROGRESS_EMIT_INTERVAL),
+            None
+        );
+        assert_eq!(throttle.flush(), Some(chunk(5, 5)));
+        assert_eq!(throttle.flush(), None);
+    }
+
+    #[test]
+    fn transfer_rate_paces_to_the_bandwidth_limit() {
+        let start = Instant::now();
+        let mut rate = TransferRate::new(start);
+
+        let (delay, _) = rate.record(500, start, 1_000);
+        assert_eq!(delay, Duration::from_millis(500));
+        let (delay, reported) = rate.record(500, start + Duration::from_millis(500), 1_000);
+        assert_eq!(delay, Duration::from_millis(500));
+        assert_eq!(reported, 1_000);
+
+        // Next window reports the previous window's rate; unlimited never pauses.
+        let (delay, reported) = rate.record(100, start + Duration::from_secs(1), 0);
+        assert_eq!(delay, Duration::ZERO);
+        assert_eq!(reported, 1_000);
+    }
+
+    #[test]
+    fn owed_delay_keeps_the_longest_pause_until_taken() {
+        let owed = OwedDelay::default();
+        owed.owe(Duration::from_millis(300));
+        owed.owe(Duration::from_millis(200));
+        assert_eq!(owed.take(), Duration::from_millis(300));
+        assert_eq!(owed.take(), Duration::ZERO);
+    }
+}
@@ -1,13 +1,309 @@
+use crate::settings::{self, Setting};
 use home::home_dir;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// Hub directory chosen with `move_model_cache`; takes precedence over the environment.
+static HUB_DIR_OVERRIDE: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Prefix of the per-repo model directories in a Hugging Face hub cache.
+const MODEL_DIR_PREFIX: &str = "models--";
+
+/// Which setting decided the hub directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HubDirSource {
+    /// Set by moving the model cache from within OpenChat.
+    Override,
+    /// `HF_HUB_CACHE` names the hub directory itself.
+    HfHubCache,
+    /// `HF_HOME` names the Hugging Face home; the hub is its `hub` subdirectory.
+    HfHome,
+    /// `XDG_CACHE_HOME/huggingface/hub` (macOS and Linux).
+    XdgCacheHome,
+    /// The platform default.
+    Default,
+}
+
+/// The effective Hugging Face hub directory and where it came from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HubDir {
+    pub path: PathBuf,
+    pub source: HubDirSource,
+}
+
+/// Resolves the hub directory. A stored override wins; otherwise this follows
+/// `huggingface_hub`, so models already cached by the CLI or Python libraries are reused:
+/// `HF_HUB_CACHE`, then `HF_HOME/hub`, then `XDG_CACHE_HOME/huggingface/hub`, then the
+/// platform default. Empty values are ignored.
+pub fn hub_dir() -> HubDir {
+    if let Some(path) = hub_dir_override() {
+        return HubDir {
+            path,
+            source: HubDirSource::Override,
+        };
+    }
+    resolve_hub_dir(|key| {
+        std::env::var_os(key)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+    })
+}
+
+fn resolve_hub_dir(env: impl Fn(&str) -> Option<PathBuf>) -> HubDir {
+    if let Some(path) = env("HF_HUB_CACHE") {
+        return HubDir {
+            path,
+            source: HubDirSource::HfHubCache,
+        };
+    }
+    if let Some(home) = env("HF_HOME") {
+        return HubDir {
+            path: home.join("hub"),
+            source: HubDirSource::HfHome,
+        };
+    }
+    if cfg!(any(target_os = "macos", target_os = "linux")) {
+        if let Some(cache) = env("XDG_CACHE_HOME") {
+            return HubDir {
+                path: cache.join("huggingface").join("hub"),
+                source: HubDirSource::XdgCacheHome,
+            };
+        }
+    }
+    HubDir {
+        path: default_hub_base_dir(),
+        source: HubDirSource::Default,
+    }
+}
+
+fn huggingface_hub_base_dir() -> PathBuf {
+    hub_dir().path
+}
+
+fn hub_dir_override() -> Option<PathBuf> {
+    HUB_DIR_OVERRIDE.read().ok().and_then(|dir| dir.clone())
+}
+
+/// Applies the hub directory saved by `move_model_cache`, if any. A missing column
+/// (migrations not applied yet) leaves the environment-derived default in place.
+pub async fn load_hub_dir_override(pool: &sqlx::SqlitePool) {
+    match settings::get_setting::<String>(pool, Setting::ModelCacheDir).await {
+        Ok(dir) => set_hub_dir_override(dir.filter(|d| !d.is_empty()).map(PathBuf::from)),
+        Err(e) => log::debug!("model cache override unavailable - {e}"),
+    }
+}
+
+/// Replaces the in-memory hub directory override; `None` falls back to the environment.
+pub fn set_hub_dir_override(dir: Option<PathBuf>) {
+    if let Ok(mut current) = HUB_DIR_OVERRIDE.write() {
+        *current = dir;
+    }
+}
+
+/// Outcome of moving the model cache.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelCacheMove {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    /// Directory names of the moved models, e.g. `models--org--name`.
+    pub models: Vec<String>,
+    /// Whether any model had to be copied because the target is on another filesystem.
+    pub copied: bool,
+}
+
+/// How one model directory reached the new base, so a failed move can be undone.
+enum MovedModel {
+    Renamed { from: PathBuf, to: PathBuf },
+    Copied { from: PathBuf, to: PathBuf },
+}
+
+/// Moves the models OpenChat manages from `from` to `to`: the flat directories it
+/// downloaded itself, plus the repos in `tracked` (e.g. the configured model). Other
+/// `models--*` directories belong to the shared Hugging Face cache and stay put.
+///
+/// Each model is renamed when both sides share a filesystem, otherwise copied and
+/// compared against the original. Originals of copied models are only deleted once
+/// every model has arrived; any failure before that puts renamed models back and
+/// removes partial copies, leaving `from` as it was.
+pub fn move_model_dirs(
+    from: &Path,
+    to: &Path,
+    tracked: &[String],
+) -> Result<ModelCacheMove, String> {
+    fs::create_dir_all(to).map_err(|e| format!("failed to create {}: {e}", to.display()))?;
+    let tracked: Vec<String> = tracked
+        .iter()
+        .map(|repo_id| model_dir_name(repo_id))
+        .collect();
+    let (names, shared): (Vec<String>, Vec<String>) = model_dir_names(from)?
+        .into_iter()
+        .partition(|name| tracked.contains(name) || is_flat_model_dir(&from.join(name)));
+    if !shared.is_empty() {
+        log::info!("model cache move: leaving shared Hugging Face cache entries {shared:?}");
+    }
+    for name in &names {
+        if to.join(name).exists() {
+            return Err(format!("{} already exists", to.join(name).display()));
+        }
+    }
+
+    let mut moved = Vec::with_capacity(names.len());
+    for name in &names {
+        match move_model_dir(&from.join(name), &to.join(name)) {
+            Ok(model) => moved.push(model),
+            Err(e) => {
+                rollback_moves(moved);
+                return Err(e);
+            }
+        }
+    }
+
+    let mut copied = false;
+    for model in moved {
+        if let MovedModel::Copied { from, .. } = model {
+            copied = true;
+            if let Err(e) = fs::remove_dir_all(&from) {
+                log::warn!("model cache move: failed to remove {}: {e}", from.display());
+            }
+        }
+    }
+    Ok(ModelCacheMove {
+        from: from.to_path_buf(),
+        to: to.to_path_buf(),
+        models: names,
+        copied,
+    })
+}
+
+/// Names of complete model directories under `base`. Fails while a download is still
+/// being written there.
+fn model_dir_names(base: &Path) -> Result<Vec<String>, String> {
+    let entries = match fs::read_dir(base) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("failed to read {}: {e}", base.display())),
+    };
+    let mut names = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !name.starts_with(MODEL_DIR_PREFIX) || !entry.path().is_dir() {
+            continue;
+        }
+        if name.ends_with(".downloading") {
+            return Err(format!(
+                "a download is in progress or was interrupted ({name}); finish or remove it first"
+            ));
+        }
+        names.push(name);
+    }
+    names.sort();
+    Ok(names)
+}
+
+fn move_model_dir(from: &Path, to: &Path) -> Result<MovedModel, String> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(MovedModel::Renamed {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+        });
+    }
+    // Usually a cross-device move; copy and check before anything is deleted.
+    let copy = || -> io::Result<()> {
+        copy_tree(from, to)?;
+        if tree_manifest(from)? != tree_manifest(to)? {
+            return Err(io::Error::other("copied files do not match the originals"));
+        }
+        Ok(())
+    };
+    copy().map_err(|e| {
+        let _ = fs::remove_dir_all(to);
+        format!("failed to copy {} to {}: {e}", from.display(), to.display())
+    })?;
+    Ok(MovedModel::Copied {
+        from: from.to_path_buf(),
+        to: to.to_path_buf(),
+    })
+}
+
+fn rollback_moves(moved: Vec<MovedModel>) {
+    for model in moved.into_iter().rev() {
+        let result = match &model {
+            MovedModel::Renamed { from, to } => fs::rename(to, from),
+            MovedModel::Copied { to, .. } => fs::remove_dir_all(to),
+        };
+        if let Err(e) = result {
+            let (MovedModel::Renamed { to, .. } | MovedModel::Copied { to, .. }) = &model;
+            log::warn!("model cache move: rollback of {} failed: {e}", to.display());
+        }
+    }
+}
+
+/// Recursively copies `from` into a new directory `to`, recreating symlinks (the hub
+/// cache links snapshot files to blobs) rather than following them where possible.
+fn copy_tree(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let (src, dst) = (entry.path(), to.join(entry.file_name()));
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            copy_tree(&src, &dst)?;
+        } else if file_type.is_symlink() {
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(fs::read_link(&src)?, &dst)?;
+            #[cfg(not(unix))]
+            fs::copy(&src, &dst).map(|_| ())?;
+        } else {
+            fs::copy(&src, &dst)?;
+        }
+    }
+    Ok(())
+}
+
+/// One entry of a directory tree as compared after a copy.
+#[derive(Debug, PartialEq, Eq)]
+enum TreeEntry {
+    Dir,
+    File(u64),
+    Link(PathBuf),
+}
+
+/// Relative path and kind (with size or link target) of everything under `root`.
+fn tree_manifest(root: &Path) -> io::Result<BTreeMap<PathBuf, TreeEntry>> {
+    fn walk(root: &Path, dir: &Path, out: &mut BTreeMap<PathBuf, TreeEntry>) -> io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let rel = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+            let meta = fs::symlink_metadata(&path)?;
+            if meta.is_dir() {
+                out.insert(rel, TreeEntry::Dir);
+                walk(root, &path, out)?;
+            } else if meta.is_symlink() && cfg!(unix) {
+                out.insert(rel, TreeEntry::Link(fs::read_link(&path)?));
+            } else {
+                out.insert(rel, TreeEntry::File(fs::metadata(&path)?.len()));
+            }
+        }
+        Ok(())
+    }
+    let mut out = BTreeMap::new();
+    walk(root, root, &mut out)?;
+    Ok(out)
+}
 
 /// Resolve the OS-specific default Hugging Face hub base directory.
 ///
 /// - macOS: ~/.cache/huggingface/hub
 /// - Linux: ~/.cache/huggingface/hub
 /// - Windows: %LOCALAPPDATA%\huggingface\hub (fallback: ~/AppData/Local/huggingface/hub)
-fn huggingface_hub_base_dir() -> PathBuf {
+fn default_hub_base_dir() -> PathBuf {
     #[cfg(any(target_os = "macos", target_os = "linux"))]
     {
         let base = home_dir().unwrap_or_else(|| PathBuf::from("/"));
@@ -33,11 +329,12 @@ fn huggingface_hub_base_dir() -> PathBuf {
 /// Returns the target directory under the Hugging Face hub where the model should live.
 /// Example (Linux): ~/.cache/huggingface/hub/models--mlc-ai--Qwen3-14B-q4f16_1-MLC
 pub fn model_cache_dir(repo_id: &str) -> PathBuf {
-    let mut path = huggingface_hub_base_dir();
-    // HF hub folder naming: models--org--repo (replace '/' with '--')
-    let transformed = format!("models--{}", repo_id.replace('/', "--"));
-    path.push(transformed);
-    path
+    huggingface_hub_base_dir().join(model_dir_name(repo_id))
+}
+
+/// HF hub folder naming: models--org--repo (replace '/' with '--')
+fn model_dir_name(repo_id: &str) -> String {
+    format!("{MODEL_DIR_PREFIX}{}", repo_id.replace('/', "--"))
 }
 
 /// Returns a temporary ".downloading" directory for atomic model downloads.
@@ -70,10 +367,369 @@ pub fn is_model_cached(repo_id: &str) -> bool {
     }
 }
 
+/// Directory for locally provided models, next to the app database.
+pub fn app_models_dir(app_data_dir: &Path) -> PathBuf {
+    app_data_dir.join("models")
+}
+
+/// Where a model spec points once resolved.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ModelLocation {
+    LocalPath { path: PathBuf },
+    HuggingFace { repo_id: String, cache_dir: PathBuf },
+}
+
+/// Returns whether `spec` has the `org/name` shape of a Hugging Face repo id.
+fn looks_like_repo_id(spec: &str) -> bool {
+    let mut parts = spec.split('/');
+    let valid = |p: &str| {
+        !p.is_empty()
+            && !p.starts_with('.')
+            && p.chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    };
+    matches!((parts.next(), parts.next(), parts.next()), (Some(org), Some(name), None) if valid(org) && valid(name))
+}
+
+/// Resolves a model spec without depending on the process working directory:
+/// - absolute paths are used as-is;
+/// - `org/name` is a Hugging Face repo id, unless a directory by that name exists
+///   under `models_dir`;
+/// - anything else is a path relative to `models_dir`.
+pub fn resolve_model_path(spec: &str, models_dir: &Path) -> ModelLocation {
+    let spec = spec.trim();
+    let as_path = Path::new(spec);
+    if as_path.is_absolute() {
+        return ModelLocation::LocalPath {
+            path: as_path.to_path_buf(),
+        };
+    }
+    let local = models_dir.join(as_path);
+    if looks_like_repo_id(spec) && !local.is_dir() {
+        return ModelLocation::HuggingFace {
+            repo_id: spec.to_string(),
+            cache_dir: model_cache_dir(spec),
+        };
+    }
+    ModelLocation::LocalPath { path: local }
+}
+
+/// Model metadata read from the config files of a cached model. Every field is
+/// optional because MLX and MLC repos ship different config layouts.
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct ModelInfo {
+    pub repo_id: String,
+    pub model_type: Option<String>,
+    pub architecture: Option<String>,
+    pub context_length: Option<u64>,
+    pub quantization: Option<String>,
+    /// Parameter count as advertised in the repo name, e.g. "14B".
+    pub parameter_hint: Option<String>,
+    /// Total size of weight files on disk.
+    pub weights_size_bytes: u64,
+}
+
+/// Config files consulted by `read_model_info`, most specific first.
+pub const MODEL_CONFIG_FILES: &[&str] = &["mlc-chat-config.json", "config.json"];
+
+/// Keys that hold the context length across config formats, in order of preference.
+const CONTEXT_LENGTH_KEYS: &[&str] = &[
+    "context_window_size",
+    "max_position_embeddings",
+    "max_sequence_length",
+    "n_positions",
+    "seq_length",
+];
+
+/// Accepts `owner/name` ids only, so a repo id cannot address other endpoints or
+/// escape the cache directory.
+pub fn validate_repo_id(repo_id: &str) -> Result<(), String> {
+    let valid_part = |part: &str| {
+        !part.is_empty()
+            && part != "."
+            && part != ".."
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    };
+    match repo_id.split_once('/') {
+        Some((owner, name)) if valid_part(owner) && valid_part(name) => Ok(()),
+        _ => Err(format!("invalid Hugging Face repo id: '{repo_id}'")),
+    }
+}
+
+/// Reads architecture, context length, and quantization for a cached model.
+pub fn read_model_info(repo_id: &str) -> Result<ModelInfo, String> {
+    if !is_model_cached(repo_id) {
+        return Err(format!("model not cached: {repo_id}"));
+    }
+    let dir = model_files_dir(&model_cache_dir(repo_id));
+    Ok(model_info_from_dir(repo_id, &dir))
+}
+
+fn model_info_from_dir(repo_id: &str, dir: &Path) -> ModelInfo {
+    let configs: Vec<Value> = MODEL_CONFIG_FILES
+        .iter()
+        .filter_map(|name| fs::read_to_string(dir.join(name)).ok())
+        .filter_map(|raw| serde_json::from_str(&raw).ok())
+        .collect();
+    // First config (or its nested `model_config`/`text_config`) that has the key wins.
+    let lookup = |key: &str| -> Option<&Value> {
+        configs.iter().find_map(|c| {
+            c.get(key)
+                .or_else(|| c.get("model_config").and_then(|m| m.get(key)))
+                .or_else(|| c.get("text_config").and_then(|m| m.get(key)))
+        })
+    };
+
+    ModelInfo {
+        repo_id: repo_id.to_string(),
+        model_type: lookup("model_type")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        architecture: lookup("architectures")
+            .and_then(|a| a.get(0))
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        context_length: CONTEXT_LENGTH_KEYS
+            .iter()
+            .find_map(|key| lookup(key).and_then(Value::as_u64)),
+        quantization: lookup("quantization")
+            .or_else(|| lookup("quantization_config"))
+            .and_then(describe_quantization),
+        parameter_hint: parameter_hint(repo_id),
+        weights_size_bytes: weights_size_bytes(dir),
+    }
+}
+
+/// MLC stores quantization as a string (`q4f16_1`); MLX as `{ "bits": 4, "group_size": 64 }`.
+fn describe_quantization(value: &Value) -> Option<String> {
+    if let Some(s) = value.as_str() {
+        return Some(s.to_string());
+    }
+    let bits = value.get("bits").and_then(Value::as_u64)?;
+    Some(match value.get("group_size").and_then(Value::as_u64) {
+        Some(group) => format!("{bits}-bit (group size {group})"),
+        None => format!("{bits}-bit"),
+    })
+}
+
+/// Picks a size token such as `14B`, `0.5B` or `135M` out of the repo name.
+fn parameter_hint(repo_id: &str) -> Option<String> {
+    let name = repo_id.rsplit('/').next().unwrap_or(repo_id);
+    name.split(['-', '_']).find_map(|token| {
+        let (number, unit) = token.split_at(token.len().checked_sub(1)?);
+        let is_unit = matches!(unit, "B" | "b" | "M" | "m");
+        (is_unit && !number.is_empty() && number.parse::<f64>().is_ok())
+            .then(|| format!("{number}{}", unit.to_ascii_uppercase()))
+    })
+}
+
+/// Tokenizer files, any one of which is enough.
+pub const MODEL_TOKENIZER_FILES: &[&str] = &["tokenizer.json", "tokenizer.model"];
+
+/// Shard indexes and the JSON pointer to the shard file names they list.
+const MODEL_SHARD_INDEXES: &[(&str, &str)] = &[
+    ("model.safetensors.index.json", "/weight_map"),
+    ("ndarray-cache.json", "/records"),
+    ("tensor-cache.json", "/records"),
+];
+
+/// Directory holding a cached model's files. The Hugging Face CLI layout keeps them
+/// in `snapshots/<rev>`, with the revision of `main` in `refs/main`; without a usable
+/// ref the most recently modified snapshot is used. Flat caches (the app's own
+/// downloads) are returned as-is.
+pub fn model_files_dir(cache_dir: &Path) -> PathBuf {
+    let snapshots = cache_dir.join("snapshots");
+    if !snapshots.is_dir() {
+        return cache_dir.to_path_buf();
+    }
+    let from_ref = fs::read_to_string(cache_dir.join("refs").join("main"))
+        .ok()
+        .map(|rev| rev.trim().to_string())
+        .filter(|rev| !rev.is_empty() && !rev.contains(['/', '\\']) && rev != "..")
+        .map(|rev| snapshots.join(rev))
+        .filter(|dir| dir.is_dir());
+    from_ref
+        .or_else(|| {
+            fs::read_dir(&snapshots)
+                .ok()?
+                .flatten()
+                .filter(|e| e.path().is_dir())
+                .max_by_key(|e| e.metadata().and_then(|m| m.modified()).ok())
+                .map(|e| e.path())
+        })
+        .unwrap_or_else(|| cache_dir.to_path_buf())
+}
+
+/// Whether `cache_dir` is a flat cache rather than the Hugging Face CLI's
+/// `refs`/`snapshots`/`blobs` layout. Only flat caches may be moved aside for repair.
+pub fn is_flat_model_dir(cache_dir: &Path) -> bool {
+    ["refs", "snapshots", "blobs"]
+        .iter()
+        .all(|name| !cache_dir.join(name).exists())
+}
+
+/// Returns the files a cached model needs but lacks (empty files count as missing):
+/// a config, a tokenizer, and every shard its index lists, or at least one weight
+/// file when there is no index. Stricter than `is_model_cached`, which only checks
+/// that the directory is non-empty. `dir` may use the snapshot layout (see
+/// `model_files_dir`).
+pub fn missing_model_files(dir: &Path) -> Vec<String> {
+    let dir = &model_files_dir(dir);
+    let present =
+        |name: &str| fs::metadata(dir.join(name)).is_ok_and(|m| m.is_file() && m.len() > 0);
+    let mut missing = Vec::new();
+    if !MODEL_CONFIG_FILES.iter().any(|name| present(name)) {
+        missing.push("config.json".to_string());
+    }
+    if !MODEL_TOKENIZER_FILES.iter().any(|name| present(name)) {
+        missing.push("tokenizer.json".to_string());
+    }
+    match weight_shards(dir) {
+        Some(shards) => missing.extend(shards.into_iter().filter(|shard| !present(shard))),
+        None if weights_size_bytes(dir) == 0 => missing.push("*.safetensors".to_string()),
+        None => {}
+    }
+    missing
+}
+
+/// Shard file names listed by the first index in `MODEL_SHARD_INDEXES` that exists,
+/// sorted and deduplicated.
+fn weight_shards(dir: &Path) -> Option<Vec<String>> {
+    MODEL_SHARD_INDEXES.iter().find_map(|(index, pointer)| {
+        let raw = fs::read_to_string(dir.join(index)).ok()?;
+        let json: Value = serde_json::from_str(&raw).ok()?;
+        let names: Vec<&str> = match json.pointer(pointer)? {
+            Value::Object(map) => map.values().filter_map(Value::as_str).collect(),
+            Value::Array(records) => records
+                .iter()
+                .filter_map(|r| r.get("dataPath").and_then(Value::as_str))
+                .collect(),
+            _ => return None,
+        };
+        let mut shards: Vec<String> = names.into_iter().map(str::to_string).collect();
+        shards.sort();
+        shards.dedup();
+        Some(shards)
+    })
+}
+
+fn weights_size_bytes(dir: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .filter(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            name.ends_with(".safetensors") || name.starts_with("params_shard_")
+        })
+        // Snapshot files are symlinks into `blobs`; follow them for the real size
+        .filter_map(|e| fs::metadata(e.path()).ok())
+        .map(|m| m.len())
+        .sum()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn hub_dir_follows_huggingface_env_precedence() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |key: &str| {
+                vars.iter()
+                    .find(|(k, _)| *k == key)
+                    .map(|(_, v)| PathBuf::from(v))
+            }
+        };
+        let resolved = resolve_hub_dir(env(&[("HF_HUB_CACHE", "/hub"), ("HF_HOME", "/hf")]));
+        assert_eq!(resolved.path, PathBuf::from("/hub"));
+        assert_eq!(resolved.source, HubDirSource::HfHubCache);
+
+        let resolved = resolve_hub_dir(env(&[("HF_HOME", "/hf"), ("XDG_CACHE_HOME", "/xdg")]));
+        assert_eq!(resolved.path, PathBuf::from("/hf/hub"));
+        assert_eq!(resolved.source, HubDirSource::HfHome);
+
+        let resolved = resolve_hub_dir(env(&[]));
+        assert_eq!(resolved.path, default_hub_base_dir());
+        assert_eq!(resolved.source, HubDirSource::Default);
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "openchat-model-store-{name}-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn moves_managed_model_dirs_and_leaves_other_entries() {
+        let root = temp_dir("move");
+        let (from, to) = (root.join("from"), root.join("to"));
+        for name in ["models--org--name", "models--org--other"] {
+            let snapshot = from.join(name).join("snapshots").join("main");
+            fs::create_dir_all(&snapshot).unwrap();
+            fs::write(snapshot.join("config.json"), b"{}").unwrap();
+        }
+        fs::create_dir_all(from.join("models--org--flat")).unwrap();
+        fs::create_dir_all(from.join("datasets--org--data")).unwrap();
+
+        let moved = move_model_dirs(&from, &to, &["org/name".to_string()]).unwrap();
+        assert_eq!(
+            moved.models,
+            vec![
+                "models--org--flat".to_string(),
+                "models--org--name".to_string()
+            ]
+        );
+        assert!(!from.join("models--org--name").exists());
+        assert!(from.join("models--org--other").exists());
+        assert!(from.join("datasets--org--data").exists());
+        assert_eq!(
+            fs::read(to.join("models--org--name/snapshots/main/config.json")).unwrap(),
+            b"{}"
+        );
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn move_refuses_partial_downloads_and_existing_targets() {
+        let root = temp_dir("move-refuse");
+        let (from, to) = (root.join("from"), root.join("to"));
+        fs::create_dir_all(from.join("models--org--name.downloading")).unwrap();
+        assert!(move_model_dirs(&from, &to, &[]).is_err());
+
+        fs::remove_dir_all(from.join("models--org--name.downloading")).unwrap();
+        fs::create_dir_all(from.join("models--org--name")).unwrap();
+        fs::create_dir_all(to.join("models--org--name")).unwrap();
+        assert!(move_model_dirs(&from, &to, &[]).is_err());
+        assert!(from.join("models--org--name").exists());
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn copied_tree_matches_manifest() {
+        let root = temp_dir("copy");
+        let src = root.join("src");
+        fs::create_dir_all(src.join("blobs")).unwrap();
+        fs::write(src.join("blobs").join("abc"), b"weights").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("blobs/abc", src.join("link")).unwrap();
+
+        copy_tree(&src, &root.join("dst")).unwrap();
+        assert_eq!(
+            tree_manifest(&src).unwrap(),
+            tree_manifest(&root.join("dst")).unwrap()
+        );
+        let _ = fs::remove_dir_all(&root);
+    }
+
     #[test]
     fn builds_cache_dir() {
         let repo = "mlc-ai/Qwen3-14B-q4f16_1-MLC";
@@ -96,4 +752,116 @@ mod tests {
             assert!(dir_str.to_lowercase().contains("hub"));
         }
     }
+
+    #[test]
+    fn reads_model_info_from_mlx_config() {
+        let dir = std::env::temp_dir().join(format!("openchat-model-info-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("config.json"),
+            r#"{"model_type":"qwen3","architectures":["Qwen3ForCausalLM"],
+               "max_position_embeddings":40960,"quantization":{"group_size":64,"bits":4}}"#,
+        )
+        .unwrap();
+        fs::write(dir.join("model.safetensors"), vec![0u8; 128]).unwrap();
+
+        let info = model_info_from_dir("mlx-community/Qwen3-14B-4bit", &dir);
+        assert_eq!(info.model_type.as_deref(), Some("qwen3"));
+        assert_eq!(info.architecture.as_deref(), Some("Qwen3ForCausalLM"));
+        assert_eq!(info.context_length, Some(40960));
+        assert_eq!(info.quantization.as_deref(), Some("4-bit (group size 64)"));
+        assert_eq!(info.parameter_hint.as_deref(), Some("14B"));
+        assert_eq!(info.weights_size_bytes, 128);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn parameter_hint_handles_fractional_and_missing_sizes() {
+        assert_eq!(
+            parameter_hint("mlc-ai/Qwen2.5-0.5B-Instruct-q4f16_1-MLC").as_deref(),
+            Some("0.5B")
+        );
+        assert_eq!(parameter_hint("org/SmolLM-135M").as_deref(), Some("135M"));
+        assert_eq!(parameter_hint("org/some-model"), None);
+    }
+
+    #[test]
+    fn resolves_model_specs_against_models_dir() {
+        let models_dir =
+            std::env::temp_dir().join(format!("openchat-models-{}", std::process::id()));
+        fs::create_dir_all(models_dir.join("local").join("Qwen3-0.6B-MLX-4bit")).unwrap();
+
+        assert_eq!(
+            resolve_model_path("mlx-community/Qwen3-0.6B-4bit", &models_dir),
+            ModelLocation::HuggingFace {
+                repo_id: "mlx-community/Qwen3-0.6B-4bit".into(),
+                cache_dir: model_cache_dir("mlx-community/Qwen3-0.6B-4bit"),
+            }
+        );
+        // An existing directory wins over the repo-id interpretation.
+        assert_eq!(
+            resolve_model_path("local/Qwen3-0.6B-MLX-4bit", &models_dir),
+            ModelLocation::LocalPath {
+                path: models_dir.join("local/Qwen3-0.6B-MLX-4bit")
+            }
+        );
+        assert_eq!(
+            resolve_model_path("./Qwen3-0.6B-MLX-4bit", &models_dir),
+            ModelLocation::LocalPath {
+                path: models_dir.join("./Qwen3-0.6B-MLX-4bit")
+            }
+        );
+        let absolute = models_dir.join("abs");
+        assert_eq!(
+            resolve_model_path(absolute.to_str().unwrap(), &models_dir),
+            ModelLocation::LocalPath { path: absolute }
+        );
+
+        let _ = fs::remove_dir_all(&models_dir);
+    }
+
+    #[test]
+    fn reports_missing_model_files() {
+        let dir = temp_dir("verify");
+        assert_eq!(
+            missing_model_files(&dir),
+            vec!["config.json", "tokenizer.json", "*.safetensors"]
+        );
+
+        fs::write(dir.join("config.json"), b"{}").unwrap();
+        fs::write(dir.join("tokenizer.json"), b"{}").unwrap();
+        fs::write(
+            dir.join("model.safetensors.index.json"),
+            br#"{ "weight_map": { "a": "model-1.safetensors", "b": "model-2.safetensors", "c": "model-1.safetensors" } }"#,
+        )
+        .unwrap();
+        fs::write(dir.join("model-1.safetensors"), b"weights").unwrap();
+        fs::write(dir.join("model-2.safetensors"), b"").unwrap();
+        assert_eq!(missing_model_files(&dir), vec!["model-2.safetensors"]);
+
+        fs::write(dir.join("model-2.safetensors"), b"weights").unwrap();
+        assert!(missing_model_files(&dir).is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn checks_files_in_the_snapshot_named_by_refs_main() {
+        let dir = temp_dir("snapshot");
+        let snapshot = dir.join("snapshots").join("abc123");
+        fs::create_dir_all(&snapshot).unwrap();
+        fs::create_dir_all(dir.join("snapshots").join("old")).unwrap();
+        fs::create_dir_all(dir.join("refs")).unwrap();
+        fs::write(dir.join("refs").join("main"), b"abc123\n").unwrap();
+        for name in ["config.json", "tokenizer.json", "model.safetensors"] {
+            fs::write(snapshot.join(name), b"{}").unwrap();
+        }
+
+        assert_eq!(model_files_dir(&dir), snapshot);
+        assert!(missing_model_files(&dir).is_empty());
+        assert!(!is_flat_model_dir(&dir));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
@@ -0,0 +1,143 @@
+//! Domain types shared by persistence and the provider-facing code.
+
+use serde::{Deserialize, Serialize};
+
+/// Author of a message, stored in `messages.role`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    User,
+    Assistant,
+    System,
+    Tool,
+}
+
+impl Role {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::User => "user",
+            Role::Assistant => "assistant",
+            Role::System => "system",
+            Role::Tool => "tool",
+        }
+    }
+
+    /// Only assistant messages may carry `reasoning`.
+    pub fn allows_reasoning(self) -> bool {
+        self == Role::Assistant
+    }
+}
+
+impl std::str::FromStr for Role {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "user" => Ok(Role::User),
+            "assistant" => Ok(Role::Assistant),
+            "system" => Ok(Role::System),
+            "tool" => Ok(Role::Tool),
+            other => Err(format!(
+                "invalid message role '{other}': expected user, assistant, system or tool"
+            )),
+        }
+    }
+}
+
+/// A tool call made by an assistant message, rebuilt from the `tool` message that
+/// answers it.
+#[derive(Debug, Clone)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    /// JSON-encoded arguments, as the chat API expects them.
+    pub arguments: String,
+}
+
+/// A stored message as sent back to the model on the next turn.
+#[derive(Debug, Clone)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String,
+    pub tool_call_id: Option<String>,
+    /// Calls an assistant message made; each must be answered by a following tool
+    /// message, and tool messages are only valid after such a call.
+    pub tool_calls: Vec<ToolCall>,
+}
+
+impl ChatMessage {
+    /// Renders the message in the OpenAI-compatible chat shape. Tool results carry
+    /// the `tool_call_id` of the call they answer and assistant messages the
+    /// `tool_calls` they made; reasoning is never sent back.
+    pub fn to_api_message(&self) -> Result<serde_json::Value, String> {
+        let role: Role = self.role.parse()?;
+        Ok(match role {
+            Role::Assistant if !self.tool_calls.is_empty() => serde_json::json!({
+                "role": role.as_str(),
+                "content": self.content,
+                "tool_calls": self
+                    .tool_calls
+                    .iter()
+                    .map(|call| serde_json::json!({
+                        "id": call.id,
+                        "type": "function",
+                        "function": { "name": call.name, "arguments": call.arguments },
+                    }))
+                    .collect::<Vec<_>>(),
+            }),
+            Role::Tool => serde_json::json!({
+                "role": role.as_str(),
+                "tool_call_id": self
+                    .tool_call_id
+                    .as_deref()
+                    .ok_or("tool message is missing tool_call_id")?,
+                "content": self.content,
+            }),
+            _ => serde_json::json!({ "role": role.as_str(), "content": self.content }),
+        })
+    }
+}
+
+/// Index of the first of `roles` to send when at most `max` messages may be: the
+/// newest `max`, moved past any tool results whose requesting assistant message was
+/// cut off. `None` or `0` sends everything.
+pub fn history_start(roles: &[&str], max: Option<usize>) -> usize {
+    let Some(max) = max.filter(|max| *max > 0) else {
+        return 0;
+    };
+    let mut start = roles.len().saturating_sub(max);
+    while roles.get(start) == Some(&Role::Tool.as_str()) {
+        start += 1;
+    }
+    start
+}
+
+/// Checks that message metadata is a JSON object, so features can add keys to it
+/// without clobbering each other.
+pub fn validate_metadata(metadata: &serde_json::Value) -> Result<(), String> {
+    if !metadata.is_object() {
+        return Err("message metadata must be a JSON object".to_string());
+    }
+    Ok(())
+}
+
+/// Checks a history limit: a message count, where `0` means no limit.
+pub fn validate_max_history(max: i64) -> Result<(), String> {
+    if max < 0 {
+        return Err(format!(
+            "max history messages must be 0 (no limit) or more, got {max}"
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that a message with `role` may carry `reasoning`.
+pub fn validate_reasoning(role: Role, reasoning: Option<&str>) -> Result<(), String> {
+    if reasoning.is_some() && !role.allows_reasoning() {
+        return Err(format!(
+            "reasoning is only allowed on assistant messages, not '{}'",
+            role.as_str()
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,316 @@
+//! Best-effort lookup of which local process is listening on a TCP port, and of
+//! processes by executable path. Uses `lsof`/`ps` on Unix and `netstat`/`tasklist` on
+//! Windows. Also forcible termination of a process by id.
+
+use serde::Serialize;
+use std::path::Path;
+use std::process::Command;
+
+/// A process found listening on a port.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PortOccupant {
+    pub pid: u32,
+    pub name: Option<String>,
+}
+
+impl std::fmt::Display for PortOccupant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{name} (pid {})", self.pid),
+            None => write!(f, "pid {}", self.pid),
+        }
+    }
+}
+
+/// A running process found by executable path.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct RunningProcess {
+    pub pid: u32,
+    pub name: String,
+}
+
+/// Kills process `pid` immediately (SIGKILL / `taskkill /F`) without waiting for it.
+pub fn force_kill(pid: u32) {
+    #[cfg(unix)]
+    {
+        let Ok(pid) = i32::try_from(pid) else {
+            return;
+        };
+        // SAFETY: kill(2) has no memory-safety preconditions.
+        if unsafe { libc::kill(pid, libc::SIGKILL) } != 0 {
+            log::warn!("force_kill: pid {pid}: {}", std::io::Error::last_os_error());
+        }
+    }
+    #[cfg(windows)]
+    {
+        if let Err(e) = Command::new("taskkill")
+            .args(["/F", "/T", "/PID", &pid.to_string()])
+            .output()
+        {
+            log::warn!("force_kill: pid {pid}: {e}");
+        }
+    }
+}
+
+/// Returns the process listening on `port`, if any can be identified. Blocks while the
+/// platform tool runs, so call it from a blocking context.
+pub fn port_occupant(port: u16) -> Option<PortOccupant> {
+    #[cfg(unix)]
+    {
+        let output = Command::new("lsof")
+            .args(["-nP", &format!("-iTCP:{port}"), "-sTCP:LISTEN", "-Fpc"])
+            .output()
+            .ok()?;
+        parse_lsof_output(&String::from_utf8_lossy(&output.stdout))
+    }
+    #[cfg(windows)]
+    {
+        let output = Command::new("netstat")
+            .args(["-ano", "-p", "TCP"])
+            .output()
+            .ok()?;
+        let pid = parse_netstat_listener(&String::from_utf8_lossy(&output.stdout), port)?;
+        let name = Command::new("tasklist")
+            .args(["/FI", &format!("PID eq {pid}"), "/FO", "CSV", "/NH"])
+            .output()
+            .ok()
+            .and_then(|o| {
+                let text = String::from_utf8_lossy(&o.stdout);
+                text.split(',')
+                    .next()
+                    .map(|n| n.trim().trim_matches('"').to_string())
+            })
+            .filter(|n| !n.is_empty() && !n.starts_with("INFO:"));
+        Some(PortOccupant { pid, name })
+    }
+}
+
+/// Returns processes of the current user that were started from `executable` (their
+/// argv[0] is exactly that path) and whose parent is not a running program from the
+/// same directory, i.e. not the child of another live app instance. Excludes this
+/// process. Blocks while the platform tool runs, so call it from a blocking context.
+pub fn find_processes(executable: &Path) -> Vec<RunningProcess> {
+    #[cfg(unix)]
+    let found = Command::new("ps")
+        .args(["-axo", "pid=,ppid=,uid=,args="])
+        .output()
+        .map(|o| {
+            // SAFETY: getuid(2) has no preconditions and cannot fail.
+            let uid = unsafe { libc::getuid() };
+            parse_ps_output(&String::from_utf8_lossy(&o.stdout), executable, uid)
+        });
+    #[cfg(windows)]
+    let found = {
+        let image = executable
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut args = vec![
+            "/FO".to_string(),
+            "CSV".into(),
+            "/NH".into(),
+            "/FI".into(),
+            format!("IMAGENAME eq {image}"),
+        ];
+        if let Ok(user) = std::env::var("USERNAME") {
+            args.extend(["/FI".to_string(), format!("USERNAME eq {user}")]);
+        }
+        Command::new("tasklist")
+            .args(&args)
+            .output()
+            .map(|o| parse_tasklist_output(&String::from_utf8_lossy(&o.stdout), &image))
+    };
+    let own = std::process::id();
+    match found {
+        Ok(found) => found.into_iter().filter(|p| p.pid != own).collect(),
+        Err(e) => {
+            log::warn!("find_processes: {e}");
+            Vec::new()
+        }
+    }
+}
+
+/// One row of `ps -o pid=,ppid=,uid=,args=` output.
+struct PsRow<'a> {
+    pid: u32,
+    ppid: u32,
+    uid: u32,
+    args: &'a str,
+}
+
+fn parse_ps_row(line: &str) -> Option<PsRow<'_>> {
+    let line = line.trim_start();
+    let (pid, rest) = line.split_once(char::is_whitespace)?;
+    let rest = rest.trim_start();
+    let (ppid, rest) = rest.split_once(char::is_whitespace)?;
+    let rest = rest.trim_start();
+    let (uid, args) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    Some(PsRow {
+        pid: pid.parse().ok()?,
+        ppid: ppid.parse().ok()?,
+        uid: uid.parse().ok()?,
+        args: args.trim_start(),
+    })
+}
+
+/// Whether the command line `args` was started as `program`. Compares the whole path
+/// rather than splitting on whitespace, so paths with spaces match and arguments that
+/// merely mention the program (`tail -f openchat-mlx-server.log`) do not.
+fn started_as(args: &str, program: &str) -> bool {
+    args.strip_prefix(program)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(' '))
+}
+
+/// Parses `ps -o pid=,ppid=,uid=,args=` output into processes of user `uid` whose
+/// argv[0] is `executable` and whose parent was not started from its directory.
+#[cfg_attr(not(unix), allow(dead_code))]
+fn parse_ps_output(output: &str, executable: &Path, uid: u32) -> Vec<RunningProcess> {
+    let program = executable.to_string_lossy();
+    let app_dir = executable
+        .parent()
+        .map(|dir| format!("{}/", dir.to_string_lossy()));
+    let rows: Vec<PsRow<'_>> = output.lines().filter_map(parse_ps_row).collect();
+    let parent_is_app = |ppid: u32| {
+        rows.iter().any(|p| {
+            p.pid == ppid
+                && app_dir
+                    .as_deref()
+                    .is_some_and(|dir| p.args.starts_with(dir))
+        })
+    };
+    rows.iter()
+        .filter(|r| r.uid == uid && started_as(r.args, &program) && !parent_is_app(r.ppid))
+        .map(|r| RunningProcess {
+            pid: r.pid,
+            name: executable
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| program.to_string()),
+        })
+        .collect()
+}
+
+/// Parses `tasklist /FO CSV /NH` rows (`"name","pid",...`) whose image name is `image`.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn parse_tasklist_output(output: &str, image: &str) -> Vec<RunningProcess> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut cols = line.split("\",\"");
+            let name = cols.next()?.trim().trim_start_matches('"');
+            let pid = cols.next()?.trim_end_matches('"').parse().ok()?;
+            name.eq_ignore_ascii_case(image).then(|| RunningProcess {
+                pid,
+                name: name.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Parses `lsof -F pc` output (`p<pid>` then `c<command>` lines) into the first process.
+#[cfg_attr(not(unix), allow(dead_code))]
+fn parse_lsof_output(output: &str) -> Option<PortOccupant> {
+    let mut occupant: Option<PortOccupant> = None;
+    for line in output.lines() {
+        if let Some(pid) = line.strip_prefix('p') {
+            if occupant.is_some() {
+                break;
+            }
+            occupant = Some(PortOccupant {
+                pid: pid.trim().parse().ok()?,
+                name: None,
+            });
+        } else if let Some(name) = line.strip_prefix('c') {
+            if let Some(o) = occupant.as_mut() {
+                o.name = Some(name.trim().to_string());
+            }
+        }
+    }
+    occupant
+}
+
+/// Finds the pid of the `LISTENING` row for `port` in `netstat -ano` output.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn parse_netstat_listener(output: &str, port: u16) -> Option<u32> {
+    let suffix = format!(":{port}");
+    output.lines().find_map(|line| {
+        let cols: Vec<&str> = line.split_whitespace().collect();
+        match cols.as_slice() {
+            [_, local, _, state, pid] if local.ends_with(&suffix) && *state == "LISTENING" => {
+                pid.parse().ok()
+            }
+            _ => None,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        parse_lsof_output, parse_netstat_listener, parse_ps_output, parse_tasklist_output,
+        PortOccupant, RunningProcess,
+    };
+    use std::path::Path;
+
+    #[test]
+    fn parses_first_process_from_lsof_field_output() {
+        let out = "p4242\ncpython3\nf5\np99\ncother\n";
+        assert_eq!(
+            parse_lsof_output(out),
+            Some(PortOccupant {
+                pid: 4242,
+                name: Some("python3".into())
+            })
+        );
+        assert_eq!(parse_lsof_output(""), None);
+    }
+
+    #[test]
+    fn parses_listening_pid_from_netstat() {
+        let out = "\
+  Proto  Local Address          Foreign Address        State           PID
+  TCP    0.0.0.0:135            0.0.0.0:0              LISTENING       1000
+  TCP    127.0.0.1:8000         127.0.0.1:50000        ESTABLISHED     7
+  TCP    127.0.0.1:8000         0.0.0.0:0              LISTENING       4242
+";
+        assert_eq!(parse_netstat_listener(out, 8000), Some(4242));
+        assert_eq!(parse_netstat_listener(out, 9000), None);
+    }
+
+    #[test]
+    fn finds_orphans_by_exact_argv0_for_the_current_user() {
+        let exe = Path::new("/Users/a b/OpenChat.app/Contents/MacOS/openchat-mlx-server");
+        let out = "\
+    1     0   0 /sbin/launchd
+  4242     1 501 /Users/a b/OpenChat.app/Contents/MacOS/openchat-mlx-server --port 8000
+  4243     1 502 /Users/a b/OpenChat.app/Contents/MacOS/openchat-mlx-server --port 8001
+  4300     1 501 /Users/a b/OpenChat.app/Contents/MacOS/openchat-mlx-server-old
+  5000     1 501 cat /tmp/openchat-mlx-server.log
+  5100     1 501 less /Users/a b/OpenChat.app/Contents/MacOS/openchat-mlx-server
+  6000     1 501 /Users/a b/OpenChat.app/Contents/MacOS/openchat
+  6001  6000 501 /Users/a b/OpenChat.app/Contents/MacOS/openchat-mlx-server --port 8002
+";
+        let found = parse_ps_output(out, exe, 501);
+        assert_eq!(
+            found,
+            vec![RunningProcess {
+                pid: 4242,
+                name: "openchat-mlx-server".into()
+            }]
+        );
+    }
+
+    #[test]
+    fn finds_processes_by_image_name_in_tasklist_output() {
+        let out = r#""System Idle Process","0","Services","0","8 K"
+"openchat-mlx-server.exe","4242","Console","1","120,000 K"
+"#;
+        assert_eq!(
+            parse_tasklist_output(out, "openchat-mlx-server.exe"),
+            vec![RunningProcess {
+                pid: 4242,
+                name: "openchat-mlx-server.exe".into()
+            }]
+        );
+    }
+}
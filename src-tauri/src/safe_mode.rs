@@ -0,0 +1,39 @@
+//! Safe mode: a launch that skips auto-starting the MLC server and auto-connecting
+//! MCP sessions, so a bad model or server config can be fixed from settings.
+
+/// Environment variable that enables safe mode when set to a truthy value.
+pub const SAFE_MODE_ENV: &str = "OPENCHAT_SAFE_MODE";
+
+/// Whether this launch is in safe mode. Managed in app state at startup.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SafeMode(pub bool);
+
+impl SafeMode {
+    /// Reads `OPENCHAT_SAFE_MODE` from the environment.
+    pub fn from_env() -> Self {
+        Self(std::env::var(SAFE_MODE_ENV).is_ok_and(|value| is_truthy(&value)))
+    }
+}
+
+/// `1`, `true`, `yes` and `on` (any case) enable safe mode; anything else does not.
+fn is_truthy(value: &str) -> bool {
+    matches!(
+        value.trim().to_ascii_lowercase().as_str(),
+        "1" | "true" | "yes" | "on"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_truthy;
+
+    #[test]
+    fn accepts_common_truthy_values_only() {
+        for value in ["1", "true", "TRUE", " yes ", "On"] {
+            assert!(is_truthy(value), "{value:?}");
+        }
+        for value in ["", "0", "false", "off", "no", "safe"] {
+            assert!(!is_truthy(value), "{value:?}");
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! Secret storage (API keys, tokens) backed by the OS keychain.
+//!
+//! Secrets go to the platform credential store (macOS Keychain, Windows Credential
+//! Manager, Secret Service on Linux). When no credential store is available they fall
+//! back to the `secrets` table so the app keeps working, at the cost of plaintext
+//! storage. Values are never logged.
+
+use keyring::Entry;
+use sqlx::SqlitePool;
+
+use crate::settings::{self, Setting};
+
+/// Keychain service name under which all app secrets are stored.
+const KEYRING_SERVICE: &str = "com.openchat.app";
+
+/// Name of the OpenAI API key secret.
+pub const OPENAI_API_KEY: &str = "openai_api_key";
+
+/// Secret names are restricted so they are safe as keychain account names.
+pub fn validate_secret_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "invalid secret name '{name}': use 1-64 lowercase letters, digits or '_'"
+        ))
+    }
+}
+
+/// Result of a keychain operation that may be unsupported on this machine.
+enum Keychain<T> {
+    Ok(T),
+    Unavailable,
+}
+
+/// Runs `op` against the keychain entry for `name` on a blocking thread, since
+/// credential stores may block (or prompt the user).
+async fn with_keychain<T, F>(name: &str, op: F) -> Result<Keychain<T>, String>
+where
+    T: Send + 'static,
+    F: FnOnce(&Entry) -> keyring::Result<T> + Send + 'static,
+{
+    let name = name.to_string();
+    tokio::task::spawn_blocking(move || {
+        let result = Entry::new(KEYRING_SERVICE, &name).and_then(|entry| op(&entry));
+        match result {
+            Ok(value) => Ok(Keychain::Ok(value)),
+            Err(keyring::Error::PlatformFailure(_) | keyring::Error::NoStorageAccess(_)) => {
+                Ok(Keychain::Unavailable)
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Stores `value` under `name`, replacing any previous value.
+pub async fn set_secret(pool: &SqlitePool, name: &str, value: &str) -> Result<(), String> {
+    validate_secret_name(name)?;
+    let owned = value.to_string();
+    match with_keychain(name, move |entry| entry.set_password(&owned)).await? {
+        Keychain::Ok(()) => {
+            // Drop any plaintext copy left from a time the keychain was unavailable.
+            delete_fallback(pool, name).await
+        }
+        Keychain::Unavailable => {
+            log::warn!("secrets: keychain unavailable; storing '{name}' in the database");
+            sqlx::query(
+                "INSERT INTO secrets (name, value) VALUES (?, ?)
+                 ON CONFLICT(name) DO UPDATE SET value = excluded.value, updated_at = CURRENT_TIMESTAMP",
+            )
+            .bind(name)
+            .bind(value)
+            .execute(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+            Ok(())
+        }
+    }
+}
+
+/// Reads the secret stored under `name`, if any. For use by the backend only; commands
+/// must never return the value to the frontend.
+pub async fn get_secret(pool: &SqlitePool, name: &str) -> Result<Option<String>, String> {
+    validate_secret_name(name)?;
+    let from_keychain = with_keychain(name, |entry| match entry.get_password() {
+        Ok(value) => Ok(Some(value)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e),
+    })
+    .await?;
+    if let Keychain::Ok(Some(value)) = from_keychain {
+        return Ok(Some(value));
+    }
+    sqlx::query_scalar("SELECT value FROM secrets WHERE name = ?")
+        .bind(name)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Whether a secret is stored under `name`.
+pub async fn has_secret(pool: &SqlitePool, name: &str) -> Result<bool, String> {
+    Ok(get_secret(pool, name)
+        .await?
+        .is_some_and(|value| !value.is_empty()))
+}
+
+/// Moves an OpenAI API key still held in plaintext in `app_settings` (from before
+/// keychain storage) into the secret store, then clears the old column.
+pub async fn migrate_legacy_openai_api_key(pool: &SqlitePool) -> Result<(), String> {
+    let legacy = settings::get_setting::<String>(pool, Setting::OpenaiApiKey).await?;
+    let Some(key) = legacy.filter(|k| !k.is_empty()) else {
+        return Ok(());
+    };
+    set_secret(pool, OPENAI_API_KEY, &key).await?;
+    settings::set_setting::<String>(pool, Setting::OpenaiApiKey, None).await
+}
+
+/// Removes the secret from the keychain and the fallback table.
+pub async fn delete_secret(pool: &SqlitePool, name: &str) -> Result<(), String> {
+    validate_secret_name(name)?;
+    let deleted = with_keychain(name, |entry| match entry.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(e),
+    })
+    .await?;
+    if let Keychain::Unavailable = deleted {
+        log::debug!("secrets: keychain unavailable; deleting '{name}' from the database only");
+    }
+    delete_fallback(pool, name).await
+}
+
+async fn delete_fallback(pool: &SqlitePool, name: &str) -> Result<(), String> {
+    sqlx::query("DELETE FROM secrets WHERE name = ?")
+        .bind(name)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::validate_secret_name;
+
+    #[test]
+    fn secret_names_are_restricted() {
+        assert!(validate_secret_name("openai_api_key").is_ok());
+        assert!(validate_secret_name("hf_token2").is_ok());
+        assert!(validate_secret_name("").is_err());
+        assert!(validate_secret_name("Bad Name").is_err());
+        assert!(validate_secret_name(&"a".repeat(65)).is_err());
+    }
+}
@@ -0,0 +1,135 @@
+//! End-to-end smoke test behind the `self_test` command: server readiness, one chat
+//! round trip, and optionally a ping to each enabled MCP server. Every step is timed
+//! and reported individually so a failure points at the subsystem responsible.
+
+use crate::mlc_server::http_get_models_reqwest;
+use serde::Serialize;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+/// How long `self_test` waits for a starting server to pass its health check.
+pub const SELF_TEST_READY_TIMEOUT: Duration = Duration::from_secs(120);
+/// Timeout of the model list request.
+const SELF_TEST_MODELS_TIMEOUT: Duration = Duration::from_secs(5);
+/// Timeout of the test chat completion, which includes prompt processing.
+const SELF_TEST_CHAT_TIMEOUT: Duration = Duration::from_secs(60);
+/// Prompt sent for the round trip; any non-empty reply passes.
+const SELF_TEST_PROMPT: &str = "Reply with the single word OK.";
+
+/// Outcome of one self-test step.
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfTestStep {
+    pub name: String,
+    pub ok: bool,
+    pub duration_ms: u64,
+    /// Result summary on success, the error on failure.
+    pub detail: Option<String>,
+}
+
+/// All steps that ran, in order. Steps that depend on a failed one are skipped.
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct SelfTestReport {
+    pub ok: bool,
+    pub steps: Vec<SelfTestStep>,
+}
+
+impl SelfTestReport {
+    /// Runs `step`, records it under `name`, and returns its value if it passed.
+    pub async fn run<T, F>(
+        &mut self,
+        name: impl Into<String>,
+        step: F,
+        describe: impl FnOnce(&T) -> Option<String>,
+    ) -> Option<T>
+    where
+        F: Future<Output = Result<T, String>>,
+    {
+        let started = Instant::now();
+        let result = step.await;
+        let duration_ms = started.elapsed().as_millis() as u64;
+        let (ok, detail) = match &result {
+            Ok(value) => (true, describe(value)),
+            Err(e) => (false, Some(e.clone())),
+        };
+        self.steps.push(SelfTestStep {
+            name: name.into(),
+            ok,
+            duration_ms,
+            detail,
+        });
+        result.ok()
+    }
+
+    /// Seals the report; it passes only if every step ran and passed.
+    pub fn finish(mut self) -> Self {
+        self.ok = !self.steps.is_empty() && self.steps.iter().all(|s| s.ok);
+        self
+    }
+}
+
+/// First model the server at `addr` lists at `/v1/models`.
+pub async fn first_served_model(addr: SocketAddr) -> Result<String, String> {
+    http_get_models_reqwest(addr, SELF_TEST_MODELS_TIMEOUT)
+        .await
+        .map_err(|e| format!("listing models failed: {e}"))?
+        .into_iter()
+        .next()
+        .ok_or_else(|| "the server lists no models".to_string())
+}
+
+/// Sends `SELF_TEST_PROMPT` to `{base_url}/chat/completions` and returns the reply,
+/// failing if it is empty.
+pub async fn chat_round_trip(base_url: &str, model: &str) -> Result<String, String> {
+    let resp = crate::http_client::shared_client()
+        .post(format!("{base_url}/chat/completions"))
+        .timeout(SELF_TEST_CHAT_TIMEOUT)
+        .json(&serde_json::json!({
+            "model": model,
+            "messages": [{ "role": "user", "content": SELF_TEST_PROMPT }],
+            "max_tokens": 16,
+            "temperature": 0,
+            "stream": false,
+        }))
+        .send()
+        .await
+        .map_err(|e| format!("chat request failed: {e}"))?;
+    if !resp.status().is_success() {
+        return Err(format!("chat request failed: HTTP {}", resp.status()));
+    }
+    let body: serde_json::Value = resp
+        .json()
+        .await
+        .map_err(|e| format!("invalid chat response: {e}"))?;
+    crate::chat_provider::parse_reply(&body)
+        .map(|reply| reply.content)
+        .ok_or_else(|| "the model returned an empty reply".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SelfTestReport;
+
+    #[tokio::test]
+    async fn report_passes_only_when_every_step_passes() {
+        let mut report = SelfTestReport::default();
+        let value = report
+            .run("first", async { Ok::<_, String>(2) }, |v| {
+                Some(v.to_string())
+            })
+            .await;
+        assert_eq!(value, Some(2));
+        assert!(report.clone().finish().ok);
+
+        let failed = report
+            .run("second", async { Err::<(), _>("boom".to_string()) }, |_| {
+                None
+            })
+            .await;
+        assert!(failed.is_none());
+        let report = report.finish();
+        assert!(!report.ok);
+        assert_eq!(report.steps[1].detail.as_deref(), Some("boom"));
+        assert!(!SelfTestReport::default().finish().ok);
+    }
+}
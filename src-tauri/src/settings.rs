@@ -0,0 +1,95 @@
+//! Backend access to the single-row `app_settings` table (id = 1).
+//!
+//! The frontend owns most settings through kysely; this module covers the columns
+//! the backend itself needs at runtime. Column names are never taken from callers,
+//! only from `Setting`.
+
+use sqlx::{Sqlite, SqlitePool};
+
+/// `app_settings` columns read or written by the backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    SystemPrompt,
+    MlcLogLevel,
+    MlcMaxTokens,
+    MlcTemperature,
+    MlcTopP,
+    MlcRepetitionPenalty,
+    OpenaiApiKey,
+    MlcHost,
+    DownloadMaxBytesPerSec,
+    TelemetryEnabled,
+    MlcWatchdogEnabled,
+    MlcKillOrphans,
+    ModelCacheDir,
+    Model,
+    Theme,
+    McpDebugEnabled,
+    McpAutoconnectOnLaunch,
+    McpMaxSessions,
+    ProviderRequestLogging,
+    MaxHistoryMessages,
+    OpenaiMaxAttempts,
+}
+
+impl Setting {
+    fn column(self) -> &'static str {
+        match self {
+            Setting::SystemPrompt => "system_prompt",
+            Setting::MlcLogLevel => "mlc_log_level",
+            Setting::MlcMaxTokens => "mlc_max_tokens",
+            Setting::MlcTemperature => "mlc_temperature",
+            Setting::MlcTopP => "mlc_top_p",
+            Setting::MlcRepetitionPenalty => "mlc_repetition_penalty",
+            Setting::OpenaiApiKey => "openai_api_key",
+            Setting::MlcHost => "mlc_host",
+            Setting::DownloadMaxBytesPerSec => "download_max_bytes_per_sec",
+            Setting::TelemetryEnabled => "telemetry_enabled",
+            Setting::MlcWatchdogEnabled => "mlc_watchdog_enabled",
+            Setting::MlcKillOrphans => "mlc_kill_orphans",
+            Setting::ModelCacheDir => "model_cache_dir",
+            Setting::Model => "model",
+            Setting::Theme => "theme",
+            Setting::McpDebugEnabled => "mcp_debug_enabled",
+            Setting::McpAutoconnectOnLaunch => "mcp_autoconnect_on_launch",
+            Setting::McpMaxSessions => "mcp_max_sessions",
+            Setting::ProviderRequestLogging => "provider_request_logging",
+            Setting::MaxHistoryMessages => "max_history_messages",
+            Setting::OpenaiMaxAttempts => "openai_max_attempts",
+        }
+    }
+}
+
+/// Reads a setting. Returns `None` when unset.
+pub async fn get_setting<T>(pool: &SqlitePool, setting: Setting) -> Result<Option<T>, String>
+where
+    T: for<'r> sqlx::Decode<'r, Sqlite> + sqlx::Type<Sqlite> + Send + Unpin,
+{
+    let sql = format!("SELECT {} FROM app_settings WHERE id = 1", setting.column());
+    let value: Option<Option<T>> = sqlx::query_scalar(&sql)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(value.flatten())
+}
+
+/// Writes a setting; `None` clears it.
+pub async fn set_setting<T>(
+    pool: &SqlitePool,
+    setting: Setting,
+    value: Option<T>,
+) -> Result<(), String>
+where
+    T: for<'q> sqlx::Encode<'q, Sqlite> + sqlx::Type<Sqlite> + Send,
+{
+    let sql = format!(
+        "UPDATE app_settings SET {} = ?, updated_at = CURRENT_TIMESTAMP WHERE id = 1",
+        setting.column()
+    );
+    sqlx::query(&sql)
+        .bind(value)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
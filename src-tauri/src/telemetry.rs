@@ -0,0 +1,227 @@
+//! Opt-in local lifecycle log for support: server start/stop/crash, downloads and MCP
+//! connections, written as JSON lines to a small rotating file set under app data.
+//!
+//! Off by default. Events carry ids, timestamps and durations only; never message
+//! content, tool arguments, headers, env values or error text that could hold secrets.
+
+use crate::settings::{self, Setting};
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+/// Current log file name; rotated files are `events.1.jsonl`, `events.2.jsonl`, ...
+const LOG_FILE_NAME: &str = "events.jsonl";
+/// The current file is rotated once it exceeds this size.
+const MAX_FILE_BYTES: u64 = 512 * 1024;
+/// Rotated files kept in addition to the current one.
+const MAX_ROTATED_FILES: usize = 2;
+
+/// A lifecycle event. Keep payloads free of user content and secrets.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TelemetryEvent {
+    ServerStarted {
+        port: u16,
+    },
+    ServerStopped,
+    ServerCrashed {
+        exit_code: Option<i32>,
+        signal: Option<i32>,
+    },
+    DownloadStarted {
+        repo_id: String,
+    },
+    DownloadCompleted {
+        repo_id: String,
+        duration_ms: u64,
+    },
+    DownloadFailed {
+        repo_id: String,
+        duration_ms: u64,
+    },
+    DownloadCancelled {
+        repo_id: String,
+        duration_ms: u64,
+    },
+    DownloadPaused {
+        repo_id: String,
+        duration_ms: u64,
+    },
+    McpConnected {
+        server_id: i64,
+    },
+    McpDisconnected {
+        server_id: i64,
+    },
+    /// A chat completion sent to a provider; metadata only, never message content.
+    ProviderRequest {
+        provider: String,
+        model: String,
+        message_count: usize,
+        prompt_tokens_estimate: u64,
+        latency_ms: u64,
+        ok: bool,
+    },
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    ts: chrono::DateTime<chrono::Utc>,
+    #[serde(flatten)]
+    event: &'a TelemetryEvent,
+}
+
+/// Rotating JSON-lines event log, enabled at runtime by the user.
+pub struct TelemetryLog {
+    dir: PathBuf,
+    enabled: AtomicBool,
+    write_lock: Mutex<()>,
+}
+
+impl TelemetryLog {
+    /// Creates a disabled log writing under `dir` (created on first write).
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            enabled: AtomicBool::new(false),
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Appends `event` when enabled. Failures are logged and otherwise ignored.
+    pub fn record(&self, event: &TelemetryEvent) {
+        if !self.is_enabled() {
+            return;
+        }
+        if let Err(e) = self.append(event) {
+            log::debug!("telemetry: failed to write event - {e}");
+        }
+    }
+
+    fn append(&self, event: &TelemetryEvent) -> std::io::Result<()> {
+        let mut line = serde_json::to_string(&Record {
+            ts: chrono::Utc::now(),
+            event,
+        })?;
+        line.push('\n');
+
+        let _guard = self.write_lock.lock().unwrap_or_else(|e| e.into_inner());
+        fs::create_dir_all(&self.dir)?;
+        let current = self.dir.join(LOG_FILE_NAME);
+        if fs::metadata(&current).map(|m| m.len()).unwrap_or(0) >= MAX_FILE_BYTES {
+            self.rotate()?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&current)?
+            .write_all(line.as_bytes())
+    }
+
+    /// Shifts `events.N.jsonl` to `N+1`, dropping the oldest, and moves the current
+    /// file to `events.1.jsonl`.
+    fn rotate(&self) -> std::io::Result<()> {
+        let oldest = rotated_path(&self.dir, MAX_ROTATED_FILES);
+        if oldest.exists() {
+            fs::remove_file(oldest)?;
+        }
+        for n in (1..MAX_ROTATED_FILES).rev() {
+            let from = rotated_path(&self.dir, n);
+            if from.exists() {
+                fs::rename(from, rotated_path(&self.dir, n + 1))?;
+            }
+        }
+        fs::rename(self.dir.join(LOG_FILE_NAME), rotated_path(&self.dir, 1))
+    }
+
+    /// Returns every retained event line, oldest first, for attaching to a bug report.
+    pub fn export(&self) -> std::io::Result<String> {
+        let _guard = self.write_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = String::new();
+        let files = (1..=MAX_ROTATED_FILES)
+            .rev()
+            .map(|n| rotated_path(&self.dir, n))
+            .chain(std::iter::once(self.dir.join(LOG_FILE_NAME)));
+        for path in files {
+            match fs::read_to_string(&path) {
+                Ok(text) => out.push_str(&text),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(out)
+    }
+}
+
+fn rotated_path(dir: &Path, n: usize) -> PathBuf {
+    dir.join(format!("events.{n}.jsonl"))
+}
+
+/// Applies the persisted opt-in flag. An unreadable setting (e.g. before migrations
+/// have run) leaves logging off.
+pub async fn load_enabled(app: &AppHandle) {
+    let (Some(log), Some(pool)) = (
+        app.try_state::<TelemetryLog>(),
+        app.try_state::<sqlx::SqlitePool>(),
+    ) else {
+        return;
+    };
+    match settings::get_setting::<bool>(&pool, Setting::TelemetryEnabled).await {
+        Ok(enabled) => log.set_enabled(enabled.unwrap_or(false)),
+        Err(e) => log::debug!("telemetry: settings unavailable - {e}"),
+    }
+}
+
+/// Records `event` through the app's managed `TelemetryLog`, if any.
+pub fn record(app: &AppHandle, event: TelemetryEvent) {
+    if let Some(log) = app.try_state::<TelemetryLog>() {
+        log.record(&event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TelemetryEvent, TelemetryLog, MAX_FILE_BYTES, MAX_ROTATED_FILES};
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("openchat-telemetry-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn records_only_when_enabled_and_rotates() {
+        let dir = temp_dir("rotate");
+        let log = TelemetryLog::new(dir.clone());
+        log.record(&TelemetryEvent::ServerStopped);
+        assert_eq!(log.export().unwrap(), "");
+
+        log.set_enabled(true);
+        log.record(&TelemetryEvent::ServerStarted { port: 8000 });
+        let exported = log.export().unwrap();
+        assert!(exported.contains(r#""event":"server_started","port":8000"#));
+
+        let event = TelemetryEvent::DownloadStarted {
+            repo_id: "x".repeat(1024),
+        };
+        for _ in 0..(MAX_FILE_BYTES as usize / 1024) * (MAX_ROTATED_FILES + 2) {
+            log.record(&event);
+        }
+        let files = std::fs::read_dir(&dir).unwrap().count();
+        assert_eq!(files, MAX_ROTATED_FILES + 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,121 @@
+//! App theme persisted in `app_settings.theme` and applied to the native window
+//! chrome. The frontend follows along through `THEME_CHANGED_EVENT`.
+
+use crate::settings::{self, Setting};
+use serde::Serialize;
+use std::str::FromStr;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Emitted with the new `Theme` whenever it changes, from a command or the menu.
+pub const THEME_CHANGED_EVENT: &str = "theme-changed";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    Light,
+    Dark,
+    #[default]
+    System,
+}
+
+impl Theme {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+            Theme::System => "system",
+        }
+    }
+
+    /// Theme the View menu toggle switches to: light, dark, system, and round again.
+    pub fn next(self) -> Self {
+        match self {
+            Theme::Light => Theme::Dark,
+            Theme::Dark => Theme::System,
+            Theme::System => Theme::Light,
+        }
+    }
+
+    fn native(self) -> Option<tauri::Theme> {
+        match self {
+            Theme::Light => Some(tauri::Theme::Light),
+            Theme::Dark => Some(tauri::Theme::Dark),
+            Theme::System => None,
+        }
+    }
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "light" => Ok(Theme::Light),
+            "dark" => Ok(Theme::Dark),
+            "system" => Ok(Theme::System),
+            other => Err(format!(
+                "unknown theme '{other}' (expected 'light', 'dark' or 'system')"
+            )),
+        }
+    }
+}
+
+/// Reads the persisted theme. Unset or unrecognised values follow the system.
+pub async fn get_theme(pool: &sqlx::SqlitePool) -> Result<Theme, String> {
+    let stored = settings::get_setting::<String>(pool, Setting::Theme).await?;
+    Ok(stored.and_then(|t| t.parse().ok()).unwrap_or_default())
+}
+
+/// Persists `theme`, applies it to the window chrome and notifies the frontend.
+pub async fn set_theme(
+    app: &AppHandle,
+    pool: &sqlx::SqlitePool,
+    theme: Theme,
+) -> Result<(), String> {
+    settings::set_setting(pool, Setting::Theme, Some(theme.as_str().to_string())).await?;
+    app.set_theme(theme.native());
+    let _ = app.emit(THEME_CHANGED_EVENT, theme);
+    Ok(())
+}
+
+/// Applies the persisted theme (at startup and after a settings reset) and notifies
+/// the frontend. An unreadable setting (e.g. before migrations have run) leaves the
+/// system theme in place.
+pub async fn load_theme(app: &AppHandle) {
+    let Some(pool) = app.try_state::<sqlx::SqlitePool>() else {
+        return;
+    };
+    match get_theme(&pool).await {
+        Ok(theme) => {
+            app.set_theme(theme.native());
+            let _ = app.emit(THEME_CHANGED_EVENT, theme);
+        }
+        Err(e) => log::debug!("theme: settings unavailable - {e}"),
+    }
+}
+
+/// Switches to the next theme; backs the View menu toggle.
+pub async fn toggle_theme(app: &AppHandle) -> Result<Theme, String> {
+    let pool = app
+        .try_state::<sqlx::SqlitePool>()
+        .ok_or("database is not available")?;
+    let theme = get_theme(&pool).await?.next();
+    set_theme(app, &pool, theme).await?;
+    Ok(theme)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Theme;
+
+    #[test]
+    fn parses_themes_and_cycles_through_all_of_them() {
+        assert_eq!(" Dark ".parse::<Theme>(), Ok(Theme::Dark));
+        assert!("sepia".parse::<Theme>().is_err());
+        let mut theme = Theme::Light;
+        for expected in [Theme::Dark, Theme::System, Theme::Light] {
+            theme = theme.next();
+            assert_eq!(theme, expected);
+        }
+    }
+}
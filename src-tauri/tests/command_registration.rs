@@ -0,0 +1,69 @@
+//! Guards against commands that are defined but never registered.
+//!
+//! Every `#[tauri::command]` in `src/commands.rs` must appear as `commands::<name>`
+//! in the `generate_handler!` list in `src/lib.rs`, and vice versa.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+
+fn read_source(relative: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(relative);
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("read {}: {e}", path.display()))
+}
+
+/// Names of functions annotated with `#[tauri::command]`.
+fn defined_commands(source: &str) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    let mut pending = false;
+    for line in source.lines().map(str::trim) {
+        if line.starts_with("#[tauri::command") {
+            pending = true;
+            continue;
+        }
+        if pending {
+            if let Some(pos) = line.find("fn ") {
+                let rest = &line[pos + 3..];
+                let name: String = rest
+                    .chars()
+                    .take_while(|c| c.is_alphanumeric() || *c == '_')
+                    .collect();
+                names.insert(name);
+                pending = false;
+            }
+        }
+    }
+    names
+}
+
+/// Names listed as `commands::<name>` inside `generate_handler![...]`.
+fn registered_commands(source: &str) -> BTreeSet<String> {
+    let start = source
+        .find("generate_handler![")
+        .expect("generate_handler! not found in lib.rs");
+    let body = &source[start..];
+    let end = body.find(']').expect("unterminated generate_handler!");
+    body[..end]
+        .lines()
+        .map(str::trim)
+        .filter_map(|line| line.strip_prefix("commands::"))
+        .map(|name| name.trim_end_matches(',').to_string())
+        .collect()
+}
+
+#[test]
+fn every_defined_command_is_registered() {
+    let defined = defined_commands(&read_source("src/commands.rs"));
+    let registered = registered_commands(&read_source("src/lib.rs"));
+    assert!(!defined.is_empty(), "no commands found in commands.rs");
+
+    let unregistered: Vec<_> = defined.difference(&registered).collect();
+    assert!(
+        unregistered.is_empty(),
+        "commands defined but not registered in lib.rs: {unregistered:?}"
+    );
+    let unknown: Vec<_> = registered.difference(&defined).collect();
+    assert!(
+        unknown.is_empty(),
+        "commands registered in lib.rs but not defined in commands.rs: {unknown:?}"
+    );
+}
@@ -11,6 +11,7 @@ import { AppContextProvider, useAppContext } from '@/contexts/app-context'
 import { DownloadProgressProvider } from '@/contexts/download-progress-context'
 import { ModelManagerProvider } from '@/contexts/model-manager-context'
 import { useDownloadToasts } from '@/hooks/use-download-toast'
+import { useMcpResumeReconnect } from '@/hooks/use-mcp-resume'
 
 import './App.css'
 
@@ -20,6 +21,9 @@ function AppContent() {
   // Automatically manage download toasts
   useDownloadToasts()
 
+  // Bring MCP sessions back after the machine wakes from sleep
+  useMcpResumeReconnect()
+
   return (
     <div className="h-screen flex flex-col select-none">
       <div className="flex-1 overflow-hidden flex flex-col">
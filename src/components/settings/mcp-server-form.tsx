@@ -97,6 +97,7 @@ export function McpServerFormDialog({
         enabled: !!values.enabled,
         commandLine: values.commandLine || '',
         env: values.env || [],
+        envFile: values.envFile,
       }
       return formToConfig(config)
     } else {
@@ -195,6 +196,18 @@ export function McpServerFormDialog({
                 )}
               </label>
 
+              <label className="text-sm block">
+                <div className="mb-1">Env file</div>
+                <Input
+                  {...form.register('envFile')}
+                  placeholder="~/.config/my-server/.env"
+                />
+                <div className="text-xs text-muted-foreground mt-1">
+                  Variables are loaded each time the server starts; the ones
+                  below take precedence.
+                </div>
+              </label>
+
               {/* ENV key/values */}
               <div className="space-y-2">
                 <div className="text-sm font-medium">Environment variables</div>
@@ -18,6 +18,9 @@ export function McpTestResult({ result }: McpTestResultProps) {
           <span className="text-sm font-medium text-green-800 dark:text-green-200">
             Connection successful
           </span>
+          <span className="ml-auto text-xs text-green-700 dark:text-green-300">
+            {result.transport} · {result.latencyMs} ms
+          </span>
         </div>
 
         <div className="mt-2 text-sm text-green-700 dark:text-green-300">
@@ -55,6 +58,9 @@ export function McpTestResult({ result }: McpTestResultProps) {
         <span className="text-sm font-medium text-red-800 dark:text-red-200">
           Connection failed
         </span>
+        <span className="ml-auto text-xs text-red-700 dark:text-red-300">
+          {result.transport} · {result.latencyMs} ms
+        </span>
       </div>
       {result.error && (
         <div className="mt-2 text-sm text-red-700 dark:text-red-300">
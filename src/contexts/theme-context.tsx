@@ -7,7 +7,13 @@ import {
   type ReactNode,
 } from 'react'
 
-type Theme = 'dark' | 'light' | 'system'
+import {
+  getTheme as getStoredTheme,
+  setTheme as storeTheme,
+  type Theme,
+} from '@/lib/commands'
+import { subscribeToThemeChanges } from '@/lib/events'
+
 type Scheme = Exclude<Theme, 'system'>
 
 const DEFAULT_STORAGE_KEY = 'vite-ui-theme'
@@ -55,6 +61,45 @@ export function ThemeProvider({
 
   const resolvedTheme: Scheme = theme === 'system' ? getSystemScheme() : theme
 
+  // The backend owns the theme; localStorage only avoids a flash on startup.
+  // Follow its persisted value and any change made from the View menu.
+  useEffect(() => {
+    const cacheTheme = (nextTheme: Theme) => {
+      try {
+        localStorage.setItem(storageKey, nextTheme)
+      } catch {
+        // no-op if storage is unavailable
+      }
+      setTheme(nextTheme)
+    }
+
+    let cancelled = false
+    let unlisten: (() => void) | undefined
+
+    getStoredTheme()
+      .then((stored) => {
+        if (!cancelled) {
+          cacheTheme(stored)
+        }
+      })
+      .catch((error: unknown) => {
+        console.error('[ThemeProvider] Failed to load theme', error)
+      })
+
+    void subscribeToThemeChanges(cacheTheme).then((fn) => {
+      if (cancelled) {
+        fn()
+      } else {
+        unlisten = fn
+      }
+    })
+
+    return () => {
+      cancelled = true
+      unlisten?.()
+    }
+  }, [storageKey])
+
   useLayoutEffect(() => {
     const root = window.document.documentElement
     const schemeToApply: Scheme = theme === 'system' ? getSystemScheme() : theme
@@ -90,6 +135,9 @@ export function ThemeProvider({
         // no-op if storage is unavailable
       }
       setTheme(nextTheme)
+      storeTheme(nextTheme).catch((error: unknown) => {
+        console.error('[ThemeProvider] Failed to save theme', error)
+      })
     },
   }
 
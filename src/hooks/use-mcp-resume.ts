@@ -0,0 +1,37 @@
+import { useEffect } from 'react'
+
+import { mcpReconnectStale } from '@/lib/commands'
+
+/** Minimum time between stale-session checks, so quick tab flips stay cheap. */
+const MIN_CHECK_INTERVAL_MS = 30_000
+
+/**
+ * useMcpResumeReconnect
+ *
+ * Reconnects MCP servers whose session died while the app was hidden or the machine
+ * slept, checking whenever the window becomes visible again.
+ */
+export function useMcpResumeReconnect() {
+  useEffect(() => {
+    let lastCheck = 0
+    const onVisibilityChange = () => {
+      if (document.visibilityState !== 'visible') return
+      const now = Date.now()
+      if (now - lastCheck < MIN_CHECK_INTERVAL_MS) return
+      lastCheck = now
+      mcpReconnectStale()
+        .then((results) => {
+          const failed = results.filter((r) => !r.ok)
+          if (failed.length > 0) {
+            console.warn('[MCP] Failed to reconnect after resume:', failed)
+          }
+        })
+        .catch((error: unknown) => {
+          console.error('[MCP] Stale session check failed:', error)
+        })
+    }
+    document.addEventListener('visibilitychange', onVisibilityChange)
+    return () =>
+      document.removeEventListener('visibilitychange', onVisibilityChange)
+  }, [])
+}
@@ -2,6 +2,7 @@ import { useMutation, useQuery, useQueryClient } from '@tanstack/react-query'
 
 import {
   mcpCheckServer,
+  mcpDisconnectDisabled,
   type McpCheckResult,
   type McpServerConfig,
 } from '@/lib/commands'
@@ -26,6 +27,12 @@ import {
 export function useMcpServers(search?: string) {
   const queryClient = useQueryClient()
 
+  // Drop the live session of a server that a save, toggle or delete disabled
+  const onServersChanged = async () => {
+    await mcpDisconnectDisabled()
+    await queryClient.invalidateQueries({ queryKey: ['mcp-servers'] })
+  }
+
   const list = useQuery({
     queryKey: ['mcp-servers', search ?? ''],
     queryFn: () => getMcpServers(search),
@@ -44,24 +51,18 @@ export function useMcpServers(search?: string) {
       id: number
       attrs: Parameters<typeof updateMcpServer>[1]
     }) => updateMcpServer(vars.id, vars.attrs),
-    onSuccess: async () => {
-      await queryClient.invalidateQueries({ queryKey: ['mcp-servers'] })
-    },
+    onSuccess: onServersChanged,
   })
 
   const remove = useMutation({
     mutationFn: (id: number) => deleteMcpServer(id),
-    onSuccess: async () => {
-      await queryClient.invalidateQueries({ queryKey: ['mcp-servers'] })
-    },
+    onSuccess: onServersChanged,
   })
 
   const setEnabled = useMutation({
     mutationFn: (vars: { id: number; enabled: boolean }) =>
       setMcpServerEnabled(vars.id, vars.enabled),
-    onSuccess: async () => {
-      await queryClient.invalidateQueries({ queryKey: ['mcp-servers'] })
-    },
+    onSuccess: onServersChanged,
   })
 
   const check = async (config: McpServerConfig): Promise<McpCheckResult> =>
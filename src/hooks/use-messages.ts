@@ -5,18 +5,21 @@ import { useRef } from 'react'
 
 import { useMcp } from '@/hooks/use-mcp'
 import { useModel } from '@/hooks/use-model'
+import {
+  getEffectiveGenerationParams,
+  getProviderHistory,
+  logChatRequest,
+  mlcRequestFinished,
+  mlcRequestStarted,
+} from '@/lib/commands'
 import { getSystemPrompt } from '@/lib/db/app-settings'
 import { touchConversation } from '@/lib/db/conversations'
-import {
-  getMessages,
-  getMessagesForChat,
-  insertMessage,
-  updateMessage,
-} from '@/lib/db/messages'
+import { getMessages, insertMessage, updateMessage } from '@/lib/db/messages'
 import { createMcpToolsMap } from '@/lib/mcp-tools'
 import { createMlcClient } from '@/lib/mlc-client'
 import { mlcServer } from '@/lib/mlc-server'
 import { DEFAULT_SETTINGS_PROMPT, SYSTEM_PROMPT } from '@/lib/prompt'
+import { toModelMessages } from '@/lib/provider-history'
 import { setConversationTitleIfUnset } from '@/lib/set-conversation-title'
 import type { Message } from '@/types'
 
@@ -119,9 +122,19 @@ export function useMessages(
       const abortController = new AbortController()
       abortControllerRef.current = abortController
 
-      // Build chat messages with system prompt
-      const chatMessages: ModelMessage[] =
-        await getMessagesForChat(conversationId)
+      // Build chat messages with system prompt from the history the backend
+      // renders: trimmed to the history limit, with stored tool results paired
+      // with the assistant tool calls they answer
+      const [history, params] = await Promise.all([
+        getProviderHistory(conversationId),
+        getEffectiveGenerationParams(conversationId),
+      ])
+      const chatMessages: ModelMessage[] = toModelMessages(history.messages)
+      if (history.omitted > 0) {
+        console.log(
+          `[useMessages] History limit left out ${history.omitted} older messages`,
+        )
+      }
 
       if (chatMessages.length === 0 || chatMessages[0].role !== 'system') {
         const settingsPrompt =
@@ -164,93 +177,115 @@ export function useMessages(
       }
       const model = createMlcClient({ modelId, endpoint })
 
-      const result = streamText({
-        model,
-        messages: chatMessages,
-        abortSignal: abortController.signal,
-        tools: mcpTools,
-        toolChoice: 'auto',
-        stopWhen: stepCountIs(10),
-        onError: (error) => {
-          console.error('[useMessages] Error streaming text', error)
+      // Keep the server watchdog from probing while it is busy generating
+      const requestLease = await mlcRequestStarted()
+      const requestStarted = performance.now()
+      let requestOk = false
+      let streamFailed = false
+      try {
+        const result = streamText({
+          model,
+          messages: chatMessages,
+          abortSignal: abortController.signal,
+          tools: mcpTools,
+          toolChoice: 'auto',
+          stopWhen: stepCountIs(10),
+          // The conversation's sampling and stop settings over the global defaults
+          temperature: params.temperature ?? undefined,
+          topP: params.topP ?? undefined,
+          maxOutputTokens: params.maxTokens ?? undefined,
+          stopSequences: params.stop.length > 0 ? params.stop : undefined,
+          onError: (error) => {
+            console.error('[useMessages] Error streaming text', error)
+            streamFailed = true
 
-          // If assistant message was created, mark it as error
-          if (assistantMessageId !== null) {
-            void updateMessage(assistantMessageId, {
-              status: 'error',
+            // If assistant message was created, mark it as error
+            if (assistantMessageId !== null) {
+              void updateMessage(assistantMessageId, {
+                status: 'error',
+              })
+              void invalidateConverationQuery()
+            }
+          },
+          onFinish: () => {
+            console.log('[useMessages] Streaming finished')
+          },
+          onStepFinish: (step) => {
+            console.log('[useMessages] Step finished', step)
+          },
+        })
+
+        const addReasoning = async (reasoning: string) => {
+          accumulatedReasoning += reasoning
+          if (assistantMessageId === null) {
+            assistantMessageId = await insertMessage({
+              conversation_id: conversationId,
+              role: 'assistant',
+              content: accumulatedContent,
+              reasoning: accumulatedReasoning,
+              status: 'pending',
+              created_at: new Date().toISOString(),
+            })
+          } else {
+            await updateMessage(assistantMessageId, {
+              content: accumulatedContent,
+              reasoning: accumulatedReasoning,
             })
-            void invalidateConverationQuery()
           }
-        },
-        onFinish: () => {
-          console.log('[useMessages] Streaming finished')
-        },
-        onStepFinish: (step) => {
-          console.log('[useMessages] Step finished', step)
-        },
-      })
+          void invalidateConverationQuery()
+        }
 
-      const addReasoning = async (reasoning: string) => {
-        accumulatedReasoning += reasoning
-        if (assistantMessageId === null) {
-          assistantMessageId = await insertMessage({
-            conversation_id: conversationId,
-            role: 'assistant',
-            content: accumulatedContent,
-            reasoning: accumulatedReasoning,
-            status: 'pending',
-            created_at: new Date().toISOString(),
-          })
-        } else {
-          await updateMessage(assistantMessageId, {
-            content: accumulatedContent,
-            reasoning: accumulatedReasoning,
-          })
+        const addContent = async (content: string) => {
+          accumulatedContent += content
+          if (assistantMessageId === null) {
+            assistantMessageId = await insertMessage({
+              conversation_id: conversationId,
+              role: 'assistant',
+              content: accumulatedContent,
+              reasoning: accumulatedReasoning || null,
+              status: 'pending',
+              created_at: new Date().toISOString(),
+            })
+          } else {
+            await updateMessage(assistantMessageId, {
+              content: accumulatedContent,
+              reasoning: accumulatedReasoning || undefined,
+            })
+          }
+          void invalidateConverationQuery()
         }
-        void invalidateConverationQuery()
-      }
 
-      const addContent = async (content: string) => {
-        accumulatedContent += content
-        if (assistantMessageId === null) {
-          assistantMessageId = await insertMessage({
-            conversation_id: conversationId,
-            role: 'assistant',
-            content: accumulatedContent,
-            reasoning: accumulatedReasoning || null,
-            status: 'pending',
-            created_at: new Date().toISOString(),
-          })
-        } else {
+        for await (const part of result.fullStream) {
+          switch (part.type) {
+            case 'reasoning-delta':
+              if (part.text) {
+                await addReasoning(part.text)
+              }
+              break
+            case 'text-delta':
+              if (part.text) {
+                await addContent(part.text)
+              }
+              break
+          }
+        }
+
+        if (assistantMessageId !== null) {
           await updateMessage(assistantMessageId, {
             content: accumulatedContent,
             reasoning: accumulatedReasoning || undefined,
+            status: 'complete',
           })
         }
-        void invalidateConverationQuery()
-      }
-
-      for await (const part of result.fullStream) {
-        switch (part.type) {
-          case 'reasoning-delta':
-            if (part.text) {
-              await addReasoning(part.text)
-            }
-            break
-          case 'text-delta':
-            if (part.text) {
-              await addContent(part.text)
-            }
-            break
-        }
-      }
-
-      if (assistantMessageId !== null) {
-        await updateMessage(assistantMessageId, {
-          content: accumulatedContent,
-          reasoning: accumulatedReasoning || undefined,
-          status: 'complete',
-        })
+        requestOk = !streamFailed
+      } finally {
+        void mlcRequestFinished(requestLease)
+        void logChatRequest(
+          modelId,
+          history.includedMessageIds,
+          Math.round(performance.now() - requestStarted),
+          requestOk,
+        )
       }
 
       void touchConversation(conversationId)
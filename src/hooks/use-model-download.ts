@@ -3,7 +3,7 @@ import { useCallback, useMemo } from 'react'
 
 import type { DownloadProgressState } from '@/contexts/download-progress-context'
 import { useDownloadProgress } from '@/contexts/download-progress-context'
-import { downloadModel } from '@/lib/commands'
+import { checkModelCompatibility, downloadModel } from '@/lib/commands'
 
 interface UseModelDownloadResult {
   /** Download progress state for the model */
@@ -39,7 +39,19 @@ export function useModelDownload(repoId: string): UseModelDownloadResult {
   }
 
   const downloadMutation = useMutation({
-    mutationFn: (modelRepoId: string) => downloadModel(modelRepoId),
+    mutationFn: async (modelRepoId: string) => {
+      // Refuse repos the MLX server cannot load before starting a large download.
+      // A failed check (e.g. offline) does not block the download.
+      const compatibility = await checkModelCompatibility(modelRepoId).catch(
+        () => null,
+      )
+      if (compatibility?.verdict === 'incompatible') {
+        throw new Error(
+          `${modelRepoId} is not an MLX-compatible model: ${compatibility.reason}`,
+        )
+      }
+      await downloadModel(modelRepoId)
+    },
     onSuccess: () => {
       // Invalidate model queries to refresh the available models list
       void queryClient.invalidateQueries({ queryKey: ['mlx-models'] })
@@ -24,6 +24,7 @@ export interface McpServerConfigStdio extends McpServerConfigBase {
   command: string
   args?: string[]
   env?: Record<string, string>
+  envFile?: string | null
   cwd?: string | null
 }
 
@@ -37,6 +38,22 @@ export interface McpServerConfigHttp extends McpServerConfigBase {
 
 export type McpServerConfig = McpServerConfigStdio | McpServerConfigHttp
 
+export interface McpReconnectResult {
+  id: number
+  ok: boolean
+  error?: string | null
+}
+
+export interface McpCapabilities {
+  initialized: boolean
+  protocolVersion: string | null
+  supportsTools: boolean
+  supportsResources: boolean
+  supportsPrompts: boolean
+  supportsLogging: boolean
+  supportsCompletions: boolean
+}
+
 export interface McpToolInfo {
   name: string
   description?: string
@@ -45,6 +62,8 @@ export interface McpToolInfo {
 
 export interface McpCheckResult {
   ok: boolean
+  transport: 'stdio' | 'http'
+  latencyMs: number
   toolsCount?: number | null
   tools?: McpToolInfo[] | null
   warning?: string
@@ -62,6 +81,8 @@ interface MlcServerStatusWire {
 
 interface McpCheckResultWire {
   ok: boolean
+  transport: 'stdio' | 'http'
+  latency_ms: number
   tools_count?: number | null
   tools?: McpToolInfo[] | null
   warning?: string | null
@@ -80,6 +101,7 @@ type RustMcpServerConfig =
       command: string
       args?: string[]
       env?: Record<string, string>
+      env_file?: string | null
       cwd?: string | null
     }
   | {
@@ -110,6 +132,8 @@ function convertMlcServerStatus(wire: MlcServerStatusWire): MlcServerStatus {
 function convertMcpCheckResult(wire: McpCheckResultWire): McpCheckResult {
   return {
     ok: wire.ok,
+    transport: wire.transport,
+    latencyMs: wire.latency_ms,
     toolsCount: wire.tools_count,
     tools: wire.tools,
     warning: wire.warning ?? undefined,
@@ -133,6 +157,7 @@ function toRustMcpConfig(config: McpServerConfig): RustMcpServerConfig {
       command: config.command,
       args: config.args ?? [],
       env: config.env ?? {},
+      env_file: config.envFile ?? null,
       cwd: config.cwd ?? null,
     }
   }
@@ -182,6 +207,126 @@ export async function mlcRestart(): Promise<MlcServerStatus> {
   return convertMlcServerStatus(wire)
 }
 
+/**
+ * Marks a chat request to the local server as in flight, so the watchdog does not
+ * restart a server that is busy generating. Pass the returned lease to
+ * `mlcRequestFinished`; a lease that is never finished (e.g. after a reload)
+ * lapses on its own.
+ */
+export async function mlcRequestStarted(): Promise<number> {
+  return await invoke<number>('mlc_request_started')
+}
+
+/**
+ * Marks the chat request holding `lease` from `mlcRequestStarted` as finished.
+ */
+export async function mlcRequestFinished(lease: number): Promise<void> {
+  await invoke('mlc_request_finished', { lease })
+}
+
+/**
+ * Reports a chat request streamed to the local server for provider request
+ * logging. A no-op unless logging is enabled in settings.
+ *
+ * @param model The model the request was sent to
+ * @param messageIds Ids of the stored messages sent (`includedMessageIds`); only
+ *   their size is logged, from the database
+ * @param latencyMs Time from sending the request until the stream ended
+ * @param ok Whether the stream completed without error
+ */
+export async function logChatRequest(
+  model: string,
+  messageIds: number[],
+  latencyMs: number,
+  ok: boolean,
+): Promise<void> {
+  await invoke('log_chat_request', { model, messageIds, latencyMs, ok })
+}
+
+// ==================== Conversation Commands ====================
+
+/** Stored messages sent with the next chat request after the history limit. */
+export interface ProviderHistory {
+  /** Messages in the OpenAI-compatible chat shape */
+  messages: Record<string, unknown>[]
+  /** Ids of the stored messages included, in order */
+  includedMessageIds: number[]
+  /** Older messages left out by the history limit */
+  omitted: number
+}
+
+/**
+ * Returns the conversation's history as it should be sent to the model, trimmed to
+ * its effective `max_history_messages` limit.
+ *
+ * @param conversationId The conversation ID
+ */
+export async function getProviderHistory(
+  conversationId: number,
+): Promise<ProviderHistory> {
+  const wire = await invoke<{
+    messages: Record<string, unknown>[]
+    included_message_ids: number[]
+    omitted: number
+  }>('get_provider_history', { conversationId })
+  return {
+    messages: wire.messages,
+    includedMessageIds: wire.included_message_ids,
+    omitted: wire.omitted,
+  }
+}
+
+/** Sampling and stop settings for a conversation's requests; unset fields are omitted. */
+export interface GenerationParams {
+  temperature: number | null
+  topP: number | null
+  maxTokens: number | null
+  stop: string[]
+}
+
+/**
+ * Returns the generation params to send for a conversation: its own, with unset
+ * fields taken from the global defaults.
+ *
+ * @param conversationId The conversation ID
+ */
+export async function getEffectiveGenerationParams(
+  conversationId: number,
+): Promise<GenerationParams> {
+  const wire = await invoke<{
+    temperature: number | null
+    top_p: number | null
+    max_tokens: number | null
+    stop: string[]
+  }>('get_effective_generation_params', { conversationId })
+  return {
+    temperature: wire.temperature,
+    topP: wire.top_p,
+    maxTokens: wire.max_tokens,
+    stop: wire.stop,
+  }
+}
+
+// ==================== Appearance Commands ====================
+
+/** App theme; `system` follows the OS color scheme. */
+export type Theme = 'light' | 'dark' | 'system'
+
+/** Returns the persisted app theme (`system` when unset). */
+export async function getTheme(): Promise<Theme> {
+  return await invoke<Theme>('get_theme')
+}
+
+/**
+ * Persists the app theme and applies it to the window chrome. The backend emits
+ * `theme-changed` with the new theme.
+ *
+ * @param theme The theme to apply
+ */
+export async function setTheme(theme: Theme): Promise<Theme> {
+  return await invoke<Theme>('set_theme', { theme })
+}
+
 // ==================== MCP Server Commands ====================
 
 /**
@@ -229,10 +374,92 @@ export async function mcpCallTool(
   return await invoke<string>('mcp_call_tool', { id, tool, args })
 }
 
+/**
+ * Pings cached MCP sessions and reconnects enabled servers whose session died, for
+ * example while the machine slept.
+ *
+ * @returns Promise resolving to one result per reconnected server
+ */
+export async function mcpReconnectStale(): Promise<McpReconnectResult[]> {
+  return await invoke<McpReconnectResult[]>('mcp_reconnect_stale')
+}
+
+/**
+ * Disconnects cached MCP sessions whose server is disabled or deleted. Call after
+ * changing MCP server settings.
+ *
+ * @returns Promise resolving to the ids that were disconnected
+ */
+export async function mcpDisconnectDisabled(): Promise<number[]> {
+  return await invoke<number[]>('mcp_disconnect_disabled')
+}
+
+/**
+ * Returns the features an MCP server negotiated at initialize, connecting first if
+ * needed, so the UI can hide actions the server does not support.
+ *
+ * @param id The MCP server ID
+ * @returns Promise resolving to the server's capabilities
+ */
+export async function mcpCapabilities(id: number): Promise<McpCapabilities> {
+  const wire = await invoke<{
+    initialized: boolean
+    protocol_version: string | null
+    supports_tools: boolean
+    supports_resources: boolean
+    supports_prompts: boolean
+    supports_logging: boolean
+    supports_completions: boolean
+  }>('mcp_capabilities', { id })
+  return {
+    initialized: wire.initialized,
+    protocolVersion: wire.protocol_version,
+    supportsTools: wire.supports_tools,
+    supportsResources: wire.supports_resources,
+    supportsPrompts: wire.supports_prompts,
+    supportsLogging: wire.supports_logging,
+    supportsCompletions: wire.supports_completions,
+  }
+}
+
+export type McpLogLevel =
+  | 'debug'
+  | 'info'
+  | 'notice'
+  | 'warning'
+  | 'error'
+  | 'critical'
+  | 'alert'
+  | 'emergency'
+
+/** Payload of the `mcp-log` event raised for each server log notification. */
+export interface McpLogMessage {
+  id: number
+  level: McpLogLevel
+  logger: string | null
+  message: string
+}
+
+/**
+ * Asks an MCP server to send log notifications (`mcp-log` events) at the given
+ * level and above.
+ *
+ * @param id The MCP server ID
+ * @param level The minimum level to receive
+ */
+export async function mcpSetLogLevel(
+  id: number,
+  level: McpLogLevel,
+): Promise<void> {
+  await invoke('mcp_set_log_level', { id, level })
+}
+
 // ==================== Environment Variable Commands ====================
 
 /**
- * Retrieves an environment variable value from the host system.
+ * Retrieves an environment variable value from the host system. Only
+ * non-secret variables (`HF_ENDPOINT`, `OPENCHAT_SAFE_MODE`) may be read; the
+ * backend rejects any other name.
  *
  * @param name The environment variable name to retrieve
  * @returns Promise resolving to the variable value or null if not found
@@ -242,8 +469,43 @@ export async function getEnvVar(name: string): Promise<string | null> {
   return await invoke<string | null>('get_env_var', { name })
 }
 
+/**
+ * Whether the app was launched in safe mode (`OPENCHAT_SAFE_MODE=1`), in which case
+ * the MLX server is not started automatically.
+ *
+ * @returns Promise resolving to true when safe mode is active
+ */
+export async function isSafeMode(): Promise<boolean> {
+  return await invoke<boolean>('is_safe_mode')
+}
+
 // ==================== Model Download Commands ====================
 
+export interface ModelCompatibility {
+  repoId: string
+  verdict: 'compatible' | 'incompatible' | 'unknown'
+  reason: string
+}
+
+/**
+ * Checks from a repo's file list, tags and config whether the MLX server can load it,
+ * without downloading any weights.
+ *
+ * @param repoId The Hugging Face model repository ID
+ * @returns Promise resolving to the verdict and the reason for it
+ * @throws If the repo cannot be found or Hugging Face cannot be reached
+ */
+export async function checkModelCompatibility(
+  repoId: string,
+): Promise<ModelCompatibility> {
+  const wire = await invoke<{
+    repo_id: string
+    verdict: ModelCompatibility['verdict']
+    reason: string
+  }>('check_model_compatibility', { repoId })
+  return { repoId: wire.repo_id, verdict: wire.verdict, reason: wire.reason }
+}
+
 /**
  * Downloads a Hugging Face model to the local cache if not already present.
  * Emits `mlc-download-progress` events during the download process.
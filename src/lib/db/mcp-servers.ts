@@ -23,7 +23,10 @@ export async function getMcpServers(search?: string): Promise<McpServerRow[]> {
     })
   }
 
-  return await query.orderBy('created_at', 'desc').execute()
+  return await query
+    .orderBy('priority', 'desc')
+    .orderBy('created_at', 'desc')
+    .execute()
 }
 
 /**
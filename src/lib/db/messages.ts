@@ -4,14 +4,31 @@ import { getKysely } from '@/lib/kysely'
 import type { Message } from '@/types'
 import type { DB } from '@/types/db'
 
+/** Columns accepted by `insertMessage`; `metadata` is serialized to JSON text. */
+export type InsertMessageAttrs = Omit<
+  Insertable<DB['messages']>,
+  'metadata'
+> & {
+  metadata?: Record<string, unknown> | null
+}
+
+/**
+ * Inserts a message and returns its id.
+ *
+ * @param attrs Columns of the new row.
+ */
 export async function insertMessage(
-  attrs: Insertable<DB['messages']>,
+  attrs: InsertMessageAttrs,
 ): Promise<number> {
   const db = await getKysely()
+  const { metadata, ...columns } = attrs
 
   const row = await db
     .insertInto('messages')
-    .values(attrs)
+    .values({
+      ...columns,
+      metadata: metadata ? JSON.stringify(metadata) : null,
+    })
     .returning('id')
     .executeTakeFirstOrThrow()
 
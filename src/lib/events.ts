@@ -1,5 +1,7 @@
 import { listen, type UnlistenFn } from '@tauri-apps/api/event'
 
+import type { Theme } from '@/lib/commands'
+
 // ==================== Event Constants ====================
 
 export const MENU_NEW_CHAT = 'tauri://menu-new-chat'
@@ -8,6 +10,7 @@ export const MENU_RELOAD = 'tauri://menu-reload'
 
 const MLC_STATUS_CHANGED_EVENT = 'mlc-status-changed'
 const MLC_DOWNLOAD_PROGRESS_EVENT = 'mlc-download-progress'
+const THEME_CHANGED_EVENT = 'theme-changed'
 
 // ==================== Type Definitions ====================
 
@@ -207,6 +210,21 @@ export async function subscribeToDownloadProgress(
   )
 }
 
+/**
+ * Subscribes to app theme changes, whether made through `setTheme` or the View
+ * menu toggle.
+ *
+ * @param onEvent Callback invoked with the new theme
+ * @returns Promise resolving to an unsubscribe function
+ */
+export async function subscribeToThemeChanges(
+  onEvent: (theme: Theme) => void,
+): Promise<UnlistenFn> {
+  return await listen<Theme>(THEME_CHANGED_EVENT, (event) => {
+    onEvent(event.payload)
+  })
+}
+
 // ==================== Event Utilities ====================
 
 /**
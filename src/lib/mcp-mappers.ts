@@ -22,6 +22,7 @@ export function rowToForm(row: McpServerRow): McpServerFormValues {
       enabled: !!row.enabled,
       commandLine,
       env: parseStringRecord(row.env),
+      envFile: row.env_file ?? undefined,
     }
   }
   const headers = parseStringRecord(row.headers)
@@ -59,6 +60,7 @@ export function configToForm(config: McpServerConfig): McpServerFormValues {
         key,
         value: String(value ?? ''),
       })),
+      envFile: config.envFile ?? undefined,
     }
   }
   return {
@@ -95,6 +97,7 @@ export function formToConfig(values: McpServerFormValues): McpServerConfig {
       args,
       cwd: null,
       env: kvArrayToRecord(values.env),
+      envFile: values.envFile?.trim() || null,
     }
   }
   return {
@@ -131,6 +134,7 @@ export function formToDbInsert(values: McpServerFormValues) {
       command,
       args: JSON.stringify(args),
       env: JSON.stringify(kvArrayToRecord(values.env)),
+      env_file: values.envFile?.trim() || null,
       cwd: null,
     }
   }
@@ -1,5 +1,6 @@
 import { generateText } from 'ai'
 
+import { isSafeMode } from '@/lib/commands'
 import { getModelOrDefault } from '@/lib/db/app-settings'
 import { mlcServer, type MlcServerStatus } from '@/lib/mlc-server'
 import { modelStore, type ModelStatus } from '@/lib/model-store'
@@ -104,6 +105,14 @@ class ModelManagerService {
       mlcServer.addStatusListener(() => this.notifyListeners())
       modelStore.addStatusListener(() => this.notifyListeners())
 
+      // In safe mode, come up without a server so settings can be fixed
+      if (await isSafeMode()) {
+        console.warn('[ModelManager] Safe mode: skipping MLX server start')
+        this.isInitialized = true
+        this.notifyListeners()
+        return
+      }
+
       // 3. Start the MLX server and load current model in parallel
       const [, currentModel] = await Promise.all([
         mlcServer.start(),
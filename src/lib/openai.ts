@@ -1,21 +1,15 @@
 import { createOpenAI } from '@ai-sdk/openai'
 
-import { getEnvVar } from '@/lib/commands'
-
 /**
- * Creates an OpenAI model instance using the `OPENAI_API_KEY` provided by
- * the host environment via Tauri. Defaults to `gpt-4o-mini`.
+ * Creates an OpenAI model instance. Defaults to `gpt-4o-mini`.
+ *
+ * The API key is not readable from the environment through Tauri; cloud turns
+ * normally run in the backend, which reads the stored key itself.
  *
+ * @param apiKey The OpenAI API key.
  * @returns A configured model instance.
- * @throws If the API key is not available.
  */
-export async function createOpenAiModel() {
-  const apiKey = await getEnvVar('OPENAI_API_KEY')
-
-  if (!apiKey) {
-    throw new Error('OPENAI_API_KEY environment variable is not set')
-  }
-
+export function createOpenAiModel(apiKey: string) {
   return createOpenAI({
     apiKey,
   })('gpt-4o-mini')
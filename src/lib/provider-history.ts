@@ -0,0 +1,74 @@
+import type { ModelMessage } from 'ai'
+
+interface ApiToolCall {
+  id: string
+  function: { name: string; arguments: string }
+}
+
+/**
+ * Converts messages in the OpenAI-compatible chat shape (as returned by
+ * `getProviderHistory`) into AI SDK messages. Assistant `tool_calls` become
+ * tool-call parts and `tool` messages become tool results, named after the call
+ * they answer.
+ *
+ * @param messages Messages in the chat API shape.
+ * @returns Messages for `streamText`.
+ */
+export function toModelMessages(
+  messages: Record<string, unknown>[],
+): ModelMessage[] {
+  const toolNames = new Map<string, string>()
+
+  return messages.map((message): ModelMessage => {
+    const content = typeof message.content === 'string' ? message.content : ''
+    switch (message.role) {
+      case 'system':
+        return { role: 'system', content }
+      case 'assistant': {
+        const toolCalls = (message.tool_calls ?? []) as ApiToolCall[]
+        if (toolCalls.length === 0) {
+          return { role: 'assistant', content }
+        }
+        for (const call of toolCalls) {
+          toolNames.set(call.id, call.function.name)
+        }
+        return {
+          role: 'assistant',
+          content: [
+            ...(content ? [{ type: 'text' as const, text: content }] : []),
+            ...toolCalls.map((call) => ({
+              type: 'tool-call' as const,
+              toolCallId: call.id,
+              toolName: call.function.name,
+              input: parseArguments(call.function.arguments),
+            })),
+          ],
+        }
+      }
+      case 'tool': {
+        const toolCallId = String(message.tool_call_id ?? '')
+        return {
+          role: 'tool',
+          content: [
+            {
+              type: 'tool-result',
+              toolCallId,
+              toolName: toolNames.get(toolCallId) ?? '',
+              output: { type: 'text', value: content },
+            },
+          ],
+        }
+      }
+      default:
+        return { role: 'user', content }
+    }
+  })
+}
+
+function parseArguments(raw: string): unknown {
+  try {
+    return JSON.parse(raw)
+  } catch {
+    return {}
+  }
+}
@@ -28,7 +28,13 @@ export async function setConversationTitleIfUnset(
     return
   }
 
-  const chatMessages: ModelMessage[] = await getMessagesForChat(conversationId)
+  // Only the user/assistant text matters for a title
+  const rows = await getMessagesForChat(conversationId)
+  const chatMessages: ModelMessage[] = rows.flatMap((row) =>
+    row.role === 'user' || row.role === 'assistant'
+      ? [{ role: row.role, content: row.content }]
+      : [],
+  )
 
   console.log('Generating title for conversation', conversationId)
 
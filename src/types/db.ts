@@ -7,10 +7,12 @@ export interface ConversationsTable {
   updated_at: ColumnType<string, string | undefined, string>
 }
 
+export type MessageRole = 'user' | 'assistant' | 'system' | 'tool'
+
 export interface MessagesTable {
   id: ColumnType<number, never, never>
   conversation_id: ColumnType<number, number, never>
-  role: ColumnType<'user' | 'assistant', 'user' | 'assistant', never>
+  role: ColumnType<MessageRole, MessageRole, never>
   content: ColumnType<string, string, string>
   reasoning: ColumnType<string | null, string | null | undefined, string | null>
   status: ColumnType<
@@ -18,6 +20,23 @@ export interface MessagesTable {
     'pending' | 'complete' | 'error' | undefined,
     'pending' | 'complete' | 'error'
   >
+  /** Set on `tool` messages: the call they answer and the tool that ran */
+  tool_name: ColumnType<string | null, string | null | undefined, never>
+  tool_call_id: ColumnType<string | null, string | null | undefined, never>
+  /** JSON arguments of the call, replayed with the assistant's `tool_calls` */
+  tool_arguments: ColumnType<
+    string | null,
+    string | null | undefined,
+    never
+  >
+  /** The assistant message that requested the tool call */
+  parent_message_id: ColumnType<
+    number | null,
+    number | null | undefined,
+    never
+  >
+  /** JSON object text: attachment references, provider, model used. */
+  metadata: ColumnType<string | null, string | null | undefined, string | null>
   created_at: ColumnType<string, string | undefined, never>
 }
 
@@ -42,6 +61,11 @@ export interface McpServersTable {
   command: ColumnType<string | null, string | null | undefined, string | null>
   args: ColumnType<string | null, string | null | undefined, string | null>
   env: ColumnType<string | null, string | null | undefined, string | null>
+  env_file: ColumnType<
+    string | null,
+    string | null | undefined,
+    string | null
+  >
   cwd: ColumnType<string | null, string | null | undefined, string | null>
   url: ColumnType<string | null, string | null | undefined, string | null>
   headers: ColumnType<string | null, string | null | undefined, string | null>
@@ -61,6 +85,7 @@ export interface McpServersTable {
     number | null | undefined,
     number | null
   >
+  priority: ColumnType<number, number | undefined, number>
   created_at: ColumnType<string, string | undefined, never>
   updated_at: ColumnType<string, string | undefined, string>
 }
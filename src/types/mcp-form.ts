@@ -14,6 +14,7 @@ export const McpServerFormSchema = z.discriminatedUnion('transport', [
     ...common,
     commandLine: z.string().min(1, 'Command line is required'),
     env: z.array(stringKV).default([]),
+    envFile: z.string().optional().nullable(),
   }),
   z.object({
     transport: z.literal('http'),
@@ -14,6 +14,7 @@ export interface McpServerStdio extends McpServerBase {
   command: string
   args?: string[]
   env?: Record<string, string>
+  envFile?: string | null
   cwd?: string | null
 }
 
@@ -35,6 +36,8 @@ export interface McpToolInfo {
 
 export interface McpCheckResult {
   ok: boolean
+  transport: 'stdio' | 'http'
+  latencyMs: number
   toolsCount?: number | null
   tools?: McpToolInfo[] | null
   warning?: string